@@ -0,0 +1,986 @@
+//! vault-admin: operator CLI for the Shaw 402 vault program
+//!
+//! Reads the RPC URL and signer keypair from the standard Solana CLI config
+//! (`~/.config/solana/cli/config.yml`), overridable with `--url` / `--keypair`.
+//! The vault defaults to the one owned by the signer; pass `--vault` when the
+//! signer is the guardian rather than the authority.
+//!
+//! Once the vault has a config timelock, config commands must be queued with
+//! `--propose` and applied later with `execute-proposal`.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use shaw_vault::automation::AutomationCrank;
+use shaw_vault::client::{self, pda};
+use shaw_vault::{
+    AccrualSchedule, AuditAction, ConfigChange, DepositType, HarvestSource, StakeStrategy, YieldTrigger,
+    AUDIT_LOG_CAPACITY, VAULT_LABEL_LEN, YIELD_HISTORY_CAPACITY,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(name = "vault-admin", version, about = "Admin operations for the Shaw 402 vault")]
+struct Cli {
+    /// Solana CLI config file (defaults to the standard location)
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// RPC URL (overrides the config file)
+    #[arg(long, short = 'u', global = true)]
+    url: Option<String>,
+
+    /// Signer keypair path (overrides the config file)
+    #[arg(long, short = 'k', global = true)]
+    keypair: Option<String>,
+
+    /// Vault address (defaults to the vault owned by the signer)
+    #[arg(long, global = true)]
+    vault: Option<Pubkey>,
+
+    /// Program label of the signer's vault, e.g. us-regulated (defaults to the unlabeled vault)
+    #[arg(long, global = true, value_parser = parse_label)]
+    label: Option<[u8; VAULT_LABEL_LEN]>,
+
+    /// Queue config changes as a timelocked proposal instead of applying them
+    #[arg(long, global = true)]
+    propose: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the vault owned by the signer (a labeled program vault with --label)
+    Initialize,
+    /// Print the vault's configuration and state
+    Show,
+    /// Update general vault parameters (unset options are left unchanged)
+    Config {
+        #[arg(long)]
+        min_deposit_sol: Option<u64>,
+        #[arg(long)]
+        min_deposit_token: Option<u64>,
+        #[arg(long)]
+        staking_enabled: Option<bool>,
+        #[arg(long)]
+        cashback_bps: Option<u16>,
+        #[arg(long)]
+        referral_fee_bps: Option<u16>,
+    },
+    /// Set the per-epoch withdrawal cap
+    WithdrawalLimits {
+        #[arg(long)]
+        epoch_duration: i64,
+        #[arg(long)]
+        cap_amount: u64,
+        #[arg(long)]
+        cap_tvl_bps: u16,
+    },
+    /// Set per-merchant order limits (0 disables either)
+    OrderLimits {
+        #[arg(long)]
+        max_orders_per_hour: u32,
+        #[arg(long)]
+        max_daily_volume: u64,
+    },
+    /// Dampen repeat purchases: credit for a buyer's 1st, 2nd and later orders per window
+    RepeatPurchases {
+        #[arg(long)]
+        window: i64,
+        #[arg(long, value_delimiter = ',', num_args = 3)]
+        credit_bps: Vec<u16>,
+    },
+    /// Set the settlement platform fee for Bronze, Silver, Gold and Platinum merchants
+    PlatformFees {
+        #[arg(value_delimiter = ',', num_args = 4)]
+        bps: Vec<u16>,
+    },
+    /// Reject settlements with the given SPL Token instructions before and/or after them
+    SettlementGuard {
+        #[arg(long)]
+        before: bool,
+        #[arg(long)]
+        after: bool,
+        /// SPL Token instruction tags to block (default: Transfer, TransferChecked)
+        #[arg(long, value_delimiter = ',', default_value = "3,12")]
+        tags: Vec<u8>,
+    },
+    /// Set how long merchants keep a tier after their volume dips below it
+    TierGrace { seconds: i64 },
+    /// Set how many slots a deposit is held before withdrawals and reward claims
+    HoldingPeriod { slots: u64 },
+    /// Set the smallest order credited toward volume metrics (USD micro-units)
+    MinOrder { min_order_usd: u64 },
+    /// Cap rewards paid per withdrawal epoch and flag abnormal payouts for the guardian
+    RewardBreaker {
+        /// Most rewards paid out per withdrawal epoch (0 = no cap)
+        #[arg(long, default_value_t = 0)]
+        payout_cap: u64,
+        /// Flag payouts above this multiple of their expected rewards (0 = off)
+        #[arg(long, default_value_t = 0)]
+        anomaly_multiple_bps: u32,
+    },
+    /// Set lock boosts (6 months, 1 year, 3 years, 5 years) and the early exit penalty
+    LockTerms {
+        #[arg(long, value_delimiter = ',', num_args = 4)]
+        boosts: Vec<u16>,
+        #[arg(long)]
+        early_exit_penalty_bps: u16,
+    },
+    /// Set the bond agents post when registered
+    AgentBond { lamports: u64 },
+    /// Set the insurance fee taken from platform profit
+    InsuranceFee { bps: u16 },
+    /// Set the performance fee taken from harvested yield and the treasury it goes to
+    PerformanceFee {
+        bps: u16,
+        #[arg(long)]
+        treasury: Pubkey,
+    },
+    /// Cap total yield accrued across merchants between two unix timestamps
+    RewardEpoch {
+        budget: u64,
+        #[arg(long)]
+        start: i64,
+        #[arg(long)]
+        end: i64,
+    },
+    /// Distribute harvested yield to all SOL deposits (SPL deposits with --mint)
+    Harvest {
+        amount: u64,
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// The SOL harvested is MEV tips (Jito stake strategy) rather than inflation rewards
+        #[arg(long)]
+        mev_tips: bool,
+    },
+    /// Set how the vault's SOL is staked (native or jito)
+    StakeStrategy {
+        #[arg(value_parser = parse_stake_strategy)]
+        strategy: StakeStrategy,
+    },
+    /// Set when deposit yield accrues (daily or solana-epoch)
+    AccrualSchedule {
+        #[arg(value_parser = parse_accrual_schedule)]
+        schedule: AccrualSchedule,
+    },
+    /// Open the record of harvests during the current Solana epoch
+    InitEpochRecord,
+    /// Set how long streamed rewards take to be released (seconds, 0 = streaming off)
+    RewardStream { seconds: i64 },
+    /// Create the svSOL share mint (svUSDC for SPL token deposits with --token)
+    InitShareMint {
+        #[arg(long)]
+        token: bool,
+    },
+    /// Only let vault shares move to approved holders (--off lifts the restriction)
+    RestrictShareTransfers {
+        #[arg(long)]
+        off: bool,
+    },
+    /// Approve a wallet to receive vault shares while transfers are restricted
+    ApproveHolder { wallet: Pubkey },
+    /// Withdraw a wallet's share holder approval
+    RevokeHolder { wallet: Pubkey },
+    /// Require deposits to carry a KYC attestation from the verifier (--off lifts the requirement)
+    RequireKyc {
+        verifier: Pubkey,
+        #[arg(long)]
+        off: bool,
+    },
+    /// Call PROGRAM's notifier interface after every deposit and settlement (--off removes it)
+    Notifier {
+        program: Option<Pubkey>,
+        #[arg(long)]
+        off: bool,
+    },
+    /// Attest that a wallet passed KYC (signed by the vault's KYC verifier)
+    IssueKyc {
+        wallet: Pubkey,
+        /// Unix time the attestation stops counting (0 = never)
+        #[arg(long, default_value_t = 0)]
+        expires_at: i64,
+    },
+    /// Revoke a wallet's KYC attestation (the KYC verifier or the authority)
+    RevokeKyc { wallet: Pubkey },
+    /// Set the share of SPL deposits kept undeployed for instant withdrawals (basis points)
+    LiquidityBuffer { bps: u16 },
+    /// Set the share of SPL deposits a lending strategy targets (basis points)
+    LendingAllocation {
+        /// Reserve of an approved lending strategy
+        reserve: Pubkey,
+        bps: u16,
+        /// Key allowed to rebalance besides the authority
+        #[arg(long)]
+        operator: Option<Pubkey>,
+        /// Most principal a single rebalance moves (0 = unbounded)
+        #[arg(long, default_value_t = 0)]
+        max_amount: u64,
+    },
+    /// Pay booked performance fees to the treasury (token fees with --mint)
+    CollectFees {
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+    /// Hand the guardian role to another key
+    SetGuardian { guardian: Pubkey },
+    /// Set the delay config proposals wait before they can execute
+    ConfigTimelock { seconds: i64 },
+    /// Apply the pending config proposal once its timelock has elapsed
+    ExecuteProposal,
+    /// Cancel the pending config proposal (guardian veto, or the authority)
+    CancelProposal,
+    /// Enter emergency mode (signer must be the guardian)
+    Pause,
+    /// Leave emergency mode (signer must be the guardian)
+    Unpause,
+    /// Pause new deposits of one mint, leaving its withdrawals open (authority or guardian;
+    /// So11111111111111111111111111111111111111112 for SOL)
+    PauseMintDeposits {
+        mint: Pubkey,
+        /// Resume the mint's deposits instead
+        #[arg(long)]
+        off: bool,
+    },
+    /// Suspend a stablecoin's deposits and SPL accrual when its oracle price leaves
+    /// [LOWER, UPPER] micro-USD (both 0 turns the check off)
+    DepegBounds { mint: Pubkey, lower: u64, upper: u64 },
+    /// Compare a stablecoin's oracle price with its depeg bounds
+    CheckDepeg {
+        mint: Pubkey,
+        /// Pyth PriceUpdateV2 account for the mint's feed
+        #[arg(long)]
+        price_update: Pubkey,
+    },
+    /// Show a registered mint's pause and depeg state and the principal deposited in it
+    MintStatus { mint: Pubkey },
+    /// Freeze a merchant's deposit
+    Freeze { merchant: Pubkey },
+    /// Unfreeze a merchant's deposit
+    Unfreeze { merchant: Pubkey },
+    /// Create the vault stats and insurance fund accounts, and the vault's token
+    /// account for each accepted mint
+    InitAccounts {
+        #[arg(long, value_delimiter = ',')]
+        mints: Vec<Pubkey>,
+    },
+    /// Create the reserve cashback and referral rewards are paid from, in `mint`
+    InitRewardReserve { mint: Pubkey },
+    /// Check the vault's books against its SOL and token balances (fails on drift)
+    AssertInvariants {
+        /// Every SPL mint registered with the vault
+        #[arg(long, value_delimiter = ',')]
+        mints: Vec<Pubkey>,
+    },
+    /// Create the audit log of a vault initialized before it existed
+    InitAuditLog,
+    /// Print the authority actions recorded in the audit log, oldest first
+    AuditLog,
+    /// Grow a vault created with an older layout to the current size
+    MigrateVault,
+    /// Create the vault's address lookup table, seeded with its static accounts
+    CreateLookupTable,
+    /// Add addresses (price feeds, mints, ...) to the vault's lookup table
+    ExtendLookupTable {
+        #[arg(value_delimiter = ',', required = true)]
+        addresses: Vec<Pubkey>,
+    },
+    /// Run a crank (sweep-rewards, refresh-metrics or fulfill-withdrawals) on an automation
+    /// thread (--off deletes it)
+    Automate {
+        #[arg(value_parser = parse_automation_crank)]
+        crank: AutomationCrank,
+        /// Merchant of a sweep or metrics refresh
+        #[arg(long)]
+        merchant: Option<Pubkey>,
+        /// Mint paid out (omit for SOL); fulfillment takes one thread per mint
+        #[arg(long)]
+        mint: Option<Pubkey>,
+        /// Cron schedule, e.g. "0 */15 * * * * *" for every 15 minutes
+        #[arg(long)]
+        schedule: Option<String>,
+        /// Lamports toward the thread program's fees
+        #[arg(long, default_value_t = 100_000_000)]
+        fund: u64,
+        #[arg(long)]
+        off: bool,
+    },
+    /// Create the yield history of a merchant registered before it existed
+    InitYieldHistory { merchant: Pubkey },
+    /// Print a merchant's recent yield changes, oldest first
+    YieldHistory { merchant: Pubkey },
+    /// Grow a merchant's metrics account created with an older layout to the current size
+    MigrateMerchantMetrics { merchant: Pubkey },
+    /// Print a merchant's settled volume by currency
+    CurrencyVolume { merchant: Pubkey },
+    /// Roll a merchant's ended month (YYYY-MM) into its monthly statement
+    RollStatement {
+        merchant: Pubkey,
+        #[arg(value_parser = parse_month)]
+        month: u32,
+    },
+    /// Print a merchant's monthly statement (YYYY-MM)
+    Statement {
+        merchant: Pubkey,
+        #[arg(value_parser = parse_month)]
+        month: u32,
+    },
+    /// Accept a mint for oracle-priced settlement (So11111111111111111111111111111111111111112 for SOL)
+    PriceFeed {
+        mint: Pubkey,
+        /// Pyth price feed id (hex)
+        #[arg(long, value_parser = parse_feed_id)]
+        feed_id: [u8; 32],
+        /// Oldest price accepted (seconds)
+        #[arg(long, default_value_t = 60)]
+        max_age: i64,
+    },
+    /// Create the reward token mint and its emission schedule
+    InitEmission {
+        #[arg(long)]
+        epoch_duration: i64,
+        #[arg(long)]
+        epoch_emission: u64,
+        #[arg(long)]
+        decay_bps: u16,
+        #[arg(long)]
+        max_supply: u64,
+    },
+    /// Change the reward token emission rate
+    SetEmission {
+        #[arg(long)]
+        epoch_emission: u64,
+        #[arg(long)]
+        decay_bps: u16,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let config_file = match cli.config.as_deref() {
+        Some(path) => path.to_string(),
+        None => solana_cli_config::CONFIG_FILE
+            .as_ref()
+            .context("unable to locate the Solana CLI config")?
+            .clone(),
+    };
+    // A missing config file falls back to the defaults (localhost RPC, default keypair path)
+    let config = solana_cli_config::Config::load(&config_file).unwrap_or_default();
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+    let signer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow::anyhow!("failed to read keypair {}: {}", keypair_path, err))?;
+
+    let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    let authority = signer.pubkey();
+    let label = cli.label.unwrap_or_default();
+    let vault = cli.vault.unwrap_or_else(|| pda::labeled_vault(&authority, &label));
+
+    let ix = match cli.command {
+        Command::Initialize => {
+            if cli.vault.is_some() {
+                bail!("initialize always creates a signer's vault (pick it with --label); drop --vault");
+            }
+            println!("Vault: {}", vault);
+            client::initialize(&authority, label)
+        }
+        Command::Show => return show(&rpc, &vault),
+        Command::AuditLog => return show_audit_log(&rpc, &vault),
+        Command::YieldHistory { merchant } => return show_yield_history(&rpc, &vault, &merchant),
+        Command::CurrencyVolume { merchant } => return show_currency_volume(&rpc, &vault, &merchant),
+        Command::MintStatus { mint } => return show_mint_status(&rpc, &vault, &mint),
+        Command::Statement { merchant, month } => return show_statement(&rpc, &vault, &merchant, month),
+        Command::Config {
+            min_deposit_sol,
+            min_deposit_token,
+            staking_enabled,
+            cashback_bps,
+            referral_fee_bps,
+        } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::VaultConfig {
+                min_deposit_sol,
+                min_deposit_token,
+                staking_enabled,
+                cashback_bps,
+                referral_fee_bps,
+            },
+        ),
+        Command::WithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::WithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps },
+        ),
+        Command::OrderLimits { max_orders_per_hour, max_daily_volume } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::OrderRateLimits {
+                max_orders_per_hour,
+                max_daily_order_volume: max_daily_volume,
+            },
+        ),
+        Command::RepeatPurchases { window, credit_bps } => {
+            let credit_bps: [u16; 3] = credit_bps
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("--credit-bps takes exactly three values"))?;
+            config_ix(
+                cli.propose,
+                &vault,
+                &authority,
+                ConfigChange::RepeatPurchaseDampening { window, credit_bps },
+            )
+        }
+        Command::PlatformFees { bps } => {
+            let platform_fee_bps_by_tier: [u16; 4] = bps
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("platform fees take exactly four values"))?;
+            config_ix(
+                cli.propose,
+                &vault,
+                &authority,
+                ConfigChange::PlatformFees { platform_fee_bps_by_tier },
+            )
+        }
+        Command::SettlementGuard { before, after, tags } => {
+            anyhow::ensure!(tags.iter().all(|tag| *tag < 32), "token instruction tags must be below 32");
+            let blocked_token_ixs = tags.iter().fold(0u32, |mask, tag| mask | (1 << tag));
+            config_ix(
+                cli.propose,
+                &vault,
+                &authority,
+                ConfigChange::SettlementGuard { guard_before: before, guard_after: after, blocked_token_ixs },
+            )
+        }
+        Command::RewardBreaker { payout_cap, anomaly_multiple_bps } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::RewardCircuitBreaker { payout_cap, anomaly_multiple_bps },
+        ),
+        Command::HoldingPeriod { slots } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::HoldingPeriod { min_holding_slots: slots },
+        ),
+        Command::MinOrder { min_order_usd } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::MinOrderAmount { min_order_usd },
+        ),
+        Command::TierGrace { seconds } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::TierDowngradeGrace { grace_period: seconds },
+        ),
+        Command::LockTerms { boosts, early_exit_penalty_bps } => {
+            let lock_boost_schedule: [u16; 4] = boosts
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("--boosts takes exactly four values"))?;
+            config_ix(
+                cli.propose,
+                &vault,
+                &authority,
+                ConfigChange::LockTerms { lock_boost_schedule, early_exit_penalty_bps },
+            )
+        }
+        Command::AgentBond { lamports } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::AgentBond { agent_bond_lamports: lamports },
+        ),
+        Command::InsuranceFee { bps } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::InsuranceFee { insurance_fee_bps: bps },
+        ),
+        Command::PerformanceFee { bps, treasury } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::PerformanceFee { performance_fee_bps: bps, treasury },
+        ),
+        Command::RewardEpoch { budget, start, end } => {
+            client::fund_reward_epoch(&vault, &authority, budget, start, end)
+        }
+        Command::Harvest { amount, mint, mev_tips } => {
+            let source = if mev_tips { HarvestSource::MevTips } else { HarvestSource::Inflation };
+            // Record the harvest in the current epoch's record when there is one
+            let epoch = rpc.get_epoch_info()?.epoch;
+            let epoch = rpc.get_account(&pda::epoch_record(&vault, epoch)).is_ok().then_some(epoch);
+            client::harvest_rewards(&vault, &authority, mint, amount, source, epoch)
+        }
+        Command::AccrualSchedule { schedule } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::AccrualSchedule { schedule },
+        ),
+        Command::InitEpochRecord => {
+            let epoch = rpc.get_epoch_info()?.epoch;
+            println!("Epoch record: {}", pda::epoch_record(&vault, epoch));
+            client::initialize_epoch_record(&vault, &authority, epoch)
+        }
+        Command::InitShareMint { token } => {
+            let deposit_token = if token { DepositType::SplToken } else { DepositType::Sol };
+            println!("Share mint: {}", pda::share_mint(&vault, &deposit_token));
+            client::initialize_share_mint(&vault, &authority, deposit_token)
+        }
+        Command::RestrictShareTransfers { off } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::ShareTransferRestriction { restricted: !off },
+        ),
+        Command::ApproveHolder { wallet } => client::approve_share_holder(&vault, &authority, &wallet),
+        Command::RevokeHolder { wallet } => client::revoke_share_holder(&vault, &authority, &wallet),
+        Command::RequireKyc { verifier, off } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::KycPolicy { required: !off, verifier },
+        ),
+        Command::Notifier { program, off } => {
+            let program = match (program, off) {
+                (Some(program), false) => program,
+                (None, true) => Pubkey::default(),
+                _ => bail!("pass a notifier program, or --off to remove it"),
+            };
+            config_ix(cli.propose, &vault, &authority, ConfigChange::Notifier { program })
+        }
+        Command::IssueKyc { wallet, expires_at } => {
+            println!("KYC attestation: {}", pda::kyc_attestation(&vault, &wallet));
+            client::issue_kyc_attestation(&vault, &authority, &wallet, expires_at)
+        }
+        Command::RevokeKyc { wallet } => client::revoke_kyc_attestation(&vault, &authority, &wallet),
+        Command::RewardStream { seconds } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::RewardStreamDuration { duration: seconds },
+        ),
+        Command::StakeStrategy { strategy } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::StakeStrategy { strategy },
+        ),
+        Command::LiquidityBuffer { bps } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::LiquidityBuffer { liquidity_buffer_bps: bps },
+        ),
+        Command::LendingAllocation { reserve, bps, operator, max_amount } => client::set_lending_allocation(
+            &vault,
+            &authority,
+            &reserve,
+            bps,
+            &operator.unwrap_or_default(),
+            max_amount,
+        ),
+        Command::CollectFees { mint } => {
+            let state: shaw_vault::Vault = client::fetch_account(&rpc, &vault)?;
+            client::collect_performance_fees(&vault, &authority, &state.treasury, mint)
+        }
+        Command::SetGuardian { guardian } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::Guardian { new_guardian: guardian },
+        ),
+        Command::ConfigTimelock { seconds } => config_ix(
+            cli.propose,
+            &vault,
+            &authority,
+            ConfigChange::ConfigTimelock { delay: seconds },
+        ),
+        Command::ExecuteProposal => {
+            let proposal = client::fetch_config_proposal(&rpc, &vault)?;
+            client::execute_config_change(&vault, &authority, &proposal.proposer)
+        }
+        Command::CancelProposal => {
+            let proposal = client::fetch_config_proposal(&rpc, &vault)?;
+            client::cancel_config_change(&vault, &authority, &proposal.proposer)
+        }
+        Command::Pause => client::set_emergency_mode(&vault, &authority, true),
+        Command::Unpause => client::set_emergency_mode(&vault, &authority, false),
+        Command::PauseMintDeposits { mint, off } => {
+            // Track the mint on first use, or flip its existing flag
+            if rpc.get_account(&pda::supported_mint(&vault, &mint)).is_ok() {
+                client::set_mint_deposits_paused(&vault, &authority, &mint, !off)
+            } else {
+                client::register_supported_mint(&vault, &authority, mint, !off)
+            }
+        }
+        Command::DepegBounds { mint, lower, upper } => {
+            client::set_depeg_bounds(&vault, &authority, &mint, lower, upper)
+        }
+        Command::CheckDepeg { mint, price_update } => client::check_depeg(&vault, &mint, &price_update),
+        Command::Freeze { merchant } => client::freeze_merchant(&vault, &authority, &merchant),
+        Command::Unfreeze { merchant } => client::unfreeze_merchant(&vault, &authority, &merchant),
+        Command::InitAccounts { mints } => client::initialize_vault_accounts(&vault, &authority, &mints),
+        Command::InitRewardReserve { mint } => {
+            println!("Reward reserve: {} (fund it with token transfers)", pda::reward_reserve(&vault));
+            client::initialize_reward_reserve(&vault, &authority, &mint)
+        }
+        Command::AssertInvariants { mints } => client::assert_invariants(&vault, &mints),
+        Command::InitAuditLog => client::initialize_audit_log(&vault, &authority),
+        Command::InitYieldHistory { merchant } => client::initialize_yield_history(&vault, &merchant, &authority),
+        Command::RollStatement { merchant, month } => {
+            let last_month = client::fetch_merchant_metrics(&rpc, &vault, &merchant)?.last_statement_month;
+            let previous_month = (last_month > 0).then_some(last_month);
+            client::roll_monthly_statement(&vault, &merchant, &authority, month, previous_month)
+        }
+        Command::MigrateMerchantMetrics { merchant } => {
+            client::migrate_merchant_metrics(&vault, &merchant, &authority)
+        }
+        Command::MigrateVault => {
+            if cli.vault.is_some() {
+                bail!("migrate-vault always migrates a signer's vault (pick it with --label); drop --vault");
+            }
+            client::migrate_vault(&vault, &authority)
+        }
+        Command::CreateLookupTable => {
+            if cli.vault.is_some() {
+                bail!("create-lookup-table always uses a signer's vault (pick it with --label); drop --vault");
+            }
+            let recent_slot = rpc.get_slot_with_commitment(CommitmentConfig::finalized())?;
+            println!("Lookup table: {}", pda::lookup_table(&vault, recent_slot));
+            client::create_lookup_table(&vault, &authority, recent_slot)
+        }
+        Command::ExtendLookupTable { addresses } => {
+            let state: shaw_vault::Vault = client::fetch_account(&rpc, &vault)?;
+            if state.lookup_table == Pubkey::default() {
+                bail!("the vault has no lookup table; run create-lookup-table first");
+            }
+            client::extend_lookup_table(&vault, &authority, &state.lookup_table, addresses)
+        }
+        Command::Automate { crank, merchant, mint, schedule, fund, off } => {
+            if merchant.is_some() != crank.per_merchant() {
+                bail!("sweep-rewards and refresh-metrics take --merchant; fulfill-withdrawals doesn't");
+            }
+            match (schedule, off) {
+                (Some(schedule), false) => {
+                    println!("Thread: {}", client::automation_thread(&vault, crank, merchant, mint));
+                    client::create_automation_thread(&vault, &authority, crank, merchant, mint, &schedule, fund)
+                }
+                (None, true) => client::delete_automation_thread(&vault, &authority, crank, merchant, mint),
+                _ => bail!("pass a --schedule, or --off to delete the thread"),
+            }
+        }
+        Command::PriceFeed { mint, feed_id, max_age } => {
+            // Register the feed, or update it if the mint is already accepted
+            if rpc.get_account(&pda::price_feed(&vault, &mint)).is_ok() {
+                client::update_price_feed(&vault, &authority, &mint, feed_id, max_age)
+            } else {
+                client::register_price_feed(&vault, &authority, mint, feed_id, max_age)
+            }
+        }
+        Command::InitEmission { epoch_duration, epoch_emission, decay_bps, max_supply } => {
+            client::initialize_reward_emission(
+                &vault,
+                &authority,
+                epoch_duration,
+                epoch_emission,
+                decay_bps,
+                max_supply,
+            )
+        }
+        Command::SetEmission { epoch_emission, decay_bps } => {
+            client::set_emission_schedule(&vault, &authority, epoch_emission, decay_bps)
+        }
+    };
+
+    send(&rpc, &signer, &[ix])
+}
+
+fn parse_label(name: &str) -> Result<[u8; VAULT_LABEL_LEN]> {
+    if name.is_empty() || name.len() > VAULT_LABEL_LEN {
+        bail!("labels are 1 to {} bytes", VAULT_LABEL_LEN);
+    }
+    let mut label = [0; VAULT_LABEL_LEN];
+    label[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(label)
+}
+
+fn parse_stake_strategy(name: &str) -> Result<StakeStrategy> {
+    match name {
+        "native" => Ok(StakeStrategy::Native),
+        "jito" => Ok(StakeStrategy::Jito),
+        _ => bail!("unknown stake strategy {} (expected native or jito)", name),
+    }
+}
+
+fn parse_automation_crank(name: &str) -> Result<AutomationCrank> {
+    match name {
+        "sweep-rewards" => Ok(AutomationCrank::SweepRewards),
+        "refresh-metrics" => Ok(AutomationCrank::RefreshMetrics),
+        "fulfill-withdrawals" => Ok(AutomationCrank::FulfillWithdrawals),
+        _ => bail!(
+            "unknown crank {} (expected sweep-rewards, refresh-metrics or fulfill-withdrawals)",
+            name
+        ),
+    }
+}
+
+fn parse_accrual_schedule(name: &str) -> Result<AccrualSchedule> {
+    match name {
+        "daily" => Ok(AccrualSchedule::Daily),
+        "solana-epoch" => Ok(AccrualSchedule::SolanaEpoch),
+        _ => bail!("unknown accrual schedule {} (expected daily or solana-epoch)", name),
+    }
+}
+
+/// Calendar month `YYYY-MM` as the program's month index (`year * 12 + month - 1`)
+fn parse_month(text: &str) -> Result<u32> {
+    let (year, month) = text.split_once('-').context("month must be YYYY-MM")?;
+    let (year, month): (u32, u32) = (year.parse()?, month.parse()?);
+    if !(1..=12).contains(&month) {
+        bail!("month must be between 01 and 12");
+    }
+    Ok(year * 12 + month - 1)
+}
+
+fn parse_feed_id(hex: &str) -> Result<[u8; 32]> {
+    let hex = hex.trim_start_matches("0x");
+    if hex.len() != 64 {
+        bail!("feed id must be 32 bytes of hex");
+    }
+    let mut feed_id = [0u8; 32];
+    for (byte, pair) in feed_id.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair)?, 16)?;
+    }
+    Ok(feed_id)
+}
+
+/// Apply a config change directly, or queue it behind the timelock with `--propose`
+fn config_ix(propose: bool, vault: &Pubkey, authority: &Pubkey, change: ConfigChange) -> Instruction {
+    if propose {
+        client::propose_config_change(vault, authority, change)
+    } else {
+        client::set_config(vault, authority, change)
+    }
+}
+
+fn send(rpc: &RpcClient, signer: &Keypair, ixs: &[Instruction]) -> Result<()> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&signer.pubkey()), &[signer], blockhash);
+    let signature = rpc
+        .send_and_confirm_transaction(&tx)
+        .context("transaction failed")?;
+    println!("{}", signature);
+    Ok(())
+}
+
+fn show(rpc: &RpcClient, vault: &Pubkey) -> Result<()> {
+    let state: shaw_vault::Vault = client::fetch_account(rpc, vault)?;
+
+    println!("Vault:                  {}", vault);
+    println!("Authority:              {}", state.authority);
+    println!("Label:                  {}", state.label_str());
+    println!("Guardian:               {}", state.guardian);
+    println!("Emergency mode:         {}", state.emergency_mode);
+    println!(
+        "Total deposits:         {} ({} SOL / {} SPL)",
+        state.total_deposits, state.total_sol_deposits, state.total_token_deposits
+    );
+    println!("Merchants:              {}", state.total_merchants);
+    println!("Min deposit (SOL/SPL):  {} / {}", state.min_deposit_sol, state.min_deposit_token);
+    println!(
+        "Performance fee:        {} bps to {} (changed at {})",
+        state.performance_fee_bps, state.treasury, state.performance_fee_updated_at
+    );
+    println!(
+        "Fees owed (SOL/SPL):    {} / {} ({} lifetime)",
+        state.performance_fees_sol, state.performance_fees_token, state.total_performance_fees
+    );
+    println!("Staking enabled:        {}", state.staking_enabled);
+    println!("Cashback / referral:    {} / {} bps", state.cashback_bps, state.referral_fee_bps);
+    println!("Insurance fee:          {} bps", state.insurance_fee_bps);
+    println!("Agent bond:             {} lamports", state.agent_bond_lamports);
+    println!("Lock boosts:            {:?} bps", state.lock_boost_schedule);
+    println!("Early exit penalty:     {} bps", state.early_exit_penalty_bps);
+    println!(
+        "Withdrawal cap:         {} or {} bps of TVL per {}s",
+        state.withdraw_cap_amount, state.withdraw_cap_tvl_bps, state.withdraw_epoch_duration
+    );
+    println!(
+        "Withdrawal queue:       {} pending",
+        state.withdraw_queue_tail - state.withdraw_queue_head
+    );
+    println!(
+        "Order limits:           {} orders/hour, {} volume/day per merchant",
+        state.max_orders_per_hour, state.max_daily_order_volume
+    );
+    println!(
+        "Repeat purchases:       {:?} bps per {}s window",
+        state.repeat_purchase_credit_bps, state.repeat_purchase_window
+    );
+    println!("Tier downgrade grace:   {}s", state.tier_downgrade_grace);
+    println!("Holding period:         {} slots", state.min_holding_slots);
+    println!("Minimum order:          {}", state.min_order_usd());
+    println!(
+        "Reward breaker:         {} / {} paid this epoch, anomaly at {} bps",
+        state.rewards_paid_this_epoch, state.reward_payout_cap, state.reward_anomaly_multiple_bps
+    );
+    println!("Platform fees:          {:?} bps by tier", state.platform_fee_bps_by_tier);
+    println!(
+        "Settlement guard:       before {}, after {}, blocked token instructions {:#x}",
+        state.settlement_guard_before, state.settlement_guard_after, state.settlement_blocked_token_ixs
+    );
+    println!("Rewards paid:           {}", state.total_rewards_paid);
+    println!("Stake strategy:         {:?}", state.stake_strategy);
+    println!("Accrual schedule:       {:?}", state.accrual_schedule);
+    println!("Reward stream:          {}s", state.reward_stream_duration);
+    println!(
+        "Share value:            {:?} / {:?} per whole svSOL / svUSDC",
+        state.share_value(&DepositType::Sol, 10u64.pow(DepositType::Sol.decimals().into())),
+        state.share_value(&DepositType::SplToken, 10u64.pow(DepositType::SplToken.decimals().into()))
+    );
+    println!("Share transfers:        restricted {}", state.share_transfers_restricted);
+    println!("KYC:                    required {} (verifier {})", state.kyc_required, state.kyc_verifier);
+    if state.notifier_program != Pubkey::default() {
+        println!("Notifier:               {}", state.notifier_program);
+    }
+    println!(
+        "Depegged stablecoins:   {} (SPL accrual suspended since {})",
+        state.depegged_mints, state.depeg_suspended_at
+    );
+    println!(
+        "Lending allocation:     {} bps ({} lent, {} bps idle, buffer {} bps)",
+        state.lending_allocation_bps,
+        state.total_lent,
+        state.idle_token_bps(),
+        state.liquidity_buffer_bps
+    );
+    println!(
+        "Harvested:              {} ({} MEV tips)",
+        state.total_harvested, state.total_mev_tips_harvested
+    );
+    if state.reward_epoch_end > 0 {
+        println!(
+            "Reward epoch:           {} / {} accrued, [{}, {})",
+            state.reward_epoch_accrued,
+            state.reward_epoch_budget,
+            state.reward_epoch_start,
+            state.reward_epoch_end
+        );
+    }
+    println!("Config timelock:        {}s", state.config_timelock);
+    if state.lookup_table != Pubkey::default() {
+        println!("Lookup table:           {}", state.lookup_table);
+    }
+    if let Ok(proposal) = client::fetch_config_proposal(rpc, vault) {
+        println!(
+            "Pending proposal:       {:?} (executable at {})",
+            proposal.change, proposal.executable_at
+        );
+    }
+    Ok(())
+}
+
+fn show_audit_log(rpc: &RpcClient, vault: &Pubkey) -> Result<()> {
+    let log = client::fetch_audit_log(rpc, vault)?;
+
+    println!("{} actions recorded, last {} kept", log.total_entries, log.count);
+    let oldest = (log.head as usize + AUDIT_LOG_CAPACITY - log.count as usize) % AUDIT_LOG_CAPACITY;
+    for i in 0..log.count as usize {
+        let entry = &log.entries[(oldest + i) % AUDIT_LOG_CAPACITY];
+        let action = match AuditAction::from_code(entry.action) {
+            Some(action) => format!("{:?}", action),
+            None => format!("Unknown({})", entry.action),
+        };
+        println!(
+            "#{:<5} {} {:<26} by {} target {} amount {} detail {}",
+            entry.seq, entry.timestamp, action, entry.actor, entry.target, entry.amount, entry.detail
+        );
+    }
+    Ok(())
+}
+
+fn show_currency_volume(rpc: &RpcClient, vault: &Pubkey, merchant: &Pubkey) -> Result<()> {
+    let metrics = client::fetch_merchant_metrics(rpc, vault, merchant)?;
+
+    println!("Total volume (USD micro-units): {}", metrics.total_volume_usd);
+    for entry in metrics.currency_volumes.iter().filter(|entry| entry.mint != Pubkey::default()) {
+        println!(
+            "{:<44} {:>6} orders, {} settled, {} USD micro-units",
+            entry.mint, entry.order_count, entry.amount, entry.volume_usd
+        );
+    }
+    println!("Other currencies (USD micro-units): {}", metrics.other_currency_volume_usd);
+    Ok(())
+}
+
+fn show_mint_status(rpc: &RpcClient, vault: &Pubkey, mint: &Pubkey) -> Result<()> {
+    let supported_mint = client::fetch_supported_mint(rpc, vault, mint)?;
+
+    println!("Mint:                   {}", supported_mint.mint);
+    println!("Deposited principal:    {}", supported_mint.total_token_deposits);
+    println!("Lent principal:         {}", supported_mint.total_lent);
+    println!(
+        "Deposits paused:        {} (since {})",
+        supported_mint.deposits_paused, supported_mint.paused_at
+    );
+    if supported_mint.has_depeg_bounds() {
+        println!(
+            "Depeg bounds:           [{}, {}] micro-USD, depegged {} (since {})",
+            supported_mint.depeg_lower_bound,
+            supported_mint.depeg_upper_bound,
+            supported_mint.depegged,
+            supported_mint.depegged_at
+        );
+    }
+    Ok(())
+}
+
+fn show_statement(rpc: &RpcClient, vault: &Pubkey, merchant: &Pubkey, month: u32) -> Result<()> {
+    let statement = client::fetch_monthly_statement(rpc, vault, merchant, month)?;
+
+    println!("Statement {}-{:02} for {}", month / 12, month % 12 + 1, statement.merchant);
+    println!("Period: {} to {}", statement.opened_at, statement.closed_at);
+    println!("Orders: {} (lifetime {})", statement.orders, statement.cumulative_orders);
+    println!(
+        "Volume (USD micro-units): {} (lifetime {})",
+        statement.volume_usd, statement.cumulative_volume_usd
+    );
+    println!("Unique customers (rolling): {}", statement.unique_customers);
+    println!("Rewards accrued: {}", statement.rewards_accrued);
+    println!("Platform fees paid (USD micro-units): {}", statement.fees_paid_usd);
+    Ok(())
+}
+
+fn show_yield_history(rpc: &RpcClient, vault: &Pubkey, merchant: &Pubkey) -> Result<()> {
+    let history = client::fetch_yield_history(rpc, vault, merchant)?;
+
+    println!("{} yield changes recorded, last {} kept", history.total_entries, history.count);
+    let oldest = (history.head as usize + YIELD_HISTORY_CAPACITY - history.count as usize) % YIELD_HISTORY_CAPACITY;
+    for i in 0..history.count as usize {
+        let entry = &history.entries[(oldest + i) % YIELD_HISTORY_CAPACITY];
+        let trigger = match YieldTrigger::from_code(entry.trigger) {
+            Some(trigger) => format!("{:?}", trigger),
+            None => format!("Unknown({})", entry.trigger),
+        };
+        println!(
+            "{} {:<16} {:>5} -> {:>5} bps",
+            entry.timestamp, trigger, entry.previous_bps, entry.yield_bps
+        );
+    }
+    Ok(())
+}
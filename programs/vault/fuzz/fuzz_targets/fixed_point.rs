@@ -0,0 +1,39 @@
+//! Fuzz the fixed-point helpers against a u128 reference
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use shaw_vault::math::{
+    mul_div_ceil, mul_div_floor, q64_div_ceil, q64_div_floor, q64_from_ratio_ceil,
+    q64_from_ratio_floor, q64_mul_ceil, q64_mul_floor,
+};
+
+fuzz_target!(|input: (u64, u64, u64)| {
+    let (a, b, d) = input;
+
+    let floor = mul_div_floor(a, b, d);
+    let ceil = mul_div_ceil(a, b, d);
+    if d == 0 {
+        assert!(floor.is_none() && ceil.is_none());
+        return;
+    }
+    let exact = a as u128 * b as u128;
+    assert_eq!(floor, u64::try_from(exact / d as u128).ok());
+    assert_eq!(ceil, u64::try_from(exact.div_ceil(d as u128)).ok());
+
+    if let (Some(ratio_floor), Some(ratio_ceil)) = (q64_from_ratio_floor(b, d), q64_from_ratio_ceil(b, d)) {
+        assert!(ratio_floor <= ratio_ceil);
+
+        // Rounding down never pays more than the exact product, rounding up never less
+        if let (Some(paid), Some(exact)) = (q64_mul_floor(a, ratio_floor), floor) {
+            assert!(paid <= exact);
+        }
+        if let (Some(owed), Some(exact)) = (q64_mul_ceil(a, ratio_ceil), ceil) {
+            assert!(owed >= exact);
+        }
+        if ratio_floor > 0 {
+            if let (Some(down), Some(up)) = (q64_div_floor(a, ratio_floor), q64_div_ceil(a, ratio_floor)) {
+                assert!(down <= up);
+            }
+        }
+    }
+});
@@ -0,0 +1,66 @@
+//! Fuzz the yield, accrual, tier and decay calculations for panics and broken bounds
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use shaw_vault::math::yield_for_days;
+use shaw_vault::rewards::{
+    calculate_merchant_tier, decay_metric, dynamic_yield_bps, harvest_for_days,
+    volume_bonus_bps, whole_days_between, BASE_YIELD_BPS, TIER_PLATINUM,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    lock_max_apy_bps: u16,
+    lock_boost_bps: u16,
+    profit_share_allocated: u64,
+    total_deposited_value: u64,
+    current_month_volume: u64,
+    extra_volume: u64,
+    performance_fee_bps: u16,
+    from: i64,
+    until: i64,
+    elapsed: i64,
+}
+
+fuzz_target!(|input: Input| {
+    let boost = input.lock_boost_bps;
+    let yield_bps = dynamic_yield_bps(
+        input.lock_max_apy_bps,
+        boost,
+        input.profit_share_allocated,
+        input.total_deposited_value,
+        input.current_month_volume,
+    );
+    assert!(yield_bps <= input.lock_max_apy_bps.saturating_add(boost));
+    assert!(yield_bps >= BASE_YIELD_BPS.min(input.lock_max_apy_bps).saturating_add(boost));
+
+    let more_volume = dynamic_yield_bps(
+        input.lock_max_apy_bps,
+        boost,
+        input.profit_share_allocated,
+        input.total_deposited_value,
+        input.current_month_volume.saturating_add(input.extra_volume),
+    );
+    assert!(more_volume >= yield_bps);
+    assert!(volume_bonus_bps(input.current_month_volume, input.lock_max_apy_bps) <= input.lock_max_apy_bps);
+
+    let days = whole_days_between(input.from, input.until);
+    assert!(days >= 0);
+    if let Some(harvest) = harvest_for_days(
+        input.total_deposited_value,
+        yield_bps,
+        input.performance_fee_bps.min(10_000),
+        days as u64,
+    ) {
+        assert_eq!(harvest.gross, yield_for_days(input.total_deposited_value, yield_bps as u64, days as u64).unwrap());
+        assert_eq!(harvest.performance_fee + harvest.net, harvest.gross);
+    }
+
+    let tier = calculate_merchant_tier(input.current_month_volume, input.from, input.until);
+    assert!(tier <= TIER_PLATINUM);
+    assert!(calculate_merchant_tier(input.current_month_volume.saturating_add(input.extra_volume), input.from, input.until) >= tier);
+
+    let decayed = decay_metric(input.current_month_volume, input.elapsed);
+    assert!(decayed <= input.current_month_volume);
+});
@@ -0,0 +1,149 @@
+//! Automation thread interface (Clockwork v2)
+//!
+//! `create_automation_thread` sets up a keeper thread on a thread program that
+//! speaks the Clockwork v2 interface (`thread_create` / `thread_delete`), so the
+//! permissionless cranks run on a cron schedule without an operator's keeper.
+//! Each thread is owned by a vault PDA ([THREAD_AUTHORITY_SEED, vault, crank])
+//! and holds one instruction, `plan_automation_crank`, whose `ThreadResponse`
+//! hands the thread the crank to run next, with the thread as its keeper:
+//!
+//! - metrics refresh and reward sweeps plan the merchant's crank once it is due
+//! - withdrawal fulfillment looks up the head of the queue over a few planning
+//!   steps, then fulfills that ticket (one ticket per run)
+//!
+//! Only the types and accounts the vault uses are mirrored here.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+
+/// Clockwork v2 thread program
+pub const THREAD_PROGRAM_ID: Pubkey = pubkey!("CLoCKyJ6DXBJqqu2VWx9RLbgnwwR6BMHHuyasVmfMzBh");
+
+/// Thread address seed under the thread program: [THREAD_SEED, authority, id]
+pub const THREAD_SEED: &[u8] = b"thread";
+
+/// Owner of a vault's threads for one crank: [THREAD_AUTHORITY_SEED, vault, crank]
+pub const THREAD_AUTHORITY_SEED: &[u8] = b"thread_authority";
+
+/// Longest cron schedule a thread is created with
+pub const MAX_CRON_SCHEDULE_LEN: usize = 64;
+
+/// First 8 bytes of sha256("global:thread_create")
+const THREAD_CREATE_DISCRIMINATOR: [u8; 8] = [54, 1, 238, 224, 71, 244, 252, 173];
+
+/// First 8 bytes of sha256("global:thread_delete")
+const THREAD_DELETE_DISCRIMINATOR: [u8; 8] = [146, 6, 95, 17, 35, 98, 44, 140];
+
+/// Permissionless crank an automation thread runs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutomationCrank {
+    /// `sweep_rewards` for one merchant
+    SweepRewards,
+    /// `refresh_merchant_metrics` for one merchant
+    RefreshMetrics,
+    /// `fulfill_withdrawals` for the tickets of one asset
+    FulfillWithdrawals,
+}
+
+impl AutomationCrank {
+    /// Runs for a single merchant (the others take one thread per asset)
+    pub fn per_merchant(&self) -> bool {
+        !matches!(self, AutomationCrank::FulfillWithdrawals)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SerializableAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// An instruction as a thread stores it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SerializableInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<SerializableAccount>,
+    pub data: Vec<u8>,
+}
+
+impl From<Instruction> for SerializableInstruction {
+    fn from(ix: Instruction) -> Self {
+        SerializableInstruction {
+            program_id: ix.program_id,
+            accounts: ix
+                .accounts
+                .into_iter()
+                .map(|meta| SerializableAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data,
+        }
+    }
+}
+
+/// When a thread runs (variants in the thread program's order)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Account { address: Pubkey, offset: u64, size: u64 },
+    Cron { schedule: String, skippable: bool },
+    Now,
+    Slot { slot: u64 },
+    Epoch { epoch: u64 },
+    Timestamp { unix_ts: i64 },
+}
+
+/// Return data a thread reads after each instruction it runs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThreadResponse {
+    pub close_to: Option<Pubkey>,
+    /// Instruction to run next instead of the thread's own
+    pub dynamic_instruction: Option<SerializableInstruction>,
+    pub trigger: Option<Trigger>,
+}
+
+/// Thread `authority` owns under `id`
+pub fn thread(authority: &Pubkey, id: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[THREAD_SEED, authority.as_ref(), id], &THREAD_PROGRAM_ID).0
+}
+
+/// `thread_create`: `payer` funds the thread with rent plus `amount` lamports for fees
+pub fn thread_create(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    id: Vec<u8>,
+    amount: u64,
+    instructions: Vec<SerializableInstruction>,
+    trigger: Trigger,
+) -> Result<Instruction> {
+    let mut data = THREAD_CREATE_DISCRIMINATOR.to_vec();
+    (amount, &id, instructions, trigger).serialize(&mut data)?;
+
+    Ok(Instruction {
+        program_id: THREAD_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new(thread(authority, &id), false),
+        ],
+        data,
+    })
+}
+
+/// `thread_delete`: closes the thread, its lamports going to `close_to`
+pub fn thread_delete(authority: &Pubkey, close_to: &Pubkey, thread: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: THREAD_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*close_to, false),
+            AccountMeta::new(*thread, false),
+        ],
+        data: THREAD_DELETE_DISCRIMINATOR.to_vec(),
+    }
+}
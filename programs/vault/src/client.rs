@@ -0,0 +1,3134 @@
+//! Rust client for the Shaw 402 vault program
+//!
+//! PDA derivation, one instruction builder per program instruction and account
+//! fetch/decode helpers over `solana-client`. Enabled with the `client` feature.
+//!
+//! Builders take the vault address (see `pda::vault`) and the wallets involved;
+//! every program-derived account is filled in. Token accounts are assumed to be
+//! associated token accounts, including the vault's own (owned by the vault PDA),
+//! and SOL deposits are held by the vault PDA itself.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::address_lookup_table::{self, state::AddressLookupTable, AddressLookupTableAccount};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::{v0, CompileError};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ZeroCopy};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::{token, token_2022};
+use solana_client::rpc_client::RpcClient;
+
+use crate::automation::{self, AutomationCrank};
+use crate::{
+    accounts, instruction, lending, vault_label_seed, AccrualSchedule, AuditLog, ConfigChange, ConfigProposal,
+    DepositType, DisputeStatus, EpochRecord, HarvestSource, LendingStrategy, LockPeriod, LockTerm, MerchantDeposit,
+    MerchantMetrics, MerchantProfile, MonthlyStatement, OrderInput, OrderReceipt, PaymentHold, PayoutConfig,
+    PayoutSplit, RecoveryConfig, SpendingGrant, StakeStrategy, SupportedMint, Vault, WithdrawalWhitelist,
+    YieldHistory, VAULT_LABEL_LEN,
+};
+
+pub use crate::pda;
+
+// ============================================================================
+// Instruction Builders
+// ============================================================================
+
+/// Where a merchant payout out of the vault goes
+pub struct Payout<'a> {
+    /// The profile's current payout wallet, or the holder of the deposit's position NFT
+    pub payout_wallet: Pubkey,
+    /// Mint of an SPL deposit (None for SOL)
+    /// The native mint pays a SOL deposit as wSOL into the payout wallet's
+    /// associated token account; append `sync_native` on it to the transaction.
+    /// Withdrawals create the payout wallet's associated token account if missing.
+    pub mint: Option<Pubkey>,
+    /// Wallets of the active payout splits, in `PayoutConfig` order (none for
+    /// deposits with a position NFT)
+    pub split_wallets: &'a [Pubkey],
+    /// The deposit was made with `deposit_for` (withdrawals then pass its sponsorship)
+    pub sponsored: bool,
+    /// Operator's share token account, for a tokenized deposit (its shares are burned)
+    pub share_token_account: Option<Pubkey>,
+}
+
+impl Payout<'_> {
+    /// Mint of an SPL deposit (None for SOL, wrapped or not)
+    fn spl_mint(&self) -> Option<Pubkey> {
+        self.mint.filter(|mint| *mint != token::spl_token::native_mint::ID)
+    }
+
+    /// Asset of the deposit being paid out
+    fn deposit_token(&self) -> DepositType {
+        match self.spl_mint() {
+            Some(_) => DepositType::SplToken,
+            None => DepositType::Sol,
+        }
+    }
+
+    /// Split recipients: the wallet itself for SOL, its token account for SPL
+    fn split_recipients(&self) -> Vec<AccountMeta> {
+        self.split_wallets
+            .iter()
+            .map(|wallet| match self.spl_mint() {
+                Some(mint) => AccountMeta::new(get_associated_token_address(wallet, &mint), false),
+                None => AccountMeta::new(*wallet, false),
+            })
+            .collect()
+    }
+}
+
+/// A queued withdrawal to fulfill in `fulfill_withdrawals`
+pub struct QueuedWithdrawal<'a> {
+    /// Ticket id (position in the vault's withdrawal queue)
+    pub id: u64,
+    pub merchant: Pubkey,
+    pub payout: Payout<'a>,
+}
+
+fn build(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+fn build_with_remaining(
+    accounts: impl ToAccountMetas,
+    args: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut ix = build(accounts, args);
+    ix.accounts.extend(remaining_accounts);
+    ix
+}
+
+/// Token program, only needed for SPL payouts
+fn token_program_for(mint: Option<Pubkey>) -> Option<Pubkey> {
+    mint.map(|_| token::ID)
+}
+
+/// `label` of all zeros creates the authority's default vault (`pda::vault`)
+pub fn initialize(authority: &Pubkey, label: [u8; VAULT_LABEL_LEN]) -> Instruction {
+    let (vault, bump) = Pubkey::find_program_address(
+        &[crate::VAULT_SEED, authority.as_ref(), vault_label_seed(&label)],
+        &crate::ID,
+    );
+    build(
+        accounts::Initialize {
+            vault,
+            audit_log: pda::audit_log(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::Initialize { bump, label },
+    )
+}
+
+/// `referrer` records a referral relationship along with the first deposit
+pub fn deposit_sol(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    amount: u64,
+    lock_period: LockPeriod,
+    lock_term: Option<LockTerm>,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::DepositSol {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            vault_sol_account: *vault,
+            merchant: *merchant,
+            system_program: system_program::ID,
+            referral_code: referrer.map(|referrer| pda::referral_code(vault, &referrer)),
+            referral: referrer.map(|_| pda::referral(vault, merchant)),
+            kyc_attestation: pda::kyc_attestation(vault, merchant),
+            supported_mint: pda::supported_mint(vault, &token::spl_token::native_mint::ID),
+            recovery_config: pda::recovery_config(vault, merchant),
+        },
+        instruction::DepositSol { amount, lock_period, lock_term },
+    )
+}
+
+/// `referrer` records a referral relationship along with the first deposit
+pub fn deposit_token(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    lock_period: LockPeriod,
+    lock_term: Option<LockTerm>,
+    referrer: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::DepositTokenAccounts {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            merchant_token_account: get_associated_token_address(merchant, mint),
+            vault_token_account: get_associated_token_address(vault, mint),
+            mint: *mint,
+            merchant: *merchant,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            associated_token_program: associated_token::ID,
+            referral_code: referrer.map(|referrer| pda::referral_code(vault, &referrer)),
+            referral: referrer.map(|_| pda::referral(vault, merchant)),
+            kyc_attestation: pda::kyc_attestation(vault, merchant),
+            supported_mint: pda::supported_mint(vault, mint),
+            recovery_config: pda::recovery_config(vault, merchant),
+        },
+        instruction::DepositToken { amount, lock_period, lock_term },
+    )
+}
+
+/// Deposit `amount` wSOL from the merchant's associated wSOL account as a SOL position
+pub fn deposit_wsol(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    amount: u64,
+    lock_period: LockPeriod,
+    lock_term: Option<LockTerm>,
+) -> Instruction {
+    let native_mint = token::spl_token::native_mint::ID;
+    build(
+        accounts::DepositWsol {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            vault_sol_account: *vault,
+            merchant_wsol_account: get_associated_token_address(merchant, &native_mint),
+            wsol_unwrap_account: pda::wsol_unwrap(vault, merchant),
+            native_mint,
+            merchant: *merchant,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            kyc_attestation: pda::kyc_attestation(vault, merchant),
+            supported_mint: pda::supported_mint(vault, &native_mint),
+            recovery_config: pda::recovery_config(vault, merchant),
+        },
+        instruction::DepositWsol { amount, lock_period, lock_term },
+    )
+}
+
+/// Third party funding a merchant's deposit
+pub struct Sponsor {
+    pub wallet: Pubkey,
+    /// Mint of an SPL deposit (None for SOL)
+    pub mint: Option<Pubkey>,
+    /// Share of the merchant's rewards owed to the sponsor (basis points)
+    pub share_bps: u16,
+}
+
+/// Signed by both the sponsor and `merchant`
+pub fn deposit_for(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    sponsor: &Sponsor,
+    amount: u64,
+    lock_period: LockPeriod,
+    lock_term: Option<LockTerm>,
+) -> Instruction {
+    build(
+        accounts::DepositFor {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            sponsorship: pda::sponsorship(vault, merchant),
+            vault_sol_account: *vault,
+            sponsor_token_account: sponsor
+                .mint
+                .map(|mint| get_associated_token_address(&sponsor.wallet, &mint)),
+            mint: sponsor.mint,
+            vault_token_account: sponsor.mint.map(|mint| get_associated_token_address(vault, &mint)),
+            sponsor: sponsor.wallet,
+            merchant: *merchant,
+            token_program: token_program_for(sponsor.mint),
+            system_program: system_program::ID,
+            associated_token_program: sponsor.mint.map(|_| associated_token::ID),
+            kyc_attestation: pda::kyc_attestation(vault, merchant),
+            sponsor_kyc_attestation: pda::kyc_attestation(vault, &sponsor.wallet),
+            supported_mint: pda::supported_mint(vault, &sponsor.mint.unwrap_or(token::spl_token::native_mint::ID)),
+            recovery_config: pda::recovery_config(vault, merchant),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::DepositFor { amount, lock_period, lock_term, sponsor_share_bps: sponsor.share_bps },
+    )
+}
+
+fn withdraw_accounts(vault: &Pubkey, merchant: &Pubkey, operator: &Pubkey, payout: &Payout) -> accounts::Withdraw {
+    accounts::Withdraw {
+        vault: *vault,
+        merchant_deposit: pda::merchant_deposit(vault, merchant),
+        merchant_profile: pda::merchant_profile(vault, merchant),
+        vault_sol_account: *vault,
+        payout_wallet: payout.payout_wallet,
+        payout_token_account: payout
+            .mint
+            .map(|mint| get_associated_token_address(&payout.payout_wallet, &mint)),
+        payout_config: pda::payout_config(vault, merchant),
+        vault_token_account: payout.spl_mint().map(|mint| get_associated_token_address(vault, &mint)),
+        merchant: *merchant,
+        operator: *operator,
+        token_program: token_program_for(payout.mint),
+        system_program: system_program::ID,
+        sponsorship: payout.sponsored.then(|| pda::sponsorship(vault, merchant)),
+        mint: payout.mint,
+        associated_token_program: payout.mint.map(|_| associated_token::ID),
+        guardian: None,
+        share_mint: payout.share_token_account.map(|_| pda::share_mint(vault, &payout.deposit_token())),
+        share_token_account: payout.share_token_account,
+        share_token_program: payout.share_token_account.map(|_| token_2022::ID),
+        supported_mint: pda::supported_mint(vault, &payout.spl_mint().unwrap_or(token::spl_token::native_mint::ID)),
+        recovery_config: pda::recovery_config(vault, merchant),
+        event_authority: pda::event_authority(),
+        program: crate::ID,
+    }
+}
+
+/// `operator` is the merchant or their withdraw delegate
+pub fn withdraw(vault: &Pubkey, merchant: &Pubkey, operator: &Pubkey, payout: &Payout) -> Instruction {
+    build_with_remaining(
+        withdraw_accounts(vault, merchant, operator, payout),
+        instruction::Withdraw {},
+        payout.split_recipients(),
+    )
+}
+
+/// `withdraw` co-signed by the guardian, for a payout flagged by the reward circuit breaker
+pub fn withdraw_cosigned(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    operator: &Pubkey,
+    payout: &Payout,
+    guardian: &Pubkey,
+) -> Instruction {
+    build_with_remaining(
+        accounts::Withdraw { guardian: Some(*guardian), ..withdraw_accounts(vault, merchant, operator, payout) },
+        instruction::Withdraw {},
+        payout.split_recipients(),
+    )
+}
+
+/// `operator` is the merchant or their withdraw delegate
+pub fn withdraw_early(vault: &Pubkey, merchant: &Pubkey, operator: &Pubkey, payout: &Payout) -> Instruction {
+    build_with_remaining(
+        withdraw_accounts(vault, merchant, operator, payout),
+        instruction::WithdrawEarly {},
+        payout.split_recipients(),
+    )
+}
+
+/// `operator` is the merchant or their withdraw delegate
+pub fn emergency_withdraw(vault: &Pubkey, merchant: &Pubkey, operator: &Pubkey, payout: &Payout) -> Instruction {
+    build_with_remaining(
+        withdraw_accounts(vault, merchant, operator, payout),
+        instruction::EmergencyWithdraw {},
+        payout.split_recipients(),
+    )
+}
+
+/// `mint` is the deposit's mint (the native mint for SOL)
+pub fn compound_rewards(vault: &Pubkey, merchant: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::CompoundRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant: *merchant,
+            recovery_config: pda::recovery_config(vault, merchant),
+            supported_mint: pda::supported_mint(vault, mint),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CompoundRewards {},
+    )
+}
+
+pub fn sweep_rewards(vault: &Pubkey, merchant: &Pubkey, keeper: &Pubkey, payout: &Payout) -> Instruction {
+    build_with_remaining(
+        accounts::SweepRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            payout_config: pda::payout_config(vault, merchant),
+            vault_sol_account: *vault,
+            payout_wallet: payout.payout_wallet,
+            payout_token_account: payout
+                .mint
+                .map(|mint| get_associated_token_address(&payout.payout_wallet, &mint)),
+            vault_token_account: payout.spl_mint().map(|mint| get_associated_token_address(vault, &mint)),
+            merchant: *merchant,
+            keeper: *keeper,
+            token_program: token_program_for(payout.spl_mint()),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SweepRewards {},
+        payout.split_recipients(),
+    )
+}
+
+pub fn stream_rewards(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::StreamRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant: *merchant,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::StreamRewards {},
+    )
+}
+
+pub fn release_streamed_rewards(vault: &Pubkey, merchant: &Pubkey, keeper: &Pubkey, payout: &Payout) -> Instruction {
+    build_with_remaining(
+        accounts::ReleaseStreamedRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            payout_config: pda::payout_config(vault, merchant),
+            vault_sol_account: *vault,
+            payout_wallet: payout.payout_wallet,
+            payout_token_account: payout
+                .mint
+                .map(|mint| get_associated_token_address(&payout.payout_wallet, &mint)),
+            vault_token_account: payout.spl_mint().map(|mint| get_associated_token_address(vault, &mint)),
+            merchant: *merchant,
+            keeper: *keeper,
+            token_program: token_program_for(payout.spl_mint()),
+        },
+        instruction::ReleaseStreamedRewards {},
+        payout.split_recipients(),
+    )
+}
+
+/// `ticket_id` is the vault's current `withdraw_queue_tail`
+pub fn request_withdrawal(vault: &Pubkey, merchant: &Pubkey, ticket_id: u64) -> Instruction {
+    build(
+        accounts::RequestWithdrawal {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            withdrawal_ticket: pda::withdrawal_ticket(vault, ticket_id),
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::RequestWithdrawal {},
+    )
+}
+
+/// `withdrawals` must start at the vault's `withdraw_queue_head`, in queue order
+pub fn fulfill_withdrawals(
+    vault: &Pubkey,
+    keeper: &Pubkey,
+    mint: Option<Pubkey>,
+    withdrawals: &[QueuedWithdrawal],
+) -> Instruction {
+    let mut remaining_accounts = Vec::new();
+    for withdrawal in withdrawals {
+        let payout = &withdrawal.payout;
+        remaining_accounts.extend([
+            AccountMeta::new(pda::withdrawal_ticket(vault, withdrawal.id), false),
+            AccountMeta::new(pda::merchant_deposit(vault, &withdrawal.merchant), false),
+            AccountMeta::new(pda::merchant_profile(vault, &withdrawal.merchant), false),
+            AccountMeta::new(withdrawal.merchant, false),
+            AccountMeta::new(payout.payout_wallet, false),
+            AccountMeta::new(
+                payout
+                    .mint
+                    .map(|mint| get_associated_token_address(&payout.payout_wallet, &mint))
+                    .unwrap_or(payout.payout_wallet),
+                false,
+            ),
+            AccountMeta::new_readonly(pda::payout_config(vault, &withdrawal.merchant), false),
+        ]);
+        remaining_accounts.extend(payout.split_recipients());
+    }
+
+    build_with_remaining(
+        accounts::FulfillWithdrawals {
+            vault: *vault,
+            vault_sol_account: *vault,
+            vault_token_account: mint.map(|mint| get_associated_token_address(vault, &mint)),
+            keeper: *keeper,
+            token_program: token_program_for(mint),
+            system_program: system_program::ID,
+            sol_supported_mint: pda::supported_mint(vault, &token::spl_token::native_mint::ID),
+            token_supported_mint: pda::supported_mint(vault, &mint.unwrap_or(token::spl_token::native_mint::ID)),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::FulfillWithdrawals {},
+        remaining_accounts,
+    )
+}
+
+pub fn cancel_withdrawal_request(vault: &Pubkey, merchant: &Pubkey, ticket_id: u64) -> Instruction {
+    build(
+        accounts::CancelWithdrawalRequest {
+            vault: *vault,
+            withdrawal_ticket: pda::withdrawal_ticket(vault, ticket_id),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant: *merchant,
+        },
+        instruction::CancelWithdrawalRequest {},
+    )
+}
+
+fn update_vault_config_accounts(vault: &Pubkey, authority: &Pubkey) -> accounts::UpdateVaultConfig {
+    accounts::UpdateVaultConfig {
+        vault: *vault,
+        audit_log: pda::audit_log(vault),
+        authority: *authority,
+        event_authority: pda::event_authority(),
+        program: crate::ID,
+    }
+}
+
+pub fn set_withdrawal_limits(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    epoch_duration: i64,
+    cap_amount: u64,
+    cap_tvl_bps: u16,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetWithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps },
+    )
+}
+
+pub fn set_order_rate_limits(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    max_orders_per_hour: u32,
+    max_daily_order_volume: u64,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetOrderRateLimits { max_orders_per_hour, max_daily_order_volume },
+    )
+}
+
+pub fn set_repeat_purchase_dampening(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    window: i64,
+    credit_bps: [u16; 3],
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetRepeatPurchaseDampening { window, credit_bps },
+    )
+}
+
+pub fn set_tier_downgrade_grace(vault: &Pubkey, authority: &Pubkey, grace_period: i64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetTierDowngradeGrace { grace_period },
+    )
+}
+
+pub fn set_platform_fees(vault: &Pubkey, authority: &Pubkey, platform_fee_bps_by_tier: [u16; 4]) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetPlatformFees { platform_fee_bps_by_tier },
+    )
+}
+
+pub fn set_reward_circuit_breaker(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    payout_cap: u64,
+    anomaly_multiple_bps: u32,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetRewardCircuitBreaker { payout_cap, anomaly_multiple_bps },
+    )
+}
+
+pub fn set_holding_period(vault: &Pubkey, authority: &Pubkey, min_holding_slots: u64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetHoldingPeriod { min_holding_slots },
+    )
+}
+
+pub fn set_stake_strategy(vault: &Pubkey, authority: &Pubkey, strategy: StakeStrategy) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetStakeStrategy { strategy },
+    )
+}
+
+pub fn set_accrual_schedule(vault: &Pubkey, authority: &Pubkey, schedule: AccrualSchedule) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetAccrualSchedule { schedule },
+    )
+}
+
+pub fn set_reward_stream_duration(vault: &Pubkey, authority: &Pubkey, duration: i64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetRewardStreamDuration { duration },
+    )
+}
+
+pub fn set_share_transfer_restriction(vault: &Pubkey, authority: &Pubkey, restricted: bool) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetShareTransferRestriction { restricted },
+    )
+}
+
+pub fn set_kyc_policy(vault: &Pubkey, authority: &Pubkey, required: bool, verifier: Pubkey) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetKycPolicy { required, verifier },
+    )
+}
+
+pub fn set_notifier(vault: &Pubkey, authority: &Pubkey, program: Pubkey) -> Instruction {
+    build(update_vault_config_accounts(vault, authority), instruction::SetNotifier { program })
+}
+
+/// Accounts to append to a deposit or settlement while the vault has a notifier:
+/// the notifier program, the notify authority, then `accounts` for the notifier
+pub fn notifier_accounts(vault: &Pubkey, notifier_program: &Pubkey, accounts: &[AccountMeta]) -> Vec<AccountMeta> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*notifier_program, false),
+        AccountMeta::new_readonly(pda::notify_authority(vault), false),
+    ];
+    metas.extend_from_slice(accounts);
+    metas
+}
+
+pub fn set_liquidity_buffer(vault: &Pubkey, authority: &Pubkey, liquidity_buffer_bps: u16) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetLiquidityBuffer { liquidity_buffer_bps },
+    )
+}
+
+pub fn set_min_order_amount(vault: &Pubkey, authority: &Pubkey, min_order_usd: u64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetMinOrderAmount { min_order_usd },
+    )
+}
+
+pub fn set_settlement_guard(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    guard_before: bool,
+    guard_after: bool,
+    blocked_token_ixs: u32,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetSettlementGuard { guard_before, guard_after, blocked_token_ixs },
+    )
+}
+
+pub fn set_emergency_mode(vault: &Pubkey, guardian: &Pubkey, enabled: bool) -> Instruction {
+    build(
+        accounts::SetEmergencyMode {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            guardian: *guardian,
+        },
+        instruction::SetEmergencyMode { enabled },
+    )
+}
+
+pub fn set_guardian(vault: &Pubkey, authority: &Pubkey, new_guardian: Pubkey) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetGuardian { new_guardian },
+    )
+}
+
+/// Fields left `None` in `config` are unchanged
+pub fn update_vault_config(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    config: instruction::UpdateVaultConfig,
+) -> Instruction {
+    build(update_vault_config_accounts(vault, authority), config)
+}
+
+pub fn set_lock_terms(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    lock_boost_schedule: [u16; 4],
+    early_exit_penalty_bps: u16,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetLockTerms { lock_boost_schedule, early_exit_penalty_bps },
+    )
+}
+
+pub fn set_config_timelock(vault: &Pubkey, authority: &Pubkey, delay: i64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetConfigTimelock { delay },
+    )
+}
+
+/// Apply `change` directly through its setter (only while no timelock is set)
+pub fn set_config(vault: &Pubkey, authority: &Pubkey, change: ConfigChange) -> Instruction {
+    match change {
+        ConfigChange::VaultConfig {
+            min_deposit_sol,
+            min_deposit_token,
+            staking_enabled,
+            cashback_bps,
+            referral_fee_bps,
+        } => update_vault_config(
+            vault,
+            authority,
+            instruction::UpdateVaultConfig {
+                min_deposit_sol,
+                min_deposit_token,
+                staking_enabled,
+                cashback_bps,
+                referral_fee_bps,
+            },
+        ),
+        ConfigChange::WithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps } => {
+            set_withdrawal_limits(vault, authority, epoch_duration, cap_amount, cap_tvl_bps)
+        }
+        ConfigChange::LockTerms { lock_boost_schedule, early_exit_penalty_bps } => {
+            set_lock_terms(vault, authority, lock_boost_schedule, early_exit_penalty_bps)
+        }
+        ConfigChange::AgentBond { agent_bond_lamports } => {
+            set_agent_bond(vault, authority, agent_bond_lamports)
+        }
+        ConfigChange::InsuranceFee { insurance_fee_bps } => {
+            set_insurance_fee(vault, authority, insurance_fee_bps)
+        }
+        ConfigChange::PerformanceFee { performance_fee_bps, treasury } => {
+            set_performance_fee(vault, authority, performance_fee_bps, treasury)
+        }
+        ConfigChange::Guardian { new_guardian } => set_guardian(vault, authority, new_guardian),
+        ConfigChange::ConfigTimelock { delay } => set_config_timelock(vault, authority, delay),
+        ConfigChange::OrderRateLimits { max_orders_per_hour, max_daily_order_volume } => {
+            set_order_rate_limits(vault, authority, max_orders_per_hour, max_daily_order_volume)
+        }
+        ConfigChange::RepeatPurchaseDampening { window, credit_bps } => {
+            set_repeat_purchase_dampening(vault, authority, window, credit_bps)
+        }
+        ConfigChange::TierDowngradeGrace { grace_period } => {
+            set_tier_downgrade_grace(vault, authority, grace_period)
+        }
+        ConfigChange::PlatformFees { platform_fee_bps_by_tier } => {
+            set_platform_fees(vault, authority, platform_fee_bps_by_tier)
+        }
+        ConfigChange::RewardCircuitBreaker { payout_cap, anomaly_multiple_bps } => {
+            set_reward_circuit_breaker(vault, authority, payout_cap, anomaly_multiple_bps)
+        }
+        ConfigChange::HoldingPeriod { min_holding_slots } => {
+            set_holding_period(vault, authority, min_holding_slots)
+        }
+        ConfigChange::MinOrderAmount { min_order_usd } => {
+            set_min_order_amount(vault, authority, min_order_usd)
+        }
+        ConfigChange::StakeStrategy { strategy } => set_stake_strategy(vault, authority, strategy),
+        ConfigChange::LiquidityBuffer { liquidity_buffer_bps } => {
+            set_liquidity_buffer(vault, authority, liquidity_buffer_bps)
+        }
+        ConfigChange::AccrualSchedule { schedule } => set_accrual_schedule(vault, authority, schedule),
+        ConfigChange::RewardStreamDuration { duration } => set_reward_stream_duration(vault, authority, duration),
+        ConfigChange::ShareTransferRestriction { restricted } => {
+            set_share_transfer_restriction(vault, authority, restricted)
+        }
+        ConfigChange::KycPolicy { required, verifier } => set_kyc_policy(vault, authority, required, verifier),
+        ConfigChange::Notifier { program } => set_notifier(vault, authority, program),
+        ConfigChange::SettlementGuard { guard_before, guard_after, blocked_token_ixs } => {
+            set_settlement_guard(vault, authority, guard_before, guard_after, blocked_token_ixs)
+        }
+    }
+}
+
+pub fn propose_config_change(vault: &Pubkey, authority: &Pubkey, change: ConfigChange) -> Instruction {
+    build(
+        accounts::ProposeConfigChange {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            config_proposal: pda::config_proposal(vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ProposeConfigChange { change },
+    )
+}
+
+/// `proposer` is refunded the proposal's rent (see `ConfigProposal::proposer`)
+pub fn execute_config_change(vault: &Pubkey, authority: &Pubkey, proposer: &Pubkey) -> Instruction {
+    build(
+        accounts::ExecuteConfigChange {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            config_proposal: pda::config_proposal(vault),
+            proposer: *proposer,
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ExecuteConfigChange {},
+    )
+}
+
+/// `canceller` is the guardian or the authority
+pub fn cancel_config_change(vault: &Pubkey, canceller: &Pubkey, proposer: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelConfigChange {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            config_proposal: pda::config_proposal(vault),
+            proposer: *proposer,
+            canceller: *canceller,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CancelConfigChange {},
+    )
+}
+
+pub fn calculate_rewards(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::CalculateRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            merchant: *merchant,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CalculateRewards {},
+    )
+}
+
+/// `buyer_has_rewards` credits cashback to a buyer who opened a rewards account;
+/// `store_label` attributes the order to a sub-merchant
+pub fn record_order(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    agent: &Pubkey,
+    order_amount_usd: u64,
+    buyer_wallet: Pubkey,
+    buyer_has_rewards: bool,
+    store_label: Option<[u8; 32]>,
+) -> Instruction {
+    build(
+        accounts::RecordOrder {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            agent: *agent,
+            merchant: *merchant,
+            buyer_rewards: buyer_has_rewards.then(|| pda::buyer_rewards(vault, &buyer_wallet)),
+            sub_merchant: store_label.map(|label| pda::sub_merchant(vault, merchant, &label)),
+        },
+        instruction::RecordOrder { order_amount_usd, buyer_wallet },
+    )
+}
+
+pub fn get_merchant_tier(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::GetMerchantTier {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            merchant: *merchant,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::GetMerchantTier {},
+    )
+}
+
+/// `referrer` shares the fee with the merchant's referrer; `with_insurance_fund`
+/// routes the insurance fee (the fund must be initialized)
+pub fn record_platform_profit(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    platform: &Pubkey,
+    platform_profit_amount: u64,
+    referrer: Option<Pubkey>,
+    with_insurance_fund: bool,
+) -> Instruction {
+    build(
+        accounts::RecordPlatformProfit {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            platform: *platform,
+            merchant: *merchant,
+            referral: referrer.map(|_| pda::referral(vault, merchant)),
+            referral_code: referrer.map(|referrer| pda::referral_code(vault, &referrer)),
+            insurance_fund: with_insurance_fund.then(|| pda::insurance_fund(vault)),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::RecordPlatformProfit { platform_profit_amount },
+    )
+}
+
+pub fn register_merchant(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    metadata_uri_hash: [u8; 32],
+    payout_wallet: Pubkey,
+) -> Instruction {
+    build(
+        accounts::RegisterMerchant {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterMerchant { metadata_uri_hash, payout_wallet },
+    )
+}
+
+pub fn create_sub_merchant(vault: &Pubkey, merchant: &Pubkey, label_hash: [u8; 32]) -> Instruction {
+    build(
+        accounts::CreateSubMerchant {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            sub_merchant: pda::sub_merchant(vault, merchant, &label_hash),
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::CreateSubMerchant { label_hash },
+    )
+}
+
+pub fn set_payout_wallet(vault: &Pubkey, merchant: &Pubkey, new_payout_wallet: Pubkey) -> Instruction {
+    build(
+        accounts::SetPayoutWallet {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            merchant: *merchant,
+            withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+        },
+        instruction::SetPayoutWallet { new_payout_wallet },
+    )
+}
+
+pub fn set_withdraw_delegate(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    delegate: Pubkey,
+    expiry: i64,
+    max_amount: u64,
+) -> Instruction {
+    build(
+        accounts::SetWithdrawDelegate {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant: *merchant,
+        },
+        instruction::SetWithdrawDelegate { delegate, expiry, max_amount },
+    )
+}
+
+pub fn create_recovery_config(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    beneficiary: Pubkey,
+    inactivity_period: i64,
+) -> Instruction {
+    build(
+        accounts::CreateRecoveryConfig {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            recovery_config: pda::recovery_config(vault, merchant),
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::CreateRecoveryConfig { beneficiary, inactivity_period },
+    )
+}
+
+fn recovery_check_in_accounts(vault: &Pubkey, merchant: &Pubkey) -> accounts::RecoveryCheckIn {
+    accounts::RecoveryCheckIn {
+        vault: *vault,
+        recovery_config: pda::recovery_config(vault, merchant),
+        merchant: *merchant,
+    }
+}
+
+pub fn update_recovery_config(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    beneficiary: Pubkey,
+    inactivity_period: i64,
+) -> Instruction {
+    build(
+        recovery_check_in_accounts(vault, merchant),
+        instruction::UpdateRecoveryConfig { beneficiary, inactivity_period },
+    )
+}
+
+pub fn recovery_check_in(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(recovery_check_in_accounts(vault, merchant), instruction::RecoveryCheckIn {})
+}
+
+pub fn close_recovery_config(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseRecoveryConfig {
+            vault: *vault,
+            recovery_config: pda::recovery_config(vault, merchant),
+            merchant: *merchant,
+        },
+        instruction::CloseRecoveryConfig {},
+    )
+}
+
+/// Signed by the beneficiary of `merchant`'s recovery config
+pub fn start_recovery(vault: &Pubkey, merchant: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+    build(
+        accounts::StartRecovery {
+            vault: *vault,
+            recovery_config: pda::recovery_config(vault, merchant),
+            beneficiary: *beneficiary,
+        },
+        instruction::StartRecovery {},
+    )
+}
+
+/// Signed by the beneficiary of `merchant`'s recovery config
+pub fn complete_recovery(vault: &Pubkey, merchant: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+    build(
+        accounts::CompleteRecovery {
+            vault: *vault,
+            recovery_config: pda::recovery_config(vault, merchant),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+            beneficiary: *beneficiary,
+        },
+        instruction::CompleteRecovery {},
+    )
+}
+
+pub fn create_payout_config(vault: &Pubkey, merchant: &Pubkey, splits: Vec<PayoutSplit>) -> Instruction {
+    build(
+        accounts::CreatePayoutConfig {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            payout_config: pda::payout_config(vault, merchant),
+            merchant: *merchant,
+            system_program: system_program::ID,
+            withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+        },
+        instruction::CreatePayoutConfig { splits },
+    )
+}
+
+fn update_payout_config_accounts(vault: &Pubkey, merchant: &Pubkey) -> accounts::UpdatePayoutConfig {
+    accounts::UpdatePayoutConfig {
+        vault: *vault,
+        payout_config: pda::payout_config(vault, merchant),
+        merchant: *merchant,
+        withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+    }
+}
+
+pub fn set_payout_splits(vault: &Pubkey, merchant: &Pubkey, splits: Vec<PayoutSplit>) -> Instruction {
+    build(
+        update_payout_config_accounts(vault, merchant),
+        instruction::SetPayoutSplits { splits },
+    )
+}
+
+pub fn set_auto_sweep(vault: &Pubkey, merchant: &Pubkey, threshold: u64, interval: i64) -> Instruction {
+    build(
+        update_payout_config_accounts(vault, merchant),
+        instruction::SetAutoSweep { threshold, interval },
+    )
+}
+
+pub fn create_withdrawal_whitelist(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateWithdrawalWhitelist {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            payout_config: pda::payout_config(vault, merchant),
+            withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::CreateWithdrawalWhitelist {},
+    )
+}
+
+fn update_withdrawal_whitelist_accounts(vault: &Pubkey, merchant: &Pubkey) -> accounts::UpdateWithdrawalWhitelist {
+    accounts::UpdateWithdrawalWhitelist {
+        vault: *vault,
+        merchant_profile: pda::merchant_profile(vault, merchant),
+        payout_config: pda::payout_config(vault, merchant),
+        withdrawal_whitelist: pda::withdrawal_whitelist(vault, merchant),
+        merchant: *merchant,
+    }
+}
+
+pub fn add_withdrawal_destination(vault: &Pubkey, merchant: &Pubkey, wallet: Pubkey) -> Instruction {
+    build(
+        update_withdrawal_whitelist_accounts(vault, merchant),
+        instruction::AddWithdrawalDestination { wallet },
+    )
+}
+
+pub fn remove_withdrawal_destination(vault: &Pubkey, merchant: &Pubkey, wallet: Pubkey) -> Instruction {
+    build(
+        update_withdrawal_whitelist_accounts(vault, merchant),
+        instruction::RemoveWithdrawalDestination { wallet },
+    )
+}
+
+pub fn register_agent(vault: &Pubkey, merchant: &Pubkey, agent: &Pubkey) -> Instruction {
+    build(
+        accounts::RegisterAgent {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            agent: *agent,
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterAgent {},
+    )
+}
+
+pub fn revoke_agent(vault: &Pubkey, merchant: &Pubkey, agent: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeAgent {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            agent: *agent,
+            merchant: *merchant,
+        },
+        instruction::RevokeAgent {},
+    )
+}
+
+pub fn reclaim_agent_bond(vault: &Pubkey, merchant: &Pubkey, agent: &Pubkey) -> Instruction {
+    build(
+        accounts::ReclaimAgentBond {
+            vault: *vault,
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            agent: *agent,
+            merchant: *merchant,
+        },
+        instruction::ReclaimAgentBond {},
+    )
+}
+
+pub fn slash_agent(
+    vault: &Pubkey,
+    guardian: &Pubkey,
+    merchant: &Pubkey,
+    agent: &Pubkey,
+    bond_destination: Pubkey,
+    evidence_hash: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::SlashAgent {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            bond_destination,
+            guardian: *guardian,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SlashAgent { evidence_hash },
+    )
+}
+
+pub fn set_agent_bond(vault: &Pubkey, authority: &Pubkey, agent_bond_lamports: u64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetAgentBond { agent_bond_lamports },
+    )
+}
+
+pub fn open_buyer_rewards(vault: &Pubkey, buyer: &Pubkey) -> Instruction {
+    build(
+        accounts::OpenBuyerRewards {
+            vault: *vault,
+            buyer_rewards: pda::buyer_rewards(vault, buyer),
+            buyer: *buyer,
+            system_program: system_program::ID,
+        },
+        instruction::OpenBuyerRewards {},
+    )
+}
+
+/// `mint` is the reward reserve's mint
+pub fn initialize_reward_reserve(vault: &Pubkey, authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeRewardReserve {
+            vault: *vault,
+            reward_reserve: pda::reward_reserve(vault),
+            mint: *mint,
+            authority: *authority,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRewardReserve {},
+    )
+}
+
+/// `mint` is the reward reserve's mint
+pub fn claim_cashback(vault: &Pubkey, buyer: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimCashback {
+            vault: *vault,
+            buyer_rewards: pda::buyer_rewards(vault, buyer),
+            reward_reserve: pda::reward_reserve(vault),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            buyer: *buyer,
+            token_program: token::ID,
+        },
+        instruction::ClaimCashback {},
+    )
+}
+
+pub fn create_referral_code(vault: &Pubkey, referrer: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateReferralCode {
+            vault: *vault,
+            referral_code: pda::referral_code(vault, referrer),
+            referrer: *referrer,
+            system_program: system_program::ID,
+        },
+        instruction::CreateReferralCode {},
+    )
+}
+
+/// `mint` is the reward reserve's mint
+pub fn claim_referral_rewards(vault: &Pubkey, referrer: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimReferralRewards {
+            vault: *vault,
+            referral_code: pda::referral_code(vault, referrer),
+            reward_reserve: pda::reward_reserve(vault),
+            referrer_token_account: get_associated_token_address(referrer, mint),
+            referrer: *referrer,
+            token_program: token::ID,
+        },
+        instruction::ClaimReferralRewards {},
+    )
+}
+
+/// `mint` is the sponsored deposit's mint (None for SOL)
+pub fn claim_sponsor_rewards(vault: &Pubkey, merchant: &Pubkey, sponsor: &Pubkey, mint: Option<Pubkey>) -> Instruction {
+    build(
+        accounts::ClaimSponsorRewards {
+            vault: *vault,
+            sponsorship: pda::sponsorship(vault, merchant),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            vault_sol_account: *vault,
+            vault_token_account: mint.map(|mint| get_associated_token_address(vault, &mint)),
+            sponsor_token_account: mint.map(|mint| get_associated_token_address(sponsor, &mint)),
+            sponsor: *sponsor,
+            token_program: token_program_for(mint),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ClaimSponsorRewards {},
+    )
+}
+
+/// Mint a payment is made in, and the price valuing it
+pub struct PricedMint {
+    /// Registered SPL mint paid in
+    pub mint: Pubkey,
+    /// Pyth `PriceUpdateV2` for the mint's price feed
+    pub price_update: Pubkey,
+}
+
+pub fn create_subscription(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    priced: &PricedMint,
+    amount: u64,
+    interval_seconds: i64,
+    max_payments: u32,
+) -> Instruction {
+    let mint = &priced.mint;
+    build(
+        accounts::CreateSubscription {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            subscription: pda::subscription(vault, merchant, buyer),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            supported_mint: pda::supported_mint(vault, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            merchant: *merchant,
+            buyer: *buyer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateSubscription { amount, interval_seconds, max_payments },
+    )
+}
+
+/// Permissionless; anyone may crank a due charge
+pub fn process_subscription(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    priced: &PricedMint,
+    cranker: &Pubkey,
+) -> Instruction {
+    let mint = &priced.mint;
+    build(
+        accounts::ProcessSubscription {
+            vault: *vault,
+            subscription: pda::subscription(vault, merchant, buyer),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            merchant_token_account: get_associated_token_address(merchant, mint),
+            supported_mint: pda::supported_mint(vault, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            cranker: *cranker,
+            token_program: token::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ProcessSubscription {},
+    )
+}
+
+pub fn cancel_subscription(vault: &Pubkey, merchant: &Pubkey, buyer: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelSubscription {
+            vault: *vault,
+            subscription: pda::subscription(vault, merchant, buyer),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            buyer: *buyer,
+            token_program: token::ID,
+        },
+        instruction::CancelSubscription {},
+    )
+}
+
+/// Let `agent` spend from the buyer's associated token account for `mint`
+pub fn create_spending_grant(
+    vault: &Pubkey,
+    buyer: &Pubkey,
+    agent: &Pubkey,
+    mint: &Pubkey,
+    period_cap: u64,
+    period_seconds: i64,
+    allowance: u64,
+) -> Instruction {
+    build(
+        accounts::CreateSpendingGrant {
+            vault: *vault,
+            spending_grant: pda::spending_grant(vault, buyer, agent),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            agent: *agent,
+            buyer: *buyer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateSpendingGrant { period_cap, period_seconds, allowance },
+    )
+}
+
+pub fn revoke_spending_grant(vault: &Pubkey, buyer: &Pubkey, agent: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeSpendingGrant {
+            vault: *vault,
+            spending_grant: pda::spending_grant(vault, buyer, agent),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            buyer: *buyer,
+            token_program: token::ID,
+        },
+        instruction::RevokeSpendingGrant {},
+    )
+}
+
+/// `priced` is the registered mint the invoice is paid in
+pub fn create_invoice(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    priced: &PricedMint,
+    amount: u64,
+    memo_hash: [u8; 32],
+    expiry: i64,
+) -> Instruction {
+    let mint = &priced.mint;
+    build(
+        accounts::CreateInvoice {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            invoice: pda::invoice(vault, merchant, &memo_hash),
+            supported_mint: pda::supported_mint(vault, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            merchant: *merchant,
+            system_program: system_program::ID,
+        },
+        instruction::CreateInvoice { amount, memo_hash, expiry },
+    )
+}
+
+pub fn pay_invoice(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    memo_hash: &[u8; 32],
+    payer: &Pubkey,
+    priced: &PricedMint,
+) -> Instruction {
+    let mint = &priced.mint;
+    build(
+        accounts::PayInvoice {
+            vault: *vault,
+            invoice: pda::invoice(vault, merchant, memo_hash),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            payer_token_account: get_associated_token_address(payer, mint),
+            merchant_token_account: get_associated_token_address(merchant, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            merchant: *merchant,
+            payer: *payer,
+            token_program: token::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::PayInvoice {},
+    )
+}
+
+pub fn cancel_invoice(vault: &Pubkey, merchant: &Pubkey, memo_hash: &[u8; 32]) -> Instruction {
+    build(
+        accounts::CancelInvoice {
+            vault: *vault,
+            invoice: pda::invoice(vault, merchant, memo_hash),
+            merchant: *merchant,
+        },
+        instruction::CancelInvoice {},
+    )
+}
+
+/// Escrow `amount` of `buyer`'s tokens of the priced mint for `merchant` until `expiry`
+pub fn authorize_payment(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    priced: &PricedMint,
+    hold_id: [u8; 32],
+    amount: u64,
+    expiry: i64,
+) -> Instruction {
+    let mint = &priced.mint;
+    let payment_hold = pda::payment_hold(vault, merchant, buyer, &hold_id);
+    build(
+        accounts::AuthorizePayment {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            payment_hold,
+            escrow_token_account: pda::hold_escrow(&payment_hold),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            mint: *mint,
+            supported_mint: pda::supported_mint(vault, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            merchant: *merchant,
+            buyer: *buyer,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::AuthorizePayment { hold_id, amount, expiry },
+    )
+}
+
+/// Capture `amount` of an authorized payment, releasing the rest to the buyer
+pub fn capture_payment(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    priced: &PricedMint,
+    hold_id: &[u8; 32],
+    amount: u64,
+) -> Instruction {
+    let mint = &priced.mint;
+    let payment_hold = pda::payment_hold(vault, merchant, buyer, hold_id);
+    build(
+        accounts::CapturePayment {
+            vault: *vault,
+            payment_hold,
+            escrow_token_account: pda::hold_escrow(&payment_hold),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_token_account: get_associated_token_address(merchant, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: priced.price_update,
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            buyer: *buyer,
+            merchant: *merchant,
+            token_program: token::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CapturePayment { amount },
+    )
+}
+
+/// Return an authorized payment to the buyer; `signer` is the merchant, or the
+/// buyer once the hold has expired
+pub fn void_authorization(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    hold_id: &[u8; 32],
+    signer: &Pubkey,
+) -> Instruction {
+    let payment_hold = pda::payment_hold(vault, merchant, buyer, hold_id);
+    build(
+        accounts::VoidAuthorization {
+            vault: *vault,
+            payment_hold,
+            escrow_token_account: pda::hold_escrow(&payment_hold),
+            buyer_token_account: get_associated_token_address(buyer, mint),
+            buyer: *buyer,
+            signer: *signer,
+            token_program: token::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::VoidAuthorization {},
+    )
+}
+
+/// `store_label` attributes the whole batch to a sub-merchant
+pub fn record_orders_batch(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    agent: &Pubkey,
+    orders: Vec<OrderInput>,
+    store_label: Option<[u8; 32]>,
+) -> Instruction {
+    let receipts = orders
+        .iter()
+        .map(|order| AccountMeta::new(pda::order_receipt(vault, merchant, &order.order_id), false))
+        .collect();
+
+    build_with_remaining(
+        accounts::RecordOrdersBatch {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            agent: *agent,
+            merchant: *merchant,
+            system_program: system_program::ID,
+            sub_merchant: store_label.map(|label| pda::sub_merchant(vault, merchant, &label)),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::RecordOrdersBatch { orders },
+        receipts,
+    )
+}
+
+/// An order attested by an agent
+pub struct Attestation {
+    pub order_id: [u8; 32],
+    pub order_amount_usd: u64,
+    pub timestamp: i64,
+    /// Hash of the off-chain order contents (zero = none)
+    pub metadata_hash: [u8; 32],
+}
+
+/// Must be preceded by the Ed25519 program instruction carrying the agent's
+/// signature over `attestation_message`
+pub fn record_attested_order(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    agent: &Pubkey,
+    relayer: &Pubkey,
+    attestation: &Attestation,
+) -> Instruction {
+    let Attestation { order_id, order_amount_usd, timestamp, metadata_hash } = *attestation;
+    build(
+        accounts::RecordAttestedOrder {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            authorized_agent: pda::authorized_agent(vault, merchant, agent),
+            order_receipt: pda::order_receipt(vault, merchant, &order_id),
+            agent: *agent,
+            merchant: *merchant,
+            relayer: *relayer,
+            instructions_sysvar: sysvar::instructions::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::RecordAttestedOrder { order_id, order_amount_usd, timestamp, metadata_hash },
+    )
+}
+
+/// Asset an order is settled in
+pub struct Settlement {
+    /// Mint paid in (the native mint for SOL)
+    pub mint: Pubkey,
+    /// Pyth `PriceUpdateV2` account pricing the mint
+    pub price_update: Pubkey,
+    /// The vault's treasury (receives the platform fee; needs an associated token
+    /// account for SPL settlement mints)
+    pub treasury: Pubkey,
+}
+
+/// Pay `merchant` `amount` of the settlement mint, less the platform fee for
+/// their tier; `metadata_hash` commits to the order contents (zero = none)
+pub fn settle_order(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    settlement: &Settlement,
+    order_id: [u8; 32],
+    amount: u64,
+    metadata_hash: [u8; 32],
+) -> Instruction {
+    let mint = &settlement.mint;
+    let spl = (*mint != token::spl_token::native_mint::ID).then_some(*mint);
+    build(
+        accounts::SettleOrder {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: settlement.price_update,
+            order_receipt: pda::order_receipt(vault, merchant, &order_id),
+            merchant: *merchant,
+            buyer: *buyer,
+            treasury: settlement.treasury,
+            buyer_token_account: spl.map(|mint| get_associated_token_address(buyer, &mint)),
+            merchant_token_account: spl.map(|mint| get_associated_token_address(merchant, &mint)),
+            treasury_token_account: spl.map(|mint| get_associated_token_address(&settlement.treasury, &mint)),
+            mint: spl,
+            token_program: token_program_for(spl),
+            system_program: system_program::ID,
+            associated_token_program: spl.map(|_| associated_token::ID),
+            instructions_sysvar: Some(sysvar::instructions::ID),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SettleOrder { order_id, amount, metadata_hash },
+    )
+}
+
+/// Buyer and agent a spending grant is between
+pub struct GrantParties {
+    pub buyer: Pubkey,
+    pub agent: Pubkey,
+}
+
+/// Settle an order from the buyer's token account for the settlement mint, as
+/// the agent holding their spending grant
+pub fn settle_order_with_grant(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    grant: &GrantParties,
+    settlement: &Settlement,
+    order_id: [u8; 32],
+    amount: u64,
+    metadata_hash: [u8; 32],
+) -> Instruction {
+    let mint = &settlement.mint;
+    build(
+        accounts::SettleOrderWithGrant {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: settlement.price_update,
+            order_receipt: pda::order_receipt(vault, merchant, &order_id),
+            merchant: *merchant,
+            spending_grant: pda::spending_grant(vault, &grant.buyer, &grant.agent),
+            agent: grant.agent,
+            treasury: settlement.treasury,
+            buyer_token_account: get_associated_token_address(&grant.buyer, mint),
+            merchant_token_account: get_associated_token_address(merchant, mint),
+            treasury_token_account: Some(get_associated_token_address(&settlement.treasury, mint)),
+            mint: *mint,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            associated_token_program: associated_token::ID,
+            instructions_sysvar: Some(sysvar::instructions::ID),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SettleOrderWithGrant { order_id, amount, metadata_hash },
+    )
+}
+
+pub fn register_price_feed(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: Pubkey,
+    feed_id: [u8; 32],
+    max_age: i64,
+) -> Instruction {
+    build(
+        accounts::RegisterPriceFeed {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            price_feed: pda::price_feed(vault, &mint),
+            mint_account: (mint != token::spl_token::native_mint::ID).then_some(mint),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterPriceFeed { mint, feed_id, max_age },
+    )
+}
+
+pub fn update_price_feed(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    feed_id: [u8; 32],
+    max_age: i64,
+) -> Instruction {
+    build(
+        accounts::UpdatePriceFeed {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            price_feed: pda::price_feed(vault, mint),
+            authority: *authority,
+        },
+        instruction::UpdatePriceFeed { feed_id, max_age },
+    )
+}
+
+/// `signer` is the vault authority or guardian
+pub fn register_supported_mint(vault: &Pubkey, signer: &Pubkey, mint: Pubkey, deposits_paused: bool) -> Instruction {
+    build(
+        accounts::RegisterSupportedMint {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            supported_mint: pda::supported_mint(vault, &mint),
+            signer: *signer,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterSupportedMint { mint, deposits_paused },
+    )
+}
+
+/// `signer` is the vault authority or guardian
+pub fn set_mint_deposits_paused(vault: &Pubkey, signer: &Pubkey, mint: &Pubkey, paused: bool) -> Instruction {
+    build(
+        accounts::SetMintDepositsPaused {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            supported_mint: pda::supported_mint(vault, mint),
+            signer: *signer,
+        },
+        instruction::SetMintDepositsPaused { paused },
+    )
+}
+
+pub fn set_depeg_bounds(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    lower_bound: u64,
+    upper_bound: u64,
+) -> Instruction {
+    build(
+        accounts::SetDepegBounds {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            supported_mint: pda::supported_mint(vault, mint),
+            authority: *authority,
+        },
+        instruction::SetDepegBounds { lower_bound, upper_bound },
+    )
+}
+
+/// Permissionless; `price_update` is a Pyth `PriceUpdateV2` for the mint's feed
+pub fn check_depeg(vault: &Pubkey, mint: &Pubkey, price_update: &Pubkey) -> Instruction {
+    build(
+        accounts::CheckDepeg {
+            vault: *vault,
+            supported_mint: pda::supported_mint(vault, mint),
+            price_feed: pda::price_feed(vault, mint),
+            price_update: *price_update,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CheckDepeg {},
+    )
+}
+
+pub fn mint_receipt_cnft(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    order_id: &[u8; 32],
+    buyer: &Pubkey,
+    agent: &Pubkey,
+    merkle_tree: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::MintReceiptCnft {
+            vault: *vault,
+            order_receipt: pda::order_receipt(vault, merchant, order_id),
+            buyer: *buyer,
+            tree_config: mpl_bubblegum::accounts::TreeConfig::find_pda(merkle_tree).0,
+            merkle_tree: *merkle_tree,
+            agent: *agent,
+            bubblegum_program: mpl_bubblegum::ID,
+            log_wrapper: mpl_bubblegum::programs::SPL_NOOP_ID,
+            compression_program: mpl_bubblegum::programs::SPL_ACCOUNT_COMPRESSION_ID,
+            system_program: system_program::ID,
+        },
+        instruction::MintReceiptCnft {},
+    )
+}
+
+/// `deposit_active` re-evaluates the merchant's yield (their deposit must exist)
+/// Refund `amount` (in the settled mint's base units) of a settled order to `buyer`
+/// `mint` is the order's settlement mint (the native mint for SOL)
+pub fn refund_order(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    order_id: [u8; 32],
+    amount: u64,
+    deposit_active: bool,
+) -> Instruction {
+    let order_receipt = pda::order_receipt(vault, merchant, &order_id);
+    let spl = (*mint != token::spl_token::native_mint::ID).then_some(*mint);
+    build(
+        accounts::RefundOrder {
+            vault: *vault,
+            order_receipt,
+            dispute: pda::order_dispute(&order_receipt),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_deposit: deposit_active.then(|| pda::merchant_deposit(vault, merchant)),
+            merchant_token_account: spl.map(|mint| get_associated_token_address(merchant, &mint)),
+            buyer_token_account: spl.map(|mint| get_associated_token_address(buyer, &mint)),
+            buyer: *buyer,
+            merchant: *merchant,
+            token_program: token_program_for(spl),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::RefundOrder { order_id, amount },
+    )
+}
+
+pub fn open_dispute(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    opener: &Pubkey,
+    order_id: [u8; 32],
+    reason_hash: [u8; 32],
+    deposit_active: bool,
+) -> Instruction {
+    let order_receipt = pda::order_receipt(vault, merchant, &order_id);
+    build(
+        accounts::OpenDispute {
+            vault: *vault,
+            merchant: *merchant,
+            order_receipt,
+            dispute: pda::order_dispute(&order_receipt),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_deposit: deposit_active.then(|| pda::merchant_deposit(vault, merchant)),
+            opener: *opener,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::OpenDispute { order_id, reason_hash },
+    )
+}
+
+/// `deposit_active` re-evaluates the merchant's yield (their deposit must exist)
+pub fn resolve_dispute(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    merchant: &Pubkey,
+    order_id: &[u8; 32],
+    outcome: DisputeStatus,
+    deposit_active: bool,
+) -> Instruction {
+    build(
+        accounts::ResolveDispute {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            dispute: pda::order_dispute(&pda::order_receipt(vault, merchant, order_id)),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_deposit: deposit_active.then(|| pda::merchant_deposit(vault, merchant)),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ResolveDispute { outcome },
+    )
+}
+
+pub fn refresh_merchant_metrics(vault: &Pubkey, merchant: &Pubkey, cranker: &Pubkey) -> Instruction {
+    build(
+        accounts::RefreshMerchantMetrics {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            cranker: *cranker,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::RefreshMerchantMetrics {},
+    )
+}
+
+/// Roll `merchant`'s calendar `month` into a statement, following the one for
+/// `previous_month` (None for the merchant's first statement)
+pub fn roll_monthly_statement(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    cranker: &Pubkey,
+    month: u32,
+    previous_month: Option<u32>,
+) -> Instruction {
+    build(
+        accounts::RollMonthlyStatement {
+            vault: *vault,
+            merchant: *merchant,
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            previous_statement: previous_month.map(|previous| pda::monthly_statement(vault, merchant, previous)),
+            statement: pda::monthly_statement(vault, merchant, month),
+            cranker: *cranker,
+            system_program: system_program::ID,
+        },
+        instruction::RollMonthlyStatement { month },
+    )
+}
+
+pub fn get_merchant_status(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::GetMerchantStatus {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+        },
+        instruction::GetMerchantStatus {},
+    )
+}
+
+pub fn get_deposit_health(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::GetMerchantStatus {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+        },
+        instruction::GetDepositHealth {},
+    )
+}
+
+pub fn preview_withdraw(vault: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::PreviewWithdraw { vault: *vault, merchant_deposit: pda::merchant_deposit(vault, merchant) },
+        instruction::PreviewWithdraw {},
+    )
+}
+
+/// Simulate for the `AccountingReport` return data; `mints` are every mint the
+/// vault holds (its token accounts are checked in that order)
+/// `mints` are the vault's registered SPL mints; each is passed as its
+/// `SupportedMint` record and the vault's token account
+pub fn assert_invariants(vault: &Pubkey, mints: &[Pubkey]) -> Instruction {
+    build_with_remaining(
+        accounts::AssertInvariants { vault: *vault },
+        instruction::AssertInvariants {},
+        mints
+            .iter()
+            .flat_map(|mint| {
+                [
+                    AccountMeta::new_readonly(pda::supported_mint(vault, mint), false),
+                    AccountMeta::new_readonly(get_associated_token_address(vault, mint), false),
+                ]
+            })
+            .collect(),
+    )
+}
+
+pub fn initialize_vault_stats(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeVaultStats {
+            vault: *vault,
+            vault_stats: pda::vault_stats(vault),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeVaultStats {},
+    )
+}
+
+/// Creates the vault's associated token account for each of `mints`
+pub fn initialize_vault_accounts(vault: &Pubkey, authority: &Pubkey, mints: &[Pubkey]) -> Instruction {
+    build_with_remaining(
+        accounts::InitializeVaultAccounts {
+            vault: *vault,
+            vault_stats: pda::vault_stats(vault),
+            insurance_fund: pda::insurance_fund(vault),
+            authority: *authority,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeVaultAccounts {},
+        mints
+            .iter()
+            .flat_map(|mint| {
+                [
+                    AccountMeta::new_readonly(*mint, false),
+                    AccountMeta::new(get_associated_token_address(vault, mint), false),
+                ]
+            })
+            .collect(),
+    )
+}
+
+pub fn initialize_audit_log(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeAuditLog {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeAuditLog {},
+    )
+}
+
+pub fn initialize_yield_history(vault: &Pubkey, merchant: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeYieldHistory {
+            vault: *vault,
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeYieldHistory {},
+    )
+}
+
+pub fn initialize_insurance_fund(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeInsuranceFund {
+            vault: *vault,
+            insurance_fund: pda::insurance_fund(vault),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeInsuranceFund {},
+    )
+}
+
+pub fn set_insurance_fee(vault: &Pubkey, authority: &Pubkey, insurance_fee_bps: u16) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetInsuranceFee { insurance_fee_bps },
+    )
+}
+
+pub fn set_performance_fee(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    performance_fee_bps: u16,
+    treasury: Pubkey,
+) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::SetPerformanceFee { performance_fee_bps, treasury },
+    )
+}
+
+pub fn pay_insurance_claim(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    merchant: &Pubkey,
+    payout_wallet: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    claim_hash: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::PayInsuranceClaim {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            insurance_fund: pda::insurance_fund(vault),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            vault_token_account: get_associated_token_address(vault, mint),
+            payout_token_account: get_associated_token_address(payout_wallet, mint),
+            authority: *authority,
+            token_program: token::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::PayInsuranceClaim { amount, claim_hash },
+    )
+}
+
+pub fn fund_reward_epoch(vault: &Pubkey, authority: &Pubkey, budget: u64, start: i64, end: i64) -> Instruction {
+    build(
+        update_vault_config_accounts(vault, authority),
+        instruction::FundRewardEpoch { budget, start, end },
+    )
+}
+
+/// Harvest `amount` of SOL yield, or of `mint` from the authority's token account
+/// Pass the current Solana `epoch` to record the harvest in its `EpochRecord`
+/// (required under the Solana epoch accrual schedule).
+pub fn harvest_rewards(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: Option<Pubkey>,
+    amount: u64,
+    source: HarvestSource,
+    epoch: Option<u64>,
+) -> Instruction {
+    let deposit_token = if mint.is_some() { DepositType::SplToken } else { DepositType::Sol };
+    build(
+        accounts::HarvestRewards {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            source_token_account: mint.map(|mint| get_associated_token_address(authority, &mint)),
+            vault_token_account: mint.map(|mint| get_associated_token_address(vault, &mint)),
+            epoch_record: epoch.map(|epoch| pda::epoch_record(vault, epoch)),
+            authority: *authority,
+            token_program: token_program_for(mint),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::HarvestRewards { deposit_token, amount, source },
+    )
+}
+
+/// Open the record of the vault's harvests during Solana `epoch` (the current one)
+pub fn initialize_epoch_record(vault: &Pubkey, payer: &Pubkey, epoch: u64) -> Instruction {
+    build(
+        accounts::InitializeEpochRecord {
+            vault: *vault,
+            epoch_record: pda::epoch_record(vault, epoch),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeEpochRecord { epoch },
+    )
+}
+
+/// Approve `reserve` for the vault's lending strategy
+pub fn approve_lending_market(vault: &Pubkey, authority: &Pubkey, reserve: &lending::Reserve) -> Instruction {
+    build(
+        accounts::ApproveLendingMarket {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            lending_strategy: pda::lending_strategy(vault, &reserve.reserve),
+            reserve: reserve.reserve,
+            liquidity_mint: reserve.liquidity_mint,
+            collateral_mint: reserve.collateral_mint,
+            vault_collateral_account: get_associated_token_address(vault, &reserve.collateral_mint),
+            authority: *authority,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ApproveLendingMarket {
+            lending_program: reserve.program,
+            lending_market: reserve.lending_market,
+            lending_market_authority: reserve.lending_market_authority,
+            reserve_liquidity_supply: reserve.liquidity_supply,
+        },
+    )
+}
+
+fn lending_liquidity_accounts(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    reserve: &lending::Reserve,
+) -> accounts::LendingLiquidity {
+    accounts::LendingLiquidity {
+        vault: *vault,
+        audit_log: pda::audit_log(vault),
+        lending_strategy: pda::lending_strategy(vault, &reserve.reserve),
+        supported_mint: pda::supported_mint(vault, &reserve.liquidity_mint),
+        vault_token_account: get_associated_token_address(vault, &reserve.liquidity_mint),
+        vault_collateral_account: get_associated_token_address(vault, &reserve.collateral_mint),
+        reserve: reserve.reserve,
+        reserve_liquidity_supply: reserve.liquidity_supply,
+        reserve_collateral_mint: reserve.collateral_mint,
+        lending_market: reserve.lending_market,
+        lending_market_authority: reserve.lending_market_authority,
+        lending_program: reserve.program,
+        authority: *authority,
+        token_program: token::ID,
+        event_authority: pda::event_authority(),
+        program: crate::ID,
+    }
+}
+
+/// Supply `amount` of idle tokens to the strategy's reserve
+/// Prepend the lending program's refresh-reserve instruction.
+pub fn supply_lending_liquidity(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    reserve: &lending::Reserve,
+    amount: u64,
+) -> Instruction {
+    build(
+        lending_liquidity_accounts(vault, authority, reserve),
+        instruction::SupplyLendingLiquidity { amount },
+    )
+}
+
+/// Redeem `collateral_amount` of the strategy's collateral
+/// Prepend the lending program's refresh-reserve instruction.
+pub fn redeem_lending_collateral(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    reserve: &lending::Reserve,
+    collateral_amount: u64,
+) -> Instruction {
+    build(
+        lending_liquidity_accounts(vault, authority, reserve),
+        instruction::RedeemLendingCollateral { collateral_amount },
+    )
+}
+
+/// Set the share of SPL deposits the strategy on `reserve` targets, and its operator
+pub fn set_lending_allocation(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    reserve: &Pubkey,
+    target_allocation_bps: u16,
+    rebalance_operator: &Pubkey,
+    max_rebalance_amount: u64,
+) -> Instruction {
+    build(
+        accounts::SetLendingAllocation {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            lending_strategy: pda::lending_strategy(vault, reserve),
+            authority: *authority,
+        },
+        instruction::SetLendingAllocation {
+            target_allocation_bps,
+            rebalance_operator: *rebalance_operator,
+            max_rebalance_amount,
+        },
+    )
+}
+
+/// Rebalance the strategy toward its target, signed by the authority or the
+/// strategy's operator
+/// Prepend the lending program's refresh-reserve instruction.
+pub fn rebalance(
+    vault: &Pubkey,
+    operator: &Pubkey,
+    reserve: &lending::Reserve,
+    max_amount: u64,
+    min_out: u64,
+) -> Instruction {
+    build(
+        lending_liquidity_accounts(vault, operator, reserve),
+        instruction::Rebalance { max_amount, min_out },
+    )
+}
+
+/// Unwind the strategy on `reserve`, signed by the guardian
+/// Prepend the lending program's refresh-reserve instruction.
+pub fn emergency_exit_strategy(vault: &Pubkey, guardian: &Pubkey, reserve: &lending::Reserve) -> Instruction {
+    build(
+        lending_liquidity_accounts(vault, guardian, reserve),
+        instruction::EmergencyExitStrategy {},
+    )
+}
+
+/// Collect SOL performance fees, or token fees for `mint` if given
+pub fn collect_performance_fees(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: Option<Pubkey>,
+) -> Instruction {
+    let deposit_token = if mint.is_some() { DepositType::SplToken } else { DepositType::Sol };
+    build(
+        accounts::CollectPerformanceFees {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            treasury: *treasury,
+            treasury_token_account: mint.map(|mint| get_associated_token_address(treasury, &mint)),
+            vault_token_account: mint.map(|mint| get_associated_token_address(vault, &mint)),
+            authority: *authority,
+            token_program: token_program_for(mint),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::CollectPerformanceFees { deposit_token },
+    )
+}
+
+/// `distribution_id` is the vault's current `distribution_count`; the authority
+/// funds `total_amount` of `mint` from its associated token account
+pub fn create_distribution(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    distribution_id: u64,
+    mint: &Pubkey,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+) -> Instruction {
+    build(
+        accounts::CreateDistribution {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            distribution: pda::distribution(vault, distribution_id),
+            mint: *mint,
+            authority_token_account: get_associated_token_address(authority, mint),
+            vault_token_account: get_associated_token_address(vault, mint),
+            authority: *authority,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateDistribution { merkle_root, total_amount },
+    )
+}
+
+pub fn claim_bonus(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    distribution_id: u64,
+    payout_wallet: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let distribution = pda::distribution(vault, distribution_id);
+    build(
+        accounts::ClaimBonus {
+            vault: *vault,
+            distribution,
+            bonus_claim: pda::bonus_claim(&distribution, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            vault_token_account: get_associated_token_address(vault, mint),
+            payout_token_account: get_associated_token_address(payout_wallet, mint),
+            merchant: *merchant,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            recovery_config: pda::recovery_config(vault, merchant),
+        },
+        instruction::ClaimBonus { amount, proof },
+    )
+}
+
+pub fn initialize_reward_emission(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    epoch_duration: i64,
+    epoch_emission: u64,
+    decay_bps: u16,
+    max_supply: u64,
+) -> Instruction {
+    build(
+        accounts::InitializeRewardEmission {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            emission_schedule: pda::emission_schedule(vault),
+            reward_mint: pda::reward_mint(vault),
+            reward_mint_authority: pda::reward_mint_authority(vault),
+            authority: *authority,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRewardEmission { epoch_duration, epoch_emission, decay_bps, max_supply },
+    )
+}
+
+pub fn set_emission_schedule(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    epoch_emission: u64,
+    decay_bps: u16,
+) -> Instruction {
+    build(
+        accounts::UpdateEmissionSchedule {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            emission_schedule: pda::emission_schedule(vault),
+            authority: *authority,
+        },
+        instruction::SetEmissionSchedule { epoch_emission, decay_bps },
+    )
+}
+
+/// `operator` is the merchant or their withdraw delegate; `split_wallets` are the
+/// active payout split wallets, paid to their reward token accounts
+pub fn claim_token_rewards(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    operator: &Pubkey,
+    payout_wallet: &Pubkey,
+    split_wallets: &[Pubkey],
+) -> Instruction {
+    let reward_mint = pda::reward_mint(vault);
+    let split_recipients = split_wallets
+        .iter()
+        .map(|wallet| AccountMeta::new(get_associated_token_address(wallet, &reward_mint), false))
+        .collect();
+
+    build_with_remaining(
+        accounts::ClaimTokenRewards {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            emission_schedule: pda::emission_schedule(vault),
+            reward_mint,
+            reward_mint_authority: pda::reward_mint_authority(vault),
+            payout_reward_account: get_associated_token_address(payout_wallet, &reward_mint),
+            payout_config: pda::payout_config(vault, merchant),
+            merchant: *merchant,
+            operator: *operator,
+            token_program: token::ID,
+            recovery_config: pda::recovery_config(vault, merchant),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::ClaimTokenRewards {},
+        split_recipients,
+    )
+}
+
+pub fn mint_tier_badge(vault: &Pubkey, merchant: &Pubkey, tier: u8) -> Instruction {
+    let badge_mint = pda::badge_mint(vault, merchant, tier);
+    build(
+        accounts::MintTierBadge {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            tier_badge: pda::tier_badge(vault, merchant, tier),
+            badge_mint,
+            badge_token_account: get_associated_token_address(merchant, &badge_mint),
+            metadata: mpl_token_metadata::accounts::Metadata::find_pda(&badge_mint).0,
+            master_edition: mpl_token_metadata::accounts::MasterEdition::find_pda(&badge_mint).0,
+            merchant: *merchant,
+            token_metadata_program: mpl_token_metadata::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MintTierBadge { tier },
+    )
+}
+
+/// `deposited_at` of the merchant's current deposit (part of the mint's address)
+pub fn mint_position_nft(vault: &Pubkey, merchant: &Pubkey, deposited_at: i64) -> Instruction {
+    let position_mint = pda::position_mint(vault, merchant, deposited_at);
+    build(
+        accounts::MintPositionNft {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            position_mint,
+            position_token_account: get_associated_token_address(merchant, &position_mint),
+            metadata: mpl_token_metadata::accounts::Metadata::find_pda(&position_mint).0,
+            master_edition: mpl_token_metadata::accounts::MasterEdition::find_pda(&position_mint).0,
+            merchant: *merchant,
+            token_metadata_program: mpl_token_metadata::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MintPositionNft {},
+    )
+}
+
+pub fn initialize_share_mint(vault: &Pubkey, authority: &Pubkey, deposit_token: DepositType) -> Instruction {
+    let share_mint = pda::share_mint(vault, &deposit_token);
+    build(
+        accounts::InitializeShareMint {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            share_mint,
+            extra_account_metas: pda::extra_account_metas(&share_mint),
+            metadata: mpl_token_metadata::accounts::Metadata::find_pda(&share_mint).0,
+            authority: *authority,
+            token_metadata_program: mpl_token_metadata::ID,
+            token_program: token_2022::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializeShareMint { deposit_token },
+    )
+}
+
+fn position_shares_accounts(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    deposit_token: &DepositType,
+    share_token_account: &Pubkey,
+) -> accounts::PositionShares {
+    accounts::PositionShares {
+        vault: *vault,
+        merchant_deposit: pda::merchant_deposit(vault, merchant),
+        share_mint: pda::share_mint(vault, deposit_token),
+        share_token_account: *share_token_account,
+        merchant: *merchant,
+        token_program: token_2022::ID,
+        event_authority: pda::event_authority(),
+        program: crate::ID,
+    }
+}
+
+/// Mints into `share_token_account`, a Token-2022 account of the asset's share mint
+pub fn tokenize_position(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    deposit_token: &DepositType,
+    share_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        position_shares_accounts(vault, merchant, deposit_token, share_token_account),
+        instruction::TokenizePosition {},
+    )
+}
+
+/// Burns the deposit's shares from `share_token_account`, owned by `merchant`
+pub fn untokenize_position(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    deposit_token: &DepositType,
+    share_token_account: &Pubkey,
+) -> Instruction {
+    build(
+        position_shares_accounts(vault, merchant, deposit_token, share_token_account),
+        instruction::UntokenizePosition {},
+    )
+}
+
+/// Accounts Token-2022 appends to a share transfer for the transfer hook, after
+/// the hook program itself
+/// Add them to a `transfer_checked` of shares to `destination_owner`.
+pub fn share_transfer_hook_accounts(
+    vault: &Pubkey,
+    share_mint: &Pubkey,
+    destination_owner: &Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*vault, false),
+        AccountMeta::new_readonly(pda::approved_holder(vault, destination_owner), false),
+        AccountMeta::new_readonly(crate::ID, false),
+        AccountMeta::new_readonly(pda::extra_account_metas(share_mint), false),
+    ]
+}
+
+pub fn approve_share_holder(vault: &Pubkey, authority: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        accounts::ApproveShareHolder {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            approved_holder: pda::approved_holder(vault, wallet),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::ApproveShareHolder { wallet: *wallet },
+    )
+}
+
+pub fn revoke_share_holder(vault: &Pubkey, authority: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeShareHolder {
+            vault: *vault,
+            audit_log: pda::audit_log(vault),
+            approved_holder: pda::approved_holder(vault, wallet),
+            authority: *authority,
+        },
+        instruction::RevokeShareHolder {},
+    )
+}
+
+/// Signed by the vault's KYC verifier; `expires_at` of 0 never expires
+pub fn issue_kyc_attestation(vault: &Pubkey, verifier: &Pubkey, wallet: &Pubkey, expires_at: i64) -> Instruction {
+    build(
+        accounts::IssueKycAttestation {
+            vault: *vault,
+            kyc_attestation: pda::kyc_attestation(vault, wallet),
+            verifier: *verifier,
+            system_program: system_program::ID,
+        },
+        instruction::IssueKycAttestation { wallet: *wallet, expires_at },
+    )
+}
+
+/// `signer` is the vault's KYC verifier or its authority
+pub fn revoke_kyc_attestation(vault: &Pubkey, signer: &Pubkey, wallet: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeKycAttestation {
+            vault: *vault,
+            kyc_attestation: pda::kyc_attestation(vault, wallet),
+            signer: *signer,
+        },
+        instruction::RevokeKycAttestation {},
+    )
+}
+
+/// `position_token_account` is the token account now holding the position NFT
+pub fn sync_position_owner(vault: &Pubkey, merchant: &Pubkey, position_token_account: &Pubkey) -> Instruction {
+    build(
+        accounts::SyncPositionOwner {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            position_token_account: *position_token_account,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SyncPositionOwner {},
+    )
+}
+
+/// Signed by both `merchant` and `recipient`
+pub fn split_position(vault: &Pubkey, merchant: &Pubkey, recipient: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::SplitPosition {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            split_deposit: pda::merchant_deposit(vault, recipient),
+            recipient_profile: pda::merchant_profile(vault, recipient),
+            recipient_metrics: pda::merchant_metrics(vault, recipient),
+            recipient_yield_history: pda::yield_history(vault, recipient),
+            merchant: *merchant,
+            recipient: *recipient,
+            system_program: system_program::ID,
+            recipient_kyc_attestation: pda::kyc_attestation(vault, recipient),
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::SplitPosition { amount },
+    )
+}
+
+/// Signed by both `merchant` and `source_merchant`
+pub fn merge_positions(vault: &Pubkey, merchant: &Pubkey, source_merchant: &Pubkey) -> Instruction {
+    build(
+        accounts::MergePositions {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_profile: pda::merchant_profile(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            yield_history: pda::yield_history(vault, merchant),
+            source_deposit: pda::merchant_deposit(vault, source_merchant),
+            source_profile: pda::merchant_profile(vault, source_merchant),
+            merchant: *merchant,
+            source_merchant: *source_merchant,
+            event_authority: pda::event_authority(),
+            program: crate::ID,
+        },
+        instruction::MergePositions {},
+    )
+}
+
+fn set_merchant_frozen_accounts(vault: &Pubkey, authority: &Pubkey, merchant: &Pubkey) -> accounts::SetMerchantFrozen {
+    accounts::SetMerchantFrozen {
+        vault: *vault,
+        audit_log: pda::audit_log(vault),
+        merchant_deposit: pda::merchant_deposit(vault, merchant),
+        authority: *authority,
+    }
+}
+
+pub fn freeze_merchant(vault: &Pubkey, authority: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        set_merchant_frozen_accounts(vault, authority, merchant),
+        instruction::FreezeMerchant {},
+    )
+}
+
+pub fn unfreeze_merchant(vault: &Pubkey, authority: &Pubkey, merchant: &Pubkey) -> Instruction {
+    build(
+        set_merchant_frozen_accounts(vault, authority, merchant),
+        instruction::UnfreezeMerchant {},
+    )
+}
+
+fn set_merchant_paused_accounts(vault: &Pubkey, merchant: &Pubkey, has_deposit: bool) -> accounts::SetMerchantPaused {
+    accounts::SetMerchantPaused {
+        vault: *vault,
+        merchant_profile: pda::merchant_profile(vault, merchant),
+        merchant_metrics: pda::merchant_metrics(vault, merchant),
+        merchant_deposit: has_deposit.then(|| pda::merchant_deposit(vault, merchant)),
+        merchant: *merchant,
+    }
+}
+
+/// `has_deposit`: the merchant has an open deposit (passed so its clocks are kept too)
+pub fn pause_merchant_activity(vault: &Pubkey, merchant: &Pubkey, has_deposit: bool) -> Instruction {
+    build(
+        set_merchant_paused_accounts(vault, merchant, has_deposit),
+        instruction::PauseMerchantActivity {},
+    )
+}
+
+pub fn resume_merchant_activity(vault: &Pubkey, merchant: &Pubkey, has_deposit: bool) -> Instruction {
+    build(
+        set_merchant_paused_accounts(vault, merchant, has_deposit),
+        instruction::ResumeMerchantActivity {},
+    )
+}
+
+pub fn migrate_vault(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateVault {
+            vault: *vault,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateVault {},
+    )
+}
+
+/// Create the vault's lookup table at `recent_slot` (see `pda::lookup_table`)
+pub fn create_lookup_table(vault: &Pubkey, authority: &Pubkey, recent_slot: u64) -> Instruction {
+    build(
+        accounts::CreateLookupTable {
+            vault: *vault,
+            lookup_table: pda::lookup_table(vault, recent_slot),
+            authority: *authority,
+            address_lookup_table_program: address_lookup_table::program::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateLookupTable { recent_slot },
+    )
+}
+
+/// Automation thread of `crank` for `merchant` (sweeps and metrics refreshes), else
+/// for the withdrawals of `mint` (the native mint for SOL)
+pub fn automation_thread(
+    vault: &Pubkey,
+    crank: AutomationCrank,
+    merchant: Option<Pubkey>,
+    mint: Option<Pubkey>,
+) -> Pubkey {
+    let id = merchant.or(mint).unwrap_or(token::spl_token::native_mint::ID);
+    pda::automation_thread(vault, crank, &id)
+}
+
+/// Run `crank` on a thread for `merchant` (sweeps and metrics refreshes) or for
+/// the withdrawals of `mint` (fulfillment; None for SOL), funded with `amount` lamports
+pub fn create_automation_thread(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    crank: AutomationCrank,
+    merchant: Option<Pubkey>,
+    mint: Option<Pubkey>,
+    schedule: &str,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::CreateAutomationThread {
+            vault: *vault,
+            thread_authority: pda::thread_authority(vault, crank),
+            thread: automation_thread(vault, crank, merchant, mint),
+            vault_token_account: mint.map(|mint| get_associated_token_address(vault, &mint)),
+            authority: *authority,
+            thread_program: automation::THREAD_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::CreateAutomationThread {
+            crank,
+            merchant,
+            schedule: schedule.to_string(),
+            amount,
+        },
+    )
+}
+
+/// Delete the thread `create_automation_thread` made for the same crank, merchant and mint
+pub fn delete_automation_thread(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    crank: AutomationCrank,
+    merchant: Option<Pubkey>,
+    mint: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::DeleteAutomationThread {
+            vault: *vault,
+            thread_authority: pda::thread_authority(vault, crank),
+            thread: automation_thread(vault, crank, merchant, mint),
+            authority: *authority,
+            thread_program: automation::THREAD_PROGRAM_ID,
+        },
+        instruction::DeleteAutomationThread { crank },
+    )
+}
+
+pub fn extend_lookup_table(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    lookup_table: &Pubkey,
+    addresses: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::ExtendLookupTable {
+            vault: *vault,
+            lookup_table: *lookup_table,
+            authority: *authority,
+            address_lookup_table_program: address_lookup_table::program::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ExtendLookupTable { addresses },
+    )
+}
+
+pub fn migrate_merchant_metrics(vault: &Pubkey, merchant: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateMerchantMetrics {
+            vault: *vault,
+            merchant: *merchant,
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateMerchantMetrics {},
+    )
+}
+
+/// `legacy_mint`: the vault authority and the registered mint an SPL deposit from
+/// before v9 was made in (the authority signs off on the backfill)
+pub fn migrate_merchant_deposit(
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    payer: &Pubkey,
+    legacy_mint: Option<(Pubkey, Pubkey)>,
+) -> Instruction {
+    build(
+        accounts::MigrateMerchantDeposit {
+            vault: *vault,
+            merchant_deposit: pda::merchant_deposit(vault, merchant),
+            merchant_metrics: pda::merchant_metrics(vault, merchant),
+            payer: *payer,
+            system_program: system_program::ID,
+            deposit_mint: legacy_mint.map(|(_, mint)| pda::supported_mint(vault, &mint)),
+            authority: legacy_mint.map(|(authority, _)| authority),
+        },
+        instruction::MigrateMerchantDeposit {},
+    )
+}
+
+pub fn snapshot_vault_stats(vault: &Pubkey, cranker: &Pubkey) -> Instruction {
+    build(
+        accounts::SnapshotVaultStats {
+            vault: *vault,
+            vault_stats: pda::vault_stats(vault),
+            cranker: *cranker,
+        },
+        instruction::SnapshotVaultStats {},
+    )
+}
+
+// ============================================================================
+// Account Fetching
+// ============================================================================
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The RPC request failed
+    Rpc(Box<solana_client::client_error::ClientError>),
+    /// The account isn't owned by the program or doesn't decode as the expected type
+    Decode(Box<anchor_lang::error::Error>),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Rpc(err) => write!(f, "rpc error: {}", err),
+            ClientError::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(err: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(err))
+    }
+}
+
+impl From<anchor_lang::error::Error> for ClientError {
+    fn from(err: anchor_lang::error::Error) -> Self {
+        ClientError::Decode(Box::new(err))
+    }
+}
+
+impl From<ErrorCode> for ClientError {
+    fn from(code: ErrorCode) -> Self {
+        anchor_lang::error::Error::from(code).into()
+    }
+}
+
+/// Decode a program account (discriminator checked)
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> std::result::Result<T, ClientError> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+/// Decode a zero-copy program account (discriminator checked)
+pub fn decode_zero_copy<T: ZeroCopy>(data: &[u8]) -> std::result::Result<T, ClientError> {
+    let size = 8 + std::mem::size_of::<T>();
+    if data.len() < size {
+        return Err(ErrorCode::AccountDidNotDeserialize.into());
+    }
+    if data[..8] != T::DISCRIMINATOR {
+        return Err(ErrorCode::AccountDiscriminatorMismatch.into());
+    }
+    Ok(bytemuck::pod_read_unaligned(&data[8..size]))
+}
+
+/// Fetch and decode a program account
+pub fn fetch_account<T: AccountDeserialize + Owner>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> std::result::Result<T, ClientError> {
+    let account = rpc.get_account(address)?;
+    if account.owner != T::owner() {
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    decode_account(&account.data)
+}
+
+/// Fetch and decode a zero-copy program account
+pub fn fetch_zero_copy<T: ZeroCopy + Owner>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> std::result::Result<T, ClientError> {
+    let account = rpc.get_account(address)?;
+    if account.owner != T::owner() {
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    decode_zero_copy(&account.data)
+}
+
+/// Fetch an address lookup table, ready to compile into a v0 message
+pub fn fetch_lookup_table(
+    rpc: &RpcClient,
+    address: &Pubkey,
+) -> std::result::Result<AddressLookupTableAccount, ClientError> {
+    let account = rpc.get_account(address)?;
+    if account.owner != address_lookup_table::program::ID {
+        return Err(ErrorCode::AccountOwnedByWrongProgram.into());
+    }
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|_| ClientError::from(ErrorCode::AccountDidNotDeserialize))?;
+    Ok(AddressLookupTableAccount { key: *address, addresses: table.addresses.to_vec() })
+}
+
+/// Compile `instructions` into a v0 message that loads accounts through
+/// `lookup_tables`, for transactions too large for a legacy message
+/// (a settlement with receipts, oracle and fee accounts, for example)
+pub fn compile_v0_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> std::result::Result<v0::Message, CompileError> {
+    v0::Message::try_compile(payer, instructions, lookup_tables, recent_blockhash)
+}
+
+pub fn fetch_vault(rpc: &RpcClient, authority: &Pubkey) -> std::result::Result<Vault, ClientError> {
+    fetch_account(rpc, &pda::vault(authority))
+}
+
+pub fn fetch_merchant_deposit(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<MerchantDeposit, ClientError> {
+    fetch_account(rpc, &pda::merchant_deposit(vault, merchant))
+}
+
+pub fn fetch_merchant_profile(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<MerchantProfile, ClientError> {
+    fetch_account(rpc, &pda::merchant_profile(vault, merchant))
+}
+
+pub fn fetch_merchant_metrics(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<MerchantMetrics, ClientError> {
+    fetch_zero_copy(rpc, &pda::merchant_metrics(vault, merchant))
+}
+
+pub fn fetch_audit_log(rpc: &RpcClient, vault: &Pubkey) -> std::result::Result<AuditLog, ClientError> {
+    fetch_zero_copy(rpc, &pda::audit_log(vault))
+}
+
+pub fn fetch_yield_history(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<YieldHistory, ClientError> {
+    fetch_zero_copy(rpc, &pda::yield_history(vault, merchant))
+}
+
+pub fn fetch_payout_config(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<PayoutConfig, ClientError> {
+    fetch_account(rpc, &pda::payout_config(vault, merchant))
+}
+
+pub fn fetch_recovery_config(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<RecoveryConfig, ClientError> {
+    fetch_account(rpc, &pda::recovery_config(vault, merchant))
+}
+
+pub fn fetch_withdrawal_whitelist(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+) -> std::result::Result<WithdrawalWhitelist, ClientError> {
+    fetch_account(rpc, &pda::withdrawal_whitelist(vault, merchant))
+}
+
+pub fn fetch_payment_hold(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    buyer: &Pubkey,
+    hold_id: &[u8; 32],
+) -> std::result::Result<PaymentHold, ClientError> {
+    fetch_account(rpc, &pda::payment_hold(vault, merchant, buyer, hold_id))
+}
+
+pub fn fetch_spending_grant(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    buyer: &Pubkey,
+    agent: &Pubkey,
+) -> std::result::Result<SpendingGrant, ClientError> {
+    fetch_account(rpc, &pda::spending_grant(vault, buyer, agent))
+}
+
+pub fn fetch_order_receipt(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    order_id: &[u8; 32],
+) -> std::result::Result<OrderReceipt, ClientError> {
+    fetch_account(rpc, &pda::order_receipt(vault, merchant, order_id))
+}
+
+pub fn fetch_epoch_record(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    epoch: u64,
+) -> std::result::Result<EpochRecord, ClientError> {
+    fetch_account(rpc, &pda::epoch_record(vault, epoch))
+}
+
+pub fn fetch_monthly_statement(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    merchant: &Pubkey,
+    month: u32,
+) -> std::result::Result<MonthlyStatement, ClientError> {
+    fetch_account(rpc, &pda::monthly_statement(vault, merchant, month))
+}
+
+pub fn fetch_lending_strategy(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    reserve: &Pubkey,
+) -> std::result::Result<LendingStrategy, ClientError> {
+    fetch_account(rpc, &pda::lending_strategy(vault, reserve))
+}
+
+pub fn fetch_config_proposal(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+) -> std::result::Result<ConfigProposal, ClientError> {
+    fetch_account(rpc, &pda::config_proposal(vault))
+}
+
+pub fn fetch_supported_mint(
+    rpc: &RpcClient,
+    vault: &Pubkey,
+    mint: &Pubkey,
+) -> std::result::Result<SupportedMint, ClientError> {
+    fetch_account(rpc, &pda::supported_mint(vault, mint))
+}
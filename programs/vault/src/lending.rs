@@ -0,0 +1,99 @@
+//! CPI into SPL token-lending markets for the vault's lending strategy
+//!
+//! Targets the SPL token-lending instruction interface shared by Solend (Save)
+//! and its forks. Instructions are encoded here directly (tag byte followed by
+//! the u64 amount) rather than through a lending SDK crate. Markets with a
+//! different interface (e.g. Kamino's klend) are not supported.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token;
+
+/// Lending programs a reserve may be approved under: SPL token-lending and Solend (Save)
+pub const APPROVED_LENDING_PROGRAMS: [Pubkey; 2] = [
+    pubkey!("LendZqTs7gn5CTSJU1jWKhKuVpjJGom45nnwPb2AMTi"),
+    pubkey!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo"),
+];
+
+/// `DepositReserveLiquidity` instruction tag
+const DEPOSIT_RESERVE_LIQUIDITY: u8 = 4;
+/// `RedeemReserveCollateral` instruction tag
+const REDEEM_RESERVE_COLLATERAL: u8 = 5;
+
+/// A lending market reserve and the accounts its deposits and redemptions touch
+#[derive(Clone, Debug)]
+pub struct Reserve {
+    /// Lending program owning the market and reserve
+    pub program: Pubkey,
+    pub lending_market: Pubkey,
+    /// PDA of the lending market that owns the reserve's token accounts
+    pub lending_market_authority: Pubkey,
+    pub reserve: Pubkey,
+    /// Reserve token account holding the supplied liquidity
+    pub liquidity_supply: Pubkey,
+    /// Mint of the supplied token
+    pub liquidity_mint: Pubkey,
+    /// Mint of the reserve's collateral (cToken) receipts
+    pub collateral_mint: Pubkey,
+}
+
+fn instruction_data(tag: u8, amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(9);
+    data.push(tag);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Supply `liquidity_amount` from `source_liquidity` to the reserve, minting
+/// collateral into `destination_collateral`
+/// The reserve must have been refreshed earlier in the same transaction.
+pub fn deposit_reserve_liquidity(
+    reserve: &Reserve,
+    source_liquidity: &Pubkey,
+    destination_collateral: &Pubkey,
+    transfer_authority: &Pubkey,
+    liquidity_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: reserve.program,
+        accounts: vec![
+            AccountMeta::new(*source_liquidity, false),
+            AccountMeta::new(*destination_collateral, false),
+            AccountMeta::new(reserve.reserve, false),
+            AccountMeta::new(reserve.liquidity_supply, false),
+            AccountMeta::new(reserve.collateral_mint, false),
+            AccountMeta::new_readonly(reserve.lending_market, false),
+            AccountMeta::new_readonly(reserve.lending_market_authority, false),
+            AccountMeta::new_readonly(*transfer_authority, true),
+            AccountMeta::new_readonly(token::ID, false),
+        ],
+        data: instruction_data(DEPOSIT_RESERVE_LIQUIDITY, liquidity_amount),
+    }
+}
+
+/// Burn `collateral_amount` from `source_collateral` for the liquidity it is
+/// worth, paid into `destination_liquidity`
+/// The reserve must have been refreshed earlier in the same transaction.
+pub fn redeem_reserve_collateral(
+    reserve: &Reserve,
+    source_collateral: &Pubkey,
+    destination_liquidity: &Pubkey,
+    transfer_authority: &Pubkey,
+    collateral_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: reserve.program,
+        accounts: vec![
+            AccountMeta::new(*source_collateral, false),
+            AccountMeta::new(*destination_liquidity, false),
+            AccountMeta::new(reserve.reserve, false),
+            AccountMeta::new(reserve.collateral_mint, false),
+            AccountMeta::new(reserve.liquidity_supply, false),
+            AccountMeta::new_readonly(reserve.lending_market, false),
+            AccountMeta::new_readonly(reserve.lending_market_authority, false),
+            AccountMeta::new_readonly(*transfer_authority, true),
+            AccountMeta::new_readonly(token::ID, false),
+        ],
+        data: instruction_data(REDEEM_RESERVE_COLLATERAL, collateral_amount),
+    }
+}
@@ -3,8 +3,54 @@
 //! A Solana smart contract for managing merchant deposits with staking rewards.
 //! Merchants deposit SOL or SPL tokens as collateral, which can be staked to earn rewards.
 
+// Amounts are written as `<dollars>_<micro-units>` (e.g. `10_000000` = $10 USDC)
+#![allow(clippy::inconsistent_digit_grouping)]
+
+pub mod automation;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod lending;
+pub mod math;
+pub mod notifier;
+pub mod oracle;
+pub mod pda;
+pub mod rewards;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod time;
+pub mod transfer_hook;
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::{system_program, Discriminator, InstructionData};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::solana_program::{address_lookup_table, ed25519_program, instruction::Instruction, keccak};
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use automation::{AutomationCrank, ThreadResponse, Trigger, MAX_CRON_SCHEDULE_LEN, THREAD_AUTHORITY_SEED};
+use anchor_spl::metadata::{
+    create_master_edition_v3, create_metadata_accounts_v3, freeze_delegated_account,
+    mpl_token_metadata::types::DataV2, CreateMasterEditionV3, CreateMetadataAccountsV3,
+    FreezeDelegatedAccount, Metadata,
+};
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::{self, Approve, CloseAccount, Mint, MintTo, Revoke, Token, TokenAccount, Transfer};
+use anchor_spl::token_2022::{self, Token2022};
+use anchor_spl::token_interface;
+use mpl_bubblegum::hash::hash_metadata;
+use mpl_bubblegum::instructions::MintV1CpiBuilder;
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use notifier::{Notification, NotificationKind, NOTIFY_AUTHORITY_SEED};
+use mpl_bubblegum::types::{MetadataArgs, TokenProgramVersion, TokenStandard};
+
+use math::{
+    bps_ceil, bps_floor, mul_div_floor, q64_div_ceil, q64_div_floor, q64_from_ratio_floor, q64_mul_floor,
+    yield_for_days, yield_for_seconds, CANONICAL_DECIMALS, Q64_ONE,
+};
+use rewards::{
+    calculate_merchant_tier, decay_metric, elapsed_seconds, hold_tier, tier_name, RewardEpoch, YieldHarvest,
+};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"); // Placeholder, will be updated after build
 
@@ -13,28 +59,110 @@ pub mod shaw_vault {
     use super::*;
 
     /// Initialize the vault
-    /// Creates the global vault state account
-    pub fn initialize(ctx: Context<Initialize>, bump: u8) -> Result<()> {
+    /// Creates the global vault state account. A non-zero `label` creates one of
+    /// several labeled vaults (programs) run by the same authority, each with its
+    /// own config and deposits.
+    pub fn initialize(ctx: Context<Initialize>, bump: u8, label: [u8; VAULT_LABEL_LEN]) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
         vault.bump = bump;
+        vault.label = label;
         vault.total_deposits = 0;
         vault.total_merchants = 0;
         vault.min_deposit_sol = 1_000_000_000; // 1 SOL
         vault.min_deposit_token = 100_000_000; // 100 USDC (6 decimals)
-        vault.reward_share_rate = 8000; // 80.00% (basis points)
+        vault.performance_fee_bps = 2000; // 20% of harvested yield
         vault.staking_enabled = true;
+        vault.cashback_bps = 0; // Buyer cashback disabled until configured
+        vault.referral_fee_bps = 1000; // 10% of referred merchants' platform fees
+        vault.total_rewards_paid = 0;
+        vault.weighted_yield_sum = 0;
+        vault.lock_boost_schedule = [25, 75, 150, 300]; // +0.25% / +0.75% / +1.5% / +3% APY
+        vault.early_exit_penalty_bps = 1000; // 10% of principal
+        vault.guardian = ctx.accounts.authority.key(); // Until a dedicated guardian is set
+        vault.insurance_fee_bps = 500; // 5% of platform fees
+        vault.agent_bond_lamports = 100_000_000; // 0.1 SOL per agent
+        vault.distribution_count = 0;
+        vault.withdraw_epoch_duration = 86400; // 1 day
+        vault.withdraw_cap_amount = 0; // Uncapped until configured
+        vault.withdraw_cap_tvl_bps = 0; // Uncapped until configured
+        vault.withdraw_epoch_start = time::now()?;
+        vault.withdraw_epoch_tvl = 0;
+        vault.withdrawn_this_epoch = 0;
+        vault.withdraw_queue_head = 0;
+        vault.withdraw_queue_tail = 0;
+        vault.emergency_mode = false;
+        vault.emergency_activated_at = 0;
+        vault.config_timelock = 0; // Config changes apply immediately until a timelock is set
+        vault.max_orders_per_hour = 0; // Unlimited until configured
+        vault.max_daily_order_volume = 0; // Unlimited until configured
+        vault.repeat_purchase_window = 0; // Repeat purchases credited in full until configured
+        vault.repeat_purchase_credit_bps = [10000, 5000, 1000]; // 100% / 50% / 10%
+        vault.tier_downgrade_grace = 0; // Tiers drop as soon as volume does until configured
+        vault.platform_fee_bps_by_tier = [0; 4]; // Settlements are fee-free until configured
+        vault.settlement_guard_before = false; // No settlement introspection until configured
+        vault.settlement_guard_after = false;
+        vault.settlement_blocked_token_ixs = 0;
+        vault.min_holding_slots = 1; // Withdrawals and claims wait at least until the next slot
+        vault.min_order_usd = MIN_ORDER_USD;
+        vault.reward_payout_cap = 0; // Reward payouts uncapped until configured
+        vault.rewards_paid_this_epoch = 0;
+        vault.reward_anomaly_multiple_bps = 0; // No anomaly check until configured
+        vault.performance_fee_updated_at = 0;
+        vault.treasury = ctx.accounts.authority.key(); // Until a dedicated treasury is set
+        vault.performance_fees_sol = 0;
+        vault.performance_fees_token = 0;
+        vault.total_performance_fees = 0;
+        vault.reward_epoch_start = 0;
+        vault.reward_epoch_end = 0; // No reward budget until an epoch is funded
+        vault.reward_epoch_budget = 0;
+        vault.reward_epoch_accrued = 0;
+        vault.reward_index_sol = 0;
+        vault.reward_index_token = 0;
+        vault.total_sol_deposits = 0;
+        vault.total_token_deposits = 0;
+        vault.total_harvested = 0;
+        vault.stake_strategy = StakeStrategy::Native;
+        vault.total_mev_tips_harvested = 0;
+        vault.lending_allocation_bps = 0;
+        vault.liquidity_buffer_bps = 0;
+        vault.total_lent = 0;
+        vault.accrual_schedule = AccrualSchedule::Daily;
+        vault.reward_stream_duration = 0;
+        vault.share_transfers_restricted = false;
+        vault.kyc_required = false;
+        vault.kyc_verifier = Pubkey::default();
+        vault.depegged_mints = 0;
+        vault.depeg_suspended_at = 0;
+        vault.notifier_program = Pubkey::default();
+        vault.sol_suspended_seconds = 0;
+        vault.spl_suspended_seconds = 0;
+
+        init_audit_log(&ctx.accounts.audit_log, ctx.accounts.vault.key(), ctx.bumps.audit_log)?;
 
-        msg!("Vault initialized with authority: {}", vault.authority);
+        msg!(
+            "Vault initialized with authority: {} | label {:?}",
+            ctx.accounts.vault.authority,
+            ctx.accounts.vault.label_str()
+        );
         Ok(())
     }
 
     /// Deposit SOL into the vault
     /// Merchants deposit SOL as collateral which can be staked
-    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64, lock_period: LockPeriod) -> Result<()> {
+    pub fn deposit_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSol<'info>>,
+        amount: u64,
+        lock_period: LockPeriod,
+        lock_term: Option<LockTerm>,
+    ) -> Result<()> {
         let vault = &ctx.accounts.vault;
         let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_kyc_attestation(vault, &ctx.accounts.kyc_attestation, time::now()?)?;
+        require_mint_deposits_open(&ctx.accounts.supported_mint)?;
+
         // Validate minimum deposit
         require!(amount >= vault.min_deposit_sol, VaultError::InsufficientDeposit);
 
@@ -53,42 +181,90 @@ pub mod shaw_vault {
         )?;
 
         // Initialize merchant deposit record
-        let current_time = Clock::get()?.unix_timestamp;
-        merchant_deposit.merchant = ctx.accounts.merchant.key();
-        merchant_deposit.vault = vault.key();
-        merchant_deposit.deposit_token = DepositType::Sol;
-        merchant_deposit.total_deposited = amount;
-        merchant_deposit.accrued_rewards = 0;
-        merchant_deposit.is_active = true;
-        merchant_deposit.deposited_at = current_time;
-        merchant_deposit.bump = ctx.bumps.merchant_deposit;
-
-        // Initialize performance metrics
-        merchant_deposit.total_orders_processed = 0;
-        merchant_deposit.total_volume_usd = 0;
-        merchant_deposit.current_month_volume = 0;
-        merchant_deposit.last_volume_reset = current_time;
-        merchant_deposit.monthly_unique_customers = 0;
-        merchant_deposit.current_yield_bps = 300; // Start with base 3% APY
-
-        // Initialize lock period and profit sharing
-        merchant_deposit.lock_period = lock_period.clone();
-        merchant_deposit.unlock_time = current_time + lock_period.duration_seconds();
-        merchant_deposit.platform_profit_earned = 0;
-        merchant_deposit.profit_share_allocated = 0;
+        let current_time = time::now()?;
+        open_deposit(
+            merchant_deposit,
+            &mut ctx.accounts.merchant_profile,
+            &*ctx.accounts.merchant_metrics.load()?,
+            &ctx.accounts.yield_history,
+            vault,
+            NewDeposit {
+                merchant: ctx.accounts.merchant.key(),
+                deposit_token: DepositType::Sol,
+                mint: native_mint::ID,
+                amount,
+                lock_period,
+                lock_term,
+                bump: ctx.bumps.merchant_deposit,
+            },
+            current_time,
+        )?;
+
+        // Record referral relationship if the merchant was referred
+        record_referral(
+            ctx.accounts.referral_code.as_mut(),
+            ctx.accounts.referral.as_mut(),
+            ctx.bumps.referral,
+            ctx.accounts.merchant.key(),
+            vault.key(),
+            current_time,
+        )?;
+
+        add_deposit_to_vault(&mut ctx.accounts.vault, merchant_deposit)?;
+        let principal = merchant_deposit.total_deposited;
+        credit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit, principal)?;
+        record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Deposit,
+            ctx.accounts.merchant.key(),
+            native_mint::ID,
+            amount,
+            [0; 32],
+        )?;
+
+        debug_assert_invariants(&ctx.accounts.vault);
 
         msg!("Deposited {} lamports from merchant {}", amount, ctx.accounts.merchant.key());
         Ok(())
     }
 
     /// Deposit SPL tokens (USDC) into the vault
-    pub fn deposit_token(ctx: Context<DepositTokenAccounts>, amount: u64, lock_period: LockPeriod) -> Result<()> {
+    pub fn deposit_token<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositTokenAccounts<'info>>,
+        amount: u64,
+        lock_period: LockPeriod,
+        lock_term: Option<LockTerm>,
+    ) -> Result<()> {
         let vault = &ctx.accounts.vault;
         let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_kyc_attestation(vault, &ctx.accounts.kyc_attestation, time::now()?)?;
+        require!(
+            load_vault_account::<SupportedMint>(&ctx.accounts.supported_mint)?.is_some(),
+            VaultError::MintNotSupported
+        );
+        require_mint_deposits_open(&ctx.accounts.supported_mint)?;
+
         // Validate minimum deposit
         require!(amount >= vault.min_deposit_token, VaultError::InsufficientDeposit);
 
+        // SPL deposits share one bucket kept in canonical micro-units
+        require!(ctx.accounts.mint.decimals == CANONICAL_DECIMALS, VaultError::UnsupportedMintDecimals);
+
+        // First deposit of a mint: create the vault's token account
+        create_associated_token_account(
+            &ctx.accounts.merchant,
+            &ctx.accounts.vault_token_account,
+            &vault.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+            &ctx.accounts.associated_token_program,
+        )?;
+
         // Transfer tokens from merchant to vault
         let cpi_accounts = Transfer {
             from: ctx.accounts.merchant_token_account.to_account_info(),
@@ -100,868 +276,15658 @@ pub mod shaw_vault {
         token::transfer(cpi_ctx, amount)?;
 
         // Initialize merchant deposit record
-        let current_time = Clock::get()?.unix_timestamp;
-        merchant_deposit.merchant = ctx.accounts.merchant.key();
-        merchant_deposit.vault = vault.key();
-        merchant_deposit.deposit_token = DepositType::SplToken;
-        merchant_deposit.total_deposited = amount;
-        merchant_deposit.accrued_rewards = 0;
-        merchant_deposit.is_active = true;
-        merchant_deposit.deposited_at = current_time;
-        merchant_deposit.bump = ctx.bumps.merchant_deposit;
-
-        // Initialize performance metrics
-        merchant_deposit.total_orders_processed = 0;
-        merchant_deposit.total_volume_usd = 0;
-        merchant_deposit.current_month_volume = 0;
-        merchant_deposit.last_volume_reset = current_time;
-        merchant_deposit.monthly_unique_customers = 0;
-        merchant_deposit.current_yield_bps = 300; // Start with base 3% APY
-
-        // Initialize lock period and profit sharing
-        merchant_deposit.lock_period = lock_period.clone();
-        merchant_deposit.unlock_time = current_time + lock_period.duration_seconds();
-        merchant_deposit.platform_profit_earned = 0;
-        merchant_deposit.profit_share_allocated = 0;
+        let current_time = time::now()?;
+        open_deposit(
+            merchant_deposit,
+            &mut ctx.accounts.merchant_profile,
+            &*ctx.accounts.merchant_metrics.load()?,
+            &ctx.accounts.yield_history,
+            vault,
+            NewDeposit {
+                merchant: ctx.accounts.merchant.key(),
+                deposit_token: DepositType::SplToken,
+                mint: ctx.accounts.mint.key(),
+                amount,
+                lock_period,
+                lock_term,
+                bump: ctx.bumps.merchant_deposit,
+            },
+            current_time,
+        )?;
+
+        // Record referral relationship if the merchant was referred
+        record_referral(
+            ctx.accounts.referral_code.as_mut(),
+            ctx.accounts.referral.as_mut(),
+            ctx.bumps.referral,
+            ctx.accounts.merchant.key(),
+            vault.key(),
+            current_time,
+        )?;
+
+        add_deposit_to_vault(&mut ctx.accounts.vault, merchant_deposit)?;
+        let principal = merchant_deposit.total_deposited;
+        credit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit, principal)?;
+        record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Deposit,
+            ctx.accounts.merchant.key(),
+            ctx.accounts.mint.key(),
+            amount,
+            [0; 32],
+        )?;
+
+        debug_assert_invariants(&ctx.accounts.vault);
 
         msg!("Deposited {} tokens from merchant {}", amount, ctx.accounts.merchant.key());
         Ok(())
     }
 
+    /// Deposit wrapped SOL as a SOL position
+    /// The wSOL is moved into a temporary vault-owned token account and unwrapped
+    /// by closing it to the merchant, who then deposits the SOL as with
+    /// `deposit_sol`. The temporary account's rent returns with it.
+    pub fn deposit_wsol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositWsol<'info>>,
+        amount: u64,
+        lock_period: LockPeriod,
+        lock_term: Option<LockTerm>,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_kyc_attestation(vault, &ctx.accounts.kyc_attestation, time::now()?)?;
+        require_mint_deposits_open(&ctx.accounts.supported_mint)?;
+
+        // Validate minimum deposit
+        require!(amount >= vault.min_deposit_sol, VaultError::InsufficientDeposit);
+
+        // Unwrap: wSOL into the temporary account, then close it to the merchant
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.merchant_wsol_account.to_account_info(),
+            to: ctx.accounts.wsol_unwrap_account.to_account_info(),
+            authority: ctx.accounts.merchant.to_account_info(),
+        };
+        token::transfer(CpiContext::new(token_program.clone(), cpi_accounts), amount)?;
+
+        let seeds = &[VAULT_SEED, vault.authority.as_ref(), vault.label_seed(), &[vault.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.wsol_unwrap_account.to_account_info(),
+            destination: ctx.accounts.merchant.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        token::close_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+
+        // Transfer the unwrapped SOL from merchant to vault
+        let ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.merchant.key(),
+            &ctx.accounts.vault_sol_account.key(),
+            amount,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &ix,
+            &[
+                ctx.accounts.merchant.to_account_info(),
+                ctx.accounts.vault_sol_account.to_account_info(),
+            ],
+        )?;
+
+        // Initialize merchant deposit record
+        let current_time = time::now()?;
+        open_deposit(
+            merchant_deposit,
+            &mut ctx.accounts.merchant_profile,
+            &*ctx.accounts.merchant_metrics.load()?,
+            &ctx.accounts.yield_history,
+            vault,
+            NewDeposit {
+                merchant: ctx.accounts.merchant.key(),
+                deposit_token: DepositType::Sol,
+                mint: native_mint::ID,
+                amount,
+                lock_period,
+                lock_term,
+                bump: ctx.bumps.merchant_deposit,
+            },
+            current_time,
+        )?;
+
+        add_deposit_to_vault(&mut ctx.accounts.vault, merchant_deposit)?;
+        let principal = merchant_deposit.total_deposited;
+        credit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit, principal)?;
+        record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Deposit,
+            ctx.accounts.merchant.key(),
+            native_mint::ID,
+            amount,
+            [0; 32],
+        )?;
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Deposited {} lamports (unwrapped from wSOL) from merchant {}", amount, ctx.accounts.merchant.key());
+        Ok(())
+    }
+
+    /// Fund a merchant's deposit from a third party (investor, franchisor)
+    /// The sponsor pays the principal, which is credited to the merchant's position
+    /// as a normal deposit. `sponsor_share_bps` of the merchant's rewards accrue to
+    /// the sponsor, claimable with `claim_sponsor_rewards`. Both sign. SPL when the
+    /// sponsor's token account is supplied, SOL otherwise.
+    pub fn deposit_for<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositFor<'info>>,
+        amount: u64,
+        lock_period: LockPeriod,
+        lock_term: Option<LockTerm>,
+        sponsor_share_bps: u16,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_kyc_attestation(vault, &ctx.accounts.kyc_attestation, time::now()?)?;
+        require_kyc_attestation(vault, &ctx.accounts.sponsor_kyc_attestation, time::now()?)?;
+        require_mint_deposits_open(&ctx.accounts.supported_mint)?;
+        require!(sponsor_share_bps <= MAX_SPONSOR_SHARE_BPS, VaultError::InvalidSponsorShare);
+
+        let deposit_token = match (
+            &ctx.accounts.sponsor_token_account,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+            &ctx.accounts.associated_token_program,
+        ) {
+            (
+                Some(sponsor_token_account),
+                Some(vault_token_account),
+                Some(token_program),
+                Some(associated_token_program),
+            ) => {
+                require!(amount >= vault.min_deposit_token, VaultError::InsufficientDeposit);
+                let mint = ctx.accounts.mint.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+                require!(
+                    sponsor_token_account.mint == mint.key() && mint.decimals == CANONICAL_DECIMALS,
+                    VaultError::UnsupportedMintDecimals
+                );
+                require!(
+                    load_vault_account::<SupportedMint>(&ctx.accounts.supported_mint)?.is_some(),
+                    VaultError::MintNotSupported
+                );
+
+                // First deposit of a mint: create the vault's token account
+                create_associated_token_account(
+                    &ctx.accounts.sponsor,
+                    vault_token_account,
+                    &vault.to_account_info(),
+                    &mint.to_account_info(),
+                    &ctx.accounts.system_program,
+                    token_program,
+                    associated_token_program,
+                )?;
+
+                let cpi_accounts = Transfer {
+                    from: sponsor_token_account.to_account_info(),
+                    to: vault_token_account.to_account_info(),
+                    authority: ctx.accounts.sponsor.to_account_info(),
+                };
+                token::transfer(CpiContext::new(token_program.to_account_info(), cpi_accounts), amount)?;
+                DepositType::SplToken
+            }
+            (None, None, _, _) => {
+                require!(amount >= vault.min_deposit_sol, VaultError::InsufficientDeposit);
+                let ix = anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.sponsor.key(),
+                    &ctx.accounts.vault_sol_account.key(),
+                    amount,
+                );
+                anchor_lang::solana_program::program::invoke(
+                    &ix,
+                    &[
+                        ctx.accounts.sponsor.to_account_info(),
+                        ctx.accounts.vault_sol_account.to_account_info(),
+                    ],
+                )?;
+                DepositType::Sol
+            }
+            _ => return err!(VaultError::MissingTokenAccount),
+        };
+
+        // Initialize merchant deposit record
+        let current_time = time::now()?;
+        open_deposit(
+            merchant_deposit,
+            &mut ctx.accounts.merchant_profile,
+            &*ctx.accounts.merchant_metrics.load()?,
+            &ctx.accounts.yield_history,
+            vault,
+            NewDeposit {
+                merchant: ctx.accounts.merchant.key(),
+                deposit_token: deposit_token.clone(),
+                mint: ctx.accounts.mint.as_ref().map_or(native_mint::ID, |mint| mint.key()),
+                amount,
+                lock_period,
+                lock_term,
+                bump: ctx.bumps.merchant_deposit,
+            },
+            current_time,
+        )?;
+
+        // Sponsor's claim on the rewards
+        merchant_deposit.sponsor = ctx.accounts.sponsor.key();
+        merchant_deposit.sponsor_share_bps = sponsor_share_bps;
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        sponsorship.sponsor = ctx.accounts.sponsor.key();
+        sponsorship.merchant = ctx.accounts.merchant.key();
+        sponsorship.vault = vault.key();
+        sponsorship.deposit_token = deposit_token;
+        sponsorship.mint = merchant_deposit.mint;
+        sponsorship.share_bps = sponsor_share_bps;
+        sponsorship.principal = amount;
+        sponsorship.accrued_rewards = 0;
+        sponsorship.total_claimed = 0;
+        sponsorship.created_at = current_time;
+        sponsorship.bump = ctx.bumps.sponsorship;
+
+        add_deposit_to_vault(&mut ctx.accounts.vault, merchant_deposit)?;
+        let principal = merchant_deposit.total_deposited;
+        credit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit, principal)?;
+        record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Deposit,
+            ctx.accounts.merchant.key(),
+            ctx.accounts.mint.as_ref().map_or(native_mint::ID, |mint| mint.key()),
+            amount,
+            [0; 32],
+        )?;
+
+        emit_cpi!(DepositSponsored {
+            merchant: ctx.accounts.merchant.key(),
+            sponsor: ctx.accounts.sponsor.key(),
+            amount,
+            sponsor_share_bps,
+        });
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Sponsor {} deposited {} for merchant {} ({} bps of rewards)",
+            ctx.accounts.sponsor.key(),
+            amount,
+            ctx.accounts.merchant.key(),
+            sponsor_share_bps
+        );
+        Ok(())
+    }
+
     /// Withdraw deposit and accrued rewards
     /// Merchants can withdraw their full deposit plus rewards
-    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+    pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
         let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
         require!(merchant_deposit.is_active, VaultError::DepositNotActive);
         require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_holding_period(merchant_deposit, &ctx.accounts.vault)?;
 
         // Calculate current rewards using dynamic yield
-        let current_time = Clock::get()?.unix_timestamp;
+        let current_time = time::now()?;
 
-        // Enforce lock period
+        // Enforce lock period and fixed-term lock
         require!(
-            current_time >= merchant_deposit.unlock_time,
+            current_time >= merchant_deposit.unlock_time
+                && current_time >= merchant_deposit.term_maturity,
             VaultError::DepositStillLocked
         );
-        let time_elapsed = current_time - merchant_deposit.deposited_at;
-        let days_elapsed = time_elapsed / 86400;
 
-        // Use merchant's current dynamic yield (updated by record_order)
-        let yield_bps = merchant_deposit.current_yield_bps;
+        // Accrue rewards since the last checkpoint (performance fee already taken)
+        let harvest = accrue_rewards(merchant_deposit, &ctx.accounts.vault, current_time)?;
+        if let Some(harvested) = book_harvest(&mut ctx.accounts.vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
+        // Whatever is still streaming is paid out with the rest
+        merchant_deposit.end_reward_stream()?;
+        let merchant_rewards = merchant_deposit.accrued_rewards;
 
-        // Calculate rewards based on dynamic APY
-        let annual_reward = merchant_deposit.total_deposited
-            .checked_mul(yield_bps as u64)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(VaultError::MathOverflow)?;
+        // Circuit breaker: abnormal rewards need the guardian, and all count against the epoch cap
+        if reward_payout_flagged(&ctx.accounts.vault, merchant_deposit, merchant_rewards, current_time)? {
+            require!(ctx.accounts.guardian.is_some(), VaultError::RewardPayoutFlagged);
+        }
+        consume_reward_payout(&mut ctx.accounts.vault, merchant_rewards, current_time)?;
 
-        let daily_reward = annual_reward
-            .checked_div(365)
+        let total_withdrawal = merchant_deposit.total_deposited
+            .checked_add(merchant_rewards)
             .ok_or(VaultError::MathOverflow)?;
 
-        let total_rewards = daily_reward
-            .checked_mul(days_elapsed as u64)
-            .ok_or(VaultError::MathOverflow)?;
+        authorize_withdrawal(merchant_deposit, ctx.accounts.operator.key(), total_withdrawal, current_time)?;
+        ctx.accounts.record_merchant_activity(current_time)?;
 
-        // Apply merchant share (80%)
-        let merchant_rewards = total_rewards
-            .checked_mul(ctx.accounts.vault.reward_share_rate as u64)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(VaultError::MathOverflow)?;
+        // Queued withdrawals go first; direct exits only use spare epoch capacity
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.withdraw_queue_head == vault.withdraw_queue_tail, VaultError::WithdrawalQueueNotEmpty);
+        consume_withdraw_capacity(vault, total_withdrawal, current_time)?;
 
-        let total_withdrawal = merchant_deposit.total_deposited
-            .checked_add(merchant_rewards)
-            .ok_or(VaultError::MathOverflow)?;
+        // A tokenized deposit only leaves the vault against shares worth the payout
+        ctx.accounts.burn_position_shares(total_withdrawal)?;
 
-        // Transfer back to merchant based on deposit type
-        match merchant_deposit.deposit_token {
-            DepositType::Sol => {
-                // Transfer SOL back
-                **ctx.accounts.vault_sol_account.to_account_info().try_borrow_mut_lamports()? -= total_withdrawal;
-                **ctx.accounts.merchant.to_account_info().try_borrow_mut_lamports()? += total_withdrawal;
-            }
-            DepositType::SplToken => {
-                // Transfer tokens back
-                let seeds = &[
-                    b"vault",
-                    ctx.accounts.vault.authority.as_ref(),
-                    &[ctx.accounts.vault.bump],
-                ];
-                let signer = &[&seeds[..]];
-
-                let vault_token_account = ctx.accounts.vault_token_account.as_ref()
-                    .ok_or(VaultError::MissingTokenAccount)?;
-                let merchant_token_account = ctx.accounts.merchant_token_account.as_ref()
-                    .ok_or(VaultError::MissingTokenAccount)?;
-                let token_program = ctx.accounts.token_program.as_ref()
-                    .ok_or(VaultError::MissingTokenAccount)?;
+        // Transfer to the merchant's payout wallet based on deposit type
+        promote_payout_wallet(&mut ctx.accounts.merchant_profile, current_time);
+        ctx.accounts.transfer_to_payout_wallet(ctx.remaining_accounts, total_withdrawal, current_time)?;
 
-                let cpi_accounts = Transfer {
-                    from: vault_token_account.to_account_info(),
-                    to: merchant_token_account.to_account_info(),
-                    authority: ctx.accounts.vault.to_account_info(),
-                };
-                let cpi_program = token_program.to_account_info();
-                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-                token::transfer(cpi_ctx, total_withdrawal)?;
-            }
-        }
+        // The sponsor's share outlives the deposit in its sponsorship account
+        ctx.accounts.settle_sponsorship()?;
 
-        // Mark deposit as withdrawn
+        // Mark deposit as withdrawn; the account is closed and the tier moves to the profile
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
         merchant_deposit.is_active = false;
         merchant_deposit.accrued_rewards = merchant_rewards;
+        ctx.accounts.merchant_profile.current_tier = merchant_deposit.current_tier;
+        ctx.accounts.merchant_profile.last_withdrawn_at = current_time;
+
+        remove_deposit_from_vault(&mut ctx.accounts.vault, merchant_deposit, merchant_rewards)?;
+        debit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit)?;
 
-        msg!("Withdrawn {} (deposit: {}, rewards: {}) to merchant {}",
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Withdrawn {} (deposit: {}, rewards: {}) to payout wallet {}",
             total_withdrawal,
             merchant_deposit.total_deposited,
             merchant_rewards,
-            ctx.accounts.merchant.key()
+            ctx.accounts.merchant_profile.payout_wallet
         );
 
         Ok(())
     }
 
-    /// Update vault parameters (admin only)
-    pub fn update_vault_config(
-        ctx: Context<UpdateVaultConfig>,
-        min_deposit_sol: Option<u64>,
-        min_deposit_token: Option<u64>,
-        reward_share_rate: Option<u16>,
-        staking_enabled: Option<bool>,
-    ) -> Result<()> {
-        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+    /// Withdraw principal before the lock period or fixed term matures
+    /// Forfeits all accrued and streaming rewards (a sponsor's share included) and pays `early_exit_penalty_bps` of principal,
+    /// which stays in the vault's reward reserve
+    pub fn withdraw_early<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_holding_period(merchant_deposit, &ctx.accounts.vault)?;
+
+        let current_time = time::now()?;
+        require!(
+            current_time < merchant_deposit.unlock_time
+                || current_time < merchant_deposit.term_maturity,
+            VaultError::DepositNotLocked
+        );
+
+        // Penalty is owed to the vault: round up
+        let penalty = bps_ceil(
+            merchant_deposit.total_deposited,
+            ctx.accounts.vault.early_exit_penalty_bps as u64,
+        )
+        .ok_or(VaultError::MathOverflow)?;
+        let payout = merchant_deposit.total_deposited
+            .checked_sub(penalty)
+            .ok_or(VaultError::MathOverflow)?;
+        authorize_withdrawal(merchant_deposit, ctx.accounts.operator.key(), payout, current_time)?;
+        ctx.accounts.record_merchant_activity(current_time)?;
 
         let vault = &mut ctx.accounts.vault;
+        require!(vault.withdraw_queue_head == vault.withdraw_queue_tail, VaultError::WithdrawalQueueNotEmpty);
+        consume_withdraw_capacity(vault, payout, current_time)?;
 
-        if let Some(min_sol) = min_deposit_sol {
-            vault.min_deposit_sol = min_sol;
-        }
-        if let Some(min_token) = min_deposit_token {
-            vault.min_deposit_token = min_token;
-        }
-        if let Some(rate) = reward_share_rate {
-            require!(rate <= 10000, VaultError::InvalidRate);
-            vault.reward_share_rate = rate;
-        }
-        if let Some(enabled) = staking_enabled {
-            vault.staking_enabled = enabled;
-        }
+        ctx.accounts.burn_position_shares(payout)?;
+        promote_payout_wallet(&mut ctx.accounts.merchant_profile, current_time);
+        ctx.accounts.transfer_to_payout_wallet(ctx.remaining_accounts, payout, current_time)?;
+
+        // Mark deposit as withdrawn; accrued rewards are forfeited
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.is_active = false;
+        merchant_deposit.accrued_rewards = 0;
+        ctx.accounts.merchant_profile.current_tier = merchant_deposit.current_tier;
+        ctx.accounts.merchant_profile.last_withdrawn_at = current_time;
+
+        remove_deposit_from_vault(&mut ctx.accounts.vault, merchant_deposit, 0)?;
+        debit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit)?;
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Early withdrawal {} (deposit: {}, penalty: {}) to payout wallet {}",
+            payout,
+            merchant_deposit.total_deposited,
+            penalty,
+            ctx.accounts.merchant_profile.payout_wallet
+        );
 
-        msg!("Vault config updated");
         Ok(())
     }
 
-    /// Get current rewards for a merchant deposit with dynamic yield
-    pub fn calculate_rewards(ctx: Context<CalculateRewards>) -> Result<u64> {
-        let merchant_deposit = &ctx.accounts.merchant_deposit;
+    /// Return exact principal while the vault is in emergency mode
+    /// Ignores lock periods and fixed terms and skips all reward math;
+    /// accrued and streaming rewards (a sponsor's share included) are not paid
+    pub fn emergency_withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
+        require!(ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeInactive);
         require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
 
-        let current_time = Clock::get()?.unix_timestamp;
-        let time_elapsed = current_time - merchant_deposit.deposited_at;
-        let days_elapsed = time_elapsed / 86400;
+        let principal = merchant_deposit.total_deposited;
+        let current_time = time::now()?;
+        authorize_withdrawal(merchant_deposit, ctx.accounts.operator.key(), principal, current_time)?;
+        ctx.accounts.record_merchant_activity(current_time)?;
 
-        // Calculate dynamic yield APY based on lock period, volume, and profit share
-        let yield_bps = calculate_dynamic_yield(
-            merchant_deposit,
-            merchant_deposit.total_deposited,
-        );
+        ctx.accounts.burn_position_shares(principal)?;
+        promote_payout_wallet(&mut ctx.accounts.merchant_profile, current_time);
+        ctx.accounts.transfer_to_payout_wallet(ctx.remaining_accounts, principal, current_time)?;
 
-        // Convert BPS to percentage (e.g., 1200 BPS = 12%)
-        let annual_reward = merchant_deposit.total_deposited
-            .checked_mul(yield_bps as u64)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(VaultError::MathOverflow)?;
+        // Mark deposit as withdrawn; the account is closed and the tier moves to the profile
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.is_active = false;
+        merchant_deposit.accrued_rewards = 0;
+        ctx.accounts.merchant_profile.current_tier = merchant_deposit.current_tier;
+        ctx.accounts.merchant_profile.last_withdrawn_at = current_time;
 
-        let daily_reward = annual_reward
-            .checked_div(365)
-            .ok_or(VaultError::MathOverflow)?;
+        remove_deposit_from_vault(&mut ctx.accounts.vault, merchant_deposit, 0)?;
+        debit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit)?;
 
-        let total_rewards = daily_reward
-            .checked_mul(days_elapsed as u64)
-            .ok_or(VaultError::MathOverflow)?;
+        debug_assert_invariants(&ctx.accounts.vault);
 
-        msg!("Current rewards: {} (yield: {}% APY, days: {})",
-            total_rewards,
-            yield_bps as f64 / 100.0,
-            days_elapsed
+        msg!("Emergency withdrawal of principal {} to payout wallet {}",
+            principal,
+            ctx.accounts.merchant_profile.payout_wallet
         );
-        Ok(total_rewards)
+
+        Ok(())
     }
 
-    /// Record a processed order to update merchant metrics
-    /// Called by payment processor agent after successful split
-    pub fn record_order(
-        ctx: Context<RecordOrder>,
-        order_amount_usd: u64,
-        _buyer_wallet: Pubkey,
-    ) -> Result<()> {
-        let authorized_agent = &ctx.accounts.authorized_agent;
+    /// Roll accrued rewards into principal instead of paying them out
+    /// Rewards are accrued to now first, then added to `total_deposited` so they
+    /// earn yield themselves; lock terms and checkpoints are unchanged
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
         let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
-        // Verify agent is authorized and active
-        require!(authorized_agent.is_active, VaultError::UnauthorizedAgent);
-        require!(
-            authorized_agent.agent == ctx.accounts.agent.key(),
-            VaultError::UnauthorizedAgent
-        );
-        require!(
-            authorized_agent.merchant == merchant_deposit.merchant,
-            VaultError::UnauthorizedAgent
-        );
-
-        let current_time = Clock::get()?.unix_timestamp;
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_holding_period(merchant_deposit, vault)?;
 
-        // Reset monthly volume if new month started
-        let month_elapsed = (current_time - merchant_deposit.last_volume_reset) / 2592000; // ~30 days
-        if month_elapsed >= 1 {
-            msg!("Resetting monthly volume (new month started)");
-            merchant_deposit.current_month_volume = 0;
-            merchant_deposit.monthly_unique_customers = 0;
-            merchant_deposit.last_volume_reset = current_time;
+        let current_time = time::now()?;
+        record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        let harvest = accrue_rewards(merchant_deposit, vault, current_time)?;
+        if let Some(harvested) = book_harvest(vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
         }
 
-        // Validate minimum order amount (anti-gaming)
-        require!(order_amount_usd >= 10_000000, VaultError::OrderTooSmall); // $10 minimum
-
-        // Update metrics
-        merchant_deposit.total_orders_processed = merchant_deposit
-            .total_orders_processed
-            .checked_add(1)
-            .ok_or(VaultError::MathOverflow)?;
-
-        merchant_deposit.total_volume_usd = merchant_deposit
-            .total_volume_usd
-            .checked_add(order_amount_usd)
-            .ok_or(VaultError::MathOverflow)?;
+        let compounded = merchant_deposit.accrued_rewards;
+        require!(compounded > 0, VaultError::NoRewardsToCompound);
 
-        merchant_deposit.current_month_volume = merchant_deposit
-            .current_month_volume
-            .checked_add(order_amount_usd)
-            .ok_or(VaultError::MathOverflow)?;
+        // Take the old principal out of the weighted yield sum before it grows
+        vault.weighted_yield_sum = vault.weighted_yield_sum.saturating_sub(
+            merchant_deposit.total_deposited as u128 * merchant_deposit.reported_yield_bps as u128,
+        );
 
-        // Track unique customer (simplified - in production, use a bloom filter or separate account)
-        merchant_deposit.monthly_unique_customers = merchant_deposit
-            .monthly_unique_customers
-            .checked_add(1)
+        merchant_deposit.total_deposited = merchant_deposit
+            .total_deposited
+            .checked_add(compounded)
             .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.accrued_rewards = 0;
 
-        // Recalculate current yield based on new metrics (lock period, volume, profit share)
+        // Profit share is measured against principal, so yield is re-evaluated
+        let previous_yield_bps = merchant_deposit.current_yield_bps;
         merchant_deposit.current_yield_bps = calculate_dynamic_yield(
             merchant_deposit,
+            &*ctx.accounts.merchant_metrics.load()?,
             merchant_deposit.total_deposited,
         );
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            merchant_deposit.current_yield_bps,
+            YieldTrigger::Compound,
+        )? {
+            emit_cpi!(updated);
+        }
+        merchant_deposit.reported_yield_bps = merchant_deposit.current_yield_bps;
+        vault.weighted_yield_sum = vault
+            .weighted_yield_sum
+            .checked_add(merchant_deposit.total_deposited as u128 * merchant_deposit.reported_yield_bps as u128)
+            .ok_or(VaultError::MathOverflow)?;
 
-        msg!("Order recorded: ${} | Total volume: ${} | Current yield: {}% APY",
-            order_amount_usd / 1_000000,
-            merchant_deposit.current_month_volume / 1_000000,
-            merchant_deposit.current_yield_bps as f64 / 100.0
+        // Compounded rewards count as paid out and as new principal
+        vault.total_deposits = vault
+            .total_deposits
+            .checked_add(compounded)
+            .ok_or(VaultError::MathOverflow)?;
+        let asset_total = asset_deposits(vault, &merchant_deposit.deposit_token);
+        *asset_total = asset_total.checked_add(compounded).ok_or(VaultError::MathOverflow)?;
+        credit_mint_deposits(&ctx.accounts.supported_mint, merchant_deposit, compounded)?;
+        vault.total_rewards_paid = vault
+            .total_rewards_paid
+            .checked_add(compounded)
+            .ok_or(VaultError::MathOverflow)?;
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        #[cfg(feature = "verbose-logs")]
+        msg!("Compounded {} rewards | New principal: {} | Yield: {} bps",
+            compounded,
+            merchant_deposit.total_deposited,
+            merchant_deposit.current_yield_bps
         );
 
         Ok(())
     }
 
-    /// Get merchant tier based on performance
-    pub fn get_merchant_tier(ctx: Context<GetMerchantTier>) -> Result<u8> {
-        let merchant_deposit = &ctx.accounts.merchant_deposit;
-
-        let tier = calculate_merchant_tier(
-            merchant_deposit.current_month_volume,
-            merchant_deposit.deposited_at,
-            Clock::get()?.unix_timestamp,
-        );
+    /// Sweep a merchant's accrued rewards to their payout wallet (permissionless crank)
+    /// Only once the merchant's auto-sweep threshold is reached and its interval has
+    /// passed since the last sweep (see `set_auto_sweep`). Payout splits apply, with
+    /// one recipient per active split in `remaining_accounts`.
+    pub fn sweep_rewards<'info>(ctx: Context<'_, '_, '_, 'info, SweepRewards<'info>>) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
-        msg!("Merchant tier: {} (volume: ${}, yield: {}%)",
-            tier_name(tier),
-            merchant_deposit.current_month_volume / 1_000000,
-            merchant_deposit.current_yield_bps as f64 / 100.0
-        );
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_holding_period(merchant_deposit, &ctx.accounts.vault)?;
 
-        Ok(tier)
-    }
+        let current_time = time::now()?;
+        let harvest = accrue_rewards(merchant_deposit, &ctx.accounts.vault, current_time)?;
+        if let Some(harvested) = book_harvest(&mut ctx.accounts.vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
 
-    /// Record platform profit from merchant's orders
-    /// Called by platform after order processing to track profit sharing
-    /// Platform gives up to 50% of profit back to merchant as yield boost
-    pub fn record_platform_profit(
-        ctx: Context<RecordPlatformProfit>,
-        platform_profit_amount: u64,
-    ) -> Result<()> {
-        let vault = &ctx.accounts.vault;
-        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        let payout_config = &mut ctx.accounts.payout_config;
+        let swept = merchant_deposit.accrued_rewards;
+        require!(
+            payout_config.sweep_threshold > 0
+                && swept >= payout_config.sweep_threshold
+                && elapsed_seconds(payout_config.last_sweep_at, current_time) >= payout_config.sweep_interval,
+            VaultError::SweepNotDue
+        );
 
-        // SECURITY: Verify caller is authorized (platform authority or authorized agent)
+        // Flagged rewards leave through a guardian co-signed withdrawal instead
         require!(
-            ctx.accounts.platform.key() == vault.authority,
-            VaultError::Unauthorized
+            !reward_payout_flagged(&ctx.accounts.vault, merchant_deposit, swept, current_time)?,
+            VaultError::RewardPayoutFlagged
         );
 
-        // Track total platform profit earned from this merchant
-        merchant_deposit.platform_profit_earned = merchant_deposit
-            .platform_profit_earned
-            .checked_add(platform_profit_amount)
-            .ok_or(VaultError::MathOverflow)?;
+        // Sweeps leave the vault like withdrawals: behind the queue and within epoch capacity
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.withdraw_queue_head == vault.withdraw_queue_tail, VaultError::WithdrawalQueueNotEmpty);
+        consume_withdraw_capacity(vault, swept, current_time)?;
+        consume_reward_payout(vault, swept, current_time)?;
 
-        // Calculate 50% profit share
-        let total_profit_share = platform_profit_amount
-            .checked_div(2)
+        promote_payout_wallet(&mut ctx.accounts.merchant_profile, current_time);
+        payout_config.promote_pending(current_time);
+        let spl_accounts = match (&ctx.accounts.vault_token_account, &ctx.accounts.token_program) {
+            (Some(vault_token), Some(token_program)) => Some((vault_token, token_program)),
+            _ => None,
+        };
+        pay_from_vault(
+            &ctx.accounts.vault,
+            &ctx.accounts.merchant_deposit,
+            &ctx.accounts.merchant_profile,
+            &ctx.accounts.vault_sol_account,
+            spl_accounts,
+            PayoutDestination {
+                payout_wallet: &ctx.accounts.payout_wallet,
+                payout_token_account: ctx.accounts.payout_token_account.as_ref().map(AsRef::as_ref),
+                splits: &payout_config.splits,
+                split_recipients: ctx.remaining_accounts,
+            },
+            swept,
+        )?;
+
+        ctx.accounts.merchant_deposit.accrued_rewards = 0;
+        payout_config.last_sweep_at = current_time;
+        let vault = &mut ctx.accounts.vault;
+        vault.total_rewards_paid = vault
+            .total_rewards_paid
+            .checked_add(swept)
             .ok_or(VaultError::MathOverflow)?;
 
-        // Calculate current APY cap for this merchant's lock period
-        let lock_max_apy = merchant_deposit.lock_period.max_apy_bps();
-        let total_deposited_value = merchant_deposit.total_deposited;
+        msg!("Swept {} rewards to payout wallet {}",
+            swept,
+            ctx.accounts.merchant_profile.payout_wallet
+        );
+        Ok(())
+    }
 
-        // Calculate maximum allowed profit share allocation (to not exceed APY cap)
-        // max_profit_share_bps = lock_max_apy - BASE_YIELD_BPS - volume_bonus
-        const BASE_YIELD_BPS: u16 = 300;
-
-        // Current profit share in BPS
-        let current_profit_share_bps = if total_deposited_value > 0 {
-            ((merchant_deposit.profit_share_allocated as u128)
-                .checked_mul(10000)
-                .unwrap_or(0)
-                .checked_div(total_deposited_value as u128)
-                .unwrap_or(0)) as u16
-        } else {
-            0
-        };
+    /// Claim accrued rewards into the deposit's reward stream
+    /// The stream releases linearly over the vault's `reward_stream_duration` (see
+    /// `release_streamed_rewards`). Claiming into a running stream spreads everything
+    /// still locked, old and new, over a fresh period from now.
+    pub fn stream_rewards(ctx: Context<StreamRewards>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
-        // Available space for more profit share
-        let max_profit_share_bps = lock_max_apy.saturating_sub(BASE_YIELD_BPS);
-        let remaining_space_bps = max_profit_share_bps.saturating_sub(current_profit_share_bps);
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require!(vault.reward_stream_duration > 0, VaultError::RewardStreamingDisabled);
+        require_holding_period(merchant_deposit, vault)?;
 
-        // Convert remaining space to absolute amount
-        let max_additional_profit_share = if total_deposited_value > 0 {
-            (total_deposited_value as u128)
-                .checked_mul(remaining_space_bps as u128)
-                .unwrap_or(0)
-                .checked_div(10000)
-                .unwrap_or(0) as u64
-        } else {
-            0
-        };
+        let current_time = time::now()?;
+        let harvest = accrue_rewards(merchant_deposit, vault, current_time)?;
+        if let Some(harvested) = book_harvest(vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
 
-        // Split profit share: allocate up to cap, excess goes to immediate rewards
-        let (profit_share_allocated, excess_rewards) = if total_profit_share <= max_additional_profit_share {
-            // All profit share fits within APY cap
-            (total_profit_share, 0)
-        } else {
-            // Cap profit share, rest becomes immediate rewards
-            (max_additional_profit_share, total_profit_share - max_additional_profit_share)
-        };
+        let claimed = merchant_deposit.accrued_rewards;
+        require!(claimed > 0, VaultError::NoRewardsToStream);
 
-        // Update profit share allocation
-        merchant_deposit.profit_share_allocated = merchant_deposit
-            .profit_share_allocated
-            .checked_add(profit_share_allocated)
-            .ok_or(VaultError::MathOverflow)?;
+        // Flagged rewards leave through a guardian co-signed withdrawal instead
+        require!(
+            !reward_payout_flagged(vault, merchant_deposit, claimed, current_time)?,
+            VaultError::RewardPayoutFlagged
+        );
 
-        // Add excess to accrued rewards (doesn't affect APY, immediate payout)
-        merchant_deposit.accrued_rewards = merchant_deposit
-            .accrued_rewards
-            .checked_add(excess_rewards)
+        merchant_deposit.unlock_reward_stream(current_time)?;
+        merchant_deposit.streaming_rewards = merchant_deposit
+            .streaming_rewards
+            .checked_add(claimed)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.accrued_rewards = 0;
+        merchant_deposit.stream_checkpoint_at = current_time;
+        merchant_deposit.stream_ends_at = current_time
+            .checked_add(vault.reward_stream_duration)
             .ok_or(VaultError::MathOverflow)?;
 
-        // Recalculate yield with new profit share
-        merchant_deposit.current_yield_bps = calculate_dynamic_yield(
-            merchant_deposit,
-            merchant_deposit.total_deposited,
-        );
-
-        msg!(
-            "Platform profit recorded: ${} | Profit share: ${} | Excess rewards: ${} | New yield: {}%",
-            platform_profit_amount / 1_000000,
-            profit_share_allocated / 1_000000,
-            excess_rewards / 1_000000,
-            merchant_deposit.current_yield_bps as f64 / 100.0
+        msg!("Streaming {} rewards | {} in stream until {}",
+            claimed,
+            merchant_deposit.streaming_rewards,
+            merchant_deposit.stream_ends_at
         );
-
         Ok(())
     }
 
-    /// Register an agent to process orders for a merchant
-    /// Only the merchant can authorize their own agents
-    pub fn register_agent(ctx: Context<RegisterAgent>) -> Result<()> {
-        let authorized_agent = &mut ctx.accounts.authorized_agent;
-        let current_time = Clock::get()?.unix_timestamp;
+    /// Pay the unlocked part of a merchant's reward stream to their payout wallet
+    /// (permissionless crank)
+    /// Payout splits apply if the merchant has a payout config, with one recipient
+    /// per active split in `remaining_accounts`.
+    pub fn release_streamed_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReleaseStreamedRewards<'info>>,
+    ) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
 
-        authorized_agent.merchant = ctx.accounts.merchant.key();
-        authorized_agent.agent = ctx.accounts.agent.key();
-        authorized_agent.vault = ctx.accounts.vault.key();
-        authorized_agent.authorized_at = current_time;
-        authorized_agent.is_active = true;
-        authorized_agent.bump = ctx.bumps.authorized_agent;
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
 
-        msg!(
-            "Agent {} authorized for merchant {}",
-            ctx.accounts.agent.key(),
-            ctx.accounts.merchant.key()
-        );
+        let current_time = time::now()?;
+        merchant_deposit.unlock_reward_stream(current_time)?;
+        let released = merchant_deposit.stream_unlocked;
+        require!(released > 0, VaultError::NothingToRelease);
+
+        // Releases leave the vault like sweeps: behind the queue and within epoch capacity
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.withdraw_queue_head == vault.withdraw_queue_tail, VaultError::WithdrawalQueueNotEmpty);
+        consume_withdraw_capacity(vault, released, current_time)?;
+        consume_reward_payout(vault, released, current_time)?;
+
+        promote_payout_wallet(&mut ctx.accounts.merchant_profile, current_time);
+        let config_info = ctx.accounts.payout_config.to_account_info();
+        let splits = if config_info.owner == &crate::ID && !config_info.data_is_empty() {
+            let payout_config = PayoutConfig::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+            payout_config.active_splits(current_time).to_vec()
+        } else {
+            Vec::new()
+        };
+        let spl_accounts = match (&ctx.accounts.vault_token_account, &ctx.accounts.token_program) {
+            (Some(vault_token), Some(token_program)) => Some((vault_token, token_program)),
+            _ => None,
+        };
+        pay_from_vault(
+            &ctx.accounts.vault,
+            &ctx.accounts.merchant_deposit,
+            &ctx.accounts.merchant_profile,
+            &ctx.accounts.vault_sol_account,
+            spl_accounts,
+            PayoutDestination {
+                payout_wallet: &ctx.accounts.payout_wallet,
+                payout_token_account: ctx.accounts.payout_token_account.as_ref().map(AsRef::as_ref),
+                splits: &splits,
+                split_recipients: ctx.remaining_accounts,
+            },
+            released,
+        )?;
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.streaming_rewards = merchant_deposit
+            .streaming_rewards
+            .checked_sub(released)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.stream_unlocked = 0;
+        let vault = &mut ctx.accounts.vault;
+        vault.total_rewards_paid = vault
+            .total_rewards_paid
+            .checked_add(released)
+            .ok_or(VaultError::MathOverflow)?;
 
+        msg!("Released {} streamed rewards | {} left in stream",
+            released,
+            merchant_deposit.streaming_rewards
+        );
         Ok(())
     }
 
-    /// Revoke agent authorization
-    /// Only the merchant can revoke their own agents
-    pub fn revoke_agent(ctx: Context<RevokeAgent>) -> Result<()> {
-        let authorized_agent = &mut ctx.accounts.authorized_agent;
+    /// Queue a full withdrawal when the epoch withdrawal cap is exhausted
+    /// Tickets are fulfilled in request order by `fulfill_withdrawals` as capacity
+    /// and liquidity free up; rewards keep accruing until then
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require!(!merchant_deposit.withdrawal_queued, VaultError::WithdrawalAlreadyQueued);
+        // Queue fulfilment has no sponsorship account to hand the sponsor's share to
+        require!(merchant_deposit.sponsor == Pubkey::default(), VaultError::SponsoredPosition);
+        // ...nor share tokens to burn
+        require!(merchant_deposit.shares == 0, VaultError::PositionTokenized);
+        require_holding_period(merchant_deposit, vault)?;
 
+        let current_time = time::now()?;
         require!(
-            authorized_agent.merchant == ctx.accounts.merchant.key(),
-            VaultError::Unauthorized
+            current_time >= merchant_deposit.unlock_time
+                && current_time >= merchant_deposit.term_maturity,
+            VaultError::DepositStillLocked
         );
 
-        authorized_agent.is_active = false;
+        let withdrawal_ticket = &mut ctx.accounts.withdrawal_ticket;
+        withdrawal_ticket.vault = vault.key();
+        withdrawal_ticket.merchant = merchant_deposit.merchant;
+        withdrawal_ticket.id = vault.withdraw_queue_tail;
+        withdrawal_ticket.requested_at = current_time;
+        withdrawal_ticket.cancelled = false;
+        withdrawal_ticket.bump = ctx.bumps.withdrawal_ticket;
 
-        msg!(
-            "Agent {} revoked for merchant {}",
-            authorized_agent.agent,
-            authorized_agent.merchant
-        );
+        vault.withdraw_queue_tail = vault
+            .withdraw_queue_tail
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.withdrawal_queued = true;
 
+        msg!("Withdrawal ticket #{} queued for merchant {}",
+            withdrawal_ticket.id,
+            withdrawal_ticket.merchant
+        );
         Ok(())
     }
-}
 
-// ============================================================================
-// Yield Calculation Functions
-// ============================================================================
+    /// Fulfill queued withdrawal tickets in FIFO order (permissionless crank)
+    /// Each ticket is passed in `remaining_accounts` as a group of
+    /// `WITHDRAWAL_TICKET_ACCOUNTS`: ticket, deposit, profile, merchant, payout
+    /// wallet, payout token account (any account for SOL deposits), payout config,
+    /// followed by one recipient per active payout split. Stops at the first
+    /// ticket the vault can't cover yet, either for lack of liquidity or of epoch
+    /// capacity; cancelled and stale tickets are closed and skipped.
+    pub fn fulfill_withdrawals<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FulfillWithdrawals<'info>>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+        require!(!ctx.remaining_accounts.is_empty(), VaultError::InvalidTicketAccounts);
 
-/// Calculate dynamic yield based on lock period, volume, and profit sharing
-/// Returns yield in basis points (BPS), e.g., 650 = 6.5%
-///
-/// New Economic Model (MVP):
-/// - Base: 3% guaranteed
-/// - Volume Bonus: Linear scaling based on monthly volume
-/// - Profit Share Bonus: Up to 50% of platform profit from merchant's orders
-/// - Capped at lock period maximum APY
-fn calculate_dynamic_yield(merchant_deposit: &MerchantDeposit, total_deposited_value: u64) -> u16 {
-    const BASE_YIELD_BPS: u16 = 300; // 3.00% guaranteed
-    const TARGET_MONTHLY_VOLUME: u64 = 1_000_000_000000; // $1M target for max volume bonus
-
-    // 1. Start with base 3%
-    let mut yield_bps = BASE_YIELD_BPS;
-
-    // 2. Calculate profit share bonus (up to 50% of platform profit)
-    // Convert profit share to APY percentage
-    let profit_share_bonus_bps = if total_deposited_value > 0 {
-        // profit_share_allocated is in micro-units (USDC 6 decimals)
-        // Calculate as percentage of deposit
-        let bonus_ratio = (merchant_deposit.profit_share_allocated as u128)
-            .checked_mul(10000) // Convert to basis points
-            .unwrap_or(0)
-            .checked_div(total_deposited_value as u128)
-            .unwrap_or(0);
-        bonus_ratio.min(u16::MAX as u128) as u16
-    } else {
-        0
-    };
+        let current_time = time::now()?;
+        let vault_key = ctx.accounts.vault.key();
+        let sol_reserve = Rent::get()?.minimum_balance(ctx.accounts.vault_sol_account.data_len());
 
-    yield_bps = yield_bps.saturating_add(profit_share_bonus_bps);
+        let remaining_accounts = ctx.remaining_accounts;
+        let mut cursor = 0;
+        let mut fulfilled: u32 = 0;
+        let mut skipped: u32 = 0;
+        while cursor < remaining_accounts.len() {
+            let group = remaining_accounts
+                .get(cursor..cursor + WITHDRAWAL_TICKET_ACCOUNTS)
+                .ok_or(VaultError::InvalidTicketAccounts)?;
+            let (ticket_info, deposit_info, profile_info) = (&group[0], &group[1], &group[2]);
+            let (merchant_info, payout_wallet_info, payout_token_info) = (&group[3], &group[4], &group[5]);
+            let payout_config_info = &group[6];
 
-    // 3. Calculate available space for volume bonus
-    let lock_max_apy = merchant_deposit.lock_period.max_apy_bps();
-    let available_for_volume = lock_max_apy.saturating_sub(yield_bps);
+            let withdrawal_ticket = Account::<WithdrawalTicket>::try_from(ticket_info)?;
+            require!(
+                withdrawal_ticket.vault == vault_key
+                    && withdrawal_ticket.id == ctx.accounts.vault.withdraw_queue_head,
+                VaultError::NotQueueHead
+            );
+            require_keys_eq!(merchant_info.key(), withdrawal_ticket.merchant, VaultError::InvalidTicketAccounts);
 
-    // 4. Calculate volume bonus (linear scaling)
-    // Scales from 0 to available_for_volume based on monthly volume
-    let volume_bonus_bps = if available_for_volume > 0 {
-        if merchant_deposit.current_month_volume >= TARGET_MONTHLY_VOLUME {
-            available_for_volume
-        } else {
-            // Linear: (current_volume / target_volume) * available_space
-            let volume_ratio = (merchant_deposit.current_month_volume as u128)
-                .checked_mul(available_for_volume as u128)
-                .unwrap_or(0)
-                .checked_div(TARGET_MONTHLY_VOLUME as u128)
-                .unwrap_or(0);
-            volume_ratio.min(available_for_volume as u128) as u16
-        }
-    } else {
-        0
-    };
+            let (payout_config_key, _) = Pubkey::find_program_address(
+                &[PAYOUT_CONFIG_SEED, vault_key.as_ref(), withdrawal_ticket.merchant.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(payout_config_info.key(), payout_config_key, VaultError::InvalidTicketAccounts);
+            let splits = load_payout_splits(payout_config_info, current_time)?;
+            let split_start = cursor + WITHDRAWAL_TICKET_ACCOUNTS;
+            let split_recipients = remaining_accounts
+                .get(split_start..split_start + splits.len())
+                .ok_or(VaultError::InvalidTicketAccounts)?;
+            cursor = split_start + splits.len();
 
-    yield_bps = yield_bps.saturating_add(volume_bonus_bps);
+            let (deposit_key, _) = Pubkey::find_program_address(
+                &[DEPOSIT_SEED, vault_key.as_ref(), withdrawal_ticket.merchant.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(deposit_info.key(), deposit_key, VaultError::InvalidTicketAccounts);
 
-    // 5. Cap at lock period maximum
-    yield_bps = yield_bps.min(lock_max_apy);
+            // Cancelled, or the deposit already left (e.g. via emergency_withdraw)
+            let deposit_live = deposit_info.owner == ctx.program_id && !deposit_info.data_is_empty();
+            let mut merchant_deposit = if withdrawal_ticket.cancelled || !deposit_live {
+                None
+            } else {
+                Some(Account::<MerchantDeposit>::try_from(deposit_info)?)
+            };
+            if let Some(deposit) = merchant_deposit.as_mut() {
+                if !deposit.withdrawal_queued {
+                    merchant_deposit = None;
+                } else if deposit.frozen {
+                    // Frozen deposits lose their place rather than block the queue
+                    deposit.withdrawal_queued = false;
+                    deposit.exit(ctx.program_id)?;
+                    merchant_deposit = None;
+                }
+            }
+            let Some(mut merchant_deposit) = merchant_deposit else {
+                withdrawal_ticket.close(merchant_info.clone())?;
+                advance_withdraw_queue(&mut ctx.accounts.vault)?;
+                skipped += 1;
+                continue;
+            };
 
-    yield_bps
-}
+            let harvest = accrue_rewards(&mut merchant_deposit, &ctx.accounts.vault, current_time)?;
+            if let Some(harvested) = book_harvest(&mut ctx.accounts.vault, &merchant_deposit, harvest, current_time)? {
+                emit_cpi!(harvested);
+            }
+            merchant_deposit.end_reward_stream()?;
+            let merchant_rewards = merchant_deposit.accrued_rewards;
 
-/// Calculate merchant tier (0=Bronze, 1=Silver, 2=Gold, 3=Platinum)
-fn calculate_merchant_tier(monthly_volume_usd: u64, deposited_at: i64, current_time: i64) -> u8 {
-    let days_deposited = (current_time - deposited_at) / 86400;
+            // Flagged rewards lose their place too; the merchant exits with the guardian's co-signature
+            if reward_payout_flagged(&ctx.accounts.vault, &merchant_deposit, merchant_rewards, current_time)? {
+                merchant_deposit.withdrawal_queued = false;
+                merchant_deposit.exit(ctx.program_id)?;
+                withdrawal_ticket.close(merchant_info.clone())?;
+                advance_withdraw_queue(&mut ctx.accounts.vault)?;
+                skipped += 1;
+                continue;
+            }
+            let total_withdrawal = merchant_deposit.total_deposited
+                .checked_add(merchant_rewards)
+                .ok_or(VaultError::MathOverflow)?;
 
-    // Tier thresholds
-    const TIER_BRONZE: u8 = 0;
-    const TIER_SILVER: u8 = 1;
-    const TIER_GOLD: u8 = 2;
-    const TIER_PLATINUM: u8 = 3;
+            // Wait for liquidity (strategy unwinding) and epoch capacity
+            let liquidity = match merchant_deposit.deposit_token {
+                DepositType::Sol => ctx.accounts.vault_sol_account.lamports().saturating_sub(sol_reserve),
+                DepositType::SplToken => match ctx.accounts.vault_token_account.as_ref() {
+                    Some(vault_token_account) if vault_token_account.mint == merchant_deposit.mint => {
+                        vault_token_account.amount
+                    }
+                    _ => break,
+                },
+            };
+            let capacity = withdraw_capacity_remaining(&mut ctx.accounts.vault, current_time)?;
+            let reward_capacity = reward_payout_remaining(&mut ctx.accounts.vault, current_time)?;
+            if total_withdrawal > liquidity || total_withdrawal > capacity || merchant_rewards > reward_capacity {
+                break;
+            }
 
-    // Volume in micro-units (6 decimals): $10k = 10_000_000000
-    const VOLUME_SILVER: u64 = 10_000_000000;    // $10k/month
-    const VOLUME_GOLD: u64 = 50_000_000000;      // $50k/month
-    const VOLUME_PLATINUM: u64 = 200_000_000000; // $200k/month
+            let mut merchant_profile = Account::<MerchantProfile>::try_from(profile_info)?;
+            let profile_key = Pubkey::create_program_address(
+                &[
+                    MERCHANT_PROFILE_SEED,
+                    vault_key.as_ref(),
+                    withdrawal_ticket.merchant.as_ref(),
+                    &[merchant_profile.bump],
+                ],
+                ctx.program_id,
+            )
+            .map_err(|_| VaultError::InvalidTicketAccounts)?;
+            require_keys_eq!(profile_info.key(), profile_key, VaultError::InvalidTicketAccounts);
+            promote_payout_wallet(&mut merchant_profile, current_time);
 
-    const DAYS_SILVER: i64 = 90;
-    const DAYS_GOLD: i64 = 180;
-    const DAYS_PLATINUM: i64 = 365;
+            let payout_token_account = match merchant_deposit.deposit_token {
+                DepositType::Sol => None,
+                DepositType::SplToken => Some(payout_token_info),
+            };
+            let spl_accounts = match (
+                ctx.accounts.vault_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) {
+                (Some(vault_token), Some(token_program)) => Some((vault_token, token_program)),
+                _ => None,
+            };
+            pay_from_vault(
+                &ctx.accounts.vault,
+                &merchant_deposit,
+                &merchant_profile,
+                &ctx.accounts.vault_sol_account,
+                spl_accounts,
+                PayoutDestination {
+                    payout_wallet: payout_wallet_info,
+                    payout_token_account,
+                    splits: &splits,
+                    split_recipients,
+                },
+                total_withdrawal,
+            )?;
 
-    // Determine tier based on both volume AND time
-    if monthly_volume_usd >= VOLUME_PLATINUM && days_deposited >= DAYS_PLATINUM {
-        TIER_PLATINUM
-    } else if monthly_volume_usd >= VOLUME_GOLD && days_deposited >= DAYS_GOLD {
-        TIER_GOLD
-    } else if monthly_volume_usd >= VOLUME_SILVER && days_deposited >= DAYS_SILVER {
-        TIER_SILVER
-    } else {
-        TIER_BRONZE
-    }
-}
+            let vault = &mut ctx.accounts.vault;
+            consume_withdraw_capacity(vault, total_withdrawal, current_time)?;
+            consume_reward_payout(vault, merchant_rewards, current_time)?;
+            merchant_deposit.is_active = false;
+            merchant_deposit.withdrawal_queued = false;
+            merchant_profile.current_tier = merchant_deposit.current_tier;
+            merchant_profile.last_withdrawn_at = current_time;
+            remove_deposit_from_vault(vault, &merchant_deposit, merchant_rewards)?;
+            advance_withdraw_queue(vault)?;
+            let supported_mint = match merchant_deposit.deposit_token {
+                DepositType::Sol => &ctx.accounts.sol_supported_mint,
+                DepositType::SplToken => &ctx.accounts.token_supported_mint,
+            };
+            debit_mint_deposits(supported_mint, &merchant_deposit)?;
+
+            merchant_profile.exit(ctx.program_id)?;
+            merchant_deposit.close(merchant_info.clone())?;
+            withdrawal_ticket.close(merchant_info.clone())?;
+            fulfilled += 1;
+
+            msg!("Withdrawal ticket #{} fulfilled: {} (deposit: {}, rewards: {})",
+                withdrawal_ticket.id,
+                total_withdrawal,
+                merchant_deposit.total_deposited,
+                merchant_rewards
+            );
+        }
+
+        debug_assert_invariants(&ctx.accounts.vault);
 
-/// Get tier name for display
-fn tier_name(tier: u8) -> &'static str {
-    match tier {
-        0 => "Bronze",
-        1 => "Silver",
-        2 => "Gold",
-        3 => "Platinum",
-        _ => "Unknown",
+        msg!("Withdrawal queue: {} fulfilled, {} skipped, next ticket #{}",
+            fulfilled,
+            skipped,
+            ctx.accounts.vault.withdraw_queue_head
+        );
+        Ok(())
     }
-}
 
-// ============================================================================
-// Account Contexts
-// ============================================================================
+    /// Cancel a queued withdrawal; the deposit stays in the vault
+    /// A ticket at the head of the queue is closed right away; others are marked
+    /// cancelled and closed by the crank when they reach the head
+    pub fn cancel_withdrawal_request(ctx: Context<CancelWithdrawalRequest>) -> Result<()> {
+        ctx.accounts.merchant_deposit.withdrawal_queued = false;
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Vault::LEN,
-        seeds = [b"vault", authority.key().as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, Vault>,
+        let ticket_id = ctx.accounts.withdrawal_ticket.id;
+        if ticket_id == ctx.accounts.vault.withdraw_queue_head {
+            ctx.accounts.withdrawal_ticket.close(ctx.accounts.merchant.to_account_info())?;
+            advance_withdraw_queue(&mut ctx.accounts.vault)?;
+        } else {
+            ctx.accounts.withdrawal_ticket.cancelled = true;
+        }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        msg!("Withdrawal ticket #{} cancelled", ticket_id);
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Configure per-epoch withdrawal limits (admin only)
+    /// Each epoch allows the lower of `cap_amount` and `cap_tvl_bps` of TVL at the
+    /// epoch start to leave the vault; 0 disables either cap
+    pub fn set_withdrawal_limits(
+        ctx: Context<UpdateVaultConfig>,
+        epoch_duration: i64,
+        cap_amount: u64,
+        cap_tvl_bps: u16,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-#[derive(Accounts)]
-pub struct DepositSol<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+        ctx.accounts.apply_config_change(
+            &ConfigChange::WithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps },
+        )
+    }
 
-    #[account(
-        init,
-        payer = merchant,
-        space = 8 + MerchantDeposit::LEN,
-        seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()],
-        bump
-    )]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+    /// Configure per-merchant order rate limits (admin only)
+    /// Orders beyond `max_orders_per_hour` are rejected; volume beyond
+    /// `max_daily_order_volume` is recorded but not credited toward yield or
+    /// tier. 0 disables either limit.
+    pub fn set_order_rate_limits(
+        ctx: Context<UpdateVaultConfig>,
+        max_orders_per_hour: u32,
+        max_daily_order_volume: u64,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    /// CHECK: Vault's SOL account (PDA)
-    #[account(mut)]
-    pub vault_sol_account: AccountInfo<'info>,
+        ctx.accounts.apply_config_change(
+            &ConfigChange::OrderRateLimits { max_orders_per_hour, max_daily_order_volume },
+        )
+    }
 
-    #[account(mut)]
-    pub merchant: Signer<'info>,
+    /// Configure repeat-purchase dampening (admin only)
+    /// Within each `window` (seconds), a buyer's first, second and later purchases
+    /// from a merchant credit `credit_bps[0]`, `[1]` and `[2]` of their volume
+    /// toward yield and tier. A window of 0 disables dampening.
+    pub fn set_repeat_purchase_dampening(
+        ctx: Context<UpdateVaultConfig>,
+        window: i64,
+        credit_bps: [u16; 3],
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    pub system_program: Program<'info, System>,
-}
+        ctx.accounts.apply_config_change(
+            &ConfigChange::RepeatPurchaseDampening { window, credit_bps },
+        )
+    }
 
-#[derive(Accounts)]
-pub struct DepositTokenAccounts<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+    /// Configure the tier downgrade grace period (admin only)
+    /// A merchant whose volume dips below their tier keeps it for `grace_period`
+    /// seconds before the metrics crank downgrades them. 0 downgrades immediately.
+    pub fn set_tier_downgrade_grace(ctx: Context<UpdateVaultConfig>, grace_period: i64) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(
-        init,
-        payer = merchant,
-        space = 8 + MerchantDeposit::LEN,
-        seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()],
-        bump
-    )]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+        ctx.accounts.apply_config_change(&ConfigChange::TierDowngradeGrace { grace_period })
+    }
 
-    #[account(mut)]
-    pub merchant_token_account: Account<'info, TokenAccount>,
+    /// Set the platform fee charged by `settle_order` for each tier (admin only)
+    /// Bronze, Silver, Gold, Platinum; a higher tier can never pay more than a lower one
+    pub fn set_platform_fees(ctx: Context<UpdateVaultConfig>, platform_fee_bps_by_tier: [u16; 4]) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
+        ctx.accounts.apply_config_change(&ConfigChange::PlatformFees { platform_fee_bps_by_tier })
+    }
 
-    #[account(mut)]
-    pub merchant: Signer<'info>,
+    /// Configure the reward payout circuit breaker (admin only)
+    /// `payout_cap` limits the rewards paid out per withdrawal epoch (0 = no cap).
+    /// A payout whose rewards exceed `anomaly_multiple_bps` of the most its deposit
+    /// could have accrued needs the guardian's co-signature (0 = no check).
+    pub fn set_reward_circuit_breaker(
+        ctx: Context<UpdateVaultConfig>,
+        payout_cap: u64,
+        anomaly_multiple_bps: u32,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        ctx.accounts.apply_config_change(
+            &ConfigChange::RewardCircuitBreaker { payout_cap, anomaly_multiple_bps },
+        )
+    }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+    /// Set how many slots a deposit is held before withdrawals and reward claims (admin only)
+    /// At least 1, so a deposit can't be withdrawn or claimed against in its own slot
+    pub fn set_holding_period(ctx: Context<UpdateVaultConfig>, min_holding_slots: u64) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(
-        mut,
-        seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()],
-        bump = merchant_deposit.bump
-    )]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+        ctx.accounts.apply_config_change(&ConfigChange::HoldingPeriod { min_holding_slots })
+    }
 
-    /// CHECK: Vault's SOL account (PDA)
-    #[account(mut)]
-    pub vault_sol_account: AccountInfo<'info>,
+    /// Set the smallest order credited toward volume metrics (admin only)
+    /// Also the floor for invoices and subscription charges. Must lie between
+    /// LOWEST_MIN_ORDER_USD and HIGHEST_MIN_ORDER_USD.
+    pub fn set_min_order_amount(ctx: Context<UpdateVaultConfig>, min_order_usd: u64) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(mut)]
-    pub merchant_token_account: Option<Account<'info, TokenAccount>>,
+        ctx.accounts.apply_config_change(&ConfigChange::MinOrderAmount { min_order_usd })
+    }
 
-    #[account(mut)]
-    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+    /// Set how the vault's SOL is staked (admin only)
+    /// `Jito` stakes with a Jito validator set, whose MEV tips can then be
+    /// harvested separately from inflation rewards
+    pub fn set_stake_strategy(ctx: Context<UpdateVaultConfig>, strategy: StakeStrategy) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(mut)]
-    pub merchant: Signer<'info>,
+        ctx.accounts.apply_config_change(&ConfigChange::StakeStrategy { strategy })
+    }
 
-    pub token_program: Option<Program<'info, Token>>,
-    pub system_program: Program<'info, System>,
-}
+    /// Set the share of each SPL mint's deposits kept undeployed for instant withdrawals (admin only)
+    /// Supplying to lending strategies can't dip the idle balance below it. The
+    /// buffer and the strategies' target allocations can't exceed 100% together.
+    pub fn set_liquidity_buffer(ctx: Context<UpdateVaultConfig>, liquidity_buffer_bps: u16) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-#[derive(Accounts)]
-pub struct UpdateVaultConfig<'info> {
-    #[account(mut, seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+        ctx.accounts.apply_config_change(&ConfigChange::LiquidityBuffer { liquidity_buffer_bps })
+    }
 
-    pub authority: Signer<'info>,
-}
+    /// Set when deposit yield accrues (admin only)
+    /// `SolanaEpoch` accrues only up to the start of the current Solana epoch,
+    /// matching when staking rewards land; harvests then need the epoch's record.
+    pub fn set_accrual_schedule(ctx: Context<UpdateVaultConfig>, schedule: AccrualSchedule) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-#[derive(Accounts)]
-pub struct CalculateRewards<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+        ctx.accounts.apply_config_change(&ConfigChange::AccrualSchedule { schedule })
+    }
 
-    #[account(seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+    /// Set how long streamed rewards take to be released (admin only, 0 = streaming off)
+    /// Streams already running keep their schedule until they are topped up.
+    pub fn set_reward_stream_duration(ctx: Context<UpdateVaultConfig>, duration: i64) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    pub merchant: Signer<'info>,
-}
+        ctx.accounts.apply_config_change(&ConfigChange::RewardStreamDuration { duration })
+    }
 
-#[derive(Accounts)]
-pub struct RecordOrder<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+    /// Restrict vault share transfers to approved holders, or lift the restriction (admin only)
+    /// Minting and burning are unaffected; see `approve_share_holder`.
+    pub fn set_share_transfer_restriction(ctx: Context<UpdateVaultConfig>, restricted: bool) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    #[account(
-        mut,
-        seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()],
-        bump = merchant_deposit.bump
-    )]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+        ctx.accounts.apply_config_change(&ConfigChange::ShareTransferRestriction { restricted })
+    }
 
-    /// Authorization record for this agent-merchant pair
-    #[account(
-        seeds = [b"agent_auth", vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
-        bump = authorized_agent.bump
-    )]
-    pub authorized_agent: Account<'info, AuthorizedAgent>,
+    /// Set the program notified of deposits and settlements (admin only, default = none)
+    /// See the `notifier` module for the interface. While one is set, deposits and
+    /// settlements must pass it and the notify authority first in `remaining_accounts`.
+    pub fn set_notifier(ctx: Context<UpdateVaultConfig>, program: Pubkey) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
 
-    /// Agent that processed the order (must be merchant's agent)
-    pub agent: Signer<'info>,
+        ctx.accounts.apply_config_change(&ConfigChange::Notifier { program })
+    }
 
-    /// Merchant wallet (for verification)
+    /// Require KYC attestations for deposits and set who issues them (admin only)
+    /// Changing the verifier invalidates attestations issued by the previous one.
+    /// Deposits already in the vault are unaffected.
+    pub fn set_kyc_policy(ctx: Context<UpdateVaultConfig>, required: bool, verifier: Pubkey) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(&ConfigChange::KycPolicy { required, verifier })
+    }
+
+    /// Attest that `wallet` passed KYC (vault's KYC verifier only)
+    /// `expires_at` of 0 never expires. Renewing means revoking and issuing again.
+    pub fn issue_kyc_attestation(ctx: Context<IssueKycAttestation>, wallet: Pubkey, expires_at: i64) -> Result<()> {
+        require_keys_eq!(ctx.accounts.verifier.key(), ctx.accounts.vault.kyc_verifier, VaultError::Unauthorized);
+
+        let current_time = time::now()?;
+        require!(expires_at == 0 || expires_at > current_time, VaultError::KycAttestationExpired);
+
+        let kyc_attestation = &mut ctx.accounts.kyc_attestation;
+        kyc_attestation.vault = ctx.accounts.vault.key();
+        kyc_attestation.wallet = wallet;
+        kyc_attestation.verifier = ctx.accounts.verifier.key();
+        kyc_attestation.issued_at = current_time;
+        kyc_attestation.expires_at = expires_at;
+        kyc_attestation.bump = ctx.bumps.kyc_attestation;
+
+        msg!("KYC attestation issued for {} (expires {})", wallet, expires_at);
+        Ok(())
+    }
+
+    /// Revoke a wallet's KYC attestation (the verifier or the vault authority)
+    /// Only blocks new deposits; existing ones stay in the vault.
+    pub fn revoke_kyc_attestation(ctx: Context<RevokeKycAttestation>) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let vault = &ctx.accounts.vault;
+        require!(signer == vault.kyc_verifier || signer == vault.authority, VaultError::Unauthorized);
+
+        msg!("KYC attestation of {} revoked", ctx.accounts.kyc_attestation.wallet);
+        Ok(())
+    }
+
+    /// Configure the settlement sandwich guard (admin only)
+    /// `blocked_token_ixs` is a bitmask of SPL Token instruction tags (bit n = tag n)
+    /// that `settle_order` rejects before (`guard_before`) and/or after
+    /// (`guard_after`) it in the same transaction. Both off disables the guard.
+    pub fn set_settlement_guard(
+        ctx: Context<UpdateVaultConfig>,
+        guard_before: bool,
+        guard_after: bool,
+        blocked_token_ixs: u32,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(
+            &ConfigChange::SettlementGuard { guard_before, guard_after, blocked_token_ixs },
+        )
+    }
+
+    /// Enable or disable emergency mode (guardian only)
+    /// While enabled: deposits and regular withdrawals are disabled, reward
+    /// accrual stops, and merchants exit through `emergency_withdraw`. The time
+    /// spent in it counts toward the vault's suspended seconds, so it stays skipped.
+    pub fn set_emergency_mode(ctx: Context<SetEmergencyMode>, enabled: bool) -> Result<()> {
+        require!(ctx.accounts.guardian.key() == ctx.accounts.vault.guardian, VaultError::Unauthorized);
+
+        let current_time = time::now()?;
+        let vault = &mut ctx.accounts.vault;
+        vault.update_suspension(current_time, |vault| {
+            if enabled && !vault.emergency_mode {
+                vault.emergency_activated_at = current_time;
+            }
+            vault.emergency_mode = enabled;
+            Ok(())
+        })?;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::EmergencyMode,
+            ctx.accounts.guardian.key(),
+            Pubkey::default(),
+            0,
+            enabled as u16,
+        )?;
+
+        msg!("Emergency mode {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    /// Hand the guardian role to a new key (admin only)
+    pub fn set_guardian(ctx: Context<UpdateVaultConfig>, new_guardian: Pubkey) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(&ConfigChange::Guardian { new_guardian })
+    }
+
+    /// Update vault parameters (admin only)
+    pub fn update_vault_config(
+        ctx: Context<UpdateVaultConfig>,
+        min_deposit_sol: Option<u64>,
+        min_deposit_token: Option<u64>,
+        staking_enabled: Option<bool>,
+        cashback_bps: Option<u16>,
+        referral_fee_bps: Option<u16>,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(
+            &ConfigChange::VaultConfig {
+                min_deposit_sol,
+                min_deposit_token,
+                staking_enabled,
+                cashback_bps,
+                referral_fee_bps,
+            },
+        )
+    }
+
+    /// Update the fixed-term lock boost schedule and early-exit penalty (admin only)
+    /// Boosts apply to new deposits; existing positions keep the boost they locked in
+    pub fn set_lock_terms(
+        ctx: Context<UpdateVaultConfig>,
+        lock_boost_schedule: [u16; 4],
+        early_exit_penalty_bps: u16,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(
+            &ConfigChange::LockTerms { lock_boost_schedule, early_exit_penalty_bps },
+        )
+    }
+
+    /// Set the delay config changes must wait before taking effect (admin only)
+    /// While non-zero, the config setters are disabled and changes go through
+    /// `propose_config_change`. The delay can be raised directly; lowering or
+    /// removing it is itself a timelocked change.
+    pub fn set_config_timelock(ctx: Context<UpdateVaultConfig>, delay: i64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(delay >= ctx.accounts.vault.config_timelock, VaultError::ConfigTimelocked);
+
+        ctx.accounts.apply_config_change(&ConfigChange::ConfigTimelock { delay })
+    }
+
+    /// Queue a config change behind the vault's timelock (admin only)
+    /// One proposal can be pending per vault; the guardian can veto it until executed
+    pub fn propose_config_change(ctx: Context<ProposeConfigChange>, change: ConfigChange) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        validate_config_change(&change)?;
+
+        let current_time = time::now()?;
+        let executable_at = current_time
+            .checked_add(ctx.accounts.vault.config_timelock)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let config_proposal = &mut ctx.accounts.config_proposal;
+        config_proposal.vault = ctx.accounts.vault.key();
+        config_proposal.proposer = ctx.accounts.authority.key();
+        config_proposal.change = change.clone();
+        config_proposal.proposed_at = current_time;
+        config_proposal.executable_at = executable_at;
+        config_proposal.bump = ctx.bumps.config_proposal;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ConfigProposed,
+            config_proposal.proposer,
+            config_proposal.key(),
+            0,
+            change.code(),
+        )?;
+
+        emit_cpi!(ConfigChangeProposed {
+            vault: config_proposal.vault,
+            proposer: config_proposal.proposer,
+            change,
+            executable_at,
+        });
+
+        msg!("Config change proposed, executable at {}", executable_at);
+        Ok(())
+    }
+
+    /// Apply a pending config change once its timelock has elapsed (admin only)
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let current_time = time::now()?;
+        let config_proposal = &ctx.accounts.config_proposal;
+        require!(current_time >= config_proposal.executable_at, VaultError::ConfigProposalNotReady);
+
+        let change = config_proposal.change.clone();
+        apply_config_change(&mut ctx.accounts.vault, &change)?;
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ConfigApplied,
+            ctx.accounts.authority.key(),
+            config_proposal.key(),
+            0,
+            change.code(),
+        )?;
+
+        emit_cpi!(ConfigChangeExecuted {
+            vault: ctx.accounts.vault.key(),
+            change,
+            executed_at: current_time,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a pending config change (guardian veto, or the authority withdrawing it)
+    /// The guardian can only cancel proposals, never initiate them
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        let canceller = ctx.accounts.canceller.key();
+        let vault = &ctx.accounts.vault;
+        require!(
+            canceller == vault.guardian || canceller == vault.authority,
+            VaultError::Unauthorized
+        );
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ConfigCancelled,
+            canceller,
+            ctx.accounts.config_proposal.key(),
+            0,
+            ctx.accounts.config_proposal.change.code(),
+        )?;
+
+        emit_cpi!(ConfigChangeCancelled {
+            vault: vault.key(),
+            cancelled_by: canceller,
+            change: ctx.accounts.config_proposal.change.clone(),
+        });
+
+        msg!("Config change cancelled by {}", canceller);
+        Ok(())
+    }
+
+    /// Get current rewards for a merchant deposit with dynamic yield
+    pub fn calculate_rewards(ctx: Context<CalculateRewards>) -> Result<u64> {
+        let current_time = time::now()?;
+
+        // Read-only: evaluate yield against volume decayed to now
+        let merchant_deposit = &ctx.accounts.merchant_deposit;
+        let mut metrics = *ctx.accounts.merchant_metrics.load()?;
+        apply_volume_decay(&mut metrics, current_time);
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+
+        // A clock behind the deposit would quote a negative age: reject it
+        require!(current_time >= merchant_deposit.deposited_at, VaultError::ClockBehindCheckpoint);
+        let days_elapsed = rewards::whole_days_between(merchant_deposit.deposited_at, current_time);
+
+        // Calculate dynamic yield APY based on lock period, volume, and profit share
+        let yield_bps = calculate_dynamic_yield(
+            merchant_deposit,
+            &metrics,
+            merchant_deposit.total_deposited,
+        );
+
+        // APY in BPS (e.g., 1200 BPS = 12%) applied over whole days, rounded down once
+        let total_rewards = yield_for_days(
+            merchant_deposit.total_deposited,
+            yield_bps as u64,
+            days_elapsed as u64,
+        )
+        .ok_or(VaultError::MathOverflow)?;
+
+        emit_cpi!(RewardsQuoted {
+            merchant: merchant_deposit.merchant,
+            rewards: total_rewards,
+            yield_bps,
+            days_elapsed,
+            quoted_at: current_time,
+        });
+
+        #[cfg(feature = "verbose-logs")]
+        msg!("Current rewards: {} (yield: {}.{:02}% APY, days: {})",
+            total_rewards,
+            yield_bps / 100,
+            yield_bps % 100,
+            days_elapsed
+        );
+        Ok(total_rewards)
+    }
+
+    /// Record a processed order to update merchant metrics
+    /// Called by payment processor agent after successful split. This is the
+    /// hottest instruction, so it only credits volume: the dynamic yield is
+    /// re-derived by the metrics crank (`refresh_merchant_metrics`) rather than
+    /// on every order, and the deposit is left untouched.
+    pub fn record_order(
+        ctx: Context<RecordOrder>,
+        order_amount_usd: u64,
+        buyer_wallet: Pubkey,
+    ) -> Result<()> {
+        let authorized_agent = &ctx.accounts.authorized_agent;
+        let merchant_deposit = &ctx.accounts.merchant_deposit;
+
+        // Verify agent is authorized and active
+        require!(authorized_agent.is_active, VaultError::UnauthorizedAgent);
+        require!(
+            authorized_agent.agent == ctx.accounts.agent.key(),
+            VaultError::UnauthorizedAgent
+        );
+        require!(
+            authorized_agent.merchant == merchant_deposit.merchant,
+            VaultError::UnauthorizedAgent
+        );
+
+        let current_time = time::now()?;
+        credit_order(
+            &ctx.accounts.vault,
+            merchant_deposit,
+            &mut *ctx.accounts.merchant_metrics.load_mut()?,
+            order_amount_usd,
+            Some(&buyer_wallet),
+            current_time,
+        )?;
+        if let Some(sub_merchant) = ctx.accounts.sub_merchant.as_mut() {
+            apply_sub_merchant_orders(sub_merchant, order_amount_usd, 1, current_time)?;
+        }
+
+        // Credit buyer cashback (only if the vault has it enabled and the buyer opted in)
+        let cashback_bps = ctx.accounts.vault.cashback_bps;
+        if let Some(buyer_rewards) = ctx.accounts.buyer_rewards.as_mut() {
+            if cashback_bps > 0 {
+                let cashback = bps_floor(order_amount_usd, cashback_bps as u64)
+                    .ok_or(VaultError::MathOverflow)?;
+
+                buyer_rewards.accrued_cashback = buyer_rewards
+                    .accrued_cashback
+                    .checked_add(cashback)
+                    .ok_or(VaultError::MathOverflow)?;
+            }
+        }
+
+        // Plain log: formatting arguments costs more compute than the rest of the order
+        msg!("Order recorded");
+        Ok(())
+    }
+
+    /// Get merchant tier based on performance
+    pub fn get_merchant_tier(ctx: Context<GetMerchantTier>) -> Result<u8> {
+        let merchant_deposit = &ctx.accounts.merchant_deposit;
+        let metrics = ctx.accounts.merchant_metrics.load()?;
+        let current_time = activity_clock(&metrics, time::now()?);
+
+        let monthly_volume = decay_metric(
+            metrics.current_month_volume,
+            elapsed_seconds(metrics.last_volume_update, current_time),
+        );
+
+        let (tier, _) = hold_tier(
+            merchant_deposit.current_tier,
+            calculate_merchant_tier(
+                monthly_volume,
+                loyalty_start(&ctx.accounts.merchant_profile, merchant_deposit),
+                current_time,
+            ),
+            merchant_deposit.tier_grace_started_at,
+            ctx.accounts.vault.tier_downgrade_grace,
+            current_time,
+        );
+
+        emit_cpi!(TierQuoted {
+            merchant: merchant_deposit.merchant,
+            tier,
+            monthly_volume_usd: monthly_volume,
+            yield_bps: merchant_deposit.current_yield_bps,
+            quoted_at: current_time,
+        });
+
+        #[cfg(feature = "verbose-logs")]
+        msg!("Merchant tier: {} (volume: ${}, yield: {}.{:02}%)",
+            tier_name(tier),
+            monthly_volume / 1_000000,
+            merchant_deposit.current_yield_bps / 100,
+            merchant_deposit.current_yield_bps % 100
+        );
+
+        Ok(tier)
+    }
+
+    /// Record platform profit from merchant's orders
+    /// Called by platform after order processing to track profit sharing
+    /// Platform gives up to 50% of profit back to merchant as yield boost
+    pub fn record_platform_profit(
+        ctx: Context<RecordPlatformProfit>,
+        platform_profit_amount: u64,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        // SECURITY: Verify caller is authorized (platform authority or authorized agent)
+        require!(
+            ctx.accounts.platform.key() == vault.authority,
+            VaultError::Unauthorized
+        );
+
+        // Track total platform profit earned from this merchant
+        merchant_deposit.platform_profit_earned = merchant_deposit
+            .platform_profit_earned
+            .checked_add(platform_profit_amount)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Calculate 50% profit share
+        let total_profit_share = platform_profit_amount
+            .checked_div(2)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Calculate current APY cap for this merchant's lock period
+        let lock_max_apy = merchant_deposit.lock_period.max_apy_bps();
+        let total_deposited_value = merchant_deposit.total_deposited;
+
+        // Calculate maximum allowed profit share allocation (to not exceed APY cap)
+        // max_profit_share_bps = lock_max_apy - BASE_YIELD_BPS - volume_bonus
+
+        // Current profit share in BPS (0 when nothing is deposited)
+        let current_profit_share_bps =
+            rewards::profit_share_bps(merchant_deposit.profit_share_allocated, total_deposited_value);
+
+        // Available space for more profit share
+        let max_profit_share_bps = lock_max_apy.saturating_sub(rewards::BASE_YIELD_BPS);
+        let remaining_space_bps = max_profit_share_bps.saturating_sub(current_profit_share_bps);
+
+        // Convert remaining space to absolute amount
+        let max_additional_profit_share =
+            bps_floor(total_deposited_value, remaining_space_bps as u64).unwrap_or(0);
+
+        // Split profit share: allocate up to cap, excess goes to immediate rewards
+        let (profit_share_allocated, excess_rewards) = if total_profit_share <= max_additional_profit_share {
+            // All profit share fits within APY cap
+            (total_profit_share, 0)
+        } else {
+            // Cap profit share, rest becomes immediate rewards
+            (max_additional_profit_share, total_profit_share - max_additional_profit_share)
+        };
+
+        // Update profit share allocation
+        merchant_deposit.profit_share_allocated = merchant_deposit
+            .profit_share_allocated
+            .checked_add(profit_share_allocated)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Add excess to accrued rewards (doesn't affect APY, immediate payout)
+        merchant_deposit.accrued_rewards = merchant_deposit
+            .accrued_rewards
+            .checked_add(excess_rewards)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Recalculate yield with new profit share (and volume decayed to now)
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        apply_volume_decay(&mut metrics, time::now()?);
+        let previous_yield_bps = merchant_deposit.current_yield_bps;
+        merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+            merchant_deposit,
+            &metrics,
+            merchant_deposit.total_deposited,
+        );
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            merchant_deposit.current_yield_bps,
+            YieldTrigger::PlatformProfit,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        // Stream the referrer's cut of this merchant's platform fees
+        match (ctx.accounts.referral.as_mut(), ctx.accounts.referral_code.as_mut()) {
+            (Some(referral), Some(referral_code)) => {
+                require!(
+                    referral.referrer == referral_code.referrer,
+                    VaultError::InvalidReferral
+                );
+
+                let referral_share = bps_floor(platform_profit_amount, vault.referral_fee_bps as u64)
+                    .ok_or(VaultError::MathOverflow)?;
+
+                referral.total_fees_shared = referral
+                    .total_fees_shared
+                    .checked_add(referral_share)
+                    .ok_or(VaultError::MathOverflow)?;
+                referral_code.accrued_rewards = referral_code
+                    .accrued_rewards
+                    .checked_add(referral_share)
+                    .ok_or(VaultError::MathOverflow)?;
+
+                msg!("Referral share: {} to referrer {}", referral_share, referral_code.referrer);
+            }
+            (None, None) => {}
+            _ => return err!(VaultError::InvalidReferral),
+        }
+
+        // Set aside the insurance fund's slice of platform fees
+        if let Some(insurance_fund) = ctx.accounts.insurance_fund.as_mut() {
+            let contribution = bps_floor(platform_profit_amount, vault.insurance_fee_bps as u64)
+                .ok_or(VaultError::MathOverflow)?;
+
+            insurance_fund.balance = insurance_fund
+                .balance
+                .checked_add(contribution)
+                .ok_or(VaultError::MathOverflow)?;
+            insurance_fund.total_contributed = insurance_fund
+                .total_contributed
+                .checked_add(contribution)
+                .ok_or(VaultError::MathOverflow)?;
+            update_insurance_coverage(insurance_fund, vault.total_deposits);
+
+            msg!("Insurance contribution: {}", contribution);
+        }
+
+        #[cfg(feature = "verbose-logs")]
+        msg!(
+            "Platform profit recorded: ${} | Profit share: ${} | Excess rewards: ${} | New yield: {}.{:02}%",
+            platform_profit_amount / 1_000000,
+            profit_share_allocated / 1_000000,
+            excess_rewards / 1_000000,
+            merchant_deposit.current_yield_bps / 100,
+            merchant_deposit.current_yield_bps % 100
+        );
+
+        Ok(())
+    }
+
+    /// Register a merchant with the vault
+    /// Creates the merchant's profile, metrics and yield history accounts, which must exist before
+    /// depositing and keep identity, agents and performance metrics across deposit cycles
+    pub fn register_merchant(
+        ctx: Context<RegisterMerchant>,
+        metadata_uri_hash: [u8; 32],
+        payout_wallet: Pubkey,
+    ) -> Result<()> {
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        let current_time = time::now()?;
+
+        merchant_profile.merchant = ctx.accounts.merchant.key();
+        merchant_profile.vault = ctx.accounts.vault.key();
+        merchant_profile.metadata_uri_hash = metadata_uri_hash;
+        merchant_profile.payout_wallet = payout_wallet;
+        merchant_profile.pending_payout_wallet = Pubkey::default();
+        merchant_profile.payout_wallet_effective_at = 0;
+        merchant_profile.created_at = current_time;
+        merchant_profile.registered_agents = Vec::new();
+        merchant_profile.current_tier = 0; // Bronze
+        merchant_profile.loyalty_started_at = 0; // Starts with the first deposit
+        merchant_profile.last_withdrawn_at = 0;
+        merchant_profile.bump = ctx.bumps.merchant_profile;
+
+        let mut metrics = ctx.accounts.merchant_metrics.load_init()?;
+        metrics.merchant = ctx.accounts.merchant.key();
+        metrics.vault = ctx.accounts.vault.key();
+        metrics.last_volume_update = current_time;
+        metrics.bump = ctx.bumps.merchant_metrics;
+        init_yield_history(
+            &ctx.accounts.yield_history,
+            ctx.accounts.vault.key(),
+            ctx.accounts.merchant.key(),
+            ctx.bumps.yield_history,
+        )?;
+
+        msg!("Merchant {} registered", merchant_profile.merchant);
+        Ok(())
+    }
+
+    /// Create a sub-merchant (store/location) under the merchant's profile
+    /// Orders recorded against it are tracked per store and still roll up to the
+    /// parent's metrics, so tier and yield stay merchant-wide
+    pub fn create_sub_merchant(ctx: Context<CreateSubMerchant>, label_hash: [u8; 32]) -> Result<()> {
+        let sub_merchant = &mut ctx.accounts.sub_merchant;
+
+        sub_merchant.merchant = ctx.accounts.merchant.key();
+        sub_merchant.vault = ctx.accounts.vault.key();
+        sub_merchant.label_hash = label_hash;
+        sub_merchant.total_orders_processed = 0;
+        sub_merchant.total_volume_usd = 0;
+        sub_merchant.current_month_volume = 0;
+        sub_merchant.last_volume_update = time::now()?;
+        sub_merchant.created_at = sub_merchant.last_volume_update;
+        sub_merchant.bump = ctx.bumps.sub_merchant;
+
+        msg!("Sub-merchant {} created for {}", sub_merchant.key(), sub_merchant.merchant);
+        Ok(())
+    }
+
+    /// Schedule a change of the merchant's payout wallet
+    /// The new wallet only receives payouts after PAYOUT_WALLET_TIMELOCK, so a
+    /// compromised signing key cannot redirect funds instantly. Scheduling the
+    /// current wallet cancels a pending change. With a withdrawal whitelist, only
+    /// approved destinations can be chosen and the switch is immediate.
+    pub fn set_payout_wallet(ctx: Context<SetPayoutWallet>, new_payout_wallet: Pubkey) -> Result<()> {
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        let current_time = time::now()?;
+
+        promote_payout_wallet(merchant_profile, current_time);
+
+        if new_payout_wallet == merchant_profile.payout_wallet {
+            merchant_profile.pending_payout_wallet = Pubkey::default();
+            merchant_profile.payout_wallet_effective_at = 0;
+            msg!("Pending payout wallet change cancelled");
+            return Ok(());
+        }
+
+        // Whitelisted destinations already sat out the timelock when they were added
+        if let Some(whitelist) = load_withdrawal_whitelist(&ctx.accounts.withdrawal_whitelist)? {
+            require!(
+                whitelist.is_approved(&new_payout_wallet, current_time),
+                VaultError::DestinationNotWhitelisted
+            );
+            merchant_profile.payout_wallet = new_payout_wallet;
+            merchant_profile.pending_payout_wallet = Pubkey::default();
+            merchant_profile.payout_wallet_effective_at = 0;
+            msg!("Payout wallet switched to whitelisted {}", new_payout_wallet);
+            return Ok(());
+        }
+
+        merchant_profile.pending_payout_wallet = new_payout_wallet;
+        merchant_profile.payout_wallet_effective_at = current_time + PAYOUT_WALLET_TIMELOCK;
+
+        msg!(
+            "Payout wallet change to {} scheduled for {}",
+            new_payout_wallet,
+            merchant_profile.payout_wallet_effective_at
+        );
+        Ok(())
+    }
+
+    /// Let another key withdraw and claim on the merchant's behalf
+    /// The delegate may move up to `max_amount` in total until `expiry`; funds
+    /// still go to the profile's payout wallet. `Pubkey::default()` revokes.
+    pub fn set_withdraw_delegate(
+        ctx: Context<SetWithdrawDelegate>,
+        delegate: Pubkey,
+        expiry: i64,
+        max_amount: u64,
+    ) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        if delegate == Pubkey::default() {
+            clear_withdraw_delegate(merchant_deposit);
+            msg!("Withdraw delegate revoked");
+            return Ok(());
+        }
+
+        // Withdrawal rights follow the position NFT instead
+        require!(merchant_deposit.position_mint == Pubkey::default(), VaultError::PositionNftExists);
+
+        require!(expiry > time::now()?, VaultError::InvalidDelegate);
+        require!(delegate != merchant_deposit.merchant, VaultError::InvalidDelegate);
+
+        merchant_deposit.withdraw_delegate = delegate;
+        merchant_deposit.delegate_expiry = expiry;
+        merchant_deposit.delegate_allowance = max_amount;
+
+        msg!("Withdraw delegate {} set until {} (max {})", delegate, expiry, max_amount);
+        Ok(())
+    }
+
+    /// Name a beneficiary who can take over the merchant's deposit after
+    /// `inactivity_period` seconds without a check-in (dead-man switch)
+    pub fn create_recovery_config(
+        ctx: Context<CreateRecoveryConfig>,
+        beneficiary: Pubkey,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        validate_recovery_config(&ctx.accounts.merchant.key(), &beneficiary, inactivity_period)?;
+
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        recovery_config.merchant = ctx.accounts.merchant.key();
+        recovery_config.vault = ctx.accounts.vault.key();
+        recovery_config.beneficiary = beneficiary;
+        recovery_config.inactivity_period = inactivity_period;
+        recovery_config.last_activity_at = time::now()?;
+        recovery_config.claim_started_at = 0;
+        recovery_config.bump = ctx.bumps.recovery_config;
+
+        msg!("Recovery beneficiary {} after {}s of inactivity", beneficiary, inactivity_period);
+        Ok(())
+    }
+
+    /// Change the beneficiary or inactivity period (counts as a check-in)
+    pub fn update_recovery_config(
+        ctx: Context<RecoveryCheckIn>,
+        beneficiary: Pubkey,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        validate_recovery_config(&ctx.accounts.merchant.key(), &beneficiary, inactivity_period)?;
+
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        recovery_config.beneficiary = beneficiary;
+        recovery_config.inactivity_period = inactivity_period;
+        recovery_config.check_in(time::now()?);
+
+        msg!("Recovery beneficiary {} after {}s of inactivity", beneficiary, inactivity_period);
+        Ok(())
+    }
+
+    /// Prove the merchant still holds their key
+    /// Restarts the inactivity period and cancels a recovery in its challenge window.
+    pub fn recovery_check_in(ctx: Context<RecoveryCheckIn>) -> Result<()> {
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        let cancelled = recovery_config.claim_started_at != 0;
+        recovery_config.check_in(time::now()?);
+
+        msg!("Merchant {} checked in (recovery cancelled: {})", recovery_config.merchant, cancelled);
+        Ok(())
+    }
+
+    /// Remove the merchant's recovery config
+    pub fn close_recovery_config(_ctx: Context<CloseRecoveryConfig>) -> Result<()> {
+        msg!("Recovery config closed");
+        Ok(())
+    }
+
+    /// Start a recovery once the merchant has been inactive for the configured period
+    /// (beneficiary only). The merchant can still cancel it by checking in until
+    /// RECOVERY_CHALLENGE_WINDOW has passed.
+    pub fn start_recovery(ctx: Context<StartRecovery>) -> Result<()> {
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        let current_time = time::now()?;
+
+        require!(recovery_config.claim_started_at == 0, VaultError::RecoveryAlreadyStarted);
+        require!(
+            elapsed_seconds(recovery_config.last_activity_at, current_time) >= recovery_config.inactivity_period,
+            VaultError::MerchantStillActive
+        );
+        recovery_config.claim_started_at = current_time;
+
+        msg!(
+            "Recovery of {} started by {}; claimable from {}",
+            recovery_config.merchant,
+            recovery_config.beneficiary,
+            current_time + RECOVERY_CHALLENGE_WINDOW
+        );
+        Ok(())
+    }
+
+    /// Hand the merchant's deposit to the beneficiary once the challenge window has
+    /// passed unchallenged (beneficiary only)
+    /// The beneficiary becomes the payout wallet and an unlimited withdraw delegate,
+    /// so it can withdraw and claim the deposit as it unlocks.
+    pub fn complete_recovery(ctx: Context<CompleteRecovery>) -> Result<()> {
+        let recovery_config = &mut ctx.accounts.recovery_config;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        let current_time = time::now()?;
+
+        require!(recovery_config.claim_started_at != 0, VaultError::RecoveryNotStarted);
+        require!(
+            elapsed_seconds(recovery_config.claim_started_at, current_time) >= RECOVERY_CHALLENGE_WINDOW,
+            VaultError::RecoveryChallengeWindowOpen
+        );
+        // Withdrawals of these need the NFT or share tokens, which the beneficiary lacks
+        require!(merchant_deposit.position_mint == Pubkey::default(), VaultError::PositionNftExists);
+        require!(merchant_deposit.shares == 0, VaultError::PositionTokenized);
+
+        let beneficiary = recovery_config.beneficiary;
+        if let Some(whitelist) = load_withdrawal_whitelist(&ctx.accounts.withdrawal_whitelist)? {
+            require!(
+                whitelist.is_approved(&beneficiary, current_time),
+                VaultError::DestinationNotWhitelisted
+            );
+        }
+
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        merchant_profile.payout_wallet = beneficiary;
+        merchant_profile.pending_payout_wallet = Pubkey::default();
+        merchant_profile.payout_wallet_effective_at = 0;
+
+        merchant_deposit.withdraw_delegate = beneficiary;
+        merchant_deposit.delegate_expiry = i64::MAX;
+        merchant_deposit.delegate_allowance = u64::MAX;
+
+        recovery_config.check_in(current_time);
+
+        msg!("Deposit of {} recovered by {}", merchant_deposit.merchant, beneficiary);
+        Ok(())
+    }
+
+    /// Create the merchant's payout config and schedule its first splits
+    /// Like payout wallet changes, splits only take effect after PAYOUT_WALLET_TIMELOCK
+    pub fn create_payout_config(ctx: Context<CreatePayoutConfig>, splits: Vec<PayoutSplit>) -> Result<()> {
+        validate_payout_splits(&splits)?;
+
+        let current_time = time::now()?;
+        require_whitelisted_splits(&ctx.accounts.withdrawal_whitelist, &splits, current_time)?;
+
+        let payout_config = &mut ctx.accounts.payout_config;
+
+        payout_config.merchant = ctx.accounts.merchant.key();
+        payout_config.vault = ctx.accounts.vault.key();
+        payout_config.splits = Vec::new();
+        payout_config.pending_splits = splits;
+        payout_config.splits_effective_at = current_time + PAYOUT_WALLET_TIMELOCK;
+        payout_config.sweep_threshold = 0;
+        payout_config.sweep_interval = 0;
+        payout_config.last_sweep_at = 0;
+        payout_config.bump = ctx.bumps.payout_config;
+
+        msg!("Payout config created; {} splits effective at {}",
+            payout_config.pending_splits.len(),
+            payout_config.splits_effective_at
+        );
+        Ok(())
+    }
+
+    /// Schedule new payout splits (an empty list removes all splits)
+    /// The change takes effect after PAYOUT_WALLET_TIMELOCK; until then the current
+    /// splits apply. Scheduling the current splits cancels a pending change.
+    pub fn set_payout_splits(ctx: Context<UpdatePayoutConfig>, splits: Vec<PayoutSplit>) -> Result<()> {
+        validate_payout_splits(&splits)?;
+
+        let current_time = time::now()?;
+        require_whitelisted_splits(&ctx.accounts.withdrawal_whitelist, &splits, current_time)?;
+
+        let payout_config = &mut ctx.accounts.payout_config;
+        payout_config.promote_pending(current_time);
+
+        if splits == payout_config.splits {
+            payout_config.pending_splits = Vec::new();
+            payout_config.splits_effective_at = 0;
+            msg!("Pending payout split change cancelled");
+            return Ok(());
+        }
+
+        payout_config.pending_splits = splits;
+        payout_config.splits_effective_at = current_time + PAYOUT_WALLET_TIMELOCK;
+
+        msg!("Payout split change scheduled for {}", payout_config.splits_effective_at);
+        Ok(())
+    }
+
+    /// Configure automatic sweeping of accrued rewards to the payout wallet
+    /// `sweep_rewards` may be cranked once rewards reach `threshold` and at least
+    /// `interval` seconds after the previous sweep; a zero threshold disables it
+    pub fn set_auto_sweep(ctx: Context<UpdatePayoutConfig>, threshold: u64, interval: i64) -> Result<()> {
+        require!(interval >= 0, VaultError::InvalidSweepConfig);
+
+        let payout_config = &mut ctx.accounts.payout_config;
+        payout_config.sweep_threshold = threshold;
+        payout_config.sweep_interval = interval;
+
+        msg!("Auto-sweep set: threshold {} | interval {}s", threshold, interval);
+        Ok(())
+    }
+
+    /// Restrict the merchant's payouts to a whitelist of destinations
+    /// Starts with the current and scheduled payout wallet and split wallets, so
+    /// payouts keep flowing. The whitelist cannot be closed again.
+    pub fn create_withdrawal_whitelist(ctx: Context<CreateWithdrawalWhitelist>) -> Result<()> {
+        let merchant_profile = &ctx.accounts.merchant_profile;
+        let whitelist = &mut ctx.accounts.withdrawal_whitelist;
+
+        whitelist.merchant = ctx.accounts.merchant.key();
+        whitelist.vault = ctx.accounts.vault.key();
+        whitelist.destinations = Vec::new();
+        whitelist.bump = ctx.bumps.withdrawal_whitelist;
+
+        whitelist.insert(merchant_profile.payout_wallet, 0)?;
+        if merchant_profile.payout_wallet_effective_at != 0 {
+            whitelist.insert(merchant_profile.pending_payout_wallet, merchant_profile.payout_wallet_effective_at)?;
+        }
+        if let Some(payout_config) = load_payout_config(&ctx.accounts.payout_config)? {
+            for split in &payout_config.splits {
+                whitelist.insert(split.wallet, 0)?;
+            }
+            for split in &payout_config.pending_splits {
+                whitelist.insert(split.wallet, payout_config.splits_effective_at)?;
+            }
+        }
+
+        msg!(
+            "Withdrawal whitelist created for {} with {} destinations",
+            whitelist.merchant,
+            whitelist.destinations.len()
+        );
+        Ok(())
+    }
+
+    /// Whitelist a payout destination
+    /// It can only be chosen as payout wallet or split after PAYOUT_WALLET_TIMELOCK,
+    /// giving the merchant time to notice an addition made with a stolen key.
+    pub fn add_withdrawal_destination(ctx: Context<UpdateWithdrawalWhitelist>, wallet: Pubkey) -> Result<()> {
+        require!(wallet != Pubkey::default(), VaultError::InvalidPayoutWallet);
+
+        let effective_at = time::now()? + PAYOUT_WALLET_TIMELOCK;
+        require!(
+            ctx.accounts.withdrawal_whitelist.insert(wallet, effective_at)?,
+            VaultError::DestinationAlreadyWhitelisted
+        );
+
+        msg!("Withdrawal destination {} approved from {}", wallet, effective_at);
+        Ok(())
+    }
+
+    /// Drop a destination from the whitelist (immediately)
+    /// The current or scheduled payout wallet and split wallets must be moved off
+    /// it first.
+    pub fn remove_withdrawal_destination(ctx: Context<UpdateWithdrawalWhitelist>, wallet: Pubkey) -> Result<()> {
+        let merchant_profile = &ctx.accounts.merchant_profile;
+        let mut in_use = wallet == merchant_profile.payout_wallet
+            || (merchant_profile.payout_wallet_effective_at != 0 && wallet == merchant_profile.pending_payout_wallet);
+        if let Some(payout_config) = load_payout_config(&ctx.accounts.payout_config)? {
+            in_use |= payout_config
+                .splits
+                .iter()
+                .chain(&payout_config.pending_splits)
+                .any(|split| split.wallet == wallet);
+        }
+        require!(!in_use, VaultError::DestinationInUse);
+
+        require!(
+            ctx.accounts.withdrawal_whitelist.remove(&wallet),
+            VaultError::DestinationNotWhitelisted
+        );
+
+        msg!("Withdrawal destination {} removed", wallet);
+        Ok(())
+    }
+
+    /// Register an agent to process orders for a merchant
+    /// Only the merchant can authorize their own agents
+    pub fn register_agent(ctx: Context<RegisterAgent>) -> Result<()> {
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        require!(
+            merchant_profile.registered_agents.len() < MAX_PROFILE_AGENTS,
+            VaultError::TooManyAgents
+        );
+        merchant_profile.registered_agents.push(ctx.accounts.agent.key());
+
+        let authorized_agent = &mut ctx.accounts.authorized_agent;
+        let current_time = time::now()?;
+
+        authorized_agent.merchant = ctx.accounts.merchant.key();
+        authorized_agent.agent = ctx.accounts.agent.key();
+        authorized_agent.vault = ctx.accounts.vault.key();
+        authorized_agent.authorized_at = current_time;
+        authorized_agent.is_active = true;
+        authorized_agent.bump = ctx.bumps.authorized_agent;
+        authorized_agent.bond_lamports = ctx.accounts.vault.agent_bond_lamports;
+        authorized_agent.revoked_at = 0;
+        authorized_agent.slashed = false;
+
+        // Escrow the agent bond in the authorization account
+        let bond = authorized_agent.bond_lamports;
+        if bond > 0 {
+            let ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.merchant.key(),
+                &ctx.accounts.authorized_agent.key(),
+                bond,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &ix,
+                &[
+                    ctx.accounts.merchant.to_account_info(),
+                    ctx.accounts.authorized_agent.to_account_info(),
+                ],
+            )?;
+        }
+
+        msg!(
+            "Agent {} authorized for merchant {} (bond: {} lamports)",
+            ctx.accounts.agent.key(),
+            ctx.accounts.merchant.key(),
+            bond
+        );
+
+        Ok(())
+    }
+
+    /// Revoke agent authorization
+    /// Only the merchant can revoke their own agents
+    pub fn revoke_agent(ctx: Context<RevokeAgent>) -> Result<()> {
+        let authorized_agent = &mut ctx.accounts.authorized_agent;
+
+        require!(
+            authorized_agent.merchant == ctx.accounts.merchant.key(),
+            VaultError::Unauthorized
+        );
+        require!(authorized_agent.is_active, VaultError::UnauthorizedAgent);
+
+        authorized_agent.is_active = false;
+        authorized_agent.revoked_at = time::now()?;
+
+        let revoked = authorized_agent.agent;
+        ctx.accounts.merchant_profile.registered_agents.retain(|agent| *agent != revoked);
+
+        msg!(
+            "Agent {} revoked for merchant {}",
+            authorized_agent.agent,
+            authorized_agent.merchant
+        );
+
+        Ok(())
+    }
+
+    /// Reclaim a revoked agent's bond and close its authorization
+    /// The bond stays slashable for AGENT_BOND_COOLDOWN after revocation so a
+    /// merchant can't dodge a pending slash by revoking first
+    pub fn reclaim_agent_bond(ctx: Context<ReclaimAgentBond>) -> Result<()> {
+        let authorized_agent = &ctx.accounts.authorized_agent;
+
+        require!(!authorized_agent.is_active, VaultError::AgentStillActive);
+        require!(
+            time::now()? >= authorized_agent.revoked_at + AGENT_BOND_COOLDOWN,
+            VaultError::AgentBondLocked
+        );
+
+        msg!(
+            "Agent {} bond of {} lamports reclaimed by merchant {}",
+            authorized_agent.agent,
+            authorized_agent.bond_lamports,
+            authorized_agent.merchant
+        );
+
+        // Account (bond + rent) is closed to the merchant
+        Ok(())
+    }
+
+    /// Slash an agent proven to have falsified orders (guardian only)
+    /// Revokes the agent and sends its bond to `bond_destination` (e.g. the
+    /// affected party, or the incinerator to burn it)
+    pub fn slash_agent(ctx: Context<SlashAgent>, evidence_hash: [u8; 32]) -> Result<()> {
+        require!(ctx.accounts.guardian.key() == ctx.accounts.vault.guardian, VaultError::Unauthorized);
+
+        let authorized_agent = &mut ctx.accounts.authorized_agent;
+        require!(!authorized_agent.slashed, VaultError::AgentAlreadySlashed);
+
+        let bond = authorized_agent.bond_lamports;
+        let current_time = time::now()?;
+
+        if authorized_agent.is_active {
+            authorized_agent.is_active = false;
+            authorized_agent.revoked_at = current_time;
+        }
+        authorized_agent.slashed = true;
+        authorized_agent.bond_lamports = 0;
+
+        let slashed = authorized_agent.agent;
+        ctx.accounts.merchant_profile.registered_agents.retain(|agent| *agent != slashed);
+
+        if bond > 0 {
+            **ctx.accounts.authorized_agent.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **ctx.accounts.bond_destination.try_borrow_mut_lamports()? += bond;
+        }
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::AgentSlashed,
+            ctx.accounts.guardian.key(),
+            slashed,
+            bond,
+            0,
+        )?;
+
+        emit_cpi!(AgentSlashed {
+            agent: slashed,
+            merchant: ctx.accounts.authorized_agent.merchant,
+            bond_slashed: bond,
+            bond_destination: ctx.accounts.bond_destination.key(),
+            evidence_hash,
+            slashed_at: current_time,
+        });
+
+        msg!("Agent {} slashed ({} lamports)", slashed, bond);
+        Ok(())
+    }
+
+    /// Set the bond escrowed for each newly registered agent (admin only)
+    pub fn set_agent_bond(ctx: Context<UpdateVaultConfig>, agent_bond_lamports: u64) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(&ConfigChange::AgentBond { agent_bond_lamports })
+    }
+
+    /// Opt a buyer wallet into the cashback program
+    /// Creates the buyer's rewards account that `record_order` credits
+    pub fn open_buyer_rewards(ctx: Context<OpenBuyerRewards>) -> Result<()> {
+        let buyer_rewards = &mut ctx.accounts.buyer_rewards;
+
+        buyer_rewards.buyer = ctx.accounts.buyer.key();
+        buyer_rewards.vault = ctx.accounts.vault.key();
+        buyer_rewards.accrued_cashback = 0;
+        buyer_rewards.total_claimed = 0;
+        buyer_rewards.last_claimed_at = 0;
+        buyer_rewards.bump = ctx.bumps.buyer_rewards;
+
+        msg!("Buyer {} opted into cashback", ctx.accounts.buyer.key());
+        Ok(())
+    }
+
+    /// Create the token account cashback and referral rewards are paid from (admin only)
+    /// Its mint is the one rewards are paid in. It is funded by plain token transfers,
+    /// so claims never draw on depositors' principal.
+    pub fn initialize_reward_reserve(ctx: Context<InitializeRewardReserve>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        msg!("Reward reserve {} created in {}", ctx.accounts.reward_reserve.key(), ctx.accounts.mint.key());
+        Ok(())
+    }
+
+    /// Claim accrued cashback
+    /// Paid out of the vault's reward reserve, in its mint
+    pub fn claim_cashback(ctx: Context<ClaimCashback>) -> Result<()> {
+        let buyer_rewards = &mut ctx.accounts.buyer_rewards;
+
+        require!(
+            buyer_rewards.buyer == ctx.accounts.buyer.key(),
+            VaultError::Unauthorized
+        );
+
+        let amount = buyer_rewards.accrued_cashback;
+        require!(amount > 0, VaultError::NoCashbackToClaim);
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_reserve.to_account_info(),
+            to: ctx.accounts.buyer_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        buyer_rewards.accrued_cashback = 0;
+        buyer_rewards.total_claimed = buyer_rewards
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        buyer_rewards.last_claimed_at = time::now()?;
+
+        msg!("Cashback claimed: {} by buyer {}", amount, ctx.accounts.buyer.key());
+        Ok(())
+    }
+
+    /// Create a referral code for merchant acquisition
+    /// Merchants that deposit with this code stream a share of their platform fees to the referrer
+    pub fn create_referral_code(ctx: Context<CreateReferralCode>) -> Result<()> {
+        let referral_code = &mut ctx.accounts.referral_code;
+
+        referral_code.referrer = ctx.accounts.referrer.key();
+        referral_code.vault = ctx.accounts.vault.key();
+        referral_code.referred_merchants = 0;
+        referral_code.accrued_rewards = 0;
+        referral_code.total_claimed = 0;
+        referral_code.created_at = time::now()?;
+        referral_code.bump = ctx.bumps.referral_code;
+
+        msg!("Referral code created for {}", ctx.accounts.referrer.key());
+        Ok(())
+    }
+
+    /// Claim accrued referral rewards
+    /// Paid out of the vault's reward reserve, in its mint
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        let referral_code = &mut ctx.accounts.referral_code;
+
+        require!(
+            referral_code.referrer == ctx.accounts.referrer.key(),
+            VaultError::Unauthorized
+        );
+
+        let amount = referral_code.accrued_rewards;
+        require!(amount > 0, VaultError::NoReferralRewardsToClaim);
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reward_reserve.to_account_info(),
+            to: ctx.accounts.referrer_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        referral_code.accrued_rewards = 0;
+        referral_code.total_claimed = referral_code
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+
+        msg!("Referral rewards claimed: {} by {}", amount, ctx.accounts.referrer.key());
+        Ok(())
+    }
+
+    /// Claim a sponsor's share of the sponsored merchant's rewards
+    /// Accrues the deposit to now while it is open; once it has been withdrawn the
+    /// final claim closes the sponsorship, returning its rent to the sponsor.
+    pub fn claim_sponsor_rewards(ctx: Context<ClaimSponsorRewards>) -> Result<()> {
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+
+        let current_time = time::now()?;
+        let deposit_info = ctx.accounts.merchant_deposit.to_account_info();
+        let mut merchant_deposit = if deposit_info.owner == &crate::ID && !deposit_info.data_is_empty() {
+            Some(MerchantDeposit::try_deserialize(&mut &deposit_info.try_borrow_data()?[..])?)
+        } else {
+            None
+        };
+        // A later, unsponsored deposit of the same merchant is not this sponsorship's
+        if merchant_deposit
+            .as_ref()
+            .is_some_and(|deposit| !deposit.is_active || deposit.sponsor != ctx.accounts.sponsor.key())
+        {
+            merchant_deposit = None;
+        }
+
+        if let Some(deposit) = merchant_deposit.as_mut() {
+            require!(!deposit.frozen, VaultError::MerchantFrozen);
+            require_holding_period(deposit, &ctx.accounts.vault)?;
+            let harvest = accrue_rewards(deposit, &ctx.accounts.vault, current_time)?;
+            if let Some(harvested) = book_harvest(&mut ctx.accounts.vault, deposit, harvest, current_time)? {
+                emit_cpi!(harvested);
+            }
+            move_sponsor_rewards(deposit, &mut ctx.accounts.sponsorship)?;
+
+            let mut data = deposit_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            deposit.try_serialize(&mut writer)?;
+        }
+
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        let amount = sponsorship.accrued_rewards;
+        if amount > 0 {
+            // Claims leave the vault like withdrawals: behind the queue and within epoch capacity
+            let vault = &mut ctx.accounts.vault;
+            require!(vault.withdraw_queue_head == vault.withdraw_queue_tail, VaultError::WithdrawalQueueNotEmpty);
+            consume_withdraw_capacity(vault, amount, current_time)?;
+            consume_reward_payout(vault, amount, current_time)?;
+
+            let spl_accounts = match (&ctx.accounts.vault_token_account, &ctx.accounts.token_program) {
+                (Some(vault_token), Some(token_program)) => Some((vault_token, token_program)),
+                _ => None,
+            };
+            let to = match sponsorship.deposit_token {
+                DepositType::Sol => ctx.accounts.sponsor.to_account_info(),
+                DepositType::SplToken => ctx
+                    .accounts
+                    .sponsor_token_account
+                    .as_ref()
+                    .ok_or(VaultError::MissingTokenAccount)?
+                    .to_account_info(),
+            };
+            transfer_from_vault(
+                &ctx.accounts.vault,
+                &sponsorship.deposit_token,
+                &ctx.accounts.vault_sol_account,
+                spl_accounts,
+                &to,
+                amount,
+            )?;
+
+            sponsorship.accrued_rewards = 0;
+            sponsorship.total_claimed = sponsorship
+                .total_claimed
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+            let vault = &mut ctx.accounts.vault;
+            vault.total_rewards_paid = vault
+                .total_rewards_paid
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+
+        msg!("Sponsor rewards claimed: {} by {}", amount, ctx.accounts.sponsor.key());
+
+        if merchant_deposit.is_none() {
+            let sponsor = ctx.accounts.sponsor.to_account_info();
+            ctx.accounts.sponsorship.close(sponsor)?;
+            msg!("Sponsorship of merchant {} closed", ctx.accounts.sponsorship.merchant);
+        }
+        Ok(())
+    }
+
+    /// Create a recurring payment schedule from a buyer to a merchant
+    /// Approves the subscription PDA as token delegate for up to `max_payments` charges
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        amount: u64,
+        interval_seconds: i64,
+        max_payments: u32,
+    ) -> Result<()> {
+        let amount_usd = ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, amount, time::now()?)?;
+        require!(amount_usd >= ctx.accounts.vault.min_order_usd(), VaultError::OrderTooSmall);
+        require!(
+            interval_seconds >= MIN_SUBSCRIPTION_INTERVAL && max_payments > 0,
+            VaultError::InvalidSubscriptionTerms
+        );
+
+        let allowance = amount
+            .checked_mul(max_payments as u64)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Approve the subscription PDA to pull payments from the buyer's token account
+        let cpi_accounts = Approve {
+            to: ctx.accounts.buyer_token_account.to_account_info(),
+            delegate: ctx.accounts.subscription.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::approve(cpi_ctx, allowance)?;
+
+        let current_time = time::now()?;
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.merchant = ctx.accounts.merchant.key();
+        subscription.buyer = ctx.accounts.buyer.key();
+        subscription.vault = ctx.accounts.vault.key();
+        subscription.buyer_token_account = ctx.accounts.buyer_token_account.key();
+        subscription.amount = amount;
+        subscription.interval_seconds = interval_seconds;
+        subscription.next_payment_at = current_time; // First charge is due immediately
+        subscription.last_payment_at = 0;
+        subscription.payments_made = 0;
+        subscription.max_payments = max_payments;
+        subscription.failed_attempts = 0;
+        subscription.is_active = true;
+        subscription.created_at = current_time;
+        subscription.bump = ctx.bumps.subscription;
+
+        msg!(
+            "Subscription created: {} every {}s from buyer {} to merchant {}",
+            amount,
+            interval_seconds,
+            ctx.accounts.buyer.key(),
+            ctx.accounts.merchant.key()
+        );
+        Ok(())
+    }
+
+    /// Charge a due subscription payment (permissionless crank)
+    /// Failed charges are retried after a delay; the subscription lapses after repeated failures
+    pub fn process_subscription(ctx: Context<ProcessSubscription>) -> Result<()> {
+        let current_time = time::now()?;
+        let subscription = &ctx.accounts.subscription;
+
+        require!(subscription.is_active, VaultError::SubscriptionNotActive);
+        require!(
+            current_time >= subscription.next_payment_at,
+            VaultError::SubscriptionNotDue
+        );
+
+        // A failed token transfer would abort the whole transaction, so check
+        // balance and allowance up front and record the failure instead
+        let amount = subscription.amount;
+        let buyer_token_account = &ctx.accounts.buyer_token_account;
+        let can_charge = buyer_token_account.amount >= amount
+            && buyer_token_account.delegate == COption::Some(subscription.key())
+            && buyer_token_account.delegated_amount >= amount;
+
+        if !can_charge {
+            let subscription = &mut ctx.accounts.subscription;
+            subscription.failed_attempts = subscription.failed_attempts.saturating_add(1);
+
+            if subscription.failed_attempts >= MAX_SUBSCRIPTION_RETRIES {
+                subscription.is_active = false;
+                msg!("Subscription lapsed after {} failed attempts", subscription.failed_attempts);
+            } else {
+                subscription.next_payment_at = current_time
+                    .checked_add(SUBSCRIPTION_RETRY_INTERVAL)
+                    .ok_or(VaultError::MathOverflow)?;
+                msg!("Subscription charge failed, retry at {}", subscription.next_payment_at);
+            }
+            return Ok(());
+        }
+
+        // Value the charge before it moves, at the mint's current price
+        let amount_usd = ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, amount, current_time)?;
+
+        // Pull payment from the buyer using the subscription PDA as delegate
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            SUBSCRIPTION_SEED,
+            vault_key.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.buyer.as_ref(),
+            &[subscription.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.merchant_token_account.to_account_info(),
+            authority: ctx.accounts.subscription.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        // Credit the payment to the merchant's metrics
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        {
+            let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+            apply_order_metrics(
+                &ctx.accounts.vault,
+                &mut ctx.accounts.merchant_deposit,
+                &mut metrics,
+                amount_usd,
+                Some(&ctx.accounts.subscription.buyer),
+                current_time,
+            )?;
+            record_currency_volume(&mut metrics, &ctx.accounts.merchant_token_account.mint, amount, amount_usd)?;
+        }
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.payments_made = subscription
+            .payments_made
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+        subscription.failed_attempts = 0;
+        subscription.last_payment_at = current_time;
+        subscription.next_payment_at = current_time
+            .checked_add(subscription.interval_seconds)
+            .ok_or(VaultError::MathOverflow)?;
+
+        if subscription.payments_made >= subscription.max_payments {
+            subscription.is_active = false;
+        }
+
+        msg!(
+            "Subscription payment {} of {} processed: {}",
+            subscription.payments_made,
+            subscription.max_payments,
+            amount
+        );
+        Ok(())
+    }
+
+    /// Cancel a subscription
+    /// Revokes the token delegation and returns rent to the buyer
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        // Only revoke if the delegation still belongs to this subscription
+        if ctx.accounts.buyer_token_account.delegate == COption::Some(ctx.accounts.subscription.key()) {
+            let cpi_accounts = Revoke {
+                source: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token::revoke(cpi_ctx)?;
+        }
+
+        msg!(
+            "Subscription cancelled: buyer {} merchant {}",
+            ctx.accounts.subscription.buyer,
+            ctx.accounts.subscription.merchant
+        );
+        Ok(())
+    }
+
+    /// Create an on-chain payment request payable by any wallet
+    /// `memo_hash` commits to the off-chain invoice contents and makes the PDA unique
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        amount: u64,
+        memo_hash: [u8; 32],
+        expiry: i64,
+    ) -> Result<()> {
+        let current_time = time::now()?;
+
+        // Invoices must be large enough to be credited as an order
+        let amount_usd = ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, amount, current_time)?;
+        require!(amount_usd >= ctx.accounts.vault.min_order_usd(), VaultError::OrderTooSmall);
+        require!(expiry > current_time, VaultError::InvoiceExpired);
+
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.merchant = ctx.accounts.merchant.key();
+        invoice.vault = ctx.accounts.vault.key();
+        invoice.amount = amount;
+        invoice.mint = ctx.accounts.supported_mint.mint;
+        invoice.memo_hash = memo_hash;
+        invoice.expiry = expiry;
+        invoice.created_at = current_time;
+        invoice.bump = ctx.bumps.invoice;
+
+        msg!("Invoice created: {} from merchant {}", amount, ctx.accounts.merchant.key());
+        Ok(())
+    }
+
+    /// Pay an invoice
+    /// Transfers tokens to the merchant, credits the order, and closes the invoice
+    pub fn pay_invoice(ctx: Context<PayInvoice>) -> Result<()> {
+        let invoice = &ctx.accounts.invoice;
+        let current_time = time::now()?;
+
+        require!(current_time <= invoice.expiry, VaultError::InvoiceExpired);
+        let amount_usd =
+            ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, invoice.amount, current_time)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.payer_token_account.to_account_info(),
+            to: ctx.accounts.merchant_token_account.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, invoice.amount)?;
+
+        // Credit the payment to the merchant's metrics
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        {
+            let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+            apply_order_metrics(
+                &ctx.accounts.vault,
+                &mut ctx.accounts.merchant_deposit,
+                &mut metrics,
+                amount_usd,
+                Some(&ctx.accounts.payer.key()),
+                current_time,
+            )?;
+            record_currency_volume(&mut metrics, &invoice.mint, invoice.amount, amount_usd)?;
+        }
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        emit_cpi!(InvoicePaid {
+            invoice: invoice.key(),
+            merchant: invoice.merchant,
+            payer: ctx.accounts.payer.key(),
+            amount: invoice.amount,
+            memo_hash: invoice.memo_hash,
+            paid_at: current_time,
+        });
+
+        msg!("Invoice paid: {} to merchant {}", invoice.amount, invoice.merchant);
+        Ok(())
+    }
+
+    /// Cancel an unpaid invoice and reclaim its rent
+    pub fn cancel_invoice(ctx: Context<CancelInvoice>) -> Result<()> {
+        msg!("Invoice cancelled: {}", ctx.accounts.invoice.key());
+        Ok(())
+    }
+
+    /// Authorize a payment: escrow `amount` of the buyer's tokens in a hold until `expiry`
+    /// The merchant captures all or part of it with `capture_payment` or releases it
+    /// with `void_authorization`. Once it expires, the buyer can void it too.
+    pub fn authorize_payment(
+        ctx: Context<AuthorizePayment>,
+        hold_id: [u8; 32],
+        amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        let current_time = time::now()?;
+
+        // Holds must be large enough to be credited as an order
+        let amount_usd = ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, amount, current_time)?;
+        require!(amount_usd >= ctx.accounts.vault.min_order_usd(), VaultError::OrderTooSmall);
+        require!(expiry > current_time, VaultError::HoldExpired);
+        require!(expiry - current_time <= MAX_HOLD_DURATION, VaultError::HoldTooLong);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let payment_hold = &mut ctx.accounts.payment_hold;
+        payment_hold.merchant = ctx.accounts.merchant.key();
+        payment_hold.vault = ctx.accounts.vault.key();
+        payment_hold.buyer = ctx.accounts.buyer.key();
+        payment_hold.mint = ctx.accounts.mint.key();
+        payment_hold.hold_id = hold_id;
+        payment_hold.amount = amount;
+        payment_hold.expiry = expiry;
+        payment_hold.authorized_at = current_time;
+        payment_hold.bump = ctx.bumps.payment_hold;
+        payment_hold.escrow_bump = ctx.bumps.escrow_token_account;
+
+        emit_cpi!(PaymentAuthorized {
+            payment_hold: payment_hold.key(),
+            merchant: payment_hold.merchant,
+            buyer: payment_hold.buyer,
+            mint: payment_hold.mint,
+            amount,
+            expiry,
+        });
+
+        msg!("Payment authorized: {} held for merchant {} until {}", amount, payment_hold.merchant, expiry);
+        Ok(())
+    }
+
+    /// Capture `amount` of an authorized payment (merchant only, before it expires)
+    /// Pays the merchant and credits the order like a paid invoice. The rest of the
+    /// hold returns to the buyer, and the hold closes.
+    pub fn capture_payment(ctx: Context<CapturePayment>, amount: u64) -> Result<()> {
+        let payment_hold = &ctx.accounts.payment_hold;
+        let current_time = time::now()?;
+
+        require!(current_time <= payment_hold.expiry, VaultError::HoldExpired);
+        require!(amount <= payment_hold.amount, VaultError::CaptureExceedsHold);
+        let amount_usd = ctx.accounts.price_feed.verified_usd_value(&ctx.accounts.price_update, amount, current_time)?;
+
+        release_hold(
+            payment_hold,
+            &ctx.accounts.escrow_token_account,
+            Some(&ctx.accounts.merchant_token_account),
+            &ctx.accounts.buyer_token_account,
+            &ctx.accounts.buyer,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        // Credit the captured amount to the merchant's metrics
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        {
+            let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+            apply_order_metrics(
+                &ctx.accounts.vault,
+                &mut ctx.accounts.merchant_deposit,
+                &mut metrics,
+                amount_usd,
+                Some(&payment_hold.buyer),
+                current_time,
+            )?;
+            record_currency_volume(&mut metrics, &payment_hold.mint, amount, amount_usd)?;
+        }
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        let payment_hold = &ctx.accounts.payment_hold;
+        emit_cpi!(PaymentCaptured {
+            payment_hold: payment_hold.key(),
+            merchant: payment_hold.merchant,
+            buyer: payment_hold.buyer,
+            mint: payment_hold.mint,
+            authorized: payment_hold.amount,
+            captured: amount,
+            released: payment_hold.amount - amount,
+            captured_at: current_time,
+        });
+
+        msg!("Payment captured: {} of {} held for merchant {}", amount, payment_hold.amount, payment_hold.merchant);
+        Ok(())
+    }
+
+    /// Void an authorized payment, returning the whole hold to the buyer
+    /// The merchant can void at any time; the buyer only once the hold has expired.
+    pub fn void_authorization(ctx: Context<VoidAuthorization>) -> Result<()> {
+        let payment_hold = &ctx.accounts.payment_hold;
+        let signer = ctx.accounts.signer.key();
+
+        if signer != payment_hold.merchant {
+            require!(signer == payment_hold.buyer, VaultError::Unauthorized);
+            require!(time::now()? > payment_hold.expiry, VaultError::HoldNotExpired);
+        }
+
+        release_hold(
+            payment_hold,
+            &ctx.accounts.escrow_token_account,
+            None,
+            &ctx.accounts.buyer_token_account,
+            &ctx.accounts.buyer,
+            &ctx.accounts.token_program,
+            0,
+        )?;
+
+        emit_cpi!(AuthorizationVoided {
+            payment_hold: payment_hold.key(),
+            merchant: payment_hold.merchant,
+            buyer: payment_hold.buyer,
+            amount: payment_hold.amount,
+            voided_by: signer,
+        });
+
+        msg!("Authorization voided: {} returned to buyer {}", payment_hold.amount, payment_hold.buyer);
+        Ok(())
+    }
+
+    /// Let `agent` settle orders on the buyer's behalf, up to `period_cap` every
+    /// `period_seconds` and `allowance` in total (base units of the token account's mint)
+    /// The grant PDA becomes the token account's delegate, replacing any earlier
+    /// delegate (a token account has only one, so this ends a subscription on it).
+    pub fn create_spending_grant(
+        ctx: Context<CreateSpendingGrant>,
+        period_cap: u64,
+        period_seconds: i64,
+        allowance: u64,
+    ) -> Result<()> {
+        require!(
+            period_cap > 0 && allowance > 0 && period_seconds >= MIN_GRANT_PERIOD,
+            VaultError::InvalidGrantTerms
+        );
+
+        let cpi_accounts = Approve {
+            to: ctx.accounts.buyer_token_account.to_account_info(),
+            delegate: ctx.accounts.spending_grant.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, allowance)?;
+
+        let current_time = time::now()?;
+        let grant = &mut ctx.accounts.spending_grant;
+        grant.buyer = ctx.accounts.buyer.key();
+        grant.agent = ctx.accounts.agent.key();
+        grant.vault = ctx.accounts.vault.key();
+        grant.buyer_token_account = ctx.accounts.buyer_token_account.key();
+        grant.mint = ctx.accounts.buyer_token_account.mint;
+        grant.period_cap = period_cap;
+        grant.period_seconds = period_seconds;
+        grant.period_start = current_time;
+        grant.spent_in_period = 0;
+        grant.total_spent = 0;
+        grant.created_at = current_time;
+        grant.bump = ctx.bumps.spending_grant;
+
+        msg!(
+            "Spending grant: agent {} may spend {} every {}s ({} total) for buyer {}",
+            grant.agent,
+            period_cap,
+            period_seconds,
+            allowance,
+            grant.buyer
+        );
+        Ok(())
+    }
+
+    /// Revoke a spending grant and its token delegation, returning rent to the buyer
+    pub fn revoke_spending_grant(ctx: Context<RevokeSpendingGrant>) -> Result<()> {
+        // Only revoke if the delegation still belongs to this grant
+        if ctx.accounts.buyer_token_account.delegate == COption::Some(ctx.accounts.spending_grant.key()) {
+            let cpi_accounts = Revoke {
+                source: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::revoke(cpi_ctx)?;
+        }
+
+        msg!(
+            "Spending grant revoked: buyer {} agent {} ({} spent)",
+            ctx.accounts.spending_grant.buyer,
+            ctx.accounts.spending_grant.agent,
+            ctx.accounts.spending_grant.total_spent
+        );
+        Ok(())
+    }
+
+    /// Record up to `MAX_BATCH_ORDERS` processed orders in one instruction
+    /// Metrics are updated once for the whole batch. Each order gets an `OrderReceipt`
+    /// PDA, passed in `remaining_accounts` in the same order as `orders`.
+    pub fn record_orders_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecordOrdersBatch<'info>>,
+        orders: Vec<OrderInput>,
+    ) -> Result<()> {
+        let authorized_agent = &ctx.accounts.authorized_agent;
+
+        // Verify agent is authorized and active
+        require!(authorized_agent.is_active, VaultError::UnauthorizedAgent);
+        require!(
+            authorized_agent.agent == ctx.accounts.agent.key(),
+            VaultError::UnauthorizedAgent
+        );
+        require!(
+            authorized_agent.merchant == ctx.accounts.merchant_deposit.merchant,
+            VaultError::UnauthorizedAgent
+        );
+
+        require!(
+            !orders.is_empty() && orders.len() <= MAX_BATCH_ORDERS,
+            VaultError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == orders.len(),
+            VaultError::InvalidReceiptAccount
+        );
+
+        let current_time = time::now()?;
+        let vault_key = ctx.accounts.vault.key();
+        let merchant_key = ctx.accounts.merchant.key();
+        let agent_key = ctx.accounts.agent.key();
+        let receipt_space = 8 + OrderReceipt::LEN;
+
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        let mut batch_volume: u64 = 0;
+        let mut credited_volume: u64 = 0;
+        let min_order_usd = ctx.accounts.vault.min_order_usd();
+        for (order, receipt_info) in orders.iter().zip(ctx.remaining_accounts.iter()) {
+            // Validate minimum order amount (anti-gaming)
+            require!(order.amount_usd >= min_order_usd, VaultError::OrderTooSmall);
+
+            let (receipt_key, receipt_bump) = Pubkey::find_program_address(
+                &[RECEIPT_SEED, vault_key.as_ref(), merchant_key.as_ref(), order.order_id.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(receipt_info.key(), receipt_key, VaultError::InvalidReceiptAccount);
+
+            // Create the receipt PDA; a duplicate order id finds it already owned by the vault
+            require_keys_eq!(*receipt_info.owner, system_program::ID, VaultError::InvalidReceiptAccount);
+            create_pda_account(
+                &ctx.accounts.agent.to_account_info(),
+                receipt_info,
+                &ctx.accounts.system_program.to_account_info(),
+                receipt_space,
+                &[
+                    RECEIPT_SEED,
+                    vault_key.as_ref(),
+                    merchant_key.as_ref(),
+                    order.order_id.as_ref(),
+                    &[receipt_bump],
+                ],
+            )?;
+
+            let receipt = OrderReceipt {
+                merchant: merchant_key,
+                vault: vault_key,
+                agent: agent_key,
+                buyer: order.buyer_wallet,
+                order_id: order.order_id,
+                amount_usd: order.amount_usd,
+                recorded_at: current_time,
+                bump: receipt_bump,
+                cnft_metadata_hash: [0u8; 32],
+                metadata_hash: order.metadata_hash,
+                settled_mint: Pubkey::default(),
+                settled_amount: 0,
+                refunded_amount: 0,
+                refunded_usd: 0,
+            };
+            let mut data = receipt_info.try_borrow_mut_data()?;
+            let mut writer: &mut [u8] = &mut data;
+            receipt.try_serialize(&mut writer)?;
+
+            batch_volume = batch_volume
+                .checked_add(order.amount_usd)
+                .ok_or(VaultError::MathOverflow)?;
+            credited_volume = credited_volume
+                .checked_add(repeat_purchase_credit(
+                    &ctx.accounts.vault,
+                    &mut metrics,
+                    &order.buyer_wallet,
+                    order.amount_usd,
+                    current_time,
+                )?)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        let previous_yield_bps = merchant_deposit.current_yield_bps;
+        apply_order_batch_metrics(
+            &ctx.accounts.vault,
+            merchant_deposit,
+            &mut metrics,
+            batch_volume,
+            credited_volume,
+            orders.len() as u64,
+            current_time,
+        )?;
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+        if let Some(sub_merchant) = ctx.accounts.sub_merchant.as_mut() {
+            apply_sub_merchant_orders(sub_merchant, batch_volume, orders.len() as u64, current_time)?;
+        }
+
+        msg!("Batch recorded: {} orders | ${} | Current yield: {} bps",
+            orders.len(),
+            batch_volume / 1_000000,
+            merchant_deposit.current_yield_bps
+        );
+
+        Ok(())
+    }
+
+    /// Record an order attested off-chain by a registered agent
+    /// The agent signs (merchant, order_id, amount, timestamp, metadata_hash) with ed25519
+    /// and any relayer submits it; the Ed25519 program instruction must immediately precede this one.
+    /// The receipt PDA (seeded by order_id) prevents replays.
+    pub fn record_attested_order(
+        ctx: Context<RecordAttestedOrder>,
+        order_id: [u8; 32],
+        order_amount_usd: u64,
+        timestamp: i64,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        let authorized_agent = &ctx.accounts.authorized_agent;
+
+        // Verify agent is authorized and active
+        require!(authorized_agent.is_active, VaultError::UnauthorizedAgent);
+        require!(
+            authorized_agent.agent == ctx.accounts.agent.key(),
+            VaultError::UnauthorizedAgent
+        );
+        require!(
+            authorized_agent.merchant == ctx.accounts.merchant_deposit.merchant,
+            VaultError::UnauthorizedAgent
+        );
+
+        // Reject stale or future-dated attestations
+        let current_time = time::now()?;
+        require!(
+            current_time.abs_diff(timestamp) <= MAX_ATTESTATION_AGE as u64,
+            VaultError::StaleAttestation
+        );
+
+        // Verify the agent's signature via the preceding Ed25519 program instruction
+        let ix_sysvar = &ctx.accounts.instructions_sysvar;
+        let current_index = load_current_index_checked(ix_sysvar)?;
+        require!(current_index > 0, VaultError::InvalidAttestation);
+        let ed25519_ix = load_instruction_at_checked((current_index - 1) as usize, ix_sysvar)?;
+
+        let message = attestation_message(
+            &ctx.accounts.merchant.key(),
+            &order_id,
+            order_amount_usd,
+            timestamp,
+            &metadata_hash,
+        );
+        verify_ed25519_attestation(&ed25519_ix, &ctx.accounts.agent.key(), &message)?;
+
+        // The buyer isn't part of the signed attestation, so it can't be dampened
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        apply_order_metrics(
+            &ctx.accounts.vault,
+            &mut ctx.accounts.merchant_deposit,
+            &mut *ctx.accounts.merchant_metrics.load_mut()?,
+            order_amount_usd,
+            None,
+            current_time,
+        )?;
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        let receipt = &mut ctx.accounts.order_receipt;
+        receipt.merchant = ctx.accounts.merchant.key();
+        receipt.vault = ctx.accounts.vault.key();
+        receipt.agent = ctx.accounts.agent.key();
+        receipt.buyer = Pubkey::default(); // Not part of the signed attestation
+        receipt.order_id = order_id;
+        receipt.amount_usd = order_amount_usd;
+        receipt.recorded_at = current_time;
+        receipt.bump = ctx.bumps.order_receipt;
+        receipt.cnft_metadata_hash = [0u8; 32];
+        receipt.metadata_hash = metadata_hash;
+        receipt.settled_mint = Pubkey::default();
+        receipt.settled_amount = 0;
+        receipt.refunded_amount = 0;
+        receipt.refunded_usd = 0;
+
+        msg!("Attested order recorded: ${} | Current yield: {} bps",
+            order_amount_usd / 1_000000,
+            ctx.accounts.merchant_deposit.current_yield_bps
+        );
+
+        Ok(())
+    }
+
+    /// Settle an order by paying the merchant in SOL or an accepted SPL token
+    /// The credited USD volume is computed from the transferred amount and a
+    /// Pyth price for the token rather than reported by an agent. Settlements in
+    /// SOL use the native mint's price feed. The platform fee for the merchant's
+    /// tier goes to the treasury; volume is credited on the full amount.
+    /// `metadata_hash` commits to the order contents and is stored on the receipt.
+    pub fn settle_order<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleOrder<'info>>,
+        order_id: [u8; 32],
+        amount: u64,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        check_settlement_guard(
+            &ctx.accounts.vault,
+            ctx.accounts.instructions_sysvar.as_ref().map(AsRef::as_ref),
+        )?;
+
+        let current_time = time::now()?;
+        let price_feed = &ctx.accounts.price_feed;
+
+        // Platform fee at the merchant's tier; the merchant receives the rest
+        let fee_bps = ctx.accounts.vault.platform_fee_bps(ctx.accounts.merchant_deposit.current_tier);
+        let platform_fee = bps_floor(amount, fee_bps as u64).ok_or(VaultError::MathOverflow)?;
+        let merchant_amount = amount.checked_sub(platform_fee).ok_or(VaultError::MathOverflow)?;
+
+        if price_feed.mint == native_mint::ID {
+            let buyer = ctx.accounts.buyer.to_account_info();
+            for (to, lamports) in [
+                (ctx.accounts.merchant.to_account_info(), merchant_amount),
+                (ctx.accounts.treasury.to_account_info(), platform_fee),
+            ] {
+                if lamports == 0 {
+                    continue;
+                }
+                let ix = anchor_lang::solana_program::system_instruction::transfer(&buyer.key(), &to.key(), lamports);
+                anchor_lang::solana_program::program::invoke(&ix, &[buyer.clone(), to])?;
+            }
+        } else {
+            let (
+                Some(buyer_token_account),
+                Some(merchant_token_account),
+                Some(mint),
+                Some(token_program),
+                Some(associated_token_program),
+            ) = (
+                ctx.accounts.buyer_token_account.as_ref(),
+                ctx.accounts.merchant_token_account.as_ref(),
+                ctx.accounts.mint.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+                ctx.accounts.associated_token_program.as_ref(),
+            ) else {
+                return err!(VaultError::InvalidSettlementAccounts);
+            };
+            require!(buyer_token_account.mint == mint.key(), VaultError::InvalidSettlementAccounts);
+
+            // First payment in a mint: the buyer creates the recipients' token accounts
+            let buyer = ctx.accounts.buyer.to_account_info();
+            create_associated_token_account(
+                &buyer,
+                merchant_token_account,
+                &ctx.accounts.merchant,
+                &mint.to_account_info(),
+                &ctx.accounts.system_program,
+                token_program,
+                associated_token_program,
+            )?;
+
+            let cpi_accounts = Transfer {
+                from: buyer_token_account.to_account_info(),
+                to: merchant_token_account.to_account_info(),
+                authority: ctx.accounts.buyer.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, merchant_amount)?;
+
+            if platform_fee > 0 {
+                let treasury_token_account = ctx
+                    .accounts
+                    .treasury_token_account
+                    .as_ref()
+                    .ok_or(VaultError::InvalidSettlementAccounts)?;
+                create_associated_token_account(
+                    &buyer,
+                    treasury_token_account,
+                    &ctx.accounts.treasury,
+                    &mint.to_account_info(),
+                    &ctx.accounts.system_program,
+                    token_program,
+                    associated_token_program,
+                )?;
+                let cpi_accounts = Transfer {
+                    from: buyer_token_account.to_account_info(),
+                    to: treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+                token::transfer(cpi_ctx, platform_fee)?;
+            }
+        }
+
+        let price_update = oracle::load_price_update(&ctx.accounts.price_update)?;
+        let price = oracle::conservative_price(
+            &price_update,
+            &price_feed.feed_id,
+            price_feed.max_age,
+            current_time,
+        )?;
+        let amount_usd = price_feed.usd_value(amount, price).ok_or(VaultError::MathOverflow)?;
+        let platform_fee_usd = price_feed.usd_value(platform_fee, price).ok_or(VaultError::MathOverflow)?;
+        ctx.accounts.merchant_deposit.total_fees_paid_usd = ctx
+            .accounts
+            .merchant_deposit
+            .total_fees_paid_usd
+            .checked_add(platform_fee_usd)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        {
+            let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+            apply_order_metrics(
+                &ctx.accounts.vault,
+                &mut ctx.accounts.merchant_deposit,
+                &mut metrics,
+                amount_usd,
+                Some(&ctx.accounts.buyer.key()),
+                current_time,
+            )?;
+            record_currency_volume(&mut metrics, &price_feed.mint, amount, amount_usd)?;
+        }
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        let receipt = &mut ctx.accounts.order_receipt;
+        receipt.merchant = ctx.accounts.merchant.key();
+        receipt.vault = ctx.accounts.vault.key();
+        receipt.agent = Pubkey::default(); // No agent: the amount is oracle-priced
+        receipt.buyer = ctx.accounts.buyer.key();
+        receipt.order_id = order_id;
+        receipt.amount_usd = amount_usd;
+        receipt.recorded_at = current_time;
+        receipt.bump = ctx.bumps.order_receipt;
+        receipt.cnft_metadata_hash = [0u8; 32];
+        receipt.metadata_hash = metadata_hash;
+        receipt.settled_mint = price_feed.mint;
+        receipt.settled_amount = amount;
+        receipt.refunded_amount = 0;
+        receipt.refunded_usd = 0;
+
+        emit_cpi!(OrderSettled {
+            merchant: receipt.merchant,
+            buyer: receipt.buyer,
+            mint: price_feed.mint,
+            amount,
+            amount_usd,
+            price: price.price,
+            exponent: price.exponent,
+            platform_fee,
+        });
+        // Settlement is agent-free and deposits nothing, so no processor fee or vault credit yet
+        emit_cpi!(SettlementBreakdown {
+            order_receipt: receipt.key(),
+            merchant: receipt.merchant,
+            mint: price_feed.mint,
+            gross: amount,
+            platform_fee,
+            processor_fee: 0,
+            net_to_merchant: merchant_amount,
+            vault_credited: 0,
+        });
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Settlement,
+            receipt.merchant,
+            price_feed.mint,
+            amount,
+            order_id,
+        )?;
+
+        msg!("Order settled: {} of {} = ${} (platform fee {})",
+            amount,
+            price_feed.mint,
+            amount_usd / 1_000000,
+            platform_fee
+        );
+        Ok(())
+    }
+
+    /// Settle an order from a buyer's token account as the agent holding their spending grant
+    /// Works like `settle_order` without the buyer's signature: the grant PDA moves the
+    /// funds as the account's delegate, and the payment counts against the grant's
+    /// per-period cap. The agent pays for the receipt and is recorded on it.
+    pub fn settle_order_with_grant<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleOrderWithGrant<'info>>,
+        order_id: [u8; 32],
+        amount: u64,
+        metadata_hash: [u8; 32],
+    ) -> Result<()> {
+        check_settlement_guard(
+            &ctx.accounts.vault,
+            ctx.accounts.instructions_sysvar.as_ref().map(AsRef::as_ref),
+        )?;
+
+        let current_time = time::now()?;
+        ctx.accounts.spending_grant.record_spend(amount, current_time)?;
+
+        let fee_bps = ctx.accounts.vault.platform_fee_bps(ctx.accounts.merchant_deposit.current_tier);
+        let platform_fee = bps_floor(amount, fee_bps as u64).ok_or(VaultError::MathOverflow)?;
+        let merchant_amount = amount.checked_sub(platform_fee).ok_or(VaultError::MathOverflow)?;
+
+        let agent = ctx.accounts.agent.to_account_info();
+        let grant = &ctx.accounts.spending_grant;
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            SPENDING_GRANT_SEED,
+            vault_key.as_ref(),
+            grant.buyer.as_ref(),
+            grant.agent.as_ref(),
+            &[grant.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let mut payees = vec![(&ctx.accounts.merchant_token_account, &ctx.accounts.merchant, merchant_amount)];
+        if platform_fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(VaultError::InvalidSettlementAccounts)?;
+            payees.push((treasury_token_account, &ctx.accounts.treasury, platform_fee));
+        }
+        for (token_account, owner, payment) in payees {
+            // First payment in a mint: the agent creates the recipients' token accounts
+            create_associated_token_account(
+                &agent,
+                token_account,
+                owner,
+                &ctx.accounts.mint.to_account_info(),
+                &ctx.accounts.system_program,
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+            )?;
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.buyer_token_account.to_account_info(),
+                to: token_account.to_account_info(),
+                authority: ctx.accounts.spending_grant.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, payment)?;
+        }
+
+        let price_feed = &ctx.accounts.price_feed;
+        let price_update = oracle::load_price_update(&ctx.accounts.price_update)?;
+        let price = oracle::conservative_price(
+            &price_update,
+            &price_feed.feed_id,
+            price_feed.max_age,
+            current_time,
+        )?;
+        let amount_usd = price_feed.usd_value(amount, price).ok_or(VaultError::MathOverflow)?;
+        let platform_fee_usd = price_feed.usd_value(platform_fee, price).ok_or(VaultError::MathOverflow)?;
+        ctx.accounts.merchant_deposit.total_fees_paid_usd = ctx
+            .accounts
+            .merchant_deposit
+            .total_fees_paid_usd
+            .checked_add(platform_fee_usd)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let buyer = ctx.accounts.spending_grant.buyer;
+        let previous_yield_bps = ctx.accounts.merchant_deposit.current_yield_bps;
+        {
+            let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+            apply_order_metrics(
+                &ctx.accounts.vault,
+                &mut ctx.accounts.merchant_deposit,
+                &mut metrics,
+                amount_usd,
+                Some(&buyer),
+                current_time,
+            )?;
+            record_currency_volume(&mut metrics, &price_feed.mint, amount, amount_usd)?;
+        }
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            ctx.accounts.merchant_deposit.current_yield_bps,
+            YieldTrigger::Orders,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        let receipt = &mut ctx.accounts.order_receipt;
+        receipt.merchant = ctx.accounts.merchant.key();
+        receipt.vault = vault_key;
+        receipt.agent = ctx.accounts.agent.key();
+        receipt.buyer = buyer;
+        receipt.order_id = order_id;
+        receipt.amount_usd = amount_usd;
+        receipt.recorded_at = current_time;
+        receipt.bump = ctx.bumps.order_receipt;
+        receipt.cnft_metadata_hash = [0u8; 32];
+        receipt.metadata_hash = metadata_hash;
+        receipt.settled_mint = price_feed.mint;
+        receipt.settled_amount = amount;
+        receipt.refunded_amount = 0;
+        receipt.refunded_usd = 0;
+
+        emit_cpi!(OrderSettled {
+            merchant: receipt.merchant,
+            buyer,
+            mint: price_feed.mint,
+            amount,
+            amount_usd,
+            price: price.price,
+            exponent: price.exponent,
+            platform_fee,
+        });
+        emit_cpi!(SettlementBreakdown {
+            order_receipt: receipt.key(),
+            merchant: receipt.merchant,
+            mint: price_feed.mint,
+            gross: amount,
+            platform_fee,
+            processor_fee: 0,
+            net_to_merchant: merchant_amount,
+            vault_credited: 0,
+        });
+        notify_downstream(
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            NotificationKind::Settlement,
+            receipt.merchant,
+            price_feed.mint,
+            amount,
+            order_id,
+        )?;
+
+        let grant = &ctx.accounts.spending_grant;
+        msg!("Order settled by agent {}: {} of {} = ${} ({} of {} spent this period)",
+            grant.agent,
+            amount,
+            price_feed.mint,
+            amount_usd / 1_000000,
+            grant.spent_in_period,
+            grant.period_cap
+        );
+        Ok(())
+    }
+
+    /// Accept `mint` for oracle-priced settlement, priced by Pyth feed `feed_id` (admin only)
+    /// Use the native mint for SOL
+    pub fn register_price_feed(
+        ctx: Context<RegisterPriceFeed>,
+        mint: Pubkey,
+        feed_id: [u8; 32],
+        max_age: i64,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(max_age > 0 && max_age <= MAX_PRICE_AGE, VaultError::StalePrice);
+
+        let decimals = if mint == native_mint::ID {
+            SOL_DECIMALS
+        } else {
+            ctx.accounts.mint_account.as_ref().ok_or(VaultError::MissingTokenAccount)?.decimals
+        };
+
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.vault = ctx.accounts.vault.key();
+        price_feed.mint = mint;
+        price_feed.feed_id = feed_id;
+        price_feed.max_age = max_age;
+        price_feed.bump = ctx.bumps.price_feed;
+        price_feed.decimals = decimals;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::PriceFeedRegistered,
+            ctx.accounts.authority.key(),
+            mint,
+            max_age as u64,
+            0,
+        )?;
+
+        msg!("Price feed registered for {}", mint);
+        Ok(())
+    }
+
+    /// Change a settlement mint's Pyth feed or staleness limit (admin only)
+    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>, feed_id: [u8; 32], max_age: i64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(max_age > 0 && max_age <= MAX_PRICE_AGE, VaultError::StalePrice);
+
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.feed_id = feed_id;
+        price_feed.max_age = max_age;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::PriceFeedUpdated,
+            ctx.accounts.authority.key(),
+            price_feed.mint,
+            max_age as u64,
+            0,
+        )?;
+
+        msg!("Price feed updated for {}", price_feed.mint);
+        Ok(())
+    }
+
+    /// Track a deposit mint's pause flag (admin or guardian), the native mint for SOL
+    pub fn register_supported_mint(
+        ctx: Context<RegisterSupportedMint>,
+        mint: Pubkey,
+        deposits_paused: bool,
+    ) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let vault = &ctx.accounts.vault;
+        require!(signer == vault.authority || signer == vault.guardian, VaultError::Unauthorized);
+
+        let supported_mint = &mut ctx.accounts.supported_mint;
+        supported_mint.vault = vault.key();
+        supported_mint.mint = mint;
+        supported_mint.deposits_paused = deposits_paused;
+        supported_mint.paused_at = if deposits_paused { time::now()? } else { 0 };
+        supported_mint.bump = ctx.bumps.supported_mint;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::MintDepositsPaused,
+            signer,
+            mint,
+            0,
+            deposits_paused as u16,
+        )?;
+
+        msg!("Supported mint {} registered (deposits paused: {})", mint, deposits_paused);
+        Ok(())
+    }
+
+    /// Pause or resume new deposits of one mint (admin or guardian)
+    /// Unlike emergency mode this leaves every other asset untouched, and
+    /// withdrawals of the paused mint stay open.
+    pub fn set_mint_deposits_paused(ctx: Context<SetMintDepositsPaused>, paused: bool) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let vault = &ctx.accounts.vault;
+        require!(signer == vault.authority || signer == vault.guardian, VaultError::Unauthorized);
+
+        let supported_mint = &mut ctx.accounts.supported_mint;
+        if paused && !supported_mint.deposits_paused {
+            supported_mint.paused_at = time::now()?;
+        }
+        supported_mint.deposits_paused = paused;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::MintDepositsPaused,
+            signer,
+            supported_mint.mint,
+            0,
+            paused as u16,
+        )?;
+
+        msg!("Deposits of {} {}", supported_mint.mint, if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    /// Set the oracle price band of a stablecoin (admin only), in micro-USD per
+    /// whole token; both zero turns the depeg check off
+    pub fn set_depeg_bounds(ctx: Context<SetDepegBounds>, lower_bound: u64, upper_bound: u64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let supported_mint = &mut ctx.accounts.supported_mint;
+        require!(supported_mint.mint != native_mint::ID, VaultError::InvalidDepegBounds);
+        require!(
+            (lower_bound == 0 && upper_bound == 0) || (lower_bound > 0 && lower_bound < upper_bound),
+            VaultError::InvalidDepegBounds
+        );
+        // A mint suspended under the old band is only released by `check_depeg`
+        require!(upper_bound > 0 || !supported_mint.depegged, VaultError::MintDepegged);
+
+        supported_mint.depeg_lower_bound = lower_bound;
+        supported_mint.depeg_upper_bound = upper_bound;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::DepegBoundsSet,
+            ctx.accounts.authority.key(),
+            supported_mint.mint,
+            lower_bound,
+            0,
+        )?;
+
+        msg!("Depeg bounds of {} set to [{}, {}]", supported_mint.mint, lower_bound, upper_bound);
+        Ok(())
+    }
+
+    /// Compare a stablecoin's oracle price with its band (permissionless crank)
+    /// Leaving the band suspends the mint's deposits and, while any stablecoin
+    /// is out, SPL yield accrual; returning to it lifts both.
+    pub fn check_depeg(ctx: Context<CheckDepeg>) -> Result<()> {
+        let current_time = time::now()?;
+        let supported_mint = &mut ctx.accounts.supported_mint;
+        require!(supported_mint.has_depeg_bounds(), VaultError::DepegBoundsNotSet);
+
+        let price_feed = &ctx.accounts.price_feed;
+        let price_update = oracle::load_price_update(&ctx.accounts.price_update)?;
+        let price = oracle::spot_price(&price_update, &price_feed.feed_id, price_feed.max_age, current_time)?;
+        let price = oracle::usd_value(1, price, 0).ok_or(VaultError::MathOverflow)?;
+
+        let depegged = supported_mint.is_out_of_bounds(price);
+        if depegged == supported_mint.depegged {
+            msg!("{} at {} micro-USD, depegged: {}", supported_mint.mint, price, depegged);
+            return Ok(());
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        vault.update_suspension(current_time, |vault| {
+            if depegged {
+                if vault.depegged_mints == 0 {
+                    vault.depeg_suspended_at = current_time;
+                }
+                vault.depegged_mints = vault.depegged_mints.checked_add(1).ok_or(VaultError::MathOverflow)?;
+            } else {
+                vault.depegged_mints = vault.depegged_mints.checked_sub(1).ok_or(VaultError::MathOverflow)?;
+                if vault.depegged_mints == 0 {
+                    vault.depeg_suspended_at = 0;
+                }
+            }
+            Ok(())
+        })?;
+        if depegged {
+            supported_mint.depegged_at = current_time;
+        }
+        supported_mint.depegged = depegged;
+
+        emit_cpi!(DepegStatusChanged {
+            vault: vault.key(),
+            mint: supported_mint.mint,
+            depegged,
+            price,
+            lower_bound: supported_mint.depeg_lower_bound,
+            upper_bound: supported_mint.depeg_upper_bound,
+            timestamp: current_time,
+        });
+
+        msg!("{} {} at {} micro-USD", supported_mint.mint, if depegged { "depegged" } else { "repegged" }, price);
+        Ok(())
+    }
+
+    /// Mint a compressed NFT receipt for a recorded order to its buyer (optional)
+    /// Mints into a Bubblegum tree whose tree delegate is the vault PDA and stores
+    /// the cNFT metadata hash on the order receipt. Only the recording agent may mint.
+    pub fn mint_receipt_cnft(ctx: Context<MintReceiptCnft>) -> Result<()> {
+        let receipt = &ctx.accounts.order_receipt;
+
+        require!(receipt.agent == ctx.accounts.agent.key(), VaultError::UnauthorizedAgent);
+        require!(receipt.buyer != Pubkey::default(), VaultError::ReceiptHasNoBuyer);
+        require_keys_eq!(ctx.accounts.buyer.key(), receipt.buyer, VaultError::ReceiptHasNoBuyer);
+        require!(receipt.cnft_metadata_hash == [0u8; 32], VaultError::ReceiptAlreadyMinted);
+
+        let order_id_hex: String = receipt.order_id.iter().map(|b| format!("{:02x}", b)).collect();
+        let metadata = MetadataArgs {
+            name: RECEIPT_CNFT_NAME.to_string(),
+            symbol: TIER_BADGE_SYMBOL.to_string(),
+            uri: format!("{}{}.json", RECEIPT_CNFT_URI_BASE, order_id_hex),
+            seller_fee_basis_points: 0,
+            primary_sale_happened: true,
+            is_mutable: false,
+            edition_nonce: None,
+            token_standard: Some(TokenStandard::NonFungible),
+            collection: None,
+            uses: None,
+            token_program_version: TokenProgramVersion::Original,
+            creators: Vec::new(),
+        };
+        let metadata_hash = hash_metadata(&metadata)?;
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        MintV1CpiBuilder::new(&ctx.accounts.bubblegum_program)
+            .tree_config(&ctx.accounts.tree_config)
+            .leaf_owner(&ctx.accounts.buyer)
+            .leaf_delegate(&ctx.accounts.buyer)
+            .merkle_tree(&ctx.accounts.merkle_tree)
+            .payer(&ctx.accounts.agent)
+            .tree_creator_or_delegate(&ctx.accounts.vault.to_account_info())
+            .log_wrapper(&ctx.accounts.log_wrapper)
+            .compression_program(&ctx.accounts.compression_program)
+            .system_program(&ctx.accounts.system_program)
+            .metadata(metadata)
+            .invoke_signed(signer)?;
+
+        ctx.accounts.order_receipt.cnft_metadata_hash = metadata_hash;
+
+        msg!("Receipt cNFT minted to buyer {}", ctx.accounts.buyer.key());
+        Ok(())
+    }
+
+    /// Dispute a recorded order (buyer or platform, within `DISPUTE_WINDOW`)
+    /// The order's volume is held out of the merchant's metrics until
+    /// `resolve_dispute`. Settlement funds never pass through the vault, so the
+    /// hold applies to metrics (and the yield/tier derived from them) only.
+    pub fn open_dispute(
+        ctx: Context<OpenDispute>,
+        order_id: [u8; 32],
+        reason_hash: [u8; 32],
+    ) -> Result<()> {
+        let receipt = &ctx.accounts.order_receipt;
+        let opener = ctx.accounts.opener.key();
+        require!(
+            opener == ctx.accounts.vault.authority
+                || (receipt.buyer != Pubkey::default() && opener == receipt.buyer),
+            VaultError::Unauthorized
+        );
+
+        let current_time = time::now()?;
+        require!(
+            elapsed_seconds(receipt.recorded_at, current_time) <= DISPUTE_WINDOW,
+            VaultError::DisputeWindowClosed
+        );
+
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        let held_volume = hold_disputed_volume(&mut metrics, receipt, current_time)?;
+        if let Some(merchant_deposit) = ctx.accounts.merchant_deposit.as_mut() {
+            let previous_yield_bps = merchant_deposit.current_yield_bps;
+            merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+                merchant_deposit,
+                &metrics,
+                merchant_deposit.total_deposited,
+            );
+            if let Some(updated) = record_yield_change(
+                &ctx.accounts.yield_history,
+                previous_yield_bps,
+                merchant_deposit.current_yield_bps,
+                YieldTrigger::DisputeOpened,
+            )? {
+                emit_cpi!(updated);
+            }
+        }
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.order_receipt = receipt.key();
+        dispute.merchant = receipt.merchant;
+        dispute.vault = ctx.accounts.vault.key();
+        dispute.opened_by = opener;
+        dispute.amount_usd = receipt.amount_usd.saturating_sub(receipt.refunded_usd);
+        dispute.held_volume = held_volume;
+        dispute.reason_hash = reason_hash;
+        dispute.status = DisputeStatus::Open;
+        dispute.opened_at = current_time;
+        dispute.resolved_at = 0;
+        dispute.bump = ctx.bumps.dispute;
+
+        emit_cpi!(DisputeOpened {
+            dispute: dispute.key(),
+            merchant: dispute.merchant,
+            order_id,
+            opened_by: opener,
+            amount_usd: dispute.amount_usd,
+            reason_hash,
+        });
+
+        msg!("Dispute opened on order ${} for merchant {}",
+            dispute.amount_usd / 1_000000,
+            dispute.merchant
+        );
+        Ok(())
+    }
+
+    /// Resolve an open dispute (admin only)
+    /// `MerchantWins` restores the held volume; `Refunded` drops the order from
+    /// the merchant's volume for good
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, outcome: DisputeStatus) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.vault.authority,
+            VaultError::Unauthorized
+        );
+        require!(outcome != DisputeStatus::Open, VaultError::InvalidDisputeOutcome);
+
+        let dispute = &mut ctx.accounts.dispute;
+        require!(dispute.status == DisputeStatus::Open, VaultError::DisputeNotOpen);
+
+        let current_time = time::now()?;
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        release_disputed_volume(
+            &mut metrics,
+            dispute,
+            outcome == DisputeStatus::MerchantWins,
+            current_time,
+        )?;
+        if let Some(merchant_deposit) = ctx.accounts.merchant_deposit.as_mut() {
+            let previous_yield_bps = merchant_deposit.current_yield_bps;
+            merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+                merchant_deposit,
+                &metrics,
+                merchant_deposit.total_deposited,
+            );
+            if let Some(updated) = record_yield_change(
+                &ctx.accounts.yield_history,
+                previous_yield_bps,
+                merchant_deposit.current_yield_bps,
+                YieldTrigger::DisputeResolved,
+            )? {
+                emit_cpi!(updated);
+            }
+        }
+
+        dispute.status = outcome.clone();
+        dispute.resolved_at = current_time;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::DisputeResolved,
+            ctx.accounts.authority.key(),
+            dispute.merchant,
+            dispute.amount_usd,
+            outcome.clone() as u16,
+        )?;
+
+        emit_cpi!(DisputeResolved {
+            dispute: dispute.key(),
+            merchant: dispute.merchant,
+            outcome,
+            amount_usd: dispute.amount_usd,
+            resolved_at: current_time,
+        });
+
+        msg!("Dispute {} resolved", dispute.key());
+        Ok(())
+    }
+
+    /// Refund part or all of a settled order to its buyer (merchant only)
+    /// May be called repeatedly until the whole order is refunded. `amount` is in
+    /// the settled mint's base units. The receipt tracks the running totals, and the
+    /// refunded share of the order's USD value comes off the merchant's volume.
+    pub fn refund_order(ctx: Context<RefundOrder>, order_id: [u8; 32], amount: u64) -> Result<()> {
+        let receipt = &ctx.accounts.order_receipt;
+        require!(receipt.settled_amount > 0, VaultError::OrderNotSettled);
+        let refunded_amount = receipt.refunded_amount.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        require!(amount > 0 && refunded_amount <= receipt.settled_amount, VaultError::InvalidRefund);
+
+        // A dispute already took the whole order off the merchant's volume unless the merchant won it
+        let dispute_info = ctx.accounts.dispute.to_account_info();
+        if dispute_info.owner == &crate::ID && !dispute_info.data_is_empty() {
+            let dispute = OrderDispute::try_deserialize(&mut &dispute_info.try_borrow_data()?[..])?;
+            require!(dispute.status == DisputeStatus::MerchantWins, VaultError::OrderDisputed);
+        }
+
+        if receipt.settled_mint == native_mint::ID {
+            let ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.merchant.key(),
+                &ctx.accounts.buyer.key(),
+                amount,
+            );
+            anchor_lang::solana_program::program::invoke(
+                &ix,
+                &[ctx.accounts.merchant.to_account_info(), ctx.accounts.buyer.to_account_info()],
+            )?;
+        } else {
+            let (Some(merchant_token_account), Some(buyer_token_account), Some(token_program)) = (
+                ctx.accounts.merchant_token_account.as_ref(),
+                ctx.accounts.buyer_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(VaultError::InvalidSettlementAccounts);
+            };
+            require!(merchant_token_account.mint == receipt.settled_mint, VaultError::InvalidSettlementAccounts);
+
+            let cpi_accounts = Transfer {
+                from: merchant_token_account.to_account_info(),
+                to: buyer_token_account.to_account_info(),
+                authority: ctx.accounts.merchant.to_account_info(),
+            };
+            token::transfer(CpiContext::new(token_program.to_account_info(), cpi_accounts), amount)?;
+        }
+
+        // Cumulative, so the refunds of a fully refunded order add up to its whole USD value
+        let refunded_usd = mul_div_floor(receipt.amount_usd, refunded_amount, receipt.settled_amount)
+            .ok_or(VaultError::MathOverflow)?;
+        let refund_usd = refunded_usd
+            .checked_sub(receipt.refunded_usd)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let current_time = time::now()?;
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        remove_refunded_volume(&mut metrics, receipt, amount, refund_usd, current_time)?;
+        if let Some(merchant_deposit) = ctx.accounts.merchant_deposit.as_mut() {
+            let previous_yield_bps = merchant_deposit.current_yield_bps;
+            merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+                merchant_deposit,
+                &metrics,
+                merchant_deposit.total_deposited,
+            );
+            if let Some(updated) = record_yield_change(
+                &ctx.accounts.yield_history,
+                previous_yield_bps,
+                merchant_deposit.current_yield_bps,
+                YieldTrigger::OrderRefunded,
+            )? {
+                emit_cpi!(updated);
+            }
+        }
+
+        let receipt = &mut ctx.accounts.order_receipt;
+        receipt.refunded_amount = refunded_amount;
+        receipt.refunded_usd = refunded_usd;
+
+        emit_cpi!(OrderRefunded {
+            order_receipt: receipt.key(),
+            merchant: receipt.merchant,
+            buyer: receipt.buyer,
+            order_id,
+            mint: receipt.settled_mint,
+            amount,
+            amount_usd: refund_usd,
+            refunded_amount,
+            refunded_usd,
+        });
+
+        msg!("Order refunded: {} of {} ({} of {} refunded so far)",
+            amount,
+            receipt.settled_mint,
+            refunded_amount,
+            receipt.settled_amount
+        );
+        Ok(())
+    }
+
+    /// Refresh a merchant's metrics (permissionless crank)
+    /// Accrues rewards, decays rolling volume, and recomputes yield and tier so
+    /// they stay current even when no orders arrive. Rate-limited per merchant.
+    pub fn refresh_merchant_metrics(ctx: Context<RefreshMerchantMetrics>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+
+        let current_time = time::now()?;
+        require!(
+            elapsed_seconds(merchant_deposit.last_metrics_refresh, current_time) >= MIN_METRICS_REFRESH_INTERVAL,
+            VaultError::MetricsRefreshTooSoon
+        );
+
+        let loyalty_started_at = loyalty_start(&ctx.accounts.merchant_profile, merchant_deposit);
+        let previous_yield_bps = merchant_deposit.current_yield_bps;
+        let previous_tier = merchant_deposit.current_tier;
+        let harvest = refresh_metrics(
+            merchant_deposit,
+            &mut *ctx.accounts.merchant_metrics.load_mut()?,
+            vault,
+            loyalty_started_at,
+            current_time,
+        )?;
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            merchant_deposit.current_yield_bps,
+            YieldTrigger::MetricsRefresh,
+        )? {
+            emit_cpi!(updated);
+        }
+        if merchant_deposit.current_tier != previous_tier {
+            emit_cpi!(TierChanged {
+                merchant: merchant_deposit.merchant,
+                previous_tier,
+                tier: merchant_deposit.current_tier,
+                changed_at: current_time,
+            });
+        }
+        if let Some(harvested) = book_harvest(vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
+        merchant_deposit.last_metrics_refresh = current_time;
+        sync_vault_yield(vault, merchant_deposit)?;
+
+        #[cfg(feature = "verbose-logs")]
+        msg!("Metrics refreshed: tier {} | yield {} bps | accrued {}",
+            tier_name(merchant_deposit.current_tier),
+            merchant_deposit.current_yield_bps,
+            harvest.net
+        );
+
+        Ok(())
+    }
+
+    /// Get a merchant's full status as typed return data
+    /// Intended for simulation: evaluates rewards, yield, and tier as of now
+    /// (as if the metrics crank had just run) without mutating state
+    pub fn get_merchant_status(ctx: Context<GetMerchantStatus>) -> Result<MerchantStatus> {
+        let (_, status) = ctx.accounts.status(time::now()?)?;
+        Ok(status)
+    }
+
+    /// Get everything that bears on a deposit in one call, as typed return data
+    /// Intended for simulation by dashboards: the status flags, lock, queued
+    /// withdrawal, rewards and tier/yield as of now, with the same evaluation
+    /// as `get_merchant_status`
+    pub fn get_deposit_health(ctx: Context<GetMerchantStatus>) -> Result<DepositHealth> {
+        let (snapshot, status) = ctx.accounts.status(time::now()?)?;
+
+        Ok(DepositHealth {
+            is_active: status.is_active,
+            frozen: snapshot.frozen,
+            emergency_mode: ctx.accounts.vault.emergency_mode,
+            is_locked: status.is_locked,
+            unlock_time: status.unlock_time,
+            withdrawal_queued: snapshot.withdrawal_queued,
+            total_deposited: status.total_deposited,
+            accrued_rewards: status.accrued_rewards,
+            tier: status.tier,
+            current_yield_bps: status.current_yield_bps,
+            health: status.health,
+        })
+    }
+
+    /// Close a merchant's calendar month into an immutable `MonthlyStatement` (permissionless crank)
+    /// The statement records what changed since the merchant's previous one, so
+    /// crank right after each month ends. `month` is a `time::calendar_month`
+    /// index; it must have ended and come after the merchant's last statement.
+    pub fn roll_monthly_statement(ctx: Context<RollMonthlyStatement>, month: u32) -> Result<()> {
+        let current_time = time::now()?;
+        require!(month < time::calendar_month(current_time), VaultError::StatementPeriodOpen);
+
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        let previous = ctx.accounts.previous_statement.as_deref();
+        require!(month > metrics.last_statement_month, VaultError::StatementOutOfOrder);
+        require!(
+            previous.map_or(0, |previous| previous.month) == metrics.last_statement_month,
+            VaultError::StatementOutOfOrder
+        );
+
+        // A closed deposit leaves nothing to read; its counters restart with the next one
+        let deposit_info = ctx.accounts.merchant_deposit.to_account_info();
+        let (deposit_started_at, total_rewards_accrued, total_fees_paid_usd) =
+            if deposit_info.owner == &crate::ID && !deposit_info.data_is_empty() {
+                let deposit = MerchantDeposit::try_deserialize(&mut &deposit_info.try_borrow_data()?[..])?;
+                (deposit.deposited_at, deposit.total_rewards_accrued, deposit.total_fees_paid_usd)
+            } else {
+                (0, 0, 0)
+            };
+        let (rewards_baseline, fees_baseline) = match previous {
+            Some(previous) if previous.deposit_started_at == deposit_started_at => {
+                (previous.cumulative_rewards_accrued, previous.cumulative_fees_paid_usd)
+            }
+            _ => (0, 0),
+        };
+
+        apply_volume_decay(&mut metrics, current_time);
+
+        let statement = &mut ctx.accounts.statement;
+        statement.merchant = ctx.accounts.merchant.key();
+        statement.vault = ctx.accounts.vault.key();
+        statement.month = month;
+        statement.opened_at = previous.map_or(0, |previous| previous.closed_at);
+        statement.closed_at = current_time;
+        statement.volume_usd = metrics
+            .total_volume_usd
+            .saturating_sub(previous.map_or(0, |previous| previous.cumulative_volume_usd));
+        statement.orders = metrics
+            .total_orders_processed
+            .checked_sub(previous.map_or(0, |previous| previous.cumulative_orders))
+            .ok_or(VaultError::MathOverflow)?;
+        statement.unique_customers = metrics.monthly_unique_customers;
+        statement.rewards_accrued = total_rewards_accrued
+            .checked_sub(rewards_baseline)
+            .ok_or(VaultError::MathOverflow)?;
+        statement.fees_paid_usd = total_fees_paid_usd
+            .checked_sub(fees_baseline)
+            .ok_or(VaultError::MathOverflow)?;
+        statement.cumulative_volume_usd = metrics.total_volume_usd;
+        statement.cumulative_orders = metrics.total_orders_processed;
+        statement.deposit_started_at = deposit_started_at;
+        statement.cumulative_rewards_accrued = total_rewards_accrued;
+        statement.cumulative_fees_paid_usd = total_fees_paid_usd;
+        statement.bump = ctx.bumps.statement;
+
+        metrics.last_statement_month = month;
+
+        msg!("Statement {}-{:02} for {}: {} orders | ${} volume | rewards {} | fees ${}",
+            month / 12,
+            month % 12 + 1,
+            statement.merchant,
+            statement.orders,
+            statement.volume_usd / 1_000000,
+            statement.rewards_accrued,
+            statement.fees_paid_usd / 1_000000
+        );
+        Ok(())
+    }
+
+    /// Preview what withdrawing the deposit would pay right now, as typed return data
+    /// Intended for simulation: accrues rewards on copies of the deposit and vault and
+    /// runs the same checks as the withdrawal that applies (`withdraw`, `withdraw_early`
+    /// or `emergency_withdraw`), reporting the first one that would fail as `blocker`
+    pub fn preview_withdraw(ctx: Context<PreviewWithdraw>) -> Result<WithdrawalPreview> {
+        let current_time = time::now()?;
+        let mut vault = (*ctx.accounts.vault).clone();
+        let mut snapshot = (*ctx.accounts.merchant_deposit).clone();
+
+        let principal = snapshot.total_deposited;
+        let unlock_time = snapshot.unlock_time.max(snapshot.term_maturity);
+        let is_locked = current_time < unlock_time;
+        let holding_until_slot = snapshot
+            .deposited_slot
+            .checked_add(vault.min_holding_slots)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let harvest = accrue_rewards(&mut snapshot, &vault, current_time)?;
+        snapshot.end_reward_stream()?;
+        let accrued_rewards = snapshot.accrued_rewards;
+
+        let route = if vault.emergency_mode {
+            WithdrawalRoute::EmergencyWithdraw
+        } else if is_locked {
+            WithdrawalRoute::WithdrawEarly
+        } else {
+            WithdrawalRoute::Withdraw
+        };
+        let (rewards_paid, penalty) = match route {
+            WithdrawalRoute::Withdraw => (accrued_rewards, 0),
+            WithdrawalRoute::WithdrawEarly => (
+                0,
+                bps_ceil(principal, vault.early_exit_penalty_bps as u64).ok_or(VaultError::MathOverflow)?,
+            ),
+            WithdrawalRoute::EmergencyWithdraw => (0, 0),
+        };
+        let total_payout = principal
+            .checked_add(rewards_paid)
+            .and_then(|total| total.checked_sub(penalty))
+            .ok_or(VaultError::MathOverflow)?;
+
+        let blocker = if !snapshot.is_active {
+            Some(WithdrawalBlocker::Inactive)
+        } else if snapshot.frozen {
+            Some(WithdrawalBlocker::Frozen)
+        } else if route == WithdrawalRoute::EmergencyWithdraw {
+            None
+        } else if time::current_slot()? < holding_until_slot {
+            Some(WithdrawalBlocker::HoldingPeriod)
+        } else if route == WithdrawalRoute::Withdraw
+            && reward_payout_flagged(&vault, &snapshot, rewards_paid, current_time)?
+        {
+            Some(WithdrawalBlocker::GuardianRequired)
+        } else if route == WithdrawalRoute::Withdraw
+            && rewards_paid > reward_payout_remaining(&mut vault, current_time)?
+        {
+            Some(WithdrawalBlocker::RewardPayoutCap)
+        } else if vault.withdraw_queue_head != vault.withdraw_queue_tail {
+            Some(WithdrawalBlocker::WithdrawalQueue)
+        } else if total_payout > withdraw_capacity_remaining(&mut vault, current_time)? {
+            Some(WithdrawalBlocker::WithdrawalCap)
+        } else {
+            None
+        };
+
+        Ok(WithdrawalPreview {
+            route,
+            principal,
+            accrued_rewards,
+            rewards_paid,
+            performance_fee: harvest.performance_fee,
+            sponsor_rewards: snapshot.sponsor_rewards,
+            early_exit_penalty: penalty,
+            total_payout,
+            unlock_time,
+            is_locked,
+            holding_until_slot,
+            blocker,
+        })
+    }
+
+    /// Create the vault's statistics ring buffer (admin only)
+    pub fn initialize_vault_stats(ctx: Context<InitializeVaultStats>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        init_vault_stats(&ctx.accounts.vault_stats, &ctx.accounts.vault, ctx.bumps.vault_stats)?;
+
+        msg!("Vault stats initialized for vault {}", ctx.accounts.vault.key());
+        Ok(())
+    }
+
+    /// Check the vault's books against its balances (permissionless)
+    /// The per-asset principal totals must add up to `total_deposits`, the vault
+    /// PDA must hold SOL principal and uncollected SOL fees above its rent reserve,
+    /// and the vault's token accounts plus the principal lent out must cover SPL
+    /// principal and uncollected SPL fees. The token accounts are passed in
+    /// `remaining_accounts` as `[supported mint, vault token account]` pairs, one
+    /// per registered SPL mint; each, plus what of the mint is lent, must also cover
+    /// its mint's principal. Fails with `AccountingInvariantViolated` on drift;
+    /// otherwise returns the figures.
+    /// Deposits still on layout v2 must be migrated first (see `migrate_merchant_deposit`).
+    pub fn assert_invariants<'info>(
+        ctx: Context<'_, '_, '_, 'info, AssertInvariants<'info>>,
+    ) -> Result<AccountingReport> {
+        let vault = &ctx.accounts.vault;
+        let vault_key = vault.key();
+        require!(ctx.remaining_accounts.len().is_multiple_of(2), VaultError::InvalidVaultAccount);
+
+        let mut mints = Vec::with_capacity(ctx.remaining_accounts.len() / 2);
+        let mut token_balance: u64 = 0;
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (supported_mint_info, token_account_info) = (&pair[0], &pair[1]);
+            let supported_mint = load_vault_account::<SupportedMint>(supported_mint_info)?
+                .ok_or(VaultError::MintNotSupported)?;
+            require_keys_eq!(supported_mint.vault, vault_key, VaultError::MintNotSupported);
+            // SOL principal (wSOL included) is backed by the vault PDA, checked below
+            require_keys_neq!(supported_mint.mint, native_mint::ID, VaultError::MintNotSupported);
+
+            require_keys_eq!(*token_account_info.owner, token::ID, VaultError::InvalidVaultAccount);
+            let token_account = TokenAccount::try_deserialize(&mut &token_account_info.try_borrow_data()?[..])?;
+            require_keys_eq!(token_account.mint, supported_mint.mint, VaultError::InvalidVaultAccount);
+            require_keys_eq!(
+                token_account_info.key(),
+                get_associated_token_address(&vault_key, &token_account.mint),
+                VaultError::InvalidVaultAccount
+            );
+            require!(!mints.contains(&token_account.mint), VaultError::InvalidVaultAccount);
+            mints.push(token_account.mint);
+
+            let mint_assets = token_account.amount.saturating_add(supported_mint.total_lent);
+            if mint_assets < supported_mint.total_token_deposits {
+                msg!(
+                    "{}: {} held and lent against {} principal",
+                    supported_mint.mint,
+                    mint_assets,
+                    supported_mint.total_token_deposits
+                );
+                return err!(VaultError::AccountingInvariantViolated);
+            }
+            token_balance = token_balance.checked_add(token_account.amount).ok_or(VaultError::MathOverflow)?;
+        }
+
+        let (sol_assets, sol_liabilities) = sol_accounting(vault, &vault.to_account_info())?;
+        let report = AccountingReport {
+            total_deposits: vault.total_deposits,
+            sol_assets,
+            sol_liabilities,
+            token_assets: token_balance.checked_add(vault.total_lent).ok_or(VaultError::MathOverflow)?,
+            token_liabilities: vault
+                .total_token_deposits
+                .checked_add(vault.performance_fees_token)
+                .ok_or(VaultError::MathOverflow)?,
+        };
+
+        msg!(
+            "Invariants: deposits {} | SOL {} / {} | SPL {} / {} ({} token accounts)",
+            report.total_deposits,
+            report.sol_assets,
+            report.sol_liabilities,
+            report.token_assets,
+            report.token_liabilities,
+            mints.len()
+        );
+        require!(principal_totals_match(vault), VaultError::AccountingInvariantViolated);
+        require!(report.sol_assets >= report.sol_liabilities, VaultError::AccountingInvariantViolated);
+        require!(report.token_assets >= report.token_liabilities, VaultError::AccountingInvariantViolated);
+        Ok(report)
+    }
+
+    /// Create the vault's companion accounts in one setup transaction (admin only)
+    /// Creates the stats ring buffer and insurance fund, and the vault's associated
+    /// token account for each accepted mint, passed as `[mint, vault token account]`
+    /// pairs in the remaining accounts. Token accounts that already exist are kept.
+    pub fn initialize_vault_accounts<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeVaultAccounts<'info>>,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(ctx.remaining_accounts.len().is_multiple_of(2), VaultError::InvalidVaultAccount);
+
+        init_vault_stats(&ctx.accounts.vault_stats, &ctx.accounts.vault, ctx.bumps.vault_stats)?;
+        init_insurance_fund(&mut ctx.accounts.insurance_fund, ctx.accounts.vault.key(), ctx.bumps.insurance_fund);
+
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let (mint, vault_token_account) = (&pair[0], &pair[1]);
+            require_keys_eq!(*mint.owner, token::ID, VaultError::InvalidVaultAccount);
+            create_associated_token_account(
+                &ctx.accounts.authority.to_account_info(),
+                vault_token_account,
+                &ctx.accounts.vault.to_account_info(),
+                mint,
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.associated_token_program.to_account_info(),
+            )?;
+        }
+
+        msg!("Vault accounts initialized for vault {} ({} token accounts)",
+            ctx.accounts.vault.key(),
+            ctx.remaining_accounts.len() / 2
+        );
+        Ok(())
+    }
+
+    /// Create the audit log for a vault initialized before it existed (admin only)
+    pub fn initialize_audit_log(ctx: Context<InitializeAuditLog>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        init_audit_log(&ctx.accounts.audit_log, ctx.accounts.vault.key(), ctx.bumps.audit_log)?;
+
+        msg!("Audit log initialized for vault {}", ctx.accounts.vault.key());
+        Ok(())
+    }
+
+    /// Create the yield history of a merchant registered before it existed
+    /// Anyone may pay for it; the merchant must be registered
+    pub fn initialize_yield_history(ctx: Context<InitializeYieldHistory>) -> Result<()> {
+        init_yield_history(
+            &ctx.accounts.yield_history,
+            ctx.accounts.vault.key(),
+            ctx.accounts.merchant_profile.merchant,
+            ctx.bumps.yield_history,
+        )?;
+
+        msg!("Yield history initialized for merchant {}", ctx.accounts.merchant_profile.merchant);
+        Ok(())
+    }
+
+    /// Create the vault's address lookup table, seeded with its static accounts (admin only)
+    /// The vault PDA is the table's authority. `recent_slot` must be a recent slot,
+    /// since the table's address derives from it. Replaces any earlier table on the
+    /// vault; see `vault_lookup_table_addresses` for what it holds.
+    pub fn create_lookup_table(ctx: Context<CreateLookupTable>, recent_slot: u64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let vault_key = ctx.accounts.vault.key();
+        let (create_ix, lookup_table) = address_lookup_table::instruction::create_lookup_table_signed(
+            vault_key,
+            ctx.accounts.authority.key(),
+            recent_slot,
+        );
+        require_keys_eq!(lookup_table, ctx.accounts.lookup_table.key(), VaultError::InvalidLookupTable);
+
+        let addresses = vault_lookup_table_addresses(&vault_key, &ctx.accounts.vault);
+        let address_count = addresses.len();
+        let extend_ix = address_lookup_table::instruction::extend_lookup_table(
+            lookup_table,
+            vault_key,
+            Some(ctx.accounts.authority.key()),
+            addresses,
+        );
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+        let account_infos = [
+            ctx.accounts.lookup_table.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.address_lookup_table_program.to_account_info(),
+        ];
+        anchor_lang::solana_program::program::invoke_signed(&create_ix, &account_infos, signer)?;
+        anchor_lang::solana_program::program::invoke_signed(&extend_ix, &account_infos, signer)?;
+
+        ctx.accounts.vault.lookup_table = lookup_table;
+
+        msg!("Lookup table {} created with {} addresses", lookup_table, address_count);
+        Ok(())
+    }
+
+    /// Add addresses to the vault's lookup table (admin only)
+    /// For accounts beyond the static ones, such as price feeds and mints
+    pub fn extend_lookup_table(ctx: Context<ExtendLookupTable>, addresses: Vec<Pubkey>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(!addresses.is_empty(), VaultError::InvalidLookupTable);
+
+        let address_count = addresses.len();
+        let extend_ix = address_lookup_table::instruction::extend_lookup_table(
+            ctx.accounts.lookup_table.key(),
+            ctx.accounts.vault.key(),
+            Some(ctx.accounts.authority.key()),
+            addresses,
+        );
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+        anchor_lang::solana_program::program::invoke_signed(
+            &extend_ix,
+            &[
+                ctx.accounts.lookup_table.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.address_lookup_table_program.to_account_info(),
+            ],
+            signer,
+        )?;
+
+        msg!("Lookup table {} extended by {} addresses", ctx.accounts.lookup_table.key(), address_count);
+        Ok(())
+    }
+
+    /// Create an automation thread running `crank` on a cron `schedule` (admin only)
+    /// Sweeps and metrics refreshes take one thread per `merchant`; withdrawal
+    /// fulfillment takes one per asset, that of the vault token account (none for
+    /// SOL). The authority pays the thread's rent plus `amount` lamports toward the
+    /// thread program's fees. See `automation` for how threads plan their cranks.
+    pub fn create_automation_thread(
+        ctx: Context<CreateAutomationThread>,
+        crank: AutomationCrank,
+        merchant: Option<Pubkey>,
+        schedule: String,
+        amount: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(merchant.is_some() == crank.per_merchant(), VaultError::InvalidAutomationThread);
+        require!(
+            !schedule.is_empty() && schedule.len() <= MAX_CRON_SCHEDULE_LEN,
+            VaultError::InvalidAutomationThread
+        );
+
+        let vault_key = ctx.accounts.vault.key();
+        let vault_token_account = ctx.accounts.vault_token_account.as_ref().map(|account| account.key());
+        let id = match (merchant, &ctx.accounts.vault_token_account) {
+            (Some(merchant), _) => merchant,
+            (None, Some(vault_token)) => vault_token.mint,
+            (None, None) => native_mint::ID,
+        };
+        let thread_authority = ctx.accounts.thread_authority.key();
+        require_keys_eq!(
+            ctx.accounts.thread.key(),
+            automation::thread(&thread_authority, id.as_ref()),
+            VaultError::InvalidAutomationThread
+        );
+
+        // The thread's one instruction; per-merchant cranks pass the merchant's accounts
+        let merchant_accounts = merchant.map(|merchant| {
+            [
+                AccountMeta::new_readonly(pda::merchant_deposit(&vault_key, &merchant), false),
+                AccountMeta::new_readonly(pda::merchant_profile(&vault_key, &merchant), false),
+                AccountMeta::new_readonly(pda::payout_config(&vault_key, &merchant), false),
+            ]
+        });
+        let plan_ix = vault_instruction(
+            accounts::PlanAutomationCrank {
+                vault: vault_key,
+                keeper: ctx.accounts.thread.key(),
+                vault_token_account,
+            },
+            instruction::PlanAutomationCrank { crank },
+            merchant_accounts.into_iter().flatten(),
+        );
+        let create_ix = automation::thread_create(
+            &thread_authority,
+            &ctx.accounts.authority.key(),
+            id.to_bytes().to_vec(),
+            amount,
+            vec![plan_ix.into()],
+            Trigger::Cron { schedule, skippable: true },
+        )?;
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &create_ix,
+            &[
+                ctx.accounts.thread_authority.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.thread.to_account_info(),
+                ctx.accounts.thread_program.to_account_info(),
+            ],
+            &[&[THREAD_AUTHORITY_SEED, vault_key.as_ref(), &[crank as u8], &[ctx.bumps.thread_authority]]],
+        )?;
+
+        msg!("Automation thread {} created: {:?} for {}", ctx.accounts.thread.key(), crank, id);
+        Ok(())
+    }
+
+    /// Delete one of the vault's automation threads (admin only)
+    /// The thread's remaining lamports go back to the authority.
+    pub fn delete_automation_thread(ctx: Context<DeleteAutomationThread>, crank: AutomationCrank) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let vault_key = ctx.accounts.vault.key();
+        let delete_ix = automation::thread_delete(
+            &ctx.accounts.thread_authority.key(),
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.thread.key(),
+        );
+        anchor_lang::solana_program::program::invoke_signed(
+            &delete_ix,
+            &[
+                ctx.accounts.thread_authority.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.thread.to_account_info(),
+                ctx.accounts.thread_program.to_account_info(),
+            ],
+            &[&[THREAD_AUTHORITY_SEED, vault_key.as_ref(), &[crank as u8], &[ctx.bumps.thread_authority]]],
+        )?;
+
+        msg!("Automation thread {} deleted", ctx.accounts.thread.key());
+        Ok(())
+    }
+
+    /// Plan an automation thread's next crank (read-only; see `automation`)
+    /// Returns the crank for the thread to run next, signed by `keeper`, or none
+    /// while it isn't due. Sweeps and metrics refreshes pass the merchant's deposit,
+    /// profile and payout config in `remaining_accounts`. Withdrawal fulfillment
+    /// passes nothing, then the head ticket, then the ticket with its deposit,
+    /// profile and payout config, planning itself until it knows them all. The
+    /// planned crank checks its accounts as usual.
+    pub fn plan_automation_crank(ctx: Context<PlanAutomationCrank>, crank: AutomationCrank) -> Result<ThreadResponse> {
+        let current_time = time::now()?;
+        let remaining_accounts = ctx.remaining_accounts;
+        let next = match crank {
+            AutomationCrank::SweepRewards => ctx.accounts.plan_sweep(remaining_accounts, current_time)?,
+            AutomationCrank::RefreshMetrics => ctx.accounts.plan_metrics_refresh(remaining_accounts, current_time)?,
+            AutomationCrank::FulfillWithdrawals => ctx.accounts.plan_fulfillment(remaining_accounts, current_time)?,
+        };
+
+        Ok(ThreadResponse {
+            dynamic_instruction: next.map(Into::into),
+            ..Default::default()
+        })
+    }
+
+    /// Initialize the protocol insurance fund (admin only)
+    /// Accounts for the slice of platform fees reserved to cover merchant losses
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        init_insurance_fund(&mut ctx.accounts.insurance_fund, ctx.accounts.vault.key(), ctx.bumps.insurance_fund);
+
+        msg!("Insurance fund initialized for vault {}", ctx.accounts.vault.key());
+        Ok(())
+    }
+
+    /// Set the share of platform fees routed to the insurance fund (admin only)
+    pub fn set_insurance_fee(ctx: Context<UpdateVaultConfig>, insurance_fee_bps: u16) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(&ConfigChange::InsuranceFee { insurance_fee_bps })
+    }
+
+    /// Set the protocol's performance fee on harvested yield and the treasury it
+    /// is paid to (admin only)
+    /// The fee moves by at most `MAX_PERFORMANCE_FEE_STEP_BPS` per change, and at
+    /// most once per `PERFORMANCE_FEE_UPDATE_INTERVAL`
+    pub fn set_performance_fee(
+        ctx: Context<UpdateVaultConfig>,
+        performance_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        authorize_direct_config(&ctx.accounts.vault, &ctx.accounts.authority)?;
+
+        ctx.accounts.apply_config_change(
+            &ConfigChange::PerformanceFee { performance_fee_bps, treasury },
+        )
+    }
+
+    /// Pay an insurance claim to a merchant affected by a loss or exploit (admin only)
+    /// `claim_hash` identifies the off-chain incident report backing the claim
+    pub fn pay_insurance_claim(
+        ctx: Context<PayInsuranceClaim>,
+        amount: u64,
+        claim_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(amount > 0, VaultError::InsufficientDeposit);
+
+        let insurance_fund = &mut ctx.accounts.insurance_fund;
+        insurance_fund.balance = insurance_fund
+            .balance
+            .checked_sub(amount)
+            .ok_or(VaultError::InsufficientInsuranceFunds)?;
+        insurance_fund.total_claims_paid = insurance_fund
+            .total_claims_paid
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        insurance_fund.claims_count = insurance_fund
+            .claims_count
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+        update_insurance_coverage(insurance_fund, ctx.accounts.vault.total_deposits);
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.payout_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::InsuranceClaimPaid,
+            ctx.accounts.authority.key(),
+            ctx.accounts.merchant_profile.merchant,
+            amount,
+            0,
+        )?;
+
+        emit_cpi!(InsuranceClaimPaid {
+            merchant: ctx.accounts.merchant_profile.merchant,
+            amount,
+            claim_hash,
+            remaining_balance: ctx.accounts.insurance_fund.balance,
+            coverage_ratio_bps: ctx.accounts.insurance_fund.coverage_ratio_bps,
+        });
+
+        msg!("Insurance claim of {} paid to merchant {}", amount, ctx.accounts.merchant_profile.merchant);
+        Ok(())
+    }
+
+    /// Pay performance fees booked on SOL or SPL deposits to the treasury (admin only)
+    /// SOL fees go to the treasury wallet, token fees to its token account
+    pub fn collect_performance_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, CollectPerformanceFees<'info>>,
+        deposit_token: DepositType,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let vault = &ctx.accounts.vault;
+        let amount = match deposit_token {
+            DepositType::Sol => vault.performance_fees_sol,
+            DepositType::SplToken => vault.performance_fees_token,
+        };
+        require!(amount > 0, VaultError::NoPerformanceFees);
+
+        let (recipient, spl_accounts) = match deposit_token {
+            DepositType::Sol => (ctx.accounts.treasury.to_account_info(), None),
+            DepositType::SplToken => {
+                let (Some(treasury_token), Some(vault_token), Some(token_program)) = (
+                    &ctx.accounts.treasury_token_account,
+                    &ctx.accounts.vault_token_account,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return err!(VaultError::MissingTokenAccount);
+                };
+                (treasury_token.to_account_info(), Some((vault_token, token_program)))
+            }
+        };
+        transfer_from_vault(
+            vault,
+            &deposit_token,
+            &vault.to_account_info(),
+            spl_accounts,
+            &recipient,
+            amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        match deposit_token {
+            DepositType::Sol => vault.performance_fees_sol = 0,
+            DepositType::SplToken => vault.performance_fees_token = 0,
+        }
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::PerformanceFeesCollected,
+            ctx.accounts.authority.key(),
+            vault.treasury,
+            amount,
+            deposit_token.clone() as u16,
+        )?;
+
+        emit_cpi!(PerformanceFeesCollected {
+            vault: vault.key(),
+            treasury: vault.treasury,
+            deposit_token,
+            amount,
+        });
+
+        debug_assert_invariants(vault);
+
+        msg!("Collected {} in performance fees to treasury {}", amount, vault.treasury);
+        Ok(())
+    }
+
+    /// Fund the next reward epoch: at most `budget` of yield accrues across all
+    /// merchants over `[start, end)` (admin only)
+    /// Once an epoch is funded, yield only accrues inside funded epochs, pro-rata
+    /// when projected demand exceeds the budget. Epochs can't overlap, and yield
+    /// not accrued before the next epoch is funded is forfeited, so crank
+    /// `refresh_merchant_metrics` before rolling over.
+    pub fn fund_reward_epoch(ctx: Context<UpdateVaultConfig>, budget: u64, start: i64, end: i64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(ctx.accounts.authority.key() == vault.authority, VaultError::Unauthorized);
+        require!(end > start && start >= vault.reward_epoch_end, VaultError::InvalidRewardEpoch);
+        require!(time::now()? >= vault.reward_epoch_end, VaultError::RewardEpochNotEnded);
+
+        vault.reward_epoch_start = start;
+        vault.reward_epoch_end = end;
+        vault.reward_epoch_budget = budget;
+        vault.reward_epoch_accrued = 0;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::RewardEpochFunded,
+            ctx.accounts.authority.key(),
+            Pubkey::default(),
+            budget,
+            0,
+        )?;
+
+        emit_cpi!(RewardEpochFunded {
+            vault: vault.key(),
+            budget,
+            start,
+            end,
+        });
+
+        msg!("Reward epoch funded: {} over [{}, {})", budget, start, end);
+        Ok(())
+    }
+
+    /// Distribute harvested yield to every SOL or SPL deposit pro-rata by principal (admin only)
+    /// The authority pays `amount` into the vault; the performance fee is booked
+    /// and the rest raises the asset's reward index, which each deposit settles
+    /// against the next time it accrues. No per-merchant crank is needed.
+    /// MEV tips (`HarvestSource::MevTips`) are SOL only, need the Jito stake
+    /// strategy, and are also totalled apart from inflation rewards.
+    pub fn harvest_rewards(
+        ctx: Context<HarvestRewards>,
+        deposit_token: DepositType,
+        amount: u64,
+        source: HarvestSource,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(amount > 0, VaultError::InsufficientDeposit);
+        if source == HarvestSource::MevTips {
+            require!(
+                deposit_token == DepositType::Sol && ctx.accounts.vault.stake_strategy == StakeStrategy::Jito,
+                VaultError::MevTipsNotEnabled
+            );
+        }
+        // Lending interest is only booked as it's realized by redeem_lending_collateral
+        require!(source != HarvestSource::LendingInterest, VaultError::InvalidHarvestSource);
+
+        match deposit_token {
+            DepositType::Sol => {
+                let ix = anchor_lang::solana_program::system_instruction::transfer(
+                    &ctx.accounts.authority.key(),
+                    &ctx.accounts.vault.key(),
+                    amount,
+                );
+                anchor_lang::solana_program::program::invoke(
+                    &ix,
+                    &[
+                        ctx.accounts.authority.to_account_info(),
+                        ctx.accounts.vault.to_account_info(),
+                    ],
+                )?;
+            }
+            DepositType::SplToken => {
+                let (Some(source), Some(vault_token), Some(token_program)) = (
+                    &ctx.accounts.source_token_account,
+                    &ctx.accounts.vault_token_account,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return err!(VaultError::MissingTokenAccount);
+                };
+                let cpi_accounts = Transfer {
+                    from: source.to_account_info(),
+                    to: vault_token.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                };
+                token::transfer(CpiContext::new(token_program.to_account_info(), cpi_accounts), amount)?;
+            }
+        }
+
+        let vault_key = ctx.accounts.vault.key();
+        let vault: &mut Vault = &mut ctx.accounts.vault;
+        let principal = *asset_deposits(vault, &deposit_token);
+        let index_before = vault.reward_index(&deposit_token);
+        let (harvest, reward_index) = distribute_harvest(vault, &deposit_token, amount)?;
+        match ctx.accounts.epoch_record.as_mut() {
+            Some(epoch_record) => {
+                let index_delta = reward_index.checked_sub(index_before).ok_or(VaultError::MathOverflow)?;
+                record_epoch_harvest(epoch_record, &deposit_token, amount, index_delta)?;
+            }
+            None => require!(
+                vault.accrual_schedule != AccrualSchedule::SolanaEpoch,
+                VaultError::EpochRecordRequired
+            ),
+        }
+        if source == HarvestSource::MevTips {
+            vault.total_mev_tips_harvested = vault
+                .total_mev_tips_harvested
+                .checked_add(amount)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::RewardsHarvested,
+            ctx.accounts.authority.key(),
+            Pubkey::default(),
+            amount,
+            deposit_token.clone() as u16,
+        )?;
+
+        emit_cpi!(RewardsHarvested {
+            vault: vault_key,
+            deposit_token,
+            source: source.clone(),
+            amount,
+            performance_fee: harvest.performance_fee,
+            distributed: harvest.net,
+            reward_index,
+        });
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Harvested {} ({:?}): {} distributed over {} principal | fee {}",
+            amount,
+            source,
+            harvest.net,
+            principal,
+            harvest.performance_fee
+        );
+        Ok(())
+    }
+
+    /// Open the record of harvests during the current Solana epoch (permissionless)
+    /// Under the Solana epoch accrual schedule, harvests must pass it.
+    pub fn initialize_epoch_record(ctx: Context<InitializeEpochRecord>, epoch: u64) -> Result<()> {
+        let (current_epoch, started_at) = time::current_epoch()?;
+        require!(epoch == current_epoch, VaultError::StaleEpochRecord);
+
+        let epoch_record = &mut ctx.accounts.epoch_record;
+        epoch_record.vault = ctx.accounts.vault.key();
+        epoch_record.epoch = epoch;
+        epoch_record.started_at = started_at;
+        epoch_record.harvested_sol = 0;
+        epoch_record.harvested_token = 0;
+        epoch_record.reward_index_delta_sol = 0;
+        epoch_record.reward_index_delta_token = 0;
+        epoch_record.harvest_count = 0;
+        epoch_record.bump = ctx.bumps.epoch_record;
+
+        msg!("Epoch {} record opened (started at {})", epoch, started_at);
+        Ok(())
+    }
+
+    /// Approve a lending market reserve for the vault's idle SPL deposits (admin only)
+    /// Creates the strategy account tracking what is supplied to the reserve and
+    /// the vault's collateral token account. The reserve must be owned by
+    /// `lending_program`, one of `lending::APPROVED_LENDING_PROGRAMS`.
+    pub fn approve_lending_market(
+        ctx: Context<ApproveLendingMarket>,
+        lending_program: Pubkey,
+        lending_market: Pubkey,
+        lending_market_authority: Pubkey,
+        reserve_liquidity_supply: Pubkey,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(
+            lending::APPROVED_LENDING_PROGRAMS.contains(&lending_program),
+            VaultError::InvalidLendingReserve
+        );
+        require_keys_eq!(*ctx.accounts.reserve.owner, lending_program, VaultError::InvalidLendingReserve);
+        // Interest is booked into the SPL reward index, which is in canonical units
+        require!(
+            ctx.accounts.liquidity_mint.decimals == CANONICAL_DECIMALS,
+            VaultError::UnsupportedMintDecimals
+        );
+
+        create_associated_token_account(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_collateral_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.collateral_mint.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+            &ctx.accounts.associated_token_program,
+        )?;
+
+        let strategy = &mut ctx.accounts.lending_strategy;
+        strategy.vault = ctx.accounts.vault.key();
+        strategy.lending_program = lending_program;
+        strategy.lending_market = lending_market;
+        strategy.lending_market_authority = lending_market_authority;
+        strategy.reserve = ctx.accounts.reserve.key();
+        strategy.reserve_liquidity_supply = reserve_liquidity_supply;
+        strategy.liquidity_mint = ctx.accounts.liquidity_mint.key();
+        strategy.collateral_mint = ctx.accounts.collateral_mint.key();
+        strategy.supplied = 0;
+        strategy.collateral = 0;
+        strategy.total_interest_harvested = 0;
+        strategy.last_harvest_at = 0;
+        strategy.bump = ctx.bumps.lending_strategy;
+        strategy.target_allocation_bps = 0;
+        strategy.rebalance_operator = Pubkey::default();
+        strategy.max_rebalance_amount = 0;
+        strategy.last_rebalanced_at = 0;
+        strategy.exited_at = 0;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::LendingMarketApproved,
+            ctx.accounts.authority.key(),
+            strategy.reserve,
+            0,
+            0,
+        )?;
+
+        msg!("Lending reserve {} approved for {}", strategy.reserve, strategy.liquidity_mint);
+        Ok(())
+    }
+
+    /// Supply idle tokens from the vault to its approved lending reserve (admin only)
+    /// Must follow the lending program's reserve refresh in the same transaction.
+    /// Supplied tokens stay owed to depositors; keep enough idle to cover withdrawals.
+    pub fn supply_lending_liquidity(ctx: Context<LendingLiquidity>, amount: u64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(amount > 0, VaultError::InsufficientDeposit);
+
+        let minted = supply_to_reserve(ctx.accounts, amount)?;
+
+        msg!("Supplied {} to reserve {} for {} collateral", amount, ctx.accounts.lending_strategy.reserve, minted);
+        Ok(())
+    }
+
+    /// Redeem collateral from the vault's lending reserve (admin only)
+    /// The redeemed collateral's share of the supplied principal returns to the
+    /// vault's idle balance; anything received above it is interest, booked into
+    /// the SPL reward index like a harvest. Must follow the lending program's
+    /// reserve refresh in the same transaction.
+    pub fn redeem_lending_collateral(ctx: Context<LendingLiquidity>, collateral_amount: u64) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+        require!(
+            collateral_amount > 0 && collateral_amount <= ctx.accounts.lending_strategy.collateral,
+            VaultError::InsufficientLendingCollateral
+        );
+
+        let redemption = redeem_from_reserve(ctx.accounts, collateral_amount)?;
+        if let Some((harvest, reward_index)) = redemption.harvest {
+            emit_cpi!(RewardsHarvested {
+                vault: ctx.accounts.vault.key(),
+                deposit_token: DepositType::SplToken,
+                source: HarvestSource::LendingInterest,
+                amount: redemption.interest,
+                performance_fee: harvest.performance_fee,
+                distributed: harvest.net,
+                reward_index,
+            });
+        }
+
+        msg!("Redeemed {} collateral from reserve {}: {} principal, {} interest",
+            collateral_amount,
+            ctx.accounts.lending_strategy.reserve,
+            redemption.principal,
+            redemption.interest
+        );
+        Ok(())
+    }
+
+    /// Set the share of its liquidity mint's deposits a lending strategy targets
+    /// and who may rebalance toward it (admin only)
+    /// Targets across the vault's strategies can't exceed 100%; the rest stays idle.
+    /// `max_rebalance_amount` bounds a single rebalance (0 = unbounded). Reactivates
+    /// a strategy shut by `emergency_exit_strategy`.
+    pub fn set_lending_allocation(
+        ctx: Context<SetLendingAllocation>,
+        target_allocation_bps: u16,
+        rebalance_operator: Pubkey,
+        max_rebalance_amount: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.lending_strategy;
+        let allocated = vault
+            .lending_allocation_bps
+            .checked_sub(strategy.target_allocation_bps)
+            .and_then(|rest| rest.checked_add(target_allocation_bps))
+            .ok_or(VaultError::InvalidAllocation)?;
+        require!(
+            allocated as u64 + vault.liquidity_buffer_bps as u64 <= math::BPS_DENOMINATOR,
+            VaultError::InvalidAllocation
+        );
+
+        vault.lending_allocation_bps = allocated;
+        strategy.target_allocation_bps = target_allocation_bps;
+        strategy.rebalance_operator = rebalance_operator;
+        strategy.max_rebalance_amount = max_rebalance_amount;
+        strategy.exited_at = 0;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::LendingAllocationSet,
+            ctx.accounts.authority.key(),
+            strategy.reserve,
+            max_rebalance_amount,
+            target_allocation_bps,
+        )?;
+
+        msg!("Reserve {} targets {} bps of its mint's deposits ({} bps allocated vault-wide), operator {}",
+            strategy.reserve,
+            target_allocation_bps,
+            allocated,
+            rebalance_operator
+        );
+        Ok(())
+    }
+
+    /// Move funds between the vault's idle balance and a lending strategy toward
+    /// the strategy's target allocation (authority or the strategy's operator)
+    /// The target and the liquidity buffer are shares of the liquidity mint's principal.
+    /// Moves at most `max_amount` (and the strategy's `max_rebalance_amount`).
+    /// `min_out` bounds slippage: the least collateral minted when supplying, or
+    /// tokens received when redeeming. Must follow the lending program's reserve
+    /// refresh in the same transaction.
+    pub fn rebalance(ctx: Context<LendingLiquidity>, max_amount: u64, min_out: u64) -> Result<()> {
+        let operator = ctx.accounts.authority.key();
+        let strategy = &ctx.accounts.lending_strategy;
+        require!(
+            operator == ctx.accounts.vault.authority
+                || (strategy.rebalance_operator != Pubkey::default() && operator == strategy.rebalance_operator),
+            VaultError::Unauthorized
+        );
+
+        let target = mul_div_floor(
+            ctx.accounts.supported_mint.total_token_deposits,
+            strategy.target_allocation_bps as u64,
+            math::BPS_DENOMINATOR,
+        )
+        .ok_or(VaultError::MathOverflow)?;
+        let mut bound = max_amount;
+        if strategy.max_rebalance_amount > 0 {
+            bound = bound.min(strategy.max_rebalance_amount);
+        }
+
+        let supplied_before = strategy.supplied;
+        let (direction, amount, collateral_amount, received) = if supplied_before < target {
+            // Never dip into the liquidity buffer to reach the target
+            let buffer = ctx
+                .accounts
+                .vault
+                .liquidity_buffer(ctx.accounts.supported_mint.total_token_deposits)
+                .ok_or(VaultError::MathOverflow)?;
+            let amount = (target - supplied_before)
+                .min(bound)
+                .min(ctx.accounts.vault_token_account.amount.saturating_sub(buffer));
+            require!(amount > 0, VaultError::NothingToRebalance);
+            let minted = supply_to_reserve(ctx.accounts, amount)?;
+            require!(minted >= min_out, VaultError::RebalanceSlippage);
+            (RebalanceDirection::Supply, amount, minted, amount)
+        } else {
+            let excess = (supplied_before - target).min(bound);
+            let collateral_amount = mul_div_floor(excess, strategy.collateral, supplied_before).unwrap_or(0);
+            require!(collateral_amount > 0, VaultError::NothingToRebalance);
+            let redemption = redeem_from_reserve(ctx.accounts, collateral_amount)?;
+            require!(redemption.received >= min_out, VaultError::RebalanceSlippage);
+            if let Some((harvest, reward_index)) = redemption.harvest {
+                emit_cpi!(RewardsHarvested {
+                    vault: ctx.accounts.vault.key(),
+                    deposit_token: DepositType::SplToken,
+                    source: HarvestSource::LendingInterest,
+                    amount: redemption.interest,
+                    performance_fee: harvest.performance_fee,
+                    distributed: harvest.net,
+                    reward_index,
+                });
+            }
+            (RebalanceDirection::Redeem, redemption.principal, collateral_amount, redemption.received)
+        };
+
+        let strategy = &mut ctx.accounts.lending_strategy;
+        strategy.last_rebalanced_at = time::now()?;
+        let reserve = strategy.reserve;
+        let supplied = strategy.supplied;
+
+        emit_cpi!(StrategyRebalanced {
+            vault: ctx.accounts.vault.key(),
+            reserve,
+            operator,
+            direction: direction.clone(),
+            amount,
+            collateral_amount,
+            received,
+            supplied,
+            target,
+        });
+
+        msg!("Rebalanced reserve {} ({:?}): {} moved, {} supplied of {} target",
+            reserve,
+            direction,
+            amount,
+            supplied,
+            target
+        );
+        Ok(())
+    }
+
+    /// Unwind a lending strategy back to the vault's idle balance (guardian only)
+    /// Redeems all of its collateral with no slippage bound, drops its target
+    /// allocation and blocks further supply until the authority sets a new
+    /// allocation. For responding to an exploit at the lending venue; must follow
+    /// the lending program's reserve refresh in the same transaction.
+    pub fn emergency_exit_strategy(ctx: Context<LendingLiquidity>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.guardian, VaultError::Unauthorized);
+
+        let collateral_amount = ctx.accounts.lending_strategy.collateral;
+        let supplied = ctx.accounts.lending_strategy.supplied;
+        let mut received = 0;
+        if collateral_amount > 0 {
+            let redemption = redeem_from_reserve(ctx.accounts, collateral_amount)?;
+            if let Some((harvest, reward_index)) = redemption.harvest {
+                emit_cpi!(RewardsHarvested {
+                    vault: ctx.accounts.vault.key(),
+                    deposit_token: DepositType::SplToken,
+                    source: HarvestSource::LendingInterest,
+                    amount: redemption.interest,
+                    performance_fee: harvest.performance_fee,
+                    distributed: harvest.net,
+                    reward_index,
+                });
+            }
+            received = redemption.received;
+        }
+        // The venue paid back less than was supplied
+        let loss = supplied.saturating_sub(received);
+
+        let vault = &mut ctx.accounts.vault;
+        let strategy = &mut ctx.accounts.lending_strategy;
+        vault.lending_allocation_bps = vault.lending_allocation_bps.saturating_sub(strategy.target_allocation_bps);
+        strategy.target_allocation_bps = 0;
+        strategy.exited_at = time::now()?;
+        let reserve = strategy.reserve;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::StrategyExited,
+            ctx.accounts.authority.key(),
+            reserve,
+            received,
+            0,
+        )?;
+
+        emit_cpi!(StrategyExited {
+            vault: ctx.accounts.vault.key(),
+            reserve,
+            guardian: ctx.accounts.authority.key(),
+            collateral_amount,
+            supplied,
+            received,
+            loss,
+        });
+
+        msg!("Exited reserve {}: {} collateral redeemed for {} ({} supplied, {} lost)",
+            reserve,
+            collateral_amount,
+            received,
+            supplied,
+            loss
+        );
+        Ok(())
+    }
+
+    /// Publish a merkle distribution of bonus rewards (admin only)
+    /// Leaves are computed off-chain (see `bonus_leaf`); the authority funds
+    /// `total_amount` of `mint` into the vault's token account up front
+    pub fn create_distribution(
+        ctx: Context<CreateDistribution>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        // Fund the whole distribution up front, so claims never touch principal
+        create_associated_token_account(
+            &ctx.accounts.authority,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+            &ctx.accounts.associated_token_program,
+        )?;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, total_amount)?;
+
+        let vault = &mut ctx.accounts.vault;
+        let distribution = &mut ctx.accounts.distribution;
+
+        distribution.vault = vault.key();
+        distribution.id = vault.distribution_count;
+        distribution.merkle_root = merkle_root;
+        distribution.mint = ctx.accounts.mint.key();
+        distribution.total_amount = total_amount;
+        distribution.total_claimed = 0;
+        distribution.num_claims = 0;
+        distribution.created_at = time::now()?;
+        distribution.bump = ctx.bumps.distribution;
+
+        vault.distribution_count = vault
+            .distribution_count
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::DistributionCreated,
+            ctx.accounts.authority.key(),
+            distribution.key(),
+            total_amount,
+            0,
+        )?;
+
+        msg!("Distribution {} created: {} total", distribution.id, total_amount);
+        Ok(())
+    }
+
+    /// Claim a merchant's bonus from a merkle distribution
+    /// Paid to the merchant's payout wallet; the claim record prevents double claims
+    pub fn claim_bonus(ctx: Context<ClaimBonus>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+
+        let merchant = ctx.accounts.merchant.key();
+        let distribution = &mut ctx.accounts.distribution;
+
+        require!(
+            verify_merkle_proof(&proof, distribution.merkle_root, bonus_leaf(&merchant, amount)),
+            VaultError::InvalidMerkleProof
+        );
+
+        distribution.total_claimed = distribution
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        require!(
+            distribution.total_claimed <= distribution.total_amount,
+            VaultError::DistributionExhausted
+        );
+        distribution.num_claims = distribution
+            .num_claims
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let bonus_claim = &mut ctx.accounts.bonus_claim;
+        bonus_claim.distribution = distribution.key();
+        bonus_claim.merchant = merchant;
+        bonus_claim.amount = amount;
+        bonus_claim.claimed_at = time::now()?;
+        bonus_claim.bump = ctx.bumps.bonus_claim;
+        record_merchant_activity(&ctx.accounts.recovery_config, bonus_claim.claimed_at)?;
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.payout_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Bonus of {} claimed by merchant {}", amount, merchant);
+        Ok(())
+    }
+
+    /// Create the protocol reward token and its emission schedule (admin only)
+    /// The mint is owned by a vault PDA, so tokens can only be issued through
+    /// `claim_token_rewards` within each epoch's budget. `epoch_emission` decays
+    /// by `decay_bps` every epoch; `max_supply` caps lifetime emission.
+    pub fn initialize_reward_emission(
+        ctx: Context<InitializeRewardEmission>,
+        epoch_duration: i64,
+        epoch_emission: u64,
+        decay_bps: u16,
+        max_supply: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.vault.authority,
+            VaultError::Unauthorized
+        );
+        require!(epoch_duration >= MIN_EMISSION_EPOCH, VaultError::InvalidEmissionSchedule);
+        require!(decay_bps <= 10000, VaultError::InvalidRate);
+
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        emission_schedule.vault = ctx.accounts.vault.key();
+        emission_schedule.reward_mint = ctx.accounts.reward_mint.key();
+        emission_schedule.epoch_duration = epoch_duration;
+        emission_schedule.epoch_emission = epoch_emission;
+        emission_schedule.decay_bps = decay_bps;
+        emission_schedule.current_epoch_start = time::now()?;
+        emission_schedule.epoch_emitted = 0;
+        emission_schedule.total_emitted = 0;
+        emission_schedule.max_supply = max_supply;
+        emission_schedule.mint_authority_bump = ctx.bumps.reward_mint_authority;
+        emission_schedule.bump = ctx.bumps.emission_schedule;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::RewardEmissionInitialized,
+            ctx.accounts.authority.key(),
+            emission_schedule.reward_mint,
+            epoch_emission,
+            decay_bps,
+        )?;
+
+        msg!("Reward emission initialized: mint {} | {} per epoch of {}s | decay {} bps | cap {}",
+            emission_schedule.reward_mint,
+            epoch_emission,
+            epoch_duration,
+            decay_bps,
+            max_supply
+        );
+        Ok(())
+    }
+
+    /// Update the emission budget and decay (admin only)
+    /// Takes effect for the current epoch; amounts already emitted are kept
+    pub fn set_emission_schedule(
+        ctx: Context<UpdateEmissionSchedule>,
+        epoch_emission: u64,
+        decay_bps: u16,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.vault.authority,
+            VaultError::Unauthorized
+        );
+        require!(decay_bps <= 10000, VaultError::InvalidRate);
+
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        roll_emission_epoch(emission_schedule, time::now()?)?;
+        emission_schedule.epoch_emission = epoch_emission;
+        emission_schedule.decay_bps = decay_bps;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::EmissionScheduleSet,
+            ctx.accounts.authority.key(),
+            emission_schedule.reward_mint,
+            epoch_emission,
+            decay_bps,
+        )?;
+
+        msg!("Emission schedule updated: {} per epoch | decay {} bps", epoch_emission, decay_bps);
+        Ok(())
+    }
+
+    /// Take accrued rewards in the protocol reward token instead of the deposit asset
+    /// Pays as much as the current epoch's remaining budget allows (1 reward unit =
+    /// 1 base unit of the 6-decimal reward token); the rest stays accrued.
+    pub fn claim_token_rewards<'info>(ctx: Context<'_, '_, '_, 'info, ClaimTokenRewards<'info>>) -> Result<()> {
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.merchant == ctx.accounts.merchant.key(), VaultError::Unauthorized);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!ctx.accounts.vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_holding_period(merchant_deposit, &ctx.accounts.vault)?;
+
+        let current_time = time::now()?;
+        let harvest = accrue_rewards(merchant_deposit, &ctx.accounts.vault, current_time)?;
+        if let Some(harvested) = book_harvest(&mut ctx.accounts.vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
+
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        roll_emission_epoch(emission_schedule, current_time)?;
+
+        let available = emission_schedule
+            .epoch_emission
+            .saturating_sub(emission_schedule.epoch_emitted)
+            .min(emission_schedule.max_supply.saturating_sub(emission_schedule.total_emitted));
+        let amount = merchant_deposit.accrued_rewards.min(available);
+        require!(amount > 0, VaultError::NoClaimableEmission);
+        authorize_withdrawal(merchant_deposit, ctx.accounts.operator.key(), amount, current_time)?;
+        if ctx.accounts.operator.key() == ctx.accounts.merchant.key() {
+            record_merchant_activity(&ctx.accounts.recovery_config, current_time)?;
+        }
+
+        merchant_deposit.accrued_rewards -= amount;
+        emission_schedule.epoch_emitted = emission_schedule
+            .epoch_emitted
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        emission_schedule.total_emitted = emission_schedule
+            .total_emitted
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            REWARD_MINT_AUTHORITY_SEED,
+            vault_key.as_ref(),
+            &[emission_schedule.mint_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Split beneficiaries' reward token accounts come in `remaining_accounts`
+        let splits = load_payout_splits(&ctx.accounts.payout_config, current_time)?;
+        require!(
+            ctx.remaining_accounts.len() == splits.len(),
+            VaultError::InvalidPayoutSplits
+        );
+
+        let mut remainder = amount;
+        for (split, recipient) in splits.iter().zip(ctx.remaining_accounts) {
+            let token_account = split_token_account(recipient, split)?;
+            require_keys_eq!(token_account.mint, ctx.accounts.reward_mint.key(), VaultError::InvalidPayoutWallet);
+
+            let share = bps_floor(amount, split.bps as u64).ok_or(VaultError::MathOverflow)?;
+            remainder = remainder.checked_sub(share).ok_or(VaultError::MathOverflow)?;
+            let cpi_accounts = MintTo {
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: recipient.clone(),
+                authority: ctx.accounts.reward_mint_authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+            token::mint_to(cpi_ctx, share)?;
+        }
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.payout_reward_account.to_account_info(),
+            authority: ctx.accounts.reward_mint_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, remainder)?;
+
+        msg!("Claimed {} rewards as protocol tokens ({} still accrued, {} left this epoch)",
+            amount,
+            merchant_deposit.accrued_rewards,
+            available - amount
+        );
+        Ok(())
+    }
+
+    /// Mint a non-transferable tier badge NFT to a merchant who has reached `tier`
+    /// (1=Silver, 2=Gold, 3=Platinum, as of the last metrics refresh). The vault
+    /// is the metadata update authority, so other apps can verify the issuer.
+    /// The badge is frozen in the merchant's wallet, making it soulbound.
+    pub fn mint_tier_badge(ctx: Context<MintTierBadge>, tier: u8) -> Result<()> {
+        require!((1..=3).contains(&tier), VaultError::InvalidTier);
+        require!(ctx.accounts.merchant_deposit.current_tier >= tier, VaultError::TierNotReached);
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Mint the single badge token
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            to: ctx.accounts.badge_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, 1)?;
+
+        // Metadata (immutable, vault as update authority)
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            mint_authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.merchant.to_account_info(),
+            update_authority: ctx.accounts.vault.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        let data = DataV2 {
+            name: format!("Shaw 402 {} Merchant", tier_name(tier)),
+            symbol: TIER_BADGE_SYMBOL.to_string(),
+            uri: format!("{}{}.json", TIER_BADGE_URI_BASE, tier_name(tier).to_lowercase()),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        create_metadata_accounts_v3(cpi_ctx, data, false, true, None)?;
+
+        // Master edition with zero prints fixes supply at 1
+        let cpi_accounts = CreateMasterEditionV3 {
+            edition: ctx.accounts.master_edition.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            update_authority: ctx.accounts.vault.to_account_info(),
+            mint_authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.merchant.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        create_master_edition_v3(cpi_ctx, Some(0))?;
+
+        // Soulbound: delegate the badge to the vault and freeze it in place
+        let cpi_accounts = Approve {
+            to: ctx.accounts.badge_token_account.to_account_info(),
+            delegate: ctx.accounts.vault.to_account_info(),
+            authority: ctx.accounts.merchant.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::approve(cpi_ctx, 1)?;
+
+        let cpi_accounts = FreezeDelegatedAccount {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            delegate: ctx.accounts.vault.to_account_info(),
+            token_account: ctx.accounts.badge_token_account.to_account_info(),
+            edition: ctx.accounts.master_edition.to_account_info(),
+            mint: ctx.accounts.badge_mint.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        freeze_delegated_account(cpi_ctx)?;
+
+        let tier_badge = &mut ctx.accounts.tier_badge;
+        tier_badge.vault = ctx.accounts.vault.key();
+        tier_badge.merchant = ctx.accounts.merchant.key();
+        tier_badge.tier = tier;
+        tier_badge.mint = ctx.accounts.badge_mint.key();
+        tier_badge.minted_at = time::now()?;
+        tier_badge.bump = ctx.bumps.tier_badge;
+
+        msg!("{} badge {} minted to merchant {}", tier_name(tier), tier_badge.mint, tier_badge.merchant);
+        Ok(())
+    }
+
+    /// Mint a transferable position NFT representing the merchant's deposit
+    /// From then on the NFT's holder (as recorded by `sync_position_owner`) holds
+    /// the withdrawal rights: only they can withdraw or claim, and payouts go to
+    /// their wallet instead of the profile's payout wallet and splits. Withdraw
+    /// delegates are cleared and cannot be set while the position NFT exists.
+    pub fn mint_position_nft(ctx: Context<MintPositionNft>) -> Result<()> {
+        let merchant_deposit = &ctx.accounts.merchant_deposit;
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(merchant_deposit.position_mint == Pubkey::default(), VaultError::PositionNftExists);
+        require!(merchant_deposit.shares == 0, VaultError::PositionTokenized);
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Mint the single position token
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            to: ctx.accounts.position_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, 1)?;
+
+        // Metadata (immutable, vault as update authority)
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.position_mint.to_account_info(),
+            mint_authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.merchant.to_account_info(),
+            update_authority: ctx.accounts.vault.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        let data = DataV2 {
+            name: POSITION_NFT_NAME.to_string(),
+            symbol: TIER_BADGE_SYMBOL.to_string(),
+            uri: format!("{}{}.json", POSITION_NFT_URI_BASE, merchant_deposit.key()),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        create_metadata_accounts_v3(cpi_ctx, data, false, true, None)?;
+
+        // Master edition with zero prints fixes supply at 1
+        let cpi_accounts = CreateMasterEditionV3 {
+            edition: ctx.accounts.master_edition.to_account_info(),
+            mint: ctx.accounts.position_mint.to_account_info(),
+            update_authority: ctx.accounts.vault.to_account_info(),
+            mint_authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.merchant.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        create_master_edition_v3(cpi_ctx, Some(0))?;
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.position_mint = ctx.accounts.position_mint.key();
+        merchant_deposit.position_owner = merchant_deposit.merchant;
+        clear_withdraw_delegate(merchant_deposit);
+
+        msg!("Position NFT {} minted for merchant {}", merchant_deposit.position_mint, merchant_deposit.merchant);
+        Ok(())
+    }
+
+    /// Point a deposit's withdrawal rights at the current holder of its position NFT
+    /// Anyone may call it after the NFT moves (e.g. to a new wallet or a custodian).
+    pub fn sync_position_owner(ctx: Context<SyncPositionOwner>) -> Result<()> {
+        let new_owner = ctx.accounts.position_token_account.owner;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        let previous_owner = merchant_deposit.position_owner;
+        if previous_owner == new_owner {
+            return Ok(());
+        }
+        merchant_deposit.position_owner = new_owner;
+
+        emit_cpi!(PositionOwnerSynced {
+            merchant_deposit: merchant_deposit.key(),
+            position_mint: merchant_deposit.position_mint,
+            previous_owner,
+            new_owner,
+        });
+
+        msg!("Position {} now held by {}", merchant_deposit.position_mint, new_owner);
+        Ok(())
+    }
+
+    /// Create the vault share mint of an asset, svSOL or svUSDC (admin only)
+    /// The vault PDA is the mint authority, so shares are only issued by
+    /// `tokenize_position` and redeemed by burning them on withdrawal. Shares are
+    /// Token-2022 tokens whose transfers go through `transfer_share_hook`.
+    pub fn initialize_share_mint(ctx: Context<InitializeShareMint>, deposit_token: DepositType) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.vault.authority,
+            VaultError::Unauthorized
+        );
+
+        let (name, symbol) = match deposit_token {
+            DepositType::Sol => (SOL_SHARE_NAME, SOL_SHARE_SYMBOL),
+            DepositType::SplToken => (TOKEN_SHARE_NAME, TOKEN_SHARE_SYMBOL),
+        };
+
+        let seeds = &[
+            VAULT_SEED,
+            ctx.accounts.vault.authority.as_ref(),
+            ctx.accounts.vault.label_seed(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Metadata (immutable, vault as update authority)
+        let cpi_accounts = CreateMetadataAccountsV3 {
+            metadata: ctx.accounts.metadata.to_account_info(),
+            mint: ctx.accounts.share_mint.to_account_info(),
+            mint_authority: ctx.accounts.vault.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            update_authority: ctx.accounts.vault.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            rent: ctx.accounts.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_metadata_program.to_account_info(), cpi_accounts, signer);
+        let data = DataV2 {
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            uri: format!("{}{}.json", SHARE_URI_BASE, symbol),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+        create_metadata_accounts_v3(cpi_ctx, data, false, true, None)?;
+
+        // Extra accounts Token-2022 passes to `transfer_share_hook`
+        let extra_account_metas = transfer_hook::extra_account_metas(&ctx.accounts.vault.key());
+        ctx.accounts.extra_account_metas.try_borrow_mut_data()?.copy_from_slice(&extra_account_metas);
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ShareMintInitialized,
+            ctx.accounts.authority.key(),
+            ctx.accounts.share_mint.key(),
+            0,
+            deposit_token as u16,
+        )?;
+
+        msg!("{} share mint {} created", symbol, ctx.accounts.share_mint.key());
+        Ok(())
+    }
+
+    /// Mint vault shares for the merchant's deposit at the current share price
+    /// Shares are a transferable claim on the position, usable as collateral
+    /// elsewhere: while they are out the deposit can only be withdrawn by burning
+    /// shares worth the payout at the share price of the time (see `shares_to_redeem`),
+    /// and cannot be split, merged, queued for withdrawal or turned into a position NFT.
+    pub fn tokenize_position(ctx: Context<PositionShares>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let merchant_deposit = &ctx.accounts.merchant_deposit;
+
+        require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require!(!merchant_deposit.withdrawal_queued, VaultError::WithdrawalAlreadyQueued);
+        require!(merchant_deposit.position_mint == Pubkey::default(), VaultError::PositionNftExists);
+        require!(merchant_deposit.shares == 0, VaultError::PositionTokenized);
+
+        let share_price = vault.share_price(&merchant_deposit.deposit_token).ok_or(VaultError::MathOverflow)?;
+        let shares = vault
+            .shares_for(&merchant_deposit.deposit_token, merchant_deposit.total_deposited)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let seeds = &[VAULT_SEED, vault.authority.as_ref(), vault.label_seed(), &[vault.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = token_2022::MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.share_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token_2022::mint_to(cpi_ctx, shares)?;
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.shares = shares;
+
+        emit_cpi!(SharesMinted {
+            merchant: merchant_deposit.merchant,
+            share_mint: ctx.accounts.share_mint.key(),
+            shares,
+            principal: merchant_deposit.total_deposited,
+            share_price,
+        });
+
+        msg!("Minted {} shares for {} principal of merchant {}",
+            shares,
+            merchant_deposit.total_deposited,
+            merchant_deposit.merchant
+        );
+        Ok(())
+    }
+
+    /// Burn all of a deposit's vault shares, making it an ordinary position again
+    pub fn untokenize_position(ctx: Context<PositionShares>) -> Result<()> {
+        let shares = ctx.accounts.merchant_deposit.shares;
+        require!(shares > 0, VaultError::PositionNotTokenized);
+
+        let cpi_accounts = token_2022::Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.share_token_account.to_account_info(),
+            authority: ctx.accounts.merchant.to_account_info(),
+        };
+        token_2022::burn(CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts), shares)?;
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        merchant_deposit.shares = 0;
+
+        emit_cpi!(SharesBurned {
+            merchant: merchant_deposit.merchant,
+            share_mint: ctx.accounts.share_mint.key(),
+            shares,
+        });
+
+        msg!("Burned {} shares of merchant {}", shares, merchant_deposit.merchant);
+        Ok(())
+    }
+
+    /// Token-2022 transfer hook of the vault share mints
+    /// While the vault restricts share transfers, shares only move to wallets
+    /// approved with `approve_share_holder` (e.g. after KYC).
+    #[interface(spl_transfer_hook_interface::execute)]
+    pub fn transfer_share_hook(ctx: Context<TransferShareHook>, amount: u64) -> Result<()> {
+        if !ctx.accounts.vault.share_transfers_restricted {
+            return Ok(());
+        }
+
+        let approved_holder = &ctx.accounts.approved_holder;
+        require!(
+            approved_holder.owner == &crate::ID && !approved_holder.data_is_empty(),
+            VaultError::ShareHolderNotApproved
+        );
+
+        msg!("{} shares sent to approved holder {}", amount, ctx.accounts.destination_token_account.owner);
+        Ok(())
+    }
+
+    /// Clear `wallet` to receive vault shares while transfers are restricted (admin only)
+    pub fn approve_share_holder(ctx: Context<ApproveShareHolder>, wallet: Pubkey) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let approved_holder = &mut ctx.accounts.approved_holder;
+        approved_holder.vault = ctx.accounts.vault.key();
+        approved_holder.wallet = wallet;
+        approved_holder.approved_at = time::now()?;
+        approved_holder.bump = ctx.bumps.approved_holder;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ShareHolderApproved,
+            ctx.accounts.authority.key(),
+            wallet,
+            0,
+            0,
+        )?;
+
+        msg!("Share holder {} approved", wallet);
+        Ok(())
+    }
+
+    /// Withdraw a wallet's share holder approval (admin only)
+    /// Shares it already holds stay put, but it cannot receive more while
+    /// transfers are restricted.
+    pub fn revoke_share_holder(ctx: Context<RevokeShareHolder>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let wallet = ctx.accounts.approved_holder.wallet;
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::ShareHolderRevoked,
+            ctx.accounts.authority.key(),
+            wallet,
+            0,
+            0,
+        )?;
+
+        msg!("Share holder {} revoked", wallet);
+        Ok(())
+    }
+
+    /// Move `amount` of principal into a new deposit owned by `recipient`
+    /// Accrued rewards and profit share move pro-rata; the new deposit keeps the
+    /// lock, fixed term and deposit time, and the recipient adopts the older
+    /// loyalty start. Both parts must meet the vault's minimum deposit.
+    pub fn split_position(ctx: Context<SplitPosition>, amount: u64) -> Result<()> {
+        let current_time = time::now()?;
+        let vault = &mut ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+
+        require_movable_position(merchant_deposit)?;
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require_kyc_attestation(vault, &ctx.accounts.recipient_kyc_attestation, current_time)?;
+
+        let min_deposit = match merchant_deposit.deposit_token {
+            DepositType::Sol => vault.min_deposit_sol,
+            DepositType::SplToken => vault.min_deposit_token,
+        };
+        let total = merchant_deposit.total_deposited;
+        let remaining = total.checked_sub(amount).ok_or(VaultError::InvalidSplitAmount)?;
+        require!(amount >= min_deposit && remaining >= min_deposit, VaultError::InvalidSplitAmount);
+
+        // Settle rewards so both parts start from the same checkpoint; a running
+        // stream ends and is split with the rest of the rewards
+        let harvest = accrue_rewards(merchant_deposit, vault, current_time)?;
+        if let Some(harvested) = book_harvest(vault, merchant_deposit, harvest, current_time)? {
+            emit_cpi!(harvested);
+        }
+        merchant_deposit.end_reward_stream()?;
+        remove_deposit_from_vault(vault, merchant_deposit, 0)?;
+
+        let moved_rewards = mul_div_floor(merchant_deposit.accrued_rewards, amount, total).ok_or(VaultError::MathOverflow)?;
+        let moved_profit = mul_div_floor(merchant_deposit.platform_profit_earned, amount, total)
+            .ok_or(VaultError::MathOverflow)?;
+        let moved_profit_share = mul_div_floor(merchant_deposit.profit_share_allocated, amount, total)
+            .ok_or(VaultError::MathOverflow)?;
+        let moved_mint_principal = mul_div_floor(merchant_deposit.mint_principal, amount, total)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let mut split = (***merchant_deposit).clone();
+        split.merchant = ctx.accounts.recipient.key();
+        split.total_deposited = amount;
+        split.accrued_rewards = moved_rewards;
+        split.platform_profit_earned = moved_profit;
+        split.profit_share_allocated = moved_profit_share;
+        split.mint_principal = moved_mint_principal;
+        split.bump = ctx.bumps.split_deposit;
+        split.current_tier = ctx.accounts.recipient_profile.current_tier;
+        split.tier_grace_started_at = 0;
+        split.last_metrics_refresh = current_time;
+        clear_withdraw_delegate(&mut split);
+        split.current_yield_bps = calculate_dynamic_yield(&split, &*ctx.accounts.recipient_metrics.load()?, amount);
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.recipient_yield_history,
+            0,
+            split.current_yield_bps,
+            YieldTrigger::PositionSplit,
+        )? {
+            emit_cpi!(updated);
+        }
+
+        merchant_deposit.total_deposited = remaining;
+        merchant_deposit.accrued_rewards = merchant_deposit
+            .accrued_rewards
+            .checked_sub(moved_rewards)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.platform_profit_earned = merchant_deposit
+            .platform_profit_earned
+            .checked_sub(moved_profit)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.profit_share_allocated = merchant_deposit
+            .profit_share_allocated
+            .checked_sub(moved_profit_share)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.mint_principal = merchant_deposit
+            .mint_principal
+            .checked_sub(moved_mint_principal)
+            .ok_or(VaultError::MathOverflow)?;
+
+        add_deposit_to_vault(vault, merchant_deposit)?;
+        add_deposit_to_vault(vault, &mut split)?;
+
+        adopt_loyalty(
+            &mut ctx.accounts.recipient_profile,
+            loyalty_start(&ctx.accounts.merchant_profile, merchant_deposit),
+        );
+
+        emit_cpi!(PositionSplit {
+            merchant: merchant_deposit.merchant,
+            recipient: split.merchant,
+            amount,
+            rewards_moved: moved_rewards,
+        });
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Split {} of {} from merchant {} to {}", amount, total, merchant_deposit.merchant, split.merchant);
+
+        ctx.accounts.split_deposit.set_inner(split);
+        Ok(())
+    }
+
+    /// Fold `source_merchant`'s deposit into the merchant's, closing it
+    /// Principal, accrued rewards and profit share are added up. The merged
+    /// deposit unlocks at the later of the two locks and terms, keeps the earlier
+    /// deposit time, and the merchant adopts the older loyalty start.
+    pub fn merge_positions(ctx: Context<MergePositions>) -> Result<()> {
+        let current_time = time::now()?;
+        let vault = &mut ctx.accounts.vault;
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        let source = &mut ctx.accounts.source_deposit;
+
+        require_movable_position(merchant_deposit)?;
+        require_movable_position(source)?;
+        require!(!vault.emergency_mode, VaultError::EmergencyModeActive);
+        require!(
+            merchant_deposit.merchant != source.merchant
+                && merchant_deposit.deposit_token == source.deposit_token
+                && merchant_deposit.mint == source.mint,
+            VaultError::CannotMergePositions
+        );
+
+        // Settle both deposits up to now (ending any reward streams) before combining them
+        for deposit in [&mut **merchant_deposit, &mut **source] {
+            let harvest = accrue_rewards(deposit, vault, current_time)?;
+            if let Some(harvested) = book_harvest(vault, deposit, harvest, current_time)? {
+                emit_cpi!(harvested);
+            }
+            deposit.end_reward_stream()?;
+            remove_deposit_from_vault(vault, deposit, 0)?;
+        }
+
+        merchant_deposit.total_deposited = merchant_deposit
+            .total_deposited
+            .checked_add(source.total_deposited)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.accrued_rewards = merchant_deposit
+            .accrued_rewards
+            .checked_add(source.accrued_rewards)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.platform_profit_earned = merchant_deposit
+            .platform_profit_earned
+            .checked_add(source.platform_profit_earned)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.profit_share_allocated = merchant_deposit
+            .profit_share_allocated
+            .checked_add(source.profit_share_allocated)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.mint_principal = merchant_deposit
+            .mint_principal
+            .checked_add(source.mint_principal)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.total_rewards_accrued = merchant_deposit
+            .total_rewards_accrued
+            .checked_add(source.total_rewards_accrued)
+            .ok_or(VaultError::MathOverflow)?;
+        merchant_deposit.total_fees_paid_usd = merchant_deposit
+            .total_fees_paid_usd
+            .checked_add(source.total_fees_paid_usd)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // The merged deposit is locked as long as either part was
+        if source.unlock_time > merchant_deposit.unlock_time {
+            merchant_deposit.lock_period = source.lock_period.clone();
+            merchant_deposit.unlock_time = source.unlock_time;
+        }
+        if source.term_maturity > merchant_deposit.term_maturity {
+            merchant_deposit.lock_term = source.lock_term.clone();
+            merchant_deposit.term_maturity = source.term_maturity;
+            merchant_deposit.lock_boost_bps = source.lock_boost_bps;
+        }
+        merchant_deposit.deposited_at = merchant_deposit.deposited_at.min(source.deposited_at);
+        merchant_deposit.deposited_slot = merchant_deposit.deposited_slot.max(source.deposited_slot);
+        // Partial days since the later checkpoint are forfeited rather than double counted
+        merchant_deposit.last_accrual_at = merchant_deposit.last_accrual_at.max(source.last_accrual_at);
+        let previous_yield_bps = merchant_deposit.current_yield_bps;
+        merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+            merchant_deposit,
+            &*ctx.accounts.merchant_metrics.load()?,
+            merchant_deposit.total_deposited,
+        );
+        if let Some(updated) = record_yield_change(
+            &ctx.accounts.yield_history,
+            previous_yield_bps,
+            merchant_deposit.current_yield_bps,
+            YieldTrigger::PositionsMerged,
+        )? {
+            emit_cpi!(updated);
+        }
+        add_deposit_to_vault(vault, merchant_deposit)?;
+
+        adopt_loyalty(
+            &mut ctx.accounts.merchant_profile,
+            loyalty_start(&ctx.accounts.source_profile, source),
+        );
+        let source_profile = &mut ctx.accounts.source_profile;
+        source_profile.current_tier = source.current_tier;
+        source_profile.last_withdrawn_at = current_time;
+        source.is_active = false;
+
+        emit_cpi!(PositionsMerged {
+            merchant: merchant_deposit.merchant,
+            source_merchant: source.merchant,
+            amount: source.total_deposited,
+            total_deposited: merchant_deposit.total_deposited,
+        });
+
+        debug_assert_invariants(&ctx.accounts.vault);
+
+        msg!("Merged {} from merchant {} | New principal: {}",
+            source.total_deposited,
+            source.merchant,
+            merchant_deposit.total_deposited
+        );
+
+        Ok(())
+    }
+
+    /// Freeze a merchant in response to fraud or a compliance incident (admin only)
+    /// Blocks withdrawals and order recording for this merchant only
+    pub fn freeze_merchant(ctx: Context<SetMerchantFrozen>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+        merchant_deposit.frozen = true;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::MerchantFrozen,
+            ctx.accounts.authority.key(),
+            merchant_deposit.merchant,
+            0,
+            0,
+        )?;
+
+        msg!("Merchant {} frozen", merchant_deposit.merchant);
+        Ok(())
+    }
+
+    /// Lift a merchant freeze (admin only)
+    pub fn unfreeze_merchant(ctx: Context<SetMerchantFrozen>) -> Result<()> {
+        require!(ctx.accounts.authority.key() == ctx.accounts.vault.authority, VaultError::Unauthorized);
+
+        let merchant_deposit = &mut ctx.accounts.merchant_deposit;
+        require!(merchant_deposit.frozen, VaultError::MerchantNotFrozen);
+        merchant_deposit.frozen = false;
+
+        record_admin_action(
+            &ctx.accounts.audit_log,
+            AuditAction::MerchantUnfrozen,
+            ctx.accounts.authority.key(),
+            merchant_deposit.merchant,
+            0,
+            0,
+        )?;
+
+        msg!("Merchant {} unfrozen", merchant_deposit.merchant);
+        Ok(())
+    }
+
+    /// Pause merchant activity for a slow season (vacation mode)
+    /// Freezes rolling volume, tier and the loyalty clock where they are and
+    /// stops new orders from being recorded until `resume_merchant_activity`.
+    /// Yield keeps accruing on the deposit.
+    pub fn pause_merchant_activity(ctx: Context<SetMerchantPaused>) -> Result<()> {
+        let current_time = time::now()?;
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        require!(metrics.paused_at == 0, VaultError::MerchantPaused);
+
+        // Settle volume decay up to now, then stop the clocks
+        apply_volume_decay(&mut metrics, current_time);
+        metrics.paused_at = current_time;
+        if let Some(merchant_deposit) = ctx.accounts.merchant_deposit.as_ref() {
+            merchant_profile.loyalty_started_at = loyalty_start(merchant_profile, merchant_deposit);
+        }
+
+        msg!("Merchant {} paused", merchant_profile.merchant);
+        Ok(())
+    }
+
+    /// Resume merchant activity after `pause_merchant_activity`
+    /// Time spent paused does not count toward volume decay, loyalty, the
+    /// redeposit grace period or a pending tier downgrade.
+    pub fn resume_merchant_activity(ctx: Context<SetMerchantPaused>) -> Result<()> {
+        let current_time = time::now()?;
+        let merchant_profile = &mut ctx.accounts.merchant_profile;
+        let mut metrics = ctx.accounts.merchant_metrics.load_mut()?;
+        require!(metrics.paused_at > 0, VaultError::MerchantNotPaused);
+
+        let paused_for = current_time.saturating_sub(metrics.paused_at);
+        if merchant_profile.loyalty_started_at > 0 {
+            merchant_profile.loyalty_started_at = merchant_profile
+                .loyalty_started_at
+                .checked_add(paused_for)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+        if merchant_profile.last_withdrawn_at > 0 {
+            merchant_profile.last_withdrawn_at = merchant_profile
+                .last_withdrawn_at
+                .checked_add(paused_for)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+        if let Some(merchant_deposit) = ctx.accounts.merchant_deposit.as_mut() {
+            if merchant_deposit.tier_grace_started_at > 0 {
+                merchant_deposit.tier_grace_started_at = merchant_deposit
+                    .tier_grace_started_at
+                    .checked_add(paused_for)
+                    .ok_or(VaultError::MathOverflow)?;
+            }
+        }
+        metrics.last_volume_update = current_time;
+        metrics.paused_at = 0;
+
+        msg!("Merchant {} resumed after {} seconds", merchant_profile.merchant, paused_for);
+        Ok(())
+    }
+
+    /// Grow a vault created with an older layout to the current size (admin only)
+    /// Fields added since the vault was created start zeroed, and the authority
+    /// pays the extra rent. New fields come out of `_reserved` where they fit,
+    /// so this is only needed once the reserved space runs out.
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+
+        // Only accounts owned by this program carrying the Vault discriminator
+        require_keys_eq!(*vault_info.owner, crate::ID, VaultError::InvalidMigration);
+        {
+            let data = vault_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == Vault::DISCRIMINATOR,
+                VaultError::InvalidMigration
+            );
+            // The authority leads every layout; the label (part of the address) may not exist yet
+            require!(
+                data[8..40] == ctx.accounts.authority.key().to_bytes(),
+                VaultError::Unauthorized
+            );
+        }
+
+        let current_len = vault_info.data_len();
+        let new_len = 8 + Vault::LEN;
+        require!(current_len < new_len, VaultError::AlreadyMigrated);
+
+        fund_rent_for_len(
+            &ctx.accounts.authority.to_account_info(),
+            &vault_info,
+            &ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+        vault_info.realloc(new_len, false)?;
+        vault_info.try_borrow_mut_data()?[current_len..].fill(0);
+
+        msg!("Vault {} migrated from {} to {} bytes", vault_info.key(), current_len, new_len);
+        Ok(())
+    }
+
+    /// Grow a MerchantMetrics account created with an older layout to the current size
+    /// Counters added since the account was created start zeroed. Anyone may pay
+    /// for it; until then the merchant's orders can't be recorded.
+    pub fn migrate_merchant_metrics(ctx: Context<MigrateMerchantMetrics>) -> Result<()> {
+        let metrics_info = ctx.accounts.merchant_metrics.to_account_info();
+
+        // Only accounts owned by this program carrying the MerchantMetrics discriminator
+        require_keys_eq!(*metrics_info.owner, crate::ID, VaultError::InvalidMigration);
+        {
+            let data = metrics_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == MerchantMetrics::DISCRIMINATOR,
+                VaultError::InvalidMigration
+            );
+        }
+
+        let current_len = metrics_info.data_len();
+        let new_len = 8 + std::mem::size_of::<MerchantMetrics>();
+        require!(current_len < new_len, VaultError::AlreadyMigrated);
+
+        fund_rent_for_len(
+            &ctx.accounts.payer.to_account_info(),
+            &metrics_info,
+            &ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+        metrics_info.realloc(new_len, false)?;
+        metrics_info.try_borrow_mut_data()?[current_len..].fill(0);
+
+        msg!("MerchantMetrics {} migrated from {} to {} bytes", metrics_info.key(), current_len, new_len);
+        Ok(())
+    }
+
+    /// Upgrade a MerchantDeposit created with an older layout to the current one
+    /// Reallocs the account (payer covers extra rent), zero-fills new fields, and
+    /// backfills the ones whose zero value would be wrong. Anyone may pay for it.
+    ///
+    /// v1 -> v2 moves the inline order metrics out to the merchant's
+    /// MerchantMetrics account, creating it if the merchant never registered one.
+    pub fn migrate_merchant_deposit(ctx: Context<MigrateMerchantDeposit>) -> Result<()> {
+        let deposit_info = ctx.accounts.merchant_deposit.to_account_info();
+
+        // Only accounts owned by this program carrying the MerchantDeposit discriminator
+        require_keys_eq!(*deposit_info.owner, crate::ID, VaultError::InvalidMigration);
+        {
+            let data = deposit_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == MerchantDeposit::DISCRIMINATOR,
+                VaultError::InvalidMigration
+            );
+        }
+
+        // Make sure the account holds rent for the current size, funded by the payer
+        let new_len = 8 + MerchantDeposit::LEN;
+        fund_rent_for_len(
+            &ctx.accounts.payer.to_account_info(),
+            &deposit_info,
+            &ctx.accounts.system_program.to_account_info(),
+            new_len,
+        )?;
+
+        // Pre-v2 layouts carry the order metrics inline after `bump`. Grow v0
+        // accounts to the v1 size (zero-filling new fields), lift the metrics
+        // out, then shift the remaining fields down over them.
+        let mut legacy_metrics = None;
+        let v2_len = 8 + MerchantDeposit::V2_LEN;
+        let v3_len = 8 + MerchantDeposit::V3_LEN;
+        let v4_len = 8 + MerchantDeposit::V4_LEN;
+        let v5_len = 8 + MerchantDeposit::V5_LEN;
+        let v6_len = 8 + MerchantDeposit::V6_LEN;
+        let v7_len = 8 + MerchantDeposit::V7_LEN;
+        let v8_len = 8 + MerchantDeposit::V8_LEN;
+        if ![new_len, v2_len, v3_len, v4_len, v5_len, v6_len, v7_len, v8_len].contains(&deposit_info.data_len()) {
+            let legacy_len = 8 + MerchantDeposit::LEGACY_LEN;
+            if deposit_info.data_len() < legacy_len {
+                deposit_info.realloc(legacy_len, true)?;
+            }
+            {
+                let mut data = deposit_info.try_borrow_mut_data()?;
+                let m = &data[LEGACY_METRICS_OFFSET..LEGACY_METRICS_OFFSET + LEGACY_METRICS_LEN];
+                legacy_metrics = Some((
+                    u64::from_le_bytes(m[0..8].try_into().unwrap()),
+                    u64::from_le_bytes(m[8..16].try_into().unwrap()),
+                    u64::from_le_bytes(m[16..24].try_into().unwrap()),
+                    i64::from_le_bytes(m[24..32].try_into().unwrap()),
+                    u32::from_le_bytes(m[32..36].try_into().unwrap()),
+                ));
+                data.copy_within(LEGACY_METRICS_OFFSET + LEGACY_METRICS_LEN..legacy_len, LEGACY_METRICS_OFFSET);
+            }
+            deposit_info.realloc(v2_len, false)?;
+        }
+        // v2 layout onwards: fields added after the account's layout start zeroed
+        let current_len = deposit_info.data_len();
+        if current_len != new_len {
+            deposit_info.realloc(new_len, false)?;
+            deposit_info.try_borrow_mut_data()?[current_len..].fill(0);
+        }
+
+        let mut merchant_deposit = {
+            let data = deposit_info.try_borrow_data()?;
+            MerchantDeposit::try_deserialize(&mut &data[..])?
+        };
+
+        // Verify this is the vault's PDA for the recorded merchant
+        let vault_key = ctx.accounts.vault.key();
+        let (expected_key, _) = Pubkey::find_program_address(
+            &[DEPOSIT_SEED, vault_key.as_ref(), merchant_deposit.merchant.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(deposit_info.key(), expected_key, VaultError::InvalidMigration);
+        require!(
+            merchant_deposit.version < MERCHANT_DEPOSIT_VERSION,
+            VaultError::AlreadyMigrated
+        );
+
+        // v0 -> v1: maintenance checkpoints default to the deposit time
+        if merchant_deposit.version < 1 {
+            if merchant_deposit.last_accrual_at == 0 {
+                merchant_deposit.last_accrual_at = merchant_deposit.deposited_at;
+            }
+            if merchant_deposit.last_metrics_refresh == 0 {
+                merchant_deposit.last_metrics_refresh = merchant_deposit.deposited_at;
+            }
+        }
+
+        // v1 -> v2: order metrics move to the MerchantMetrics account
+        let metrics_info = ctx.accounts.merchant_metrics.to_account_info();
+        let (metrics_key, metrics_bump) = Pubkey::find_program_address(
+            &[MERCHANT_METRICS_SEED, vault_key.as_ref(), merchant_deposit.merchant.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(metrics_info.key(), metrics_key, VaultError::InvalidMigration);
+
+        if metrics_info.data_is_empty() {
+            let space = 8 + std::mem::size_of::<MerchantMetrics>();
+            create_pda_account(
+                &ctx.accounts.payer.to_account_info(),
+                &metrics_info,
+                &ctx.accounts.system_program.to_account_info(),
+                space,
+                &[
+                    MERCHANT_METRICS_SEED,
+                    vault_key.as_ref(),
+                    merchant_deposit.merchant.as_ref(),
+                    &[metrics_bump],
+                ],
+            )?;
+
+            let mut data = metrics_info.try_borrow_mut_data()?;
+            data[..8].copy_from_slice(&MerchantMetrics::DISCRIMINATOR);
+            let metrics = bytemuck::from_bytes_mut::<MerchantMetrics>(&mut data[8..]);
+            metrics.merchant = merchant_deposit.merchant;
+            metrics.vault = vault_key;
+            metrics.bump = metrics_bump;
+            metrics.last_volume_update = merchant_deposit.deposited_at;
+            if let Some((total_orders, total_volume, month_volume, last_update, unique_customers)) =
+                legacy_metrics
+            {
+                metrics.total_orders_processed = total_orders;
+                metrics.total_volume_usd = total_volume;
+                metrics.current_month_volume = month_volume;
+                metrics.last_volume_update = last_update;
+                metrics.monthly_unique_customers = unique_customers;
+            }
+        } else {
+            // Already tracked there (merchant registered after the split)
+            require_keys_eq!(*metrics_info.owner, crate::ID, VaultError::InvalidMigration);
+        }
+
+        // v2 -> v3: active deposits join the reward index from here on
+        if merchant_deposit.version < 3 && merchant_deposit.is_active {
+            join_reward_index(&mut ctx.accounts.vault, &mut merchant_deposit)?;
+        }
+
+        // v8 -> v9: record the deposit mint. SPL deposits used to share one bucket,
+        // so the vault authority attests which registered mint one was made in.
+        if merchant_deposit.version < 9 {
+            merchant_deposit.mint = match merchant_deposit.deposit_token {
+                DepositType::Sol => native_mint::ID,
+                DepositType::SplToken => {
+                    require!(ctx.accounts.authority.is_some(), VaultError::Unauthorized);
+                    ctx.accounts.deposit_mint.as_ref().ok_or(VaultError::MintNotSupported)?.mint
+                }
+            };
+        }
+
+        let from_version = merchant_deposit.version;
+        merchant_deposit.version = MERCHANT_DEPOSIT_VERSION;
+
+        let mut data = deposit_info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        merchant_deposit.try_serialize(&mut writer)?;
+
+        msg!("MerchantDeposit {} migrated: v{} -> v{}",
+            deposit_info.key(),
+            from_version,
+            MERCHANT_DEPOSIT_VERSION
+        );
+        Ok(())
+    }
+
+    /// Append a daily TVL/yield snapshot to the stats ring buffer (permissionless crank)
+    pub fn snapshot_vault_stats(ctx: Context<SnapshotVaultStats>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let mut stats = ctx.accounts.vault_stats.load_mut()?;
+        let current_time = time::now()?;
+
+        require!(
+            elapsed_seconds(stats.last_snapshot_at, current_time) >= STATS_SNAPSHOT_INTERVAL,
+            VaultError::SnapshotTooSoon
+        );
+
+        // Principal-weighted average yield across active deposits
+        let average_yield_bps = if vault.total_deposits > 0 {
+            (vault.weighted_yield_sum / vault.total_deposits as u128).min(u16::MAX as u128) as u16
+        } else {
+            0
+        };
+
+        let rewards_paid = vault
+            .total_rewards_paid
+            .saturating_sub(stats.last_rewards_paid);
+
+        let head = stats.head as usize;
+        stats.snapshots[head] = StatsSnapshot {
+            timestamp: current_time,
+            tvl: vault.total_deposits,
+            rewards_paid,
+            total_merchants: vault.total_merchants,
+            average_yield_bps,
+            idle_token_bps: vault.idle_token_bps(),
+        };
+        stats.head = ((head + 1) % VAULT_STATS_CAPACITY) as u32;
+        stats.count = (stats.count + 1).min(VAULT_STATS_CAPACITY as u32);
+        stats.last_snapshot_at = current_time;
+        stats.last_rewards_paid = vault.total_rewards_paid;
+
+        msg!("Vault snapshot: tvl {} | merchants {} | avg yield {} bps | rewards paid {} | idle {} bps",
+            vault.total_deposits,
+            vault.total_merchants,
+            average_yield_bps,
+            rewards_paid,
+            vault.idle_token_bps()
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// PDA Seeds
+// ============================================================================
+
+/// Vault PDA (also holds SOL deposits): [VAULT_SEED, authority, label seed]
+/// (see `vault_label_seed`)
+#[constant]
+pub const VAULT_SEED: &[u8] = b"vault";
+/// Size of a vault label
+#[constant]
+pub const VAULT_LABEL_LEN: usize = 16;
+/// Merchant deposit: [DEPOSIT_SEED, vault, merchant]
+#[constant]
+pub const DEPOSIT_SEED: &[u8] = b"deposit";
+/// Merchant profile: [MERCHANT_PROFILE_SEED, vault, merchant]
+#[constant]
+pub const MERCHANT_PROFILE_SEED: &[u8] = b"merchant_profile";
+/// Merchant order metrics: [MERCHANT_METRICS_SEED, vault, merchant]
+#[constant]
+pub const MERCHANT_METRICS_SEED: &[u8] = b"merchant_metrics";
+/// Payout splits and auto-sweep: [PAYOUT_CONFIG_SEED, vault, merchant]
+#[constant]
+pub const PAYOUT_CONFIG_SEED: &[u8] = b"payout_config";
+/// Approved payout destinations: [WITHDRAWAL_WHITELIST_SEED, vault, merchant]
+#[constant]
+pub const WITHDRAWAL_WHITELIST_SEED: &[u8] = b"withdrawal_whitelist";
+/// Dead-man switch: [RECOVERY_CONFIG_SEED, vault, merchant]
+#[constant]
+pub const RECOVERY_CONFIG_SEED: &[u8] = b"recovery_config";
+/// Store/location: [SUB_MERCHANT_SEED, vault, merchant, label_hash]
+#[constant]
+pub const SUB_MERCHANT_SEED: &[u8] = b"sub_merchant";
+/// Agent authorization: [AGENT_AUTH_SEED, vault, merchant, agent]
+#[constant]
+pub const AGENT_AUTH_SEED: &[u8] = b"agent_auth";
+/// Order receipt: [RECEIPT_SEED, vault, merchant, order_id]
+#[constant]
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+/// Order dispute: [DISPUTE_SEED, order_receipt]
+#[constant]
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+/// Queued withdrawal: [WITHDRAWAL_TICKET_SEED, vault, id (u64 LE)]
+#[constant]
+pub const WITHDRAWAL_TICKET_SEED: &[u8] = b"withdrawal_ticket";
+/// Buyer cashback: [BUYER_REWARDS_SEED, vault, buyer]
+#[constant]
+pub const BUYER_REWARDS_SEED: &[u8] = b"buyer_rewards";
+/// Referrer's code: [REFERRAL_CODE_SEED, vault, referrer]
+#[constant]
+pub const REFERRAL_CODE_SEED: &[u8] = b"referral_code";
+/// Merchant's referral link: [REFERRAL_SEED, vault, merchant]
+#[constant]
+pub const REFERRAL_SEED: &[u8] = b"referral";
+/// Subscription: [SUBSCRIPTION_SEED, vault, merchant, buyer]
+#[constant]
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+/// Invoice: [INVOICE_SEED, vault, merchant, memo_hash]
+#[constant]
+pub const INVOICE_SEED: &[u8] = b"invoice";
+/// Authorized payment: [PAYMENT_HOLD_SEED, vault, merchant, buyer, hold_id]
+#[constant]
+pub const PAYMENT_HOLD_SEED: &[u8] = b"payment_hold";
+/// Token account escrowing an authorized payment: [HOLD_ESCROW_SEED, payment_hold]
+#[constant]
+pub const HOLD_ESCROW_SEED: &[u8] = b"hold_escrow";
+/// Buyer's spending grant to an agent: [SPENDING_GRANT_SEED, vault, buyer, agent]
+#[constant]
+pub const SPENDING_GRANT_SEED: &[u8] = b"spending_grant";
+/// Bonus distribution: [DISTRIBUTION_SEED, vault, id (u64 LE)]
+#[constant]
+pub const DISTRIBUTION_SEED: &[u8] = b"distribution";
+/// Bonus claim marker: [BONUS_CLAIM_SEED, distribution, merchant]
+#[constant]
+pub const BONUS_CLAIM_SEED: &[u8] = b"bonus_claim";
+/// Vault stats: [VAULT_STATS_SEED, vault]
+#[constant]
+pub const VAULT_STATS_SEED: &[u8] = b"vault_stats";
+/// Audit log: [AUDIT_LOG_SEED, vault]
+#[constant]
+pub const AUDIT_LOG_SEED: &[u8] = b"audit_log";
+/// Merchant yield history: [YIELD_HISTORY_SEED, vault, merchant]
+#[constant]
+pub const YIELD_HISTORY_SEED: &[u8] = b"yield_history";
+/// Insurance fund: [INSURANCE_FUND_SEED, vault]
+#[constant]
+pub const INSURANCE_FUND_SEED: &[u8] = b"insurance_fund";
+/// Reward emission schedule: [EMISSION_SCHEDULE_SEED, vault]
+#[constant]
+pub const EMISSION_SCHEDULE_SEED: &[u8] = b"emission_schedule";
+/// Reward token mint: [REWARD_MINT_SEED, vault]
+#[constant]
+pub const REWARD_MINT_SEED: &[u8] = b"reward_mint";
+/// Reward mint authority: [REWARD_MINT_AUTHORITY_SEED, vault]
+#[constant]
+pub const REWARD_MINT_AUTHORITY_SEED: &[u8] = b"reward_mint_authority";
+/// Tier badge record: [TIER_BADGE_SEED, vault, merchant, tier]
+#[constant]
+pub const TIER_BADGE_SEED: &[u8] = b"tier_badge";
+/// Tier badge mint: [BADGE_MINT_SEED, vault, merchant, tier]
+#[constant]
+pub const BADGE_MINT_SEED: &[u8] = b"badge_mint";
+/// Position NFT mint: [POSITION_MINT_SEED, vault, merchant, deposited_at]
+#[constant]
+pub const POSITION_MINT_SEED: &[u8] = b"position_mint";
+
+/// Vault share mint of an asset: [SHARE_MINT_SEED, vault, DepositType::share_mint_seed]
+#[constant]
+pub const SHARE_MINT_SEED: &[u8] = b"share_mint";
+/// Wallet cleared to receive vault shares: [APPROVED_HOLDER_SEED, vault, wallet]
+#[constant]
+pub const APPROVED_HOLDER_SEED: &[u8] = b"approved_holder";
+/// KYC attestation of a wallet: [KYC_ATTESTATION_SEED, vault, wallet]
+#[constant]
+pub const KYC_ATTESTATION_SEED: &[u8] = b"kyc_attestation";
+
+/// Temporary wSOL account of `deposit_wsol`: [WSOL_UNWRAP_SEED, vault, merchant]
+#[constant]
+pub const WSOL_UNWRAP_SEED: &[u8] = b"wsol_unwrap";
+/// Deposit sponsorship: [SPONSORSHIP_SEED, vault, merchant]
+#[constant]
+pub const SPONSORSHIP_SEED: &[u8] = b"sponsorship";
+
+#[constant]
+pub const CONFIG_PROPOSAL_SEED: &[u8] = b"config_proposal";
+
+#[constant]
+pub const PRICE_FEED_SEED: &[u8] = b"price_feed";
+/// Per-mint deposit pause flag: [SUPPORTED_MINT_SEED, vault, mint]
+#[constant]
+pub const SUPPORTED_MINT_SEED: &[u8] = b"supported_mint";
+
+/// Harvests during a Solana epoch: [EPOCH_RECORD_SEED, vault, epoch (le bytes)]
+#[constant]
+pub const EPOCH_RECORD_SEED: &[u8] = b"epoch_record";
+
+/// Lending strategy: [LENDING_STRATEGY_SEED, vault, reserve]
+#[constant]
+pub const LENDING_STRATEGY_SEED: &[u8] = b"lending_strategy";
+
+/// Merchant's monthly statement: [MONTHLY_STATEMENT_SEED, vault, merchant, month (le bytes)]
+#[constant]
+pub const MONTHLY_STATEMENT_SEED: &[u8] = b"monthly_statement";
+
+/// Token account paying buyer cashback and referral rewards: [REWARD_RESERVE_SEED, vault]
+#[constant]
+pub const REWARD_RESERVE_SEED: &[u8] = b"reward_reserve";
+
+/// Maximum beneficiary entries in a merchant's payout config
+pub const MAX_PAYOUT_SPLITS: usize = 5;
+
+/// Maximum destinations on a withdrawal whitelist (room for a payout wallet and
+/// a full split change in flight)
+pub const MAX_WITHDRAWAL_DESTINATIONS: usize = 16;
+
+/// How long after recording an order may be disputed (60 days)
+#[constant]
+pub const DISPUTE_WINDOW: i64 = 60 * 86400;
+
+/// Fixed accounts per ticket in `fulfill_withdrawals` remaining accounts (ticket,
+/// deposit, profile, merchant, payout wallet, payout token account, payout config)
+pub const WITHDRAWAL_TICKET_ACCOUNTS: usize = 7;
+
+/// Decimals of the protocol reward token (matches USDC micro-units)
+#[constant]
+pub const REWARD_TOKEN_DECIMALS: u8 = CANONICAL_DECIMALS;
+/// Shortest allowed reward emission epoch (1 day)
+#[constant]
+pub const MIN_EMISSION_EPOCH: i64 = 86400;
+/// Epochs of decay applied when catching up an idle schedule (later ones are skipped)
+#[constant]
+pub const MAX_EMISSION_CATCHUP_EPOCHS: i64 = 64;
+
+/// Maximum share of platform fees routed to the insurance fund (basis points, 2000 = 20%)
+#[constant]
+pub const MAX_INSURANCE_FEE_BPS: u16 = 2000;
+
+/// Maximum performance fee on harvested yield (basis points, 5000 = 50%)
+#[constant]
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 5000;
+/// Largest change to the performance fee in one update (basis points, 500 = 5%)
+#[constant]
+pub const MAX_PERFORMANCE_FEE_STEP_BPS: u16 = 500;
+/// Minimum time between performance fee changes (3 days)
+#[constant]
+pub const PERFORMANCE_FEE_UPDATE_INTERVAL: i64 = 3 * 86400;
+/// Maximum platform fee on a settled order (basis points, 1000 = 10%)
+#[constant]
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1000;
+/// Maximum share of a merchant's rewards owed to a sponsor (basis points, 5000 = 50%)
+#[constant]
+pub const MAX_SPONSOR_SHARE_BPS: u16 = 5000;
+
+/// Window for the per-merchant order count limit (1 hour)
+#[constant]
+pub const ORDER_RATE_WINDOW: i64 = 3600;
+
+/// Window for the per-merchant credited volume limit (1 day)
+#[constant]
+pub const ORDER_VOLUME_WINDOW: i64 = 86400;
+
+/// Decimals of native SOL (lamports)
+#[constant]
+pub const SOL_DECIMALS: u8 = 9;
+
+/// Longest staleness a settlement price feed can be configured to accept (5 minutes)
+#[constant]
+pub const MAX_PRICE_AGE: i64 = 300;
+
+/// Size of the per-merchant repeat-purchase sketch (4 counters per byte)
+pub const BUYER_FILTER_LEN: usize = 64;
+/// Settlement currencies broken out individually on each merchant's metrics
+pub const MAX_TRACKED_CURRENCIES: usize = 8;
+
+/// Longest delay that can be put on config changes (30 days)
+#[constant]
+pub const MAX_CONFIG_TIMELOCK: i64 = 30 * 86400;
+/// Longest a merchant can keep a tier after dipping below it (90 days)
+#[constant]
+pub const MAX_TIER_DOWNGRADE_GRACE: i64 = 90 * 86400;
+/// Longest holding period between a deposit and its withdrawal (about one epoch)
+#[constant]
+pub const MAX_HOLDING_SLOTS: u64 = 432_000;
+/// Shortest period streamed rewards can be released over (1 day)
+#[constant]
+pub const MIN_REWARD_STREAM_DURATION: i64 = 86400;
+/// Longest period streamed rewards can be released over (1 year)
+#[constant]
+pub const MAX_REWARD_STREAM_DURATION: i64 = 365 * 86400;
+
+/// Delay before a new payout wallet starts receiving funds (48 hours)
+#[constant]
+pub const PAYOUT_WALLET_TIMELOCK: i64 = 2 * 86400;
+
+/// Shortest inactivity period before a beneficiary may start a recovery (30 days)
+#[constant]
+pub const MIN_RECOVERY_INACTIVITY: i64 = 30 * 86400;
+
+/// How long a started recovery can be cancelled by a merchant check-in (14 days)
+#[constant]
+pub const RECOVERY_CHALLENGE_WINDOW: i64 = 14 * 86400;
+
+/// Name of compressed NFT order receipts
+#[constant]
+pub const RECEIPT_CNFT_NAME: &str = "Shaw 402 Receipt";
+/// Base URI for receipt cNFT metadata (`<base><order id hex>.json`)
+#[constant]
+pub const RECEIPT_CNFT_URI_BASE: &str = "https://demo.shaw402.com/receipts/";
+
+/// Metadata symbol for tier badges
+#[constant]
+pub const TIER_BADGE_SYMBOL: &str = "SHAW";
+/// Base URI for tier badge metadata (`<base><tier>.json`)
+#[constant]
+pub const TIER_BADGE_URI_BASE: &str = "https://demo.shaw402.com/badges/";
+/// Metadata name for deposit position NFTs
+#[constant]
+pub const POSITION_NFT_NAME: &str = "Shaw 402 Deposit Position";
+/// Base URI for position NFT metadata (`<base><merchant deposit>.json`)
+#[constant]
+pub const POSITION_NFT_URI_BASE: &str = "https://demo.shaw402.com/positions/";
+
+/// Metadata name and symbol of SOL vault shares
+#[constant]
+pub const SOL_SHARE_NAME: &str = "Shaw 402 Vault SOL";
+#[constant]
+pub const SOL_SHARE_SYMBOL: &str = "svSOL";
+/// Metadata name and symbol of SPL token (USDC) vault shares
+#[constant]
+pub const TOKEN_SHARE_NAME: &str = "Shaw 402 Vault USDC";
+#[constant]
+pub const TOKEN_SHARE_SYMBOL: &str = "svUSDC";
+/// Base URI for vault share metadata (`<base><symbol>.json`)
+#[constant]
+pub const SHARE_URI_BASE: &str = "https://demo.shaw402.com/shares/";
+
+/// Time a revoked agent's bond remains slashable before it can be reclaimed (7 days)
+#[constant]
+pub const AGENT_BOND_COOLDOWN: i64 = 7 * 86400;
+
+/// Maximum agents listed on a merchant profile
+pub const MAX_PROFILE_AGENTS: usize = 10;
+
+/// Maximum buyer cashback rate (basis points, 1000 = 10% of order value)
+#[constant]
+pub const MAX_CASHBACK_BPS: u16 = 1000;
+
+/// Default minimum order amount credited toward volume metrics ($10, anti-gaming)
+#[constant]
+pub const MIN_ORDER_USD: u64 = 10_000000;
+/// Lowest minimum order amount a vault can be configured with ($0.01)
+#[constant]
+pub const LOWEST_MIN_ORDER_USD: u64 = 10_000;
+/// Highest minimum order amount a vault can be configured with ($100,000)
+#[constant]
+pub const HIGHEST_MIN_ORDER_USD: u64 = 100_000_000000;
+
+/// Half-life of the rolling monthly volume (30 days * ln 2)
+/// With this half-life a steady order flow decays to the same value as a
+/// 30-day window, so tier and yield thresholds keep their monthly meaning.
+#[constant]
+pub const VOLUME_DECAY_HALF_LIFE: i64 = 1_796_637;
+
+/// Maximum orders per `record_orders_batch` call (bounded by transaction size)
+pub const MAX_BATCH_ORDERS: usize = 8;
+
+/// Maximum APY boost for a fixed-term lock (basis points, 500 = +5%)
+#[constant]
+pub const MAX_LOCK_BOOST_BPS: u16 = 500;
+
+/// Current MerchantDeposit layout version
+#[constant]
+pub const MERCHANT_DEPOSIT_VERSION: u8 = 9;
+/// Offset of the order metrics inlined in v0/v1 deposits (after `bump`)
+pub const LEGACY_METRICS_OFFSET: usize = 8 + 32 + 32 + 1 + 8 + 8 + 1 + 8 + 1;
+/// Size of the inlined v0/v1 order metrics (3 x u64, i64, u32)
+pub const LEGACY_METRICS_LEN: usize = 8 + 8 + 8 + 8 + 4;
+
+/// Minimum time between metric refreshes for one merchant (anti-spam, 1 hour)
+#[constant]
+pub const MIN_METRICS_REFRESH_INTERVAL: i64 = 3600;
+/// Redeposit window that keeps a merchant's loyalty clock running (14 days)
+#[constant]
+pub const LOYALTY_GRACE_PERIOD: i64 = 14 * 86400;
+/// Metrics untouched for longer than this are reported as stale (7 days)
+#[constant]
+pub const METRICS_STALE_AFTER: i64 = 7 * 86400;
+
+/// Snapshots kept in the vault stats ring buffer (~8 months of daily snapshots)
+pub const VAULT_STATS_CAPACITY: usize = 256;
+/// Authority actions kept in the audit log ring buffer
+pub const AUDIT_LOG_CAPACITY: usize = 64;
+/// Yield changes kept in each merchant's yield history ring buffer
+pub const YIELD_HISTORY_CAPACITY: usize = 32;
+/// Minimum time between vault stats snapshots (1 day)
+#[constant]
+pub const STATS_SNAPSHOT_INTERVAL: i64 = 86400;
+
+/// Maximum clock skew accepted for a signed order attestation (10 minutes)
+#[constant]
+pub const MAX_ATTESTATION_AGE: i64 = 600;
+/// Length of the signed attestation message
+pub const ATTESTATION_MESSAGE_LEN: usize = 32 + 32 + 8 + 8 + 32;
+
+/// Shortest allowed subscription billing interval (1 day)
+#[constant]
+pub const MIN_SUBSCRIPTION_INTERVAL: i64 = 86400;
+/// Delay before retrying a failed subscription payment (1 day)
+#[constant]
+pub const SUBSCRIPTION_RETRY_INTERVAL: i64 = 86400;
+/// Failed payment attempts before a subscription lapses
+#[constant]
+pub const MAX_SUBSCRIPTION_RETRIES: u8 = 3;
+/// Longest a payment authorization can hold the buyer's funds (30 days)
+#[constant]
+pub const MAX_HOLD_DURATION: i64 = 30 * 86400;
+/// Shortest spending grant period (1 hour)
+#[constant]
+pub const MIN_GRANT_PERIOD: i64 = 3600;
+
+// ============================================================================
+// Order Metrics Functions
+// ============================================================================
+
+/// Credit a settled order to the merchant's performance metrics
+/// Decays rolling volume and recalculates dynamic yield
+/// Repeat purchases by `buyer` are dampened; `None` credits the order in full
+fn apply_order_metrics(
+    vault: &Vault,
+    merchant_deposit: &mut MerchantDeposit,
+    metrics: &mut MerchantMetrics,
+    order_amount_usd: u64,
+    buyer: Option<&Pubkey>,
+    current_time: i64,
+) -> Result<()> {
+    credit_order(vault, merchant_deposit, metrics, order_amount_usd, buyer, current_time)?;
+    refresh_dynamic_yield(merchant_deposit, metrics);
+    Ok(())
+}
+
+/// Credit one order to the merchant's volume, leaving the yield to the caller
+fn credit_order(
+    vault: &Vault,
+    merchant_deposit: &MerchantDeposit,
+    metrics: &mut MerchantMetrics,
+    order_amount_usd: u64,
+    buyer: Option<&Pubkey>,
+    current_time: i64,
+) -> Result<()> {
+    // Validate minimum order amount (anti-gaming)
+    require!(order_amount_usd >= vault.min_order_usd(), VaultError::OrderTooSmall);
+
+    let credited_volume_usd = match buyer {
+        Some(buyer) => repeat_purchase_credit(vault, metrics, buyer, order_amount_usd, current_time)?,
+        None => order_amount_usd,
+    };
+    credit_order_volume(
+        vault,
+        merchant_deposit,
+        metrics,
+        order_amount_usd,
+        credited_volume_usd,
+        1,
+        current_time,
+    )
+}
+
+/// Pay `captured` of a hold's escrow to the merchant and the rest back to the buyer
+/// Then closes the escrow, whose rent returns to the buyer who paid it.
+fn release_hold<'info>(
+    payment_hold: &Account<'info, PaymentHold>,
+    escrow_token_account: &Account<'info, TokenAccount>,
+    merchant_token_account: Option<&Account<'info, TokenAccount>>,
+    buyer_token_account: &Account<'info, TokenAccount>,
+    buyer: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    captured: u64,
+) -> Result<()> {
+    let released = payment_hold.amount.checked_sub(captured).ok_or(VaultError::MathOverflow)?;
+    let seeds = &[
+        PAYMENT_HOLD_SEED,
+        payment_hold.vault.as_ref(),
+        payment_hold.merchant.as_ref(),
+        payment_hold.buyer.as_ref(),
+        payment_hold.hold_id.as_ref(),
+        &[payment_hold.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    for (destination, amount) in [
+        (merchant_token_account.map(|account| account.to_account_info()), captured),
+        (Some(buyer_token_account.to_account_info()), released),
+    ] {
+        let Some(destination) = destination.filter(|_| amount > 0) else {
+            continue;
+        };
+        let cpi_accounts = Transfer {
+            from: escrow_token_account.to_account_info(),
+            to: destination,
+            authority: payment_hold.to_account_info(),
+        };
+        token::transfer(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer), amount)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: escrow_token_account.to_account_info(),
+        destination: buyer.clone(),
+        authority: payment_hold.to_account_info(),
+    };
+    token::close_account(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer))
+}
+
+/// Add a settlement to the merchant's per-currency volume
+/// A currency takes the next free slot the first time it settles; once all
+/// MAX_TRACKED_CURRENCIES slots are taken, further currencies only add to
+/// `other_currency_volume_usd`
+fn record_currency_volume(
+    metrics: &mut MerchantMetrics,
+    mint: &Pubkey,
+    amount: u64,
+    volume_usd: u64,
+) -> Result<()> {
+    // Slots fill in order, so a currency's slot always comes before the free ones
+    let slot = metrics
+        .currency_volumes
+        .iter()
+        .position(|entry| entry.mint == *mint || entry.mint == Pubkey::default());
+    let Some(slot) = slot else {
+        metrics.other_currency_volume_usd = metrics
+            .other_currency_volume_usd
+            .checked_add(volume_usd)
+            .ok_or(VaultError::MathOverflow)?;
+        return Ok(());
+    };
+
+    let entry = &mut metrics.currency_volumes[slot];
+    entry.mint = *mint;
+    entry.order_count = entry.order_count.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    entry.amount = entry.amount.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+    entry.volume_usd = entry.volume_usd.checked_add(volume_usd).ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+/// Credit a batch of already-validated orders to the merchant's metrics
+/// Counters and yield are updated once for the whole batch. `credited_volume_usd`
+/// is the part of `batch_volume_usd` left after repeat-purchase dampening.
+fn apply_order_batch_metrics(
+    vault: &Vault,
+    merchant_deposit: &mut MerchantDeposit,
+    metrics: &mut MerchantMetrics,
+    batch_volume_usd: u64,
+    credited_volume_usd: u64,
+    order_count: u64,
+    current_time: i64,
+) -> Result<()> {
+    credit_order_volume(
+        vault,
+        merchant_deposit,
+        metrics,
+        batch_volume_usd,
+        credited_volume_usd,
+        order_count,
+        current_time,
+    )?;
+    refresh_dynamic_yield(merchant_deposit, metrics);
+    Ok(())
+}
+
+/// Add already-validated orders to the merchant's counters and rolling volume
+fn credit_order_volume(
+    vault: &Vault,
+    merchant_deposit: &MerchantDeposit,
+    metrics: &mut MerchantMetrics,
+    batch_volume_usd: u64,
+    credited_volume_usd: u64,
+    order_count: u64,
+    current_time: i64,
+) -> Result<()> {
+    require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+    require!(metrics.paused_at == 0, VaultError::MerchantPaused);
+
+    // Decay rolling volume up to now before crediting the new orders
+    apply_volume_decay(metrics, current_time);
+    let credited_volume_usd =
+        apply_order_rate_limits(vault, metrics, credited_volume_usd, order_count, current_time)?;
+
+    // Update metrics
+    metrics.total_orders_processed = metrics
+        .total_orders_processed
+        .checked_add(order_count)
+        .ok_or(VaultError::MathOverflow)?;
+
+    metrics.total_volume_usd = metrics
+        .total_volume_usd
+        .checked_add(batch_volume_usd)
+        .ok_or(VaultError::MathOverflow)?;
+
+    metrics.current_month_volume = metrics
+        .current_month_volume
+        .checked_add(credited_volume_usd)
+        .ok_or(VaultError::MathOverflow)?;
+
+    // Track unique customer (simplified - in production, use a bloom filter or separate account)
+    metrics.monthly_unique_customers = metrics
+        .monthly_unique_customers
+        .checked_add(u32::try_from(order_count).map_err(|_| VaultError::MathOverflow)?)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Recalculate current yield based on new metrics (lock period, volume, profit share)
+fn refresh_dynamic_yield(merchant_deposit: &mut MerchantDeposit, metrics: &MerchantMetrics) {
+    merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+        merchant_deposit,
+        metrics,
+        merchant_deposit.total_deposited,
+    );
+}
+
+/// Count orders against the merchant's hourly and daily windows
+/// Rejects the batch past the hourly order limit; returns how much of its
+/// creditable volume fits under the daily limit and may count toward rolling volume
+fn apply_order_rate_limits(
+    vault: &Vault,
+    metrics: &mut MerchantMetrics,
+    batch_volume_usd: u64,
+    order_count: u64,
+    current_time: i64,
+) -> Result<u64> {
+    if current_time >= metrics.order_hour_start.saturating_add(ORDER_RATE_WINDOW) {
+        metrics.order_hour_start = current_time;
+        metrics.hour_order_count = 0;
+    }
+    if current_time >= metrics.order_day_start.saturating_add(ORDER_VOLUME_WINDOW) {
+        metrics.order_day_start = current_time;
+        metrics.day_volume_credited = 0;
+    }
+
+    let hour_order_count = u64::from(metrics.hour_order_count)
+        .checked_add(order_count)
+        .ok_or(VaultError::MathOverflow)?;
+    require!(
+        vault.max_orders_per_hour == 0 || hour_order_count <= u64::from(vault.max_orders_per_hour),
+        VaultError::OrderRateLimitExceeded
+    );
+    metrics.hour_order_count = u32::try_from(hour_order_count).map_err(|_| VaultError::MathOverflow)?;
+
+    let credited_volume_usd = if vault.max_daily_order_volume == 0 {
+        batch_volume_usd
+    } else {
+        batch_volume_usd.min(vault.max_daily_order_volume.saturating_sub(metrics.day_volume_credited))
+    };
+    metrics.day_volume_credited = metrics
+        .day_volume_credited
+        .checked_add(credited_volume_usd)
+        .ok_or(VaultError::MathOverflow)?;
+
+    if credited_volume_usd < batch_volume_usd {
+        msg!("Daily volume limit reached: {} of {} credited", credited_volume_usd, batch_volume_usd);
+    }
+    Ok(credited_volume_usd)
+}
+
+/// Volume credited for an order, by how many times `buyer` already bought from
+/// the merchant in the current window (`Vault::repeat_purchase_credit_bps`)
+///
+/// Purchases are counted in a count-min sketch of 2-bit counters that is cleared
+/// each window, so a busy window can overestimate a buyer's count but never
+/// underestimate it.
+fn repeat_purchase_credit(
+    vault: &Vault,
+    metrics: &mut MerchantMetrics,
+    buyer: &Pubkey,
+    amount_usd: u64,
+    current_time: i64,
+) -> Result<u64> {
+    const COUNTER_MAX: u8 = 0b11;
+
+    if vault.repeat_purchase_window == 0 {
+        return Ok(amount_usd);
+    }
+    if current_time >= metrics.buyer_window_start.saturating_add(vault.repeat_purchase_window) {
+        metrics.buyer_window_start = current_time;
+        metrics.buyer_filter = [0; BUYER_FILTER_LEN];
+    }
+
+    // Two of the 4 * BUYER_FILTER_LEN counters, re-salted every window
+    let digest = keccak::hashv(&[buyer.as_ref(), &metrics.buyer_window_start.to_le_bytes()]).to_bytes();
+    let slots = [digest[0] as usize, digest[1] as usize];
+    let counter = |filter: &[u8; BUYER_FILTER_LEN], slot: usize| (filter[slot / 4] >> (slot % 4 * 2)) & COUNTER_MAX;
+
+    let previous_purchases = slots
+        .iter()
+        .map(|slot| counter(&metrics.buyer_filter, *slot))
+        .min()
+        .unwrap_or(0);
+    for slot in slots {
+        if counter(&metrics.buyer_filter, slot) < COUNTER_MAX {
+            metrics.buyer_filter[slot / 4] += 1 << (slot % 4 * 2);
+        }
+    }
+
+    let credit_bps = vault.repeat_purchase_credit_bps[(previous_purchases as usize).min(2)];
+    let credited = bps_floor(amount_usd, credit_bps as u64).ok_or(VaultError::MathOverflow)?;
+
+    Ok(credited)
+}
+
+/// Credit orders to a sub-merchant's per-store metrics
+/// Only informational; tier and yield come from the parent's metrics
+fn apply_sub_merchant_orders(
+    sub_merchant: &mut SubMerchant,
+    volume_usd: u64,
+    order_count: u64,
+    current_time: i64,
+) -> Result<()> {
+    let elapsed = elapsed_seconds(sub_merchant.last_volume_update, current_time);
+    if elapsed > 0 {
+        sub_merchant.current_month_volume = decay_metric(sub_merchant.current_month_volume, elapsed);
+        sub_merchant.last_volume_update = current_time;
+    }
+
+    sub_merchant.total_orders_processed = sub_merchant
+        .total_orders_processed
+        .checked_add(order_count)
+        .ok_or(VaultError::MathOverflow)?;
+    sub_merchant.total_volume_usd = sub_merchant
+        .total_volume_usd
+        .checked_add(volume_usd)
+        .ok_or(VaultError::MathOverflow)?;
+    sub_merchant.current_month_volume = sub_merchant
+        .current_month_volume
+        .checked_add(volume_usd)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Take a disputed order's volume out of the merchant's metrics
+/// Returns the rolling volume removed (the order amount decayed since it was recorded)
+fn hold_disputed_volume(
+    metrics: &mut MerchantMetrics,
+    receipt: &OrderReceipt,
+    current_time: i64,
+) -> Result<u64> {
+    apply_volume_decay(metrics, current_time);
+
+    // Refunds already came off the merchant's volume
+    let amount_usd = receipt.amount_usd.saturating_sub(receipt.refunded_usd);
+    let held_volume = decay_metric(amount_usd, elapsed_seconds(receipt.recorded_at, current_time))
+        .min(metrics.current_month_volume);
+    metrics.current_month_volume -= held_volume;
+    metrics.total_volume_usd = metrics.total_volume_usd.saturating_sub(amount_usd);
+    metrics.disputed_volume_usd = metrics
+        .disputed_volume_usd
+        .checked_add(amount_usd)
+        .ok_or(VaultError::MathOverflow)?;
+    metrics.open_disputes = metrics
+        .open_disputes
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(held_volume)
+}
+
+/// Release a resolved dispute's hold, restoring the volume if the merchant won
+/// The restored rolling volume keeps decaying from when the dispute was opened
+fn release_disputed_volume(
+    metrics: &mut MerchantMetrics,
+    dispute: &OrderDispute,
+    restore: bool,
+    current_time: i64,
+) -> Result<()> {
+    apply_volume_decay(metrics, current_time);
+
+    metrics.disputed_volume_usd = metrics.disputed_volume_usd.saturating_sub(dispute.amount_usd);
+    metrics.open_disputes = metrics.open_disputes.saturating_sub(1);
+
+    if restore {
+        metrics.current_month_volume = metrics
+            .current_month_volume
+            .checked_add(decay_metric(dispute.held_volume, elapsed_seconds(dispute.opened_at, current_time)))
+            .ok_or(VaultError::MathOverflow)?;
+        metrics.total_volume_usd = metrics
+            .total_volume_usd
+            .checked_add(dispute.amount_usd)
+            .ok_or(VaultError::MathOverflow)?;
+    }
+
+    Ok(())
+}
+
+/// Take a refund off the merchant's volume
+/// The rolling volume loses the refunded value as it has decayed since the order.
+fn remove_refunded_volume(
+    metrics: &mut MerchantMetrics,
+    receipt: &OrderReceipt,
+    amount: u64,
+    refund_usd: u64,
+    current_time: i64,
+) -> Result<()> {
+    apply_volume_decay(metrics, current_time);
+
+    let rolling_volume = decay_metric(refund_usd, elapsed_seconds(receipt.recorded_at, current_time))
+        .min(metrics.current_month_volume);
+    metrics.current_month_volume -= rolling_volume;
+    metrics.total_volume_usd = metrics.total_volume_usd.saturating_sub(refund_usd);
+
+    match metrics
+        .currency_volumes
+        .iter_mut()
+        .find(|entry| entry.mint == receipt.settled_mint)
+    {
+        Some(entry) => {
+            entry.amount = entry.amount.saturating_sub(amount);
+            entry.volume_usd = entry.volume_usd.saturating_sub(refund_usd);
+        }
+        None => metrics.other_currency_volume_usd = metrics.other_currency_volume_usd.saturating_sub(refund_usd),
+    }
+    Ok(())
+}
+
+/// Apply a scheduled payout wallet change once its timelock has passed
+fn promote_payout_wallet(merchant_profile: &mut MerchantProfile, current_time: i64) {
+    if merchant_profile.payout_wallet_effective_at != 0
+        && current_time >= merchant_profile.payout_wallet_effective_at
+    {
+        merchant_profile.payout_wallet = merchant_profile.pending_payout_wallet;
+        merchant_profile.pending_payout_wallet = Pubkey::default();
+        merchant_profile.payout_wallet_effective_at = 0;
+    }
+}
+
+/// Decay the rolling monthly metrics to `current_time`
+///
+/// Replaces the old 30-day hard reset: volume fades smoothly instead of
+/// dropping to zero, so yield doesn't cliff at month boundaries.
+fn apply_volume_decay(metrics: &mut MerchantMetrics, current_time: i64) {
+    let elapsed = elapsed_seconds(metrics.last_volume_update, activity_clock(metrics, current_time));
+    if elapsed <= 0 {
+        return;
+    }
+
+    metrics.current_month_volume = decay_metric(metrics.current_month_volume, elapsed);
+    metrics.monthly_unique_customers =
+        decay_metric(metrics.monthly_unique_customers as u64, elapsed) as u32;
+    metrics.last_volume_update = current_time;
+}
+
+/// Time the merchant's volume and tier clocks have run to
+/// Stopped at `paused_at` while the merchant is paused.
+fn activity_clock(metrics: &MerchantMetrics, current_time: i64) -> i64 {
+    if metrics.paused_at > 0 {
+        current_time.min(metrics.paused_at)
+    } else {
+        current_time
+    }
+}
+
+// ============================================================================
+// Vault Accounting Functions
+// ============================================================================
+
+/// Add a new deposit to the vault totals
+fn add_deposit_to_vault(vault: &mut Vault, merchant_deposit: &mut MerchantDeposit) -> Result<()> {
+    vault.total_deposits = vault
+        .total_deposits
+        .checked_add(merchant_deposit.total_deposited)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.total_merchants = vault
+        .total_merchants
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    join_reward_index(vault, merchant_deposit)?;
+
+    merchant_deposit.reported_yield_bps = merchant_deposit.current_yield_bps;
+    vault.weighted_yield_sum = vault
+        .weighted_yield_sum
+        .checked_add(merchant_deposit.total_deposited as u128 * merchant_deposit.reported_yield_bps as u128)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Principal held in `deposit_token` (the base harvests are split over)
+fn asset_deposits<'a>(vault: &'a mut Vault, deposit_token: &DepositType) -> &'a mut u64 {
+    match deposit_token {
+        DepositType::Sol => &mut vault.total_sol_deposits,
+        DepositType::SplToken => &mut vault.total_token_deposits,
+    }
+}
+
+/// The per-asset principal totals add up to `total_deposits`
+fn principal_totals_match(vault: &Vault) -> bool {
+    vault.total_sol_deposits.checked_add(vault.total_token_deposits) == Some(vault.total_deposits)
+}
+
+/// SOL the vault PDA holds above its rent reserve, and what it owes against
+/// it: SOL principal plus uncollected SOL performance fees
+fn sol_accounting(vault: &Vault, vault_sol_account: &AccountInfo) -> Result<(u64, u64)> {
+    let rent_reserve = Rent::get()?.minimum_balance(vault_sol_account.data_len());
+    let assets = vault_sol_account.lamports().saturating_sub(rent_reserve);
+    let liabilities = vault
+        .total_sol_deposits
+        .checked_add(vault.performance_fees_sol)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok((assets, liabilities))
+}
+
+/// Debug builds re-check the invariants a mutating instruction can see on its
+/// own accounts (see `assert_invariants` for the full check)
+fn debug_assert_invariants(vault: &Account<Vault>) {
+    if cfg!(debug_assertions) {
+        debug_assert!(principal_totals_match(vault), "per-asset principal drifted from total_deposits");
+        if let Ok((assets, liabilities)) = sol_accounting(vault, &vault.to_account_info()) {
+            debug_assert!(assets >= liabilities, "vault holds {} lamports against {} owed", assets, liabilities);
+        }
+    }
+}
+
+/// Book `amount` of yield harvested into the vault for an asset's deposits
+/// The performance fee is booked and the rest raises the asset's reward index.
+/// Returns the split and the new index.
+fn distribute_harvest(vault: &mut Vault, deposit_token: &DepositType, amount: u64) -> Result<(YieldHarvest, u128)> {
+    let harvest = YieldHarvest::split(amount, vault.performance_fee_bps).ok_or(VaultError::MathOverflow)?;
+    let principal = *asset_deposits(vault, deposit_token);
+    require!(principal > 0, VaultError::NoDepositsToHarvest);
+
+    let index_increase = q64_from_ratio_floor(harvest.net, principal).ok_or(VaultError::MathOverflow)?;
+    let (index, fees) = match deposit_token {
+        DepositType::Sol => (&mut vault.reward_index_sol, &mut vault.performance_fees_sol),
+        DepositType::SplToken => (&mut vault.reward_index_token, &mut vault.performance_fees_token),
+    };
+    *index = index.checked_add(index_increase).ok_or(VaultError::MathOverflow)?;
+    *fees = fees.checked_add(harvest.performance_fee).ok_or(VaultError::MathOverflow)?;
+    let reward_index = *index;
+    vault.total_performance_fees = vault
+        .total_performance_fees
+        .checked_add(harvest.performance_fee)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.total_harvested = vault
+        .total_harvested
+        .checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok((harvest, reward_index))
+}
+
+/// Add a harvest and the reward index increase it caused to the current epoch's record
+fn record_epoch_harvest(
+    epoch_record: &mut EpochRecord,
+    deposit_token: &DepositType,
+    amount: u64,
+    index_delta: u128,
+) -> Result<()> {
+    let (current_epoch, _) = time::current_epoch()?;
+    require!(epoch_record.epoch == current_epoch, VaultError::StaleEpochRecord);
+
+    let (harvested, index) = match deposit_token {
+        DepositType::Sol => (&mut epoch_record.harvested_sol, &mut epoch_record.reward_index_delta_sol),
+        DepositType::SplToken => (&mut epoch_record.harvested_token, &mut epoch_record.reward_index_delta_token),
+    };
+    *harvested = harvested.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+    *index = index.checked_add(index_delta).ok_or(VaultError::MathOverflow)?;
+    epoch_record.harvest_count = epoch_record.harvest_count.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+/// Supply `amount` from the vault's token account to its lending reserve
+/// Returns the collateral minted for it.
+fn supply_to_reserve(accounts: &mut LendingLiquidity, amount: u64) -> Result<u64> {
+    require!(accounts.lending_strategy.exited_at == 0, VaultError::StrategyExited);
+    let buffer = accounts
+        .vault
+        .liquidity_buffer(accounts.supported_mint.total_token_deposits)
+        .ok_or(VaultError::MathOverflow)?;
+    require!(
+        accounts.vault_token_account.amount.saturating_sub(amount) >= buffer,
+        VaultError::LiquidityBufferBreached
+    );
+
+    let ix = lending::deposit_reserve_liquidity(
+        &accounts.lending_strategy.reserve_accounts(),
+        &accounts.vault_token_account.key(),
+        &accounts.vault_collateral_account.key(),
+        &accounts.vault.key(),
+        amount,
+    );
+    let liquidity_before = accounts.vault_token_account.amount;
+    let collateral_before = accounts.vault_collateral_account.amount;
+    invoke_lending(accounts, &ix)?;
+    accounts.vault_token_account.reload()?;
+    accounts.vault_collateral_account.reload()?;
+    require!(
+        liquidity_before.checked_sub(accounts.vault_token_account.amount) == Some(amount),
+        VaultError::LendingBalanceMismatch
+    );
+    let minted = accounts
+        .vault_collateral_account
+        .amount
+        .checked_sub(collateral_before)
+        .ok_or(VaultError::MathOverflow)?;
+    require!(minted > 0, VaultError::InvalidLendingReserve);
+
+    let strategy = &mut accounts.lending_strategy;
+    strategy.supplied = strategy.supplied.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+    strategy.collateral = strategy.collateral.checked_add(minted).ok_or(VaultError::MathOverflow)?;
+    accounts.vault.total_lent = accounts.vault.total_lent.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+    accounts.supported_mint.total_lent =
+        accounts.supported_mint.total_lent.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+
+    record_admin_action(
+        &accounts.audit_log,
+        AuditAction::LendingSupplied,
+        accounts.authority.key(),
+        strategy.reserve,
+        amount,
+        0,
+    )?;
+    Ok(minted)
+}
+
+/// What redeeming lending collateral returned
+struct LendingRedemption {
+    /// Tokens paid back into the vault
+    received: u64,
+    /// Supplied principal the collateral stood for
+    principal: u64,
+    /// Tokens received above the principal
+    interest: u64,
+    /// Split and resulting SPL reward index, if interest was booked
+    harvest: Option<(YieldHarvest, u128)>,
+}
+
+/// Redeem `collateral_amount` of the strategy's collateral into the vault's token account
+/// Principal leaves pro-rata to collateral, so each redemption realizes its share
+/// of the interest earned so far. With no SPL deposits left to credit, interest
+/// stays idle in the vault.
+fn redeem_from_reserve(accounts: &mut LendingLiquidity, collateral_amount: u64) -> Result<LendingRedemption> {
+    let ix = lending::redeem_reserve_collateral(
+        &accounts.lending_strategy.reserve_accounts(),
+        &accounts.vault_collateral_account.key(),
+        &accounts.vault_token_account.key(),
+        &accounts.vault.key(),
+        collateral_amount,
+    );
+    let liquidity_before = accounts.vault_token_account.amount;
+    let collateral_before = accounts.vault_collateral_account.amount;
+    invoke_lending(accounts, &ix)?;
+    accounts.vault_token_account.reload()?;
+    accounts.vault_collateral_account.reload()?;
+    require!(
+        collateral_before.checked_sub(accounts.vault_collateral_account.amount) == Some(collateral_amount),
+        VaultError::LendingBalanceMismatch
+    );
+    let received = accounts
+        .vault_token_account
+        .amount
+        .checked_sub(liquidity_before)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let strategy = &mut accounts.lending_strategy;
+    let principal = mul_div_floor(strategy.supplied, collateral_amount, strategy.collateral)
+        .ok_or(VaultError::MathOverflow)?;
+    let interest = received.saturating_sub(principal);
+    strategy.supplied = strategy.supplied.checked_sub(principal).ok_or(VaultError::MathOverflow)?;
+    strategy.collateral = strategy.collateral.checked_sub(collateral_amount).ok_or(VaultError::MathOverflow)?;
+    accounts.vault.total_lent = accounts.vault.total_lent.saturating_sub(principal);
+    accounts.supported_mint.total_lent = accounts.supported_mint.total_lent.saturating_sub(principal);
+
+    record_admin_action(
+        &accounts.audit_log,
+        AuditAction::LendingRedeemed,
+        accounts.authority.key(),
+        strategy.reserve,
+        received,
+        0,
+    )?;
+
+    let mut harvest = None;
+    if interest > 0 && accounts.vault.total_token_deposits > 0 {
+        harvest = Some(distribute_harvest(&mut accounts.vault, &DepositType::SplToken, interest)?);
+        let strategy = &mut accounts.lending_strategy;
+        strategy.total_interest_harvested = strategy
+            .total_interest_harvested
+            .checked_add(interest)
+            .ok_or(VaultError::MathOverflow)?;
+        strategy.last_harvest_at = time::now()?;
+    }
+
+    Ok(LendingRedemption { received, principal, interest, harvest })
+}
+
+/// Invoke a lending program instruction with the vault PDA as transfer authority
+fn invoke_lending(accounts: &LendingLiquidity, ix: &Instruction) -> Result<()> {
+    let vault = &accounts.vault;
+    let seeds = &[VAULT_SEED, vault.authority.as_ref(), vault.label_seed(), &[vault.bump]];
+    anchor_lang::solana_program::program::invoke_signed(
+        ix,
+        &[
+            accounts.vault_token_account.to_account_info(),
+            accounts.vault_collateral_account.to_account_info(),
+            accounts.reserve.to_account_info(),
+            accounts.reserve_liquidity_supply.to_account_info(),
+            accounts.reserve_collateral_mint.to_account_info(),
+            accounts.lending_market.to_account_info(),
+            accounts.lending_market_authority.to_account_info(),
+            accounts.vault.to_account_info(),
+            accounts.token_program.to_account_info(),
+            accounts.lending_program.to_account_info(),
+        ],
+        &[&seeds[..]],
+    )?;
+    Ok(())
+}
+
+/// Count a deposit's principal toward its asset's harvests, starting from the
+/// current reward index (earlier harvests went to earlier depositors)
+fn join_reward_index(vault: &mut Vault, merchant_deposit: &mut MerchantDeposit) -> Result<()> {
+    let asset_total = asset_deposits(vault, &merchant_deposit.deposit_token);
+    *asset_total = asset_total
+        .checked_add(merchant_deposit.total_deposited)
+        .ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.reward_index = vault.reward_index(&merchant_deposit.deposit_token);
+    Ok(())
+}
+
+/// Check that `operator` may move `amount` out of the deposit
+/// The position holder (the merchant unless a position NFT was minted) always
+/// may; a withdraw delegate only before expiry and within its remaining
+/// allowance, which is reduced by `amount`.
+fn authorize_withdrawal(
+    merchant_deposit: &mut MerchantDeposit,
+    operator: Pubkey,
+    amount: u64,
+    current_time: i64,
+) -> Result<()> {
+    if operator == merchant_deposit.position_holder() {
+        return Ok(());
+    }
+
+    require!(
+        operator == merchant_deposit.withdraw_delegate && merchant_deposit.withdraw_delegate != Pubkey::default(),
+        VaultError::Unauthorized
+    );
+    require!(current_time < merchant_deposit.delegate_expiry, VaultError::DelegateExpired);
+    merchant_deposit.delegate_allowance = merchant_deposit
+        .delegate_allowance
+        .checked_sub(amount)
+        .ok_or(VaultError::DelegateLimitExceeded)?;
+
+    Ok(())
+}
+
+/// Terms of a deposit being opened
+struct NewDeposit {
+    merchant: Pubkey,
+    deposit_token: DepositType,
+    mint: Pubkey,
+    amount: u64,
+    lock_period: LockPeriod,
+    lock_term: Option<LockTerm>,
+    bump: u8,
+}
+
+/// Initialize a freshly created deposit record at the base yield
+/// The merchant's tier carries over from their profile. The caller adds the
+/// deposit to the vault totals.
+fn open_deposit(
+    merchant_deposit: &mut MerchantDeposit,
+    merchant_profile: &mut MerchantProfile,
+    merchant_metrics: &MerchantMetrics,
+    yield_history: &AccountLoader<YieldHistory>,
+    vault: &Account<Vault>,
+    deposit: NewDeposit,
+    current_time: i64,
+) -> Result<()> {
+    merchant_deposit.merchant = deposit.merchant;
+    merchant_deposit.vault = vault.key();
+    merchant_deposit.deposit_token = deposit.deposit_token;
+    merchant_deposit.mint = deposit.mint;
+    merchant_deposit.total_deposited = deposit.amount;
+    merchant_deposit.accrued_rewards = 0;
+    merchant_deposit.is_active = true;
+    merchant_deposit.deposited_at = current_time;
+    merchant_deposit.total_rewards_accrued = 0;
+    merchant_deposit.total_fees_paid_usd = 0;
+    merchant_deposit.streaming_rewards = 0;
+    merchant_deposit.stream_unlocked = 0;
+    merchant_deposit.stream_checkpoint_at = 0;
+    merchant_deposit.stream_ends_at = 0;
+    merchant_deposit.shares = 0;
+    merchant_deposit.bump = deposit.bump;
+
+    // Performance metrics live in the merchant's metrics account; restore the tier
+    merchant_deposit.current_tier = merchant_profile.current_tier;
+    resume_loyalty(merchant_profile, current_time);
+    merchant_deposit.current_yield_bps = 300; // Start with base 3% APY
+
+    // Initialize lock period and profit sharing
+    merchant_deposit.unlock_time = current_time + deposit.lock_period.duration_seconds();
+    merchant_deposit.lock_period = deposit.lock_period;
+    merchant_deposit.platform_profit_earned = 0;
+    merchant_deposit.profit_share_allocated = 0;
+
+    // Optional fixed-term lock with APY boost
+    apply_lock_term(
+        merchant_deposit,
+        merchant_metrics,
+        deposit.lock_term,
+        &vault.lock_boost_schedule,
+        current_time,
+    );
+
+    // The starting yield goes into the history but isn't announced as an update
+    record_yield_change(yield_history, 0, merchant_deposit.current_yield_bps, YieldTrigger::Deposit)?;
+
+    // Initialize maintenance checkpoints
+    merchant_deposit.last_accrual_at = current_time;
+    merchant_deposit.suspended_seconds_checkpoint =
+        vault.suspended_seconds(&merchant_deposit.deposit_token, current_time);
+    merchant_deposit.last_metrics_refresh = current_time;
+    merchant_deposit.version = MERCHANT_DEPOSIT_VERSION;
+
+    // Starts the holding period before withdrawals and reward claims
+    merchant_deposit.deposited_slot = time::current_slot()?;
+    Ok(())
+}
+
+/// Split and merge only move plain, unencumbered deposits
+fn require_movable_position(merchant_deposit: &MerchantDeposit) -> Result<()> {
+    require!(merchant_deposit.is_active, VaultError::DepositNotActive);
+    require!(!merchant_deposit.frozen, VaultError::MerchantFrozen);
+    require!(!merchant_deposit.withdrawal_queued, VaultError::WithdrawalAlreadyQueued);
+    require!(merchant_deposit.position_mint == Pubkey::default(), VaultError::PositionNftExists);
+    require!(merchant_deposit.sponsor == Pubkey::default(), VaultError::SponsoredPosition);
+    require!(merchant_deposit.shares == 0, VaultError::PositionTokenized);
+    Ok(())
+}
+
+/// In a `kyc_required` vault, new principal needs an unexpired attestation for its
+/// wallet from the vault's current verifier
+fn require_kyc_attestation(vault: &Vault, kyc_attestation: &AccountInfo, current_time: i64) -> Result<()> {
+    if !vault.kyc_required {
+        return Ok(());
+    }
+    require!(
+        kyc_attestation.owner == &crate::ID && !kyc_attestation.data_is_empty(),
+        VaultError::KycAttestationRequired
+    );
+    let attestation = KycAttestation::try_deserialize(&mut &kyc_attestation.try_borrow_data()?[..])?;
+    require_keys_eq!(attestation.verifier, vault.kyc_verifier, VaultError::KycAttestationRequired);
+    require!(
+        attestation.expires_at == 0 || current_time < attestation.expires_at,
+        VaultError::KycAttestationExpired
+    );
+    Ok(())
+}
+
+/// Deposits of a mint whose `SupportedMint` is paused or depegged are refused;
+/// an untracked mint is open
+fn require_mint_deposits_open(supported_mint: &AccountInfo) -> Result<()> {
+    match load_vault_account::<SupportedMint>(supported_mint)? {
+        Some(supported_mint) => supported_mint.require_deposits_open(),
+        None => Ok(()),
+    }
+}
+
+/// Count `amount` of a deposit's new principal toward its mint, if the mint is registered
+/// The deposit remembers what was counted, so its withdrawal takes off exactly that.
+fn credit_mint_deposits(
+    supported_mint: &AccountInfo,
+    merchant_deposit: &mut MerchantDeposit,
+    amount: u64,
+) -> Result<()> {
+    if supported_mint.owner != &crate::ID || supported_mint.data_is_empty() {
+        return Ok(());
+    }
+    let mut data = supported_mint.try_borrow_mut_data()?;
+    let mut record = SupportedMint::try_deserialize(&mut &data[..])?;
+    record.credit_deposits(amount)?;
+    merchant_deposit.mint_principal =
+        merchant_deposit.mint_principal.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+    let mut writer: &mut [u8] = &mut data;
+    record.try_serialize(&mut writer)
+}
+
+/// Take a closing deposit's counted principal off its recorded mint
+/// Principal deposited or compounded before the mint was registered was never
+/// counted toward it.
+fn debit_mint_deposits(supported_mint: &AccountInfo, merchant_deposit: &MerchantDeposit) -> Result<()> {
+    if merchant_deposit.mint_principal == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(*supported_mint.owner, crate::ID, VaultError::InvalidVaultAccount);
+    let mut data = supported_mint.try_borrow_mut_data()?;
+    let mut record = SupportedMint::try_deserialize(&mut &data[..])?;
+    require_keys_eq!(record.mint, merchant_deposit.mint, VaultError::InvalidVaultAccount);
+    record.total_token_deposits = record
+        .total_token_deposits
+        .checked_sub(merchant_deposit.mint_principal)
+        .ok_or(VaultError::MathOverflow)?;
+    let mut writer: &mut [u8] = &mut data;
+    record.try_serialize(&mut writer)
+}
+
+/// Tell the vault's notifier program, if one is set, about a deposit or settlement
+/// `remaining_accounts` must start with the notifier program and the notify
+/// authority; the rest are passed on to the notifier, stripped of signer privileges.
+fn notify_downstream<'info>(
+    vault: &Account<Vault>,
+    remaining_accounts: &[AccountInfo<'info>],
+    kind: NotificationKind,
+    merchant: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    order_id: [u8; 32],
+) -> Result<()> {
+    if vault.notifier_program == Pubkey::default() {
+        return Ok(());
+    }
+    let [notifier, notify_authority, accounts @ ..] = remaining_accounts else {
+        return err!(VaultError::NotifierAccountsRequired);
+    };
+    require_keys_eq!(notifier.key(), vault.notifier_program, VaultError::InvalidNotifier);
+    let vault_key = vault.key();
+    let (authority_key, bump) = Pubkey::find_program_address(&[NOTIFY_AUTHORITY_SEED, vault_key.as_ref()], &crate::ID);
+    require_keys_eq!(notify_authority.key(), authority_key, VaultError::NotifierAccountsRequired);
+
+    let notification = Notification {
+        kind,
+        vault: vault_key,
+        merchant,
+        mint,
+        amount,
+        order_id,
+        timestamp: time::now()?,
+    };
+    let ix = notifier::notify(
+        notifier.key,
+        notify_authority.key,
+        &notification,
+        accounts.iter().map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: false,
+            is_writable: account.is_writable,
+        }),
+    )?;
+    let mut account_infos = Vec::with_capacity(accounts.len() + 2);
+    account_infos.push(notify_authority.clone());
+    account_infos.extend_from_slice(accounts);
+    account_infos.push(notifier.clone());
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        &account_infos,
+        &[&[NOTIFY_AUTHORITY_SEED, vault_key.as_ref(), &[bump]]],
+    )?;
+    Ok(())
+}
+
+/// Withdrawals and reward claims wait `min_holding_slots` after the latest
+/// principal added, so capital can't be flashed through the vault in one
+/// transaction to skim rewards or inflate TVL snapshots
+fn require_holding_period(merchant_deposit: &MerchantDeposit, vault: &Vault) -> Result<()> {
+    let held_until = merchant_deposit
+        .deposited_slot
+        .checked_add(vault.min_holding_slots)
+        .ok_or(VaultError::MathOverflow)?;
+    require!(time::current_slot()? >= held_until, VaultError::HoldingPeriodActive);
+    Ok(())
+}
+
+fn validate_recovery_config(merchant: &Pubkey, beneficiary: &Pubkey, inactivity_period: i64) -> Result<()> {
+    require!(
+        *beneficiary != Pubkey::default() && beneficiary != merchant && inactivity_period >= MIN_RECOVERY_INACTIVITY,
+        VaultError::InvalidRecoveryConfig
+    );
+    Ok(())
+}
+
+/// Remove the deposit's withdraw delegate, if any
+fn clear_withdraw_delegate(merchant_deposit: &mut MerchantDeposit) {
+    merchant_deposit.withdraw_delegate = Pubkey::default();
+    merchant_deposit.delegate_expiry = 0;
+    merchant_deposit.delegate_allowance = 0;
+}
+
+/// Where a payout out of the vault goes
+struct PayoutDestination<'a, 'info> {
+    /// Merchant's payout wallet (receives SOL and the unsplit remainder)
+    payout_wallet: &'a AccountInfo<'info>,
+    /// Payout wallet's token account (receives SPL and the unsplit remainder)
+    payout_token_account: Option<&'a AccountInfo<'info>>,
+    /// Active payout splits
+    splits: &'a [PayoutSplit],
+    /// One account per split: its wallet (SOL) or its token account (SPL)
+    split_recipients: &'a [AccountInfo<'info>],
+}
+
+/// Pay `amount` from the vault in the deposit's asset
+/// Each split receives its share (rounded down) and the payout wallet the rest.
+/// SOL is paid as wSOL when the payout wallet's wSOL account is supplied.
+/// A deposit with a position NFT pays its holder in full instead.
+fn pay_from_vault<'info>(
+    vault: &Account<'info, Vault>,
+    merchant_deposit: &MerchantDeposit,
+    merchant_profile: &MerchantProfile,
+    vault_sol_account: &AccountInfo<'info>,
+    spl_accounts: Option<(&Account<'info, TokenAccount>, &Program<'info, Token>)>,
+    destination: PayoutDestination<'_, 'info>,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        amount <= merchant_deposit.attributable_balance().ok_or(VaultError::MathOverflow)?,
+        VaultError::PayoutExceedsBalance
+    );
+
+    let (payout_wallet, splits) = if merchant_deposit.position_mint == Pubkey::default() {
+        (merchant_profile.payout_wallet, destination.splits)
+    } else {
+        (merchant_deposit.position_owner, &[][..])
+    };
+    require_keys_eq!(
+        destination.payout_wallet.key(),
+        payout_wallet,
+        VaultError::InvalidPayoutWallet
+    );
+    require!(
+        destination.split_recipients.len() == splits.len(),
+        VaultError::InvalidPayoutSplits
+    );
+
+    let deposit_token = &merchant_deposit.deposit_token;
+    if let (DepositType::SplToken, Some((vault_token_account, _))) = (deposit_token, spl_accounts) {
+        require_keys_eq!(vault_token_account.mint, merchant_deposit.mint, VaultError::InvalidVaultAccount);
+    }
+    let mut remainder = amount;
+    for (split, recipient) in splits.iter().zip(destination.split_recipients) {
+        match deposit_token {
+            DepositType::Sol => {
+                require_keys_eq!(recipient.key(), split.wallet, VaultError::InvalidPayoutWallet)
+            }
+            DepositType::SplToken => {
+                split_token_account(recipient, split)?;
+            }
+        }
+
+        let share = bps_floor(amount, split.bps as u64).ok_or(VaultError::MathOverflow)?;
+        transfer_from_vault(vault, deposit_token, vault_sol_account, spl_accounts, recipient, share)?;
+        remainder = remainder.checked_sub(share).ok_or(VaultError::MathOverflow)?;
+    }
+
+    match deposit_token {
+        // Wrapped on request: lamports into the wallet's wSOL account (synced by the caller)
+        DepositType::Sol => match destination.payout_token_account {
+            Some(wsol_info) => {
+                let wsol_account = load_token_account(wsol_info)?;
+                require_keys_eq!(wsol_account.mint, native_mint::ID, VaultError::InvalidPayoutWallet);
+                require_keys_eq!(wsol_account.owner, payout_wallet, VaultError::InvalidPayoutWallet);
+                transfer_from_vault(vault, deposit_token, vault_sol_account, spl_accounts, wsol_info, remainder)
+            }
+            None => transfer_from_vault(
+                vault,
+                deposit_token,
+                vault_sol_account,
+                spl_accounts,
+                destination.payout_wallet,
+                remainder,
+            ),
+        },
+        DepositType::SplToken => {
+            let payout_token_info = destination
+                .payout_token_account
+                .ok_or(VaultError::MissingTokenAccount)?;
+            require_keys_eq!(
+                load_token_account(payout_token_info)?.owner,
+                payout_wallet,
+                VaultError::InvalidPayoutWallet
+            );
+            transfer_from_vault(
+                vault,
+                deposit_token,
+                vault_sol_account,
+                spl_accounts,
+                payout_token_info,
+                remainder,
+            )
+        }
+    }
+}
+
+/// Move `amount` of the deposit asset from the vault to `to` (a wallet for SOL,
+/// a token account for SPL)
+fn transfer_from_vault<'info>(
+    vault: &Account<'info, Vault>,
+    deposit_token: &DepositType,
+    vault_sol_account: &AccountInfo<'info>,
+    spl_accounts: Option<(&Account<'info, TokenAccount>, &Program<'info, Token>)>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    match deposit_token {
+        DepositType::Sol => {
+            // Transfer SOL back, never dipping into the vault PDA's rent-exempt reserve
+            let rent_reserve = Rent::get()?.minimum_balance(vault_sol_account.data_len());
+            let available = vault_sol_account.lamports().saturating_sub(rent_reserve);
+            require!(amount <= available, VaultError::InsufficientVaultLiquidity);
+            let received = to.lamports().checked_add(amount).ok_or(VaultError::MathOverflow)?;
+
+            **vault_sol_account.try_borrow_mut_lamports()? -= amount;
+            **to.try_borrow_mut_lamports()? = received;
+        }
+        DepositType::SplToken => {
+            // Transfer tokens back
+            let seeds = &[
+                VAULT_SEED,
+                vault.authority.as_ref(),
+                vault.label_seed(),
+                &[vault.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let (vault_token_account, token_program) =
+                spl_accounts.ok_or(VaultError::MissingTokenAccount)?;
+
+            let cpi_accounts = Transfer {
+                from: vault_token_account.to_account_info(),
+                to: to.clone(),
+                authority: vault.to_account_info(),
+            };
+            let cpi_program = token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load one of the program's accounts passed unchecked (None if it doesn't exist)
+fn load_vault_account<T: AccountDeserialize>(account: &AccountInfo) -> Result<Option<T>> {
+    if account.owner != &crate::ID || account.data_is_empty() {
+        return Ok(None);
+    }
+
+    T::try_deserialize(&mut &account.try_borrow_data()?[..]).map(Some)
+}
+
+/// Payout wallet and split recipients `pay_from_vault` expects for a deposit
+/// (a position NFT's holder, without splits, once one is minted)
+fn payout_accounts(
+    deposit: &MerchantDeposit,
+    profile: &MerchantProfile,
+    splits: &[PayoutSplit],
+    mint: Option<Pubkey>,
+) -> (Pubkey, Vec<AccountMeta>) {
+    if deposit.position_mint != Pubkey::default() {
+        return (deposit.position_owner, Vec::new());
+    }
+
+    let split_recipients = splits
+        .iter()
+        .map(|split| match mint {
+            Some(mint) => AccountMeta::new(get_associated_token_address(&split.wallet, &mint), false),
+            None => AccountMeta::new(split.wallet, false),
+        })
+        .collect();
+    (profile.payout_wallet, split_recipients)
+}
+
+/// One of the program's own instructions, with `remaining_accounts` appended
+fn vault_instruction(
+    accounts: impl ToAccountMetas,
+    args: impl InstructionData,
+    remaining_accounts: impl IntoIterator<Item = AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining_accounts);
+    Instruction {
+        program_id: crate::ID,
+        accounts: metas,
+        data: args.data(),
+    }
+}
+
+/// Load a merchant's payout config PDA (None if the merchant never created one)
+fn load_payout_config(payout_config: &AccountInfo) -> Result<Option<PayoutConfig>> {
+    if payout_config.owner != &crate::ID || payout_config.data_is_empty() {
+        return Ok(None);
+    }
+
+    PayoutConfig::try_deserialize(&mut &payout_config.try_borrow_data()?[..]).map(Some)
+}
+
+/// Load the active payout splits from a merchant's payout config PDA
+/// Returns no splits if the merchant never created one
+fn load_payout_splits(payout_config: &AccountInfo, current_time: i64) -> Result<Vec<PayoutSplit>> {
+    Ok(load_payout_config(payout_config)?
+        .map(|config| config.active_splits(current_time).to_vec())
+        .unwrap_or_default())
+}
+
+/// Check the merchant in on their recovery config, if they created one
+fn record_merchant_activity(recovery_config: &AccountInfo, current_time: i64) -> Result<()> {
+    let Some(mut config) = load_vault_account::<RecoveryConfig>(recovery_config)? else {
+        return Ok(());
+    };
+    config.check_in(current_time);
+
+    let mut data = recovery_config.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    config.try_serialize(&mut writer)
+}
+
+/// Load a merchant's withdrawal whitelist PDA (None if the merchant never created one)
+fn load_withdrawal_whitelist(withdrawal_whitelist: &AccountInfo) -> Result<Option<WithdrawalWhitelist>> {
+    if withdrawal_whitelist.owner != &crate::ID || withdrawal_whitelist.data_is_empty() {
+        return Ok(None);
+    }
+
+    WithdrawalWhitelist::try_deserialize(&mut &withdrawal_whitelist.try_borrow_data()?[..]).map(Some)
+}
+
+/// With a withdrawal whitelist, every split wallet must be an approved destination
+fn require_whitelisted_splits(
+    withdrawal_whitelist: &AccountInfo,
+    splits: &[PayoutSplit],
+    current_time: i64,
+) -> Result<()> {
+    if let Some(whitelist) = load_withdrawal_whitelist(withdrawal_whitelist)? {
+        require!(
+            splits.iter().all(|split| whitelist.is_approved(&split.wallet, current_time)),
+            VaultError::DestinationNotWhitelisted
+        );
+    }
+    Ok(())
+}
+
+/// Decode an SPL token account passed unchecked
+fn load_token_account(token_account: &AccountInfo) -> Result<TokenAccount> {
+    require_keys_eq!(*token_account.owner, token::ID, VaultError::InvalidPayoutWallet);
+    TokenAccount::try_deserialize(&mut &token_account.try_borrow_data()?[..])
+}
+
+/// Decode a split recipient's token account and check it belongs to the split's wallet
+fn split_token_account(recipient: &AccountInfo, split: &PayoutSplit) -> Result<TokenAccount> {
+    let token_account = load_token_account(recipient)?;
+    require_keys_eq!(token_account.owner, split.wallet, VaultError::InvalidPayoutWallet);
+    Ok(token_account)
+}
+
+/// Create `authority`'s associated token account for `mint` unless it exists
+/// Lets first-time deposits, payouts and settlements run in one transaction.
+fn create_associated_token_account<'info>(
+    payer: &AccountInfo<'info>,
+    associated_token: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require_keys_eq!(
+        associated_token.key(),
+        get_associated_token_address(&authority.key(), &mint.key()),
+        VaultError::NotAssociatedTokenAccount
+    );
+    associated_token::create_idempotent(CpiContext::new(
+        associated_token_program.clone(),
+        associated_token::Create {
+            payer: payer.clone(),
+            associated_token: associated_token.clone(),
+            authority: authority.clone(),
+            mint: mint.clone(),
+            system_program: system_program.clone(),
+            token_program: token_program.clone(),
+        },
+    ))
+}
+
+/// Check a payout split list: at most `MAX_PAYOUT_SPLITS` non-zero entries to
+/// distinct wallets, leaving at most 100% in total
+fn validate_payout_splits(splits: &[PayoutSplit]) -> Result<()> {
+    require!(splits.len() <= MAX_PAYOUT_SPLITS, VaultError::InvalidPayoutSplits);
+
+    let mut total_bps: u32 = 0;
+    for (i, split) in splits.iter().enumerate() {
+        require!(
+            split.bps > 0
+                && split.wallet != Pubkey::default()
+                && !splits[..i].iter().any(|other| other.wallet == split.wallet),
+            VaultError::InvalidPayoutSplits
+        );
+        total_bps += split.bps as u32;
+    }
+    require!(total_bps <= 10000, VaultError::InvalidPayoutSplits);
+
+    Ok(())
+}
+
+/// Start a new withdrawal epoch if the current one has ended
+/// The TVL cap is measured against TVL at the epoch start, so it doesn't shrink
+/// as the epoch's withdrawals go out
+fn roll_withdraw_epoch(vault: &mut Vault, current_time: i64) -> Result<()> {
+    let elapsed = elapsed_seconds(vault.withdraw_epoch_start, current_time);
+    if elapsed < vault.withdraw_epoch_duration {
+        return Ok(());
+    }
+
+    let epochs = elapsed / vault.withdraw_epoch_duration;
+    vault.withdraw_epoch_start = vault
+        .withdraw_epoch_start
+        .checked_add(epochs * vault.withdraw_epoch_duration)
+        .ok_or(VaultError::MathOverflow)?;
+    vault.withdraw_epoch_tvl = vault.total_deposits;
+    vault.withdrawn_this_epoch = 0;
+    vault.rewards_paid_this_epoch = 0;
+
+    Ok(())
+}
+
+/// Amount that may still be withdrawn in the current epoch
+fn withdraw_capacity_remaining(vault: &mut Vault, current_time: i64) -> Result<u64> {
+    roll_withdraw_epoch(vault, current_time)?;
+
+    let mut limit = u64::MAX;
+    if vault.withdraw_cap_amount > 0 {
+        limit = limit.min(vault.withdraw_cap_amount);
+    }
+    if vault.withdraw_cap_tvl_bps > 0 {
+        let tvl_cap = bps_floor(vault.withdraw_epoch_tvl, vault.withdraw_cap_tvl_bps as u64)
+            .ok_or(VaultError::MathOverflow)?;
+        limit = limit.min(tvl_cap);
+    }
+
+    Ok(limit.saturating_sub(vault.withdrawn_this_epoch))
+}
+
+/// Count `amount` against the current epoch's withdrawal cap
+fn consume_withdraw_capacity(vault: &mut Vault, amount: u64, current_time: i64) -> Result<()> {
+    let remaining = withdraw_capacity_remaining(vault, current_time)?;
+    require!(amount <= remaining, VaultError::WithdrawalCapReached);
+    vault.withdrawn_this_epoch = vault
+        .withdrawn_this_epoch
+        .checked_add(amount)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Rewards that may still be paid out in the current epoch
+fn reward_payout_remaining(vault: &mut Vault, current_time: i64) -> Result<u64> {
+    roll_withdraw_epoch(vault, current_time)?;
+
+    if vault.reward_payout_cap == 0 {
+        return Ok(u64::MAX);
+    }
+    Ok(vault.reward_payout_cap.saturating_sub(vault.rewards_paid_this_epoch))
+}
+
+/// Count a reward payout against the epoch's reward cap
+fn consume_reward_payout(vault: &mut Vault, rewards: u64, current_time: i64) -> Result<()> {
+    let remaining = reward_payout_remaining(vault, current_time)?;
+    require!(rewards <= remaining, VaultError::RewardPayoutCapReached);
+    vault.rewards_paid_this_epoch = vault
+        .rewards_paid_this_epoch
+        .checked_add(rewards)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Whether `rewards` exceed `reward_anomaly_multiple_bps` of the most the deposit
+/// could have accrued (its lock period's max APY plus boost since it was made)
+/// Guards the reward reserve against math bugs; flagged payouts are logged.
+fn reward_payout_flagged(
+    vault: &Vault,
+    merchant_deposit: &MerchantDeposit,
+    rewards: u64,
+    current_time: i64,
+) -> Result<bool> {
+    if vault.reward_anomaly_multiple_bps == 0 || rewards == 0 {
+        return Ok(false);
+    }
+
+    // Round the deposit's age up to whole days so same-day rewards aren't flagged
+    let days = rewards::whole_days_between(merchant_deposit.deposited_at, current_time) as u64 + 1;
+    let max_apy_bps = merchant_deposit.lock_period.max_apy_bps() as u64 + merchant_deposit.lock_boost_bps as u64;
+    let expected = yield_for_days(merchant_deposit.total_deposited, max_apy_bps, days)
+        .ok_or(VaultError::MathOverflow)?;
+    let limit = mul_div_floor(expected, vault.reward_anomaly_multiple_bps as u64, math::BPS_DENOMINATOR)
+        .unwrap_or(u64::MAX);
+
+    let flagged = rewards > limit;
+    if flagged {
+        msg!(
+            "Reward payout flagged for {}: {} exceeds {} ({} bps of expected {})",
+            merchant_deposit.merchant,
+            rewards,
+            limit,
+            vault.reward_anomaly_multiple_bps,
+            expected
+        );
+    }
+    Ok(flagged)
+}
+
+/// Move the withdrawal queue past its head ticket
+fn advance_withdraw_queue(vault: &mut Vault) -> Result<()> {
+    vault.withdraw_queue_head = vault
+        .withdraw_queue_head
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+/// Remove a withdrawn deposit from the vault totals
+fn remove_deposit_from_vault(
+    vault: &mut Vault,
+    merchant_deposit: &MerchantDeposit,
+    rewards_paid: u64,
+) -> Result<()> {
+    vault.total_deposits = vault.total_deposits.saturating_sub(merchant_deposit.total_deposited);
+    let asset_total = asset_deposits(vault, &merchant_deposit.deposit_token);
+    *asset_total = asset_total.saturating_sub(merchant_deposit.total_deposited);
+    vault.total_merchants = vault.total_merchants.saturating_sub(1);
+    vault.weighted_yield_sum = vault.weighted_yield_sum.saturating_sub(
+        merchant_deposit.total_deposited as u128 * merchant_deposit.reported_yield_bps as u128,
+    );
+    vault.total_rewards_paid = vault
+        .total_rewards_paid
+        .checked_add(rewards_paid)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(())
+}
+
+/// Fold a merchant's current yield into the vault's weighted yield sum
+/// Called by the metrics crank, so vault averages lag per-order yield changes
+/// by at most one refresh
+fn sync_vault_yield(vault: &mut Vault, merchant_deposit: &mut MerchantDeposit) -> Result<()> {
+    let principal = merchant_deposit.total_deposited as u128;
+    vault.weighted_yield_sum = vault
+        .weighted_yield_sum
+        .saturating_sub(principal * merchant_deposit.reported_yield_bps as u128)
+        .checked_add(principal * merchant_deposit.current_yield_bps as u128)
+        .ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.reported_yield_bps = merchant_deposit.current_yield_bps;
+
+    Ok(())
+}
+
+/// Recompute the insurance fund's coverage ratio against vault TVL
+fn update_insurance_coverage(insurance_fund: &mut InsuranceFund, total_deposits: u64) {
+    insurance_fund.coverage_ratio_bps = if total_deposits > 0 {
+        mul_div_floor(insurance_fund.balance, math::BPS_DENOMINATOR, total_deposits).unwrap_or(u64::MAX)
+    } else {
+        0
+    };
+}
+
+// ============================================================================
+// Vault Account Setup Functions
+// ============================================================================
+
+fn init_vault_stats(vault_stats: &AccountLoader<VaultStats>, vault: &Account<Vault>, bump: u8) -> Result<()> {
+    let mut stats = vault_stats.load_init()?;
+    stats.vault = vault.key();
+    stats.last_snapshot_at = 0;
+    stats.last_rewards_paid = vault.total_rewards_paid;
+    stats.head = 0;
+    stats.count = 0;
+    stats.bump = bump;
+    Ok(())
+}
+
+/// Top up `account` from `payer` so it stays rent-exempt at `new_len` bytes
+fn fund_rent_for_len<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_needed = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if rent_needed > 0 {
+        let ix = anchor_lang::solana_program::system_instruction::transfer(payer.key, account.key, rent_needed);
+        anchor_lang::solana_program::program::invoke(&ix, &[payer.clone(), account.clone(), system_program.clone()])?;
+    }
+    Ok(())
+}
+
+/// Create a vault-owned PDA of `space` bytes, paid by `payer`
+/// Tops up, allocates and assigns instead of `create_account`, so lamports sent
+/// to the address beforehand can't block its creation.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    fund_rent_for_len(payer, account, system_program, space)?;
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign { account_to_assign: account.clone() },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+fn init_insurance_fund(insurance_fund: &mut InsuranceFund, vault: Pubkey, bump: u8) {
+    insurance_fund.vault = vault;
+    insurance_fund.balance = 0;
+    insurance_fund.total_contributed = 0;
+    insurance_fund.total_claims_paid = 0;
+    insurance_fund.claims_count = 0;
+    insurance_fund.coverage_ratio_bps = 0;
+    insurance_fund.bump = bump;
+}
+
+// ============================================================================
+// Audit Log Functions
+// ============================================================================
+
+fn init_audit_log(audit_log: &AccountLoader<AuditLog>, vault: Pubkey, bump: u8) -> Result<()> {
+    let mut log = audit_log.load_init()?;
+    log.vault = vault;
+    log.total_entries = 0;
+    log.head = 0;
+    log.count = 0;
+    log.bump = bump;
+    Ok(())
+}
+
+/// Append an authority action to the vault's audit log
+/// Once the ring buffer is full the oldest entry is overwritten; `seq` keeps
+/// counting, so gaps in what a reader has seen are detectable
+fn record_admin_action(
+    audit_log: &AccountLoader<AuditLog>,
+    action: AuditAction,
+    actor: Pubkey,
+    target: Pubkey,
+    amount: u64,
+    detail: u16,
+) -> Result<()> {
+    let mut log = audit_log.load_mut()?;
+    let head = log.head as usize;
+    log.entries[head] = AuditEntry {
+        seq: log.total_entries,
+        timestamp: time::now()?,
+        actor,
+        target,
+        amount,
+        action: action as u16,
+        detail,
+        _padding: [0; 4],
+    };
+    log.head = ((head + 1) % AUDIT_LOG_CAPACITY) as u32;
+    log.count = (log.count + 1).min(AUDIT_LOG_CAPACITY as u32);
+    log.total_entries = log.total_entries.checked_add(1).ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+fn init_yield_history(
+    yield_history: &AccountLoader<YieldHistory>,
+    vault: Pubkey,
+    merchant: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    let mut history = yield_history.load_init()?;
+    history.merchant = merchant;
+    history.vault = vault;
+    history.total_entries = 0;
+    history.head = 0;
+    history.count = 0;
+    history.bump = bump;
+    Ok(())
+}
+
+/// Append a yield recalculation to the merchant's yield history
+/// Recalculations that leave the yield unchanged are not recorded, so the
+/// buffer holds the last changes rather than the last order batches. Returns
+/// the `YieldUpdated` event for the caller to emit when the yield changed.
+fn record_yield_change(
+    yield_history: &AccountLoader<YieldHistory>,
+    previous_bps: u16,
+    yield_bps: u16,
+    trigger: YieldTrigger,
+) -> Result<Option<YieldUpdated>> {
+    if previous_bps == yield_bps {
+        return Ok(None);
+    }
+    let current_time = time::now()?;
+    let mut history = yield_history.load_mut()?;
+    let head = history.head as usize;
+    history.entries[head] = YieldChange {
+        timestamp: current_time,
+        previous_bps,
+        yield_bps,
+        trigger: trigger as u8,
+        _padding: [0; 3],
+    };
+    history.head = ((head + 1) % YIELD_HISTORY_CAPACITY) as u32;
+    history.count = (history.count + 1).min(YIELD_HISTORY_CAPACITY as u32);
+    history.total_entries = history.total_entries.checked_add(1).ok_or(VaultError::MathOverflow)?;
+
+    Ok(Some(YieldUpdated {
+        merchant: history.merchant,
+        previous_yield_bps: previous_bps,
+        yield_bps,
+        trigger: trigger as u8,
+        updated_at: current_time,
+    }))
+}
+
+// ============================================================================
+// Config Governance Functions
+// ============================================================================
+
+/// Config setters apply immediately only for the authority, and only while
+/// no timelock is set
+fn authorize_direct_config(vault: &Vault, authority: &Signer) -> Result<()> {
+    require!(authority.key() == vault.authority, VaultError::Unauthorized);
+    require!(vault.config_timelock == 0, VaultError::ConfigTimelocked);
+    Ok(())
+}
+
+/// Check a config change's values against the same bounds as the setters
+fn validate_config_change(change: &ConfigChange) -> Result<()> {
+    match change {
+        ConfigChange::VaultConfig { cashback_bps, referral_fee_bps, .. } => {
+            require!(
+                cashback_bps.is_none_or(|bps| bps <= MAX_CASHBACK_BPS),
+                VaultError::CashbackRateTooHigh
+            );
+            require!(referral_fee_bps.is_none_or(|bps| bps <= 10000), VaultError::InvalidRate);
+        }
+        ConfigChange::WithdrawalLimits { epoch_duration, cap_tvl_bps, .. } => {
+            require!(*epoch_duration > 0, VaultError::InvalidWithdrawalLimits);
+            require!(*cap_tvl_bps <= 10000, VaultError::InvalidRate);
+        }
+        ConfigChange::LockTerms { lock_boost_schedule, early_exit_penalty_bps } => {
+            require!(
+                lock_boost_schedule.iter().all(|boost| *boost <= MAX_LOCK_BOOST_BPS),
+                VaultError::InvalidRate
+            );
+            require!(*early_exit_penalty_bps <= 10000, VaultError::InvalidRate);
+        }
+        ConfigChange::InsuranceFee { insurance_fee_bps } => {
+            require!(*insurance_fee_bps <= MAX_INSURANCE_FEE_BPS, VaultError::InvalidRate);
+        }
+        ConfigChange::PerformanceFee { performance_fee_bps, .. } => {
+            require!(*performance_fee_bps <= MAX_PERFORMANCE_FEE_BPS, VaultError::InvalidRate);
+        }
+        ConfigChange::ConfigTimelock { delay } => {
+            require!((0..=MAX_CONFIG_TIMELOCK).contains(delay), VaultError::InvalidConfigTimelock);
+        }
+        ConfigChange::RepeatPurchaseDampening { window, credit_bps } => {
+            require!(*window >= 0, VaultError::InvalidRepeatPurchaseConfig);
+            require!(credit_bps.iter().all(|bps| *bps <= 10000), VaultError::InvalidRate);
+        }
+        ConfigChange::PlatformFees { platform_fee_bps_by_tier } => {
+            require!(
+                platform_fee_bps_by_tier.iter().all(|bps| *bps <= MAX_PLATFORM_FEE_BPS),
+                VaultError::InvalidRate
+            );
+            require!(
+                platform_fee_bps_by_tier.windows(2).all(|pair| pair[1] <= pair[0]),
+                VaultError::InvalidRate
+            );
+        }
+        ConfigChange::SettlementGuard { guard_before, guard_after, blocked_token_ixs } => {
+            require!(
+                !(*guard_before || *guard_after) || *blocked_token_ixs != 0,
+                VaultError::InvalidSettlementGuard
+            );
+        }
+        ConfigChange::RewardCircuitBreaker { anomaly_multiple_bps, .. } => {
+            // Flagging payouts below their expected rewards would flag every payout
+            require!(
+                *anomaly_multiple_bps == 0 || *anomaly_multiple_bps as u64 >= math::BPS_DENOMINATOR,
+                VaultError::InvalidCircuitBreaker
+            );
+        }
+        ConfigChange::HoldingPeriod { min_holding_slots } => {
+            require!(
+                (1..=MAX_HOLDING_SLOTS).contains(min_holding_slots),
+                VaultError::InvalidHoldingPeriod
+            );
+        }
+        ConfigChange::LiquidityBuffer { liquidity_buffer_bps } => {
+            require!(*liquidity_buffer_bps as u64 <= math::BPS_DENOMINATOR, VaultError::InvalidRate);
+        }
+        ConfigChange::KycPolicy { required, verifier } => {
+            require!(!*required || *verifier != Pubkey::default(), VaultError::InvalidKycVerifier);
+        }
+        ConfigChange::Notifier { program } => {
+            // The runtime refuses reentrancy anyway; fail at config time instead
+            require_keys_neq!(*program, crate::ID, VaultError::InvalidNotifier);
+        }
+        ConfigChange::RewardStreamDuration { duration } => {
+            require!(
+                *duration == 0 || (MIN_REWARD_STREAM_DURATION..=MAX_REWARD_STREAM_DURATION).contains(duration),
+                VaultError::InvalidRewardStreamDuration
+            );
+        }
+        ConfigChange::MinOrderAmount { min_order_usd } => {
+            require!(
+                (LOWEST_MIN_ORDER_USD..=HIGHEST_MIN_ORDER_USD).contains(min_order_usd),
+                VaultError::InvalidMinOrderAmount
+            );
+        }
+        ConfigChange::TierDowngradeGrace { grace_period } => {
+            require!(
+                (0..=MAX_TIER_DOWNGRADE_GRACE).contains(grace_period),
+                VaultError::InvalidTierDowngradeGrace
+            );
+        }
+        ConfigChange::AgentBond { .. }
+        | ConfigChange::Guardian { .. }
+        | ConfigChange::OrderRateLimits { .. }
+        | ConfigChange::StakeStrategy { .. }
+        | ConfigChange::AccrualSchedule { .. }
+        | ConfigChange::ShareTransferRestriction { .. } => {}
+    }
+    Ok(())
+}
+
+/// Validate and apply a config change, whether direct or from an executed proposal
+fn apply_config_change(vault: &mut Vault, change: &ConfigChange) -> Result<()> {
+    validate_config_change(change)?;
+
+    match change.clone() {
+        ConfigChange::VaultConfig {
+            min_deposit_sol,
+            min_deposit_token,
+            staking_enabled,
+            cashback_bps,
+            referral_fee_bps,
+        } => {
+            if let Some(min_sol) = min_deposit_sol {
+                vault.min_deposit_sol = min_sol;
+            }
+            if let Some(min_token) = min_deposit_token {
+                vault.min_deposit_token = min_token;
+            }
+            if let Some(enabled) = staking_enabled {
+                vault.staking_enabled = enabled;
+            }
+            if let Some(bps) = cashback_bps {
+                vault.cashback_bps = bps;
+            }
+            if let Some(bps) = referral_fee_bps {
+                vault.referral_fee_bps = bps;
+            }
+
+            msg!("Vault config updated");
+        }
+        ConfigChange::WithdrawalLimits { epoch_duration, cap_amount, cap_tvl_bps } => {
+            vault.withdraw_epoch_duration = epoch_duration;
+            vault.withdraw_cap_amount = cap_amount;
+            vault.withdraw_cap_tvl_bps = cap_tvl_bps;
+
+            msg!("Withdrawal limits set: {} per {}s epoch, {} bps of TVL",
+                cap_amount,
+                epoch_duration,
+                cap_tvl_bps
+            );
+        }
+        ConfigChange::LockTerms { lock_boost_schedule, early_exit_penalty_bps } => {
+            vault.lock_boost_schedule = lock_boost_schedule;
+            vault.early_exit_penalty_bps = early_exit_penalty_bps;
+
+            msg!("Lock terms updated");
+        }
+        ConfigChange::AgentBond { agent_bond_lamports } => {
+            vault.agent_bond_lamports = agent_bond_lamports;
+
+            msg!("Agent bond set to {} lamports", agent_bond_lamports);
+        }
+        ConfigChange::InsuranceFee { insurance_fee_bps } => {
+            vault.insurance_fee_bps = insurance_fee_bps;
+
+            msg!("Insurance fee set to {} bps", insurance_fee_bps);
+        }
+        ConfigChange::PerformanceFee { performance_fee_bps, treasury } => {
+            // Merchants get time to withdraw before the fee moves again
+            if performance_fee_bps != vault.performance_fee_bps {
+                require!(
+                    performance_fee_bps.abs_diff(vault.performance_fee_bps) <= MAX_PERFORMANCE_FEE_STEP_BPS,
+                    VaultError::PerformanceFeeStepTooLarge
+                );
+                let current_time = time::now()?;
+                require!(
+                    elapsed_seconds(vault.performance_fee_updated_at, current_time)
+                        >= PERFORMANCE_FEE_UPDATE_INTERVAL,
+                    VaultError::PerformanceFeeUpdateTooSoon
+                );
+                vault.performance_fee_updated_at = current_time;
+            }
+            vault.performance_fee_bps = performance_fee_bps;
+            vault.treasury = treasury;
+
+            msg!("Performance fee set to {} bps, paid to {}", performance_fee_bps, treasury);
+        }
+        ConfigChange::Guardian { new_guardian } => {
+            vault.guardian = new_guardian;
+
+            msg!("Guardian set to {}", new_guardian);
+        }
+        ConfigChange::ConfigTimelock { delay } => {
+            vault.config_timelock = delay;
+
+            msg!("Config timelock set to {}s", delay);
+        }
+        ConfigChange::OrderRateLimits { max_orders_per_hour, max_daily_order_volume } => {
+            vault.max_orders_per_hour = max_orders_per_hour;
+            vault.max_daily_order_volume = max_daily_order_volume;
+
+            msg!("Order rate limits set: {} orders/hour, {} volume/day",
+                max_orders_per_hour,
+                max_daily_order_volume
+            );
+        }
+        ConfigChange::RepeatPurchaseDampening { window, credit_bps } => {
+            vault.repeat_purchase_window = window;
+            vault.repeat_purchase_credit_bps = credit_bps;
+
+            msg!("Repeat purchase dampening set: {:?} bps per {}s window", credit_bps, window);
+        }
+        ConfigChange::TierDowngradeGrace { grace_period } => {
+            vault.tier_downgrade_grace = grace_period;
+
+            msg!("Tier downgrade grace set to {}s", grace_period);
+        }
+        ConfigChange::PlatformFees { platform_fee_bps_by_tier } => {
+            vault.platform_fee_bps_by_tier = platform_fee_bps_by_tier;
+
+            msg!("Platform fees set: {:?} bps by tier", platform_fee_bps_by_tier);
+        }
+        ConfigChange::SettlementGuard { guard_before, guard_after, blocked_token_ixs } => {
+            vault.settlement_guard_before = guard_before;
+            vault.settlement_guard_after = guard_after;
+            vault.settlement_blocked_token_ixs = blocked_token_ixs;
+
+            msg!(
+                "Settlement guard set: before {}, after {}, blocked token instructions {:#x}",
+                guard_before,
+                guard_after,
+                blocked_token_ixs
+            );
+        }
+        ConfigChange::HoldingPeriod { min_holding_slots } => {
+            vault.min_holding_slots = min_holding_slots;
+
+            msg!("Deposit holding period set to {} slots", min_holding_slots);
+        }
+        ConfigChange::MinOrderAmount { min_order_usd } => {
+            vault.min_order_usd = min_order_usd;
+
+            msg!("Minimum order amount set to {}", min_order_usd);
+        }
+        ConfigChange::StakeStrategy { strategy } => {
+            vault.stake_strategy = strategy.clone();
+
+            msg!("Stake strategy set to {:?}", strategy);
+        }
+        ConfigChange::LiquidityBuffer { liquidity_buffer_bps } => {
+            require!(
+                liquidity_buffer_bps as u64 + vault.lending_allocation_bps as u64 <= math::BPS_DENOMINATOR,
+                VaultError::InvalidAllocation
+            );
+            vault.liquidity_buffer_bps = liquidity_buffer_bps;
+
+            msg!("Liquidity buffer set to {} bps", liquidity_buffer_bps);
+        }
+        ConfigChange::AccrualSchedule { schedule } => {
+            vault.accrual_schedule = schedule.clone();
+
+            msg!("Accrual schedule set to {:?}", schedule);
+        }
+        ConfigChange::RewardStreamDuration { duration } => {
+            vault.reward_stream_duration = duration;
+
+            msg!("Reward stream duration set to {}s", duration);
+        }
+        ConfigChange::ShareTransferRestriction { restricted } => {
+            vault.share_transfers_restricted = restricted;
+
+            msg!("Share transfers restricted to approved holders: {}", restricted);
+        }
+        ConfigChange::KycPolicy { required, verifier } => {
+            vault.kyc_required = required;
+            vault.kyc_verifier = verifier;
+
+            msg!("KYC required for deposits: {} | verifier {}", required, verifier);
+        }
+        ConfigChange::Notifier { program } => {
+            vault.notifier_program = program;
+
+            msg!("Notifier program set to {}", program);
+        }
+        ConfigChange::RewardCircuitBreaker { payout_cap, anomaly_multiple_bps } => {
+            vault.reward_payout_cap = payout_cap;
+            vault.reward_anomaly_multiple_bps = anomaly_multiple_bps;
+
+            msg!(
+                "Reward circuit breaker set: {} per epoch, anomaly at {} bps of expected",
+                payout_cap,
+                anomaly_multiple_bps
+            );
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Distribution Functions
+// ============================================================================
+
+/// Merkle leaf for a bonus distribution entry:
+/// keccak(0x00 || merchant (32) || amount (u64 LE))
+fn bonus_leaf(merchant: &Pubkey, amount: u64) -> [u8; 32] {
+    keccak::hashv(&[&[0u8], merchant.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Verify a merkle proof against `root`
+/// Internal nodes are keccak(0x01 || min(a, b) || max(a, b)), so proofs don't
+/// need to carry left/right flags
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut node = leaf;
+    for sibling in proof {
+        node = if node <= *sibling {
+            keccak::hashv(&[&[1u8], &node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[&[1u8], sibling, &node]).to_bytes()
+        };
+    }
+    node == root
+}
+
+/// Advance an emission schedule to the epoch containing `current_time`
+/// Each elapsed epoch decays the budget; unused budget does not carry over.
+fn roll_emission_epoch(emission_schedule: &mut EmissionSchedule, current_time: i64) -> Result<()> {
+    let elapsed = elapsed_seconds(emission_schedule.current_epoch_start, current_time);
+    let epochs = elapsed / emission_schedule.epoch_duration;
+    if epochs <= 0 {
+        return Ok(());
+    }
+
+    for _ in 0..epochs.min(MAX_EMISSION_CATCHUP_EPOCHS) {
+        let decay = bps_floor(emission_schedule.epoch_emission, emission_schedule.decay_bps as u64)
+            .ok_or(VaultError::MathOverflow)?;
+        emission_schedule.epoch_emission -= decay;
+    }
+    emission_schedule.current_epoch_start = emission_schedule
+        .current_epoch_start
+        .checked_add(epochs * emission_schedule.epoch_duration)
+        .ok_or(VaultError::MathOverflow)?;
+    emission_schedule.epoch_emitted = 0;
+
+    Ok(())
+}
+
+// ============================================================================
+// Lookup Table Functions
+// ============================================================================
+
+/// Accounts `create_lookup_table` seeds the vault's lookup table with: the vault
+/// and its singleton PDAs, the treasury, the SOL price feed and the programs and
+/// sysvars its instructions reference
+pub fn vault_lookup_table_addresses(vault_key: &Pubkey, vault: &Vault) -> Vec<Pubkey> {
+    vec![
+        *vault_key,
+        vault.treasury,
+        pda::audit_log(vault_key),
+        pda::vault_stats(vault_key),
+        pda::insurance_fund(vault_key),
+        pda::price_feed(vault_key, &native_mint::ID),
+        pda::event_authority(),
+        crate::ID,
+        anchor_lang::system_program::ID,
+        token::ID,
+        associated_token::ID,
+        anchor_lang::solana_program::sysvar::instructions::ID,
+        native_mint::ID,
+    ]
+}
+
+// ============================================================================
+// Attestation Functions
+// ============================================================================
+
+/// Message an agent signs to attest an order:
+/// merchant (32) || order_id (32) || amount (u64 LE) || timestamp (i64 LE) ||
+/// metadata_hash (32)
+fn attestation_message(
+    merchant: &Pubkey,
+    order_id: &[u8; 32],
+    order_amount_usd: u64,
+    timestamp: i64,
+    metadata_hash: &[u8; 32],
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(ATTESTATION_MESSAGE_LEN);
+    message.extend_from_slice(merchant.as_ref());
+    message.extend_from_slice(order_id);
+    message.extend_from_slice(&order_amount_usd.to_le_bytes());
+    message.extend_from_slice(&timestamp.to_le_bytes());
+    message.extend_from_slice(metadata_hash);
+    message
+}
+
+/// Check that `ix` is an Ed25519 program instruction verifying exactly one
+/// signature by `expected_signer` over `expected_message`
+///
+/// All offsets must point into the Ed25519 instruction itself, otherwise the
+/// verified data could be swapped out for bytes from another instruction.
+fn verify_ed25519_attestation(
+    ix: &Instruction,
+    expected_signer: &Pubkey,
+    expected_message: &[u8],
+) -> Result<()> {
+    // Layout: [num_signatures: u8, padding: u8, offsets: 7 x u16 LE, ...data]
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+
+    require_keys_eq!(ix.program_id, ed25519_program::ID, VaultError::InvalidAttestation);
+
+    let data = &ix.data;
+    require!(data.len() >= HEADER_LEN + OFFSETS_LEN, VaultError::InvalidAttestation);
+    require!(data[0] == 1, VaultError::InvalidAttestation);
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = HEADER_LEN;
+    let signature_ix = read_u16(offsets + 2);
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let public_key_ix = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix = read_u16(offsets + 12);
+
+    require!(
+        signature_ix == THIS_INSTRUCTION
+            && public_key_ix == THIS_INSTRUCTION
+            && message_ix == THIS_INSTRUCTION,
+        VaultError::InvalidAttestation
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(VaultError::InvalidAttestation)?;
+    require!(public_key == expected_signer.as_ref(), VaultError::InvalidAttestation);
+
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(VaultError::InvalidAttestation)?;
+    require!(message == expected_message, VaultError::InvalidAttestation);
+
+    Ok(())
+}
+
+/// Instruction introspection guard for `settle_order`
+///
+/// While on, the settlement must be a top-level instruction (a CPI would hide
+/// its neighbours) and the transaction may not carry a blocked SPL Token
+/// instruction on the guarded side(s), so a same-transaction flash loan can't
+/// pump volume metrics or skew the fee split.
+fn check_settlement_guard(vault: &Vault, instructions_sysvar: Option<&AccountInfo>) -> Result<()> {
+    if !vault.settlement_guard_before && !vault.settlement_guard_after {
+        return Ok(());
+    }
+
+    let ix_sysvar = instructions_sysvar.ok_or(VaultError::MissingInstructionsSysvar)?;
+    let current_index = load_current_index_checked(ix_sysvar)? as usize;
+    let current_ix = load_instruction_at_checked(current_index, ix_sysvar)?;
+    require_keys_eq!(current_ix.program_id, crate::ID, VaultError::SettlementNotTopLevel);
+
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, ix_sysvar) {
+        let guarded = (index < current_index && vault.settlement_guard_before)
+            || (index > current_index && vault.settlement_guard_after);
+        require!(
+            !guarded || !is_blocked_token_instruction(&ix, vault.settlement_blocked_token_ixs),
+            VaultError::SandwichedSettlement
+        );
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Whether `ix` is an SPL Token (or Token-2022) instruction whose tag is in `blocked`
+fn is_blocked_token_instruction(ix: &Instruction, blocked: u32) -> bool {
+    if ix.program_id != token::ID && ix.program_id != anchor_spl::token_2022::ID {
+        return false;
+    }
+    match ix.data.first() {
+        Some(&tag) if tag < 32 => blocked & (1 << tag) != 0,
+        _ => false,
+    }
+}
+
+// ============================================================================
+// Referral Functions
+// ============================================================================
+
+/// Link a newly deposited merchant to their referrer
+/// Both accounts must be supplied together, or neither
+fn record_referral(
+    referral_code: Option<&mut Account<ReferralCode>>,
+    referral: Option<&mut Account<Referral>>,
+    referral_bump: Option<u8>,
+    merchant: Pubkey,
+    vault: Pubkey,
+    current_time: i64,
+) -> Result<()> {
+    let (referral_code, referral) = match (referral_code, referral) {
+        (Some(referral_code), Some(referral)) => (referral_code, referral),
+        (None, None) => return Ok(()),
+        _ => return err!(VaultError::InvalidReferral),
+    };
+
+    // No self-referrals
+    require!(referral_code.referrer != merchant, VaultError::InvalidReferral);
+
+    referral.merchant = merchant;
+    referral.referrer = referral_code.referrer;
+    referral.vault = vault;
+    referral.created_at = current_time;
+    referral.total_fees_shared = 0;
+    referral.bump = referral_bump.ok_or(VaultError::InvalidReferral)?;
+
+    referral_code.referred_merchants = referral_code
+        .referred_merchants
+        .checked_add(1)
+        .ok_or(VaultError::MathOverflow)?;
+
+    msg!("Merchant {} referred by {}", merchant, referral_code.referrer);
+    Ok(())
+}
+
+// ============================================================================
+// Yield Calculation Functions
+// ============================================================================
+
+/// Start or resume the merchant's loyalty clock on deposit
+/// A redeposit within `LOYALTY_GRACE_PERIOD` of the last withdrawal keeps the
+/// original start; a first deposit or a longer gap starts it over.
+fn resume_loyalty(merchant_profile: &mut MerchantProfile, current_time: i64) {
+    let lapsed = merchant_profile.last_withdrawn_at > 0
+        && elapsed_seconds(merchant_profile.last_withdrawn_at, current_time) > LOYALTY_GRACE_PERIOD;
+    if merchant_profile.loyalty_started_at == 0 || lapsed {
+        merchant_profile.loyalty_started_at = current_time;
+    }
+    merchant_profile.last_withdrawn_at = 0;
+}
+
+/// Keep the older of the profile's loyalty start and `loyalty_started_at`
+/// Used when principal moves between merchants (split and merge).
+fn adopt_loyalty(merchant_profile: &mut MerchantProfile, loyalty_started_at: i64) {
+    if merchant_profile.loyalty_started_at == 0
+        || (loyalty_started_at > 0 && loyalty_started_at < merchant_profile.loyalty_started_at)
+    {
+        merchant_profile.loyalty_started_at = loyalty_started_at;
+    }
+    merchant_profile.last_withdrawn_at = 0;
+}
+
+/// When the merchant's time-deposited for tiers counts from
+/// Deposits made before loyalty was tracked on the profile fall back to their own start.
+fn loyalty_start(merchant_profile: &MerchantProfile, merchant_deposit: &MerchantDeposit) -> i64 {
+    if merchant_profile.loyalty_started_at > 0 {
+        merchant_profile.loyalty_started_at
+    } else {
+        merchant_deposit.deposited_at
+    }
+}
+
+/// Calculate dynamic yield based on lock period, volume, and profit sharing
+/// Returns yield in basis points (BPS); see `rewards::dynamic_yield_bps`
+fn calculate_dynamic_yield(
+    merchant_deposit: &MerchantDeposit,
+    metrics: &MerchantMetrics,
+    total_deposited_value: u64,
+) -> u16 {
+    rewards::dynamic_yield_bps(
+        merchant_deposit.lock_period.max_apy_bps(),
+        merchant_deposit.lock_boost_bps,
+        merchant_deposit.profit_share_allocated,
+        total_deposited_value,
+        metrics.current_month_volume,
+    )
+}
+
+/// Record an optional fixed-term lock on a new position
+/// The boost is looked up from the vault schedule once and stored on the position
+fn apply_lock_term(
+    merchant_deposit: &mut MerchantDeposit,
+    metrics: &MerchantMetrics,
+    lock_term: Option<LockTerm>,
+    lock_boost_schedule: &[u16; 4],
+    current_time: i64,
+) {
+    match &lock_term {
+        Some(term) => {
+            merchant_deposit.term_maturity = current_time + term.duration_seconds();
+            merchant_deposit.lock_boost_bps = lock_boost_schedule[term.schedule_index()];
+        }
+        None => {
+            merchant_deposit.term_maturity = 0;
+            merchant_deposit.lock_boost_bps = 0;
+        }
+    }
+    merchant_deposit.lock_term = lock_term;
+    merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+        merchant_deposit,
+        metrics,
+        merchant_deposit.total_deposited,
+    );
+}
+
+/// Bring a merchant's derived metrics up to `current_time`
+/// Accrues rewards at the yield that applied so far, decays rolling volume,
+/// then re-evaluates yield and tier (tier time counts from `loyalty_started_at`).
+/// Downgrades wait out the vault's `tier_downgrade_grace`. Returns the yield harvested.
+fn refresh_metrics(
+    merchant_deposit: &mut MerchantDeposit,
+    metrics: &mut MerchantMetrics,
+    vault: &Vault,
+    loyalty_started_at: i64,
+    current_time: i64,
+) -> Result<YieldHarvest> {
+    let harvest = accrue_rewards(merchant_deposit, vault, current_time)?;
+    apply_volume_decay(metrics, current_time);
+
+    merchant_deposit.current_yield_bps = calculate_dynamic_yield(
+        merchant_deposit,
+        metrics,
+        merchant_deposit.total_deposited,
+    );
+    let tier_clock = activity_clock(metrics, current_time);
+    let computed_tier = calculate_merchant_tier(metrics.current_month_volume, loyalty_started_at, tier_clock);
+    (merchant_deposit.current_tier, merchant_deposit.tier_grace_started_at) = hold_tier(
+        merchant_deposit.current_tier,
+        computed_tier,
+        merchant_deposit.tier_grace_started_at,
+        vault.tier_downgrade_grace,
+        tier_clock,
+    );
+
+    Ok(harvest)
+}
+
+/// Harvest yield since `last_accrual_at`, accruing the merchant's net of the
+/// performance fee into `accrued_rewards`, after settling the reward index
+/// Under the daily schedule only whole days are accrued; the checkpoint advances
+/// by whole days so partial days are never lost to frequent calls. Under the
+/// Solana epoch schedule it advances to the start of the current epoch. Returns
+/// the harvest for the caller to book with `book_harvest`.
+///
+/// Time accrual spends suspended (emergency mode, or for SPL deposits a
+/// depegged stablecoin) is skipped, not deferred until the suspension lifts:
+/// the vault's suspended seconds since the deposit's checkpoint push the start
+/// of the accrual window back by that much, whether or not it is still running.
+fn accrue_rewards(
+    merchant_deposit: &mut MerchantDeposit,
+    vault: &Vault,
+    current_time: i64,
+) -> Result<YieldHarvest> {
+    settle_reward_index(merchant_deposit, vault)?;
+
+    if current_time < merchant_deposit.last_accrual_at {
+        msg!("Clock {} is behind the last accrual {}; nothing accrues", current_time, merchant_deposit.last_accrual_at);
+    }
+    let suspended_seconds = vault.suspended_seconds(&merchant_deposit.deposit_token, current_time);
+    let skipped = elapsed_seconds(merchant_deposit.suspended_seconds_checkpoint, suspended_seconds);
+    merchant_deposit.suspended_seconds_checkpoint = suspended_seconds;
+
+    // Yield at the merchant's current dynamic rate (updated by record_order), less the fee
+    let last_accrual_at = merchant_deposit
+        .last_accrual_at
+        .saturating_add(skipped)
+        .min(current_time)
+        .max(merchant_deposit.last_accrual_at);
+    let (accrued_until, gross) = match vault.accrual_schedule {
+        AccrualSchedule::Daily => {
+            let days_elapsed = rewards::whole_days_between(last_accrual_at, current_time);
+            let accrued_until = last_accrual_at
+                .checked_add(days_elapsed * 86400)
+                .ok_or(VaultError::MathOverflow)?;
+            let gross = yield_for_days(
+                merchant_deposit.total_deposited,
+                merchant_deposit.current_yield_bps as u64,
+                days_elapsed as u64,
+            );
+            (accrued_until, gross)
+        }
+        AccrualSchedule::SolanaEpoch => {
+            let (_, epoch_started_at) = time::current_epoch()?;
+            let accrued_until = current_time.min(epoch_started_at).max(last_accrual_at);
+            let gross = yield_for_seconds(
+                merchant_deposit.total_deposited,
+                merchant_deposit.current_yield_bps as u64,
+                elapsed_seconds(last_accrual_at, accrued_until) as u64,
+            );
+            (accrued_until, gross)
+        }
+    };
+    if accrued_until == last_accrual_at {
+        merchant_deposit.last_accrual_at = last_accrual_at;
+        return Ok(YieldHarvest::default());
+    }
+    let mut gross = gross.ok_or(VaultError::MathOverflow)?;
+    if vault.reward_epoch_end > 0 {
+        gross = epoch_budgeted_yield(vault, gross, last_accrual_at, accrued_until)?;
+    }
+    let harvest = YieldHarvest::split(gross, vault.performance_fee_bps).ok_or(VaultError::MathOverflow)?;
+
+    credit_rewards(merchant_deposit, harvest.net)?;
+    merchant_deposit.last_accrual_at = accrued_until;
+
+    Ok(harvest)
+}
+
+/// Credit net rewards to the deposit, setting aside its sponsor's share
+/// The sponsor's share rounds down, so dust stays with the merchant.
+fn credit_rewards(merchant_deposit: &mut MerchantDeposit, amount: u64) -> Result<()> {
+    let sponsor_share = bps_floor(amount, merchant_deposit.sponsor_share_bps as u64).ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.sponsor_rewards = merchant_deposit
+        .sponsor_rewards
+        .checked_add(sponsor_share)
+        .ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.accrued_rewards = merchant_deposit
+        .accrued_rewards
+        .checked_add(amount - sponsor_share)
+        .ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.total_rewards_accrued = merchant_deposit
+        .total_rewards_accrued
+        .checked_add(amount - sponsor_share)
+        .ok_or(VaultError::MathOverflow)?;
+    Ok(())
+}
+
+/// Move the sponsor's share set aside on the deposit to its sponsorship
+fn move_sponsor_rewards(merchant_deposit: &mut MerchantDeposit, sponsorship: &mut Sponsorship) -> Result<()> {
+    sponsorship.accrued_rewards = sponsorship
+        .accrued_rewards
+        .checked_add(merchant_deposit.sponsor_rewards)
+        .ok_or(VaultError::MathOverflow)?;
+    merchant_deposit.sponsor_rewards = 0;
+    Ok(())
+}
+
+/// Credit the deposit's share of harvests since its reward index checkpoint
+/// Harvests are already net of the performance fee. Returns the amount credited.
+fn settle_reward_index(merchant_deposit: &mut MerchantDeposit, vault: &Vault) -> Result<u64> {
+    let index = vault.reward_index(&merchant_deposit.deposit_token);
+    let index_delta = index
+        .checked_sub(merchant_deposit.reward_index)
+        .ok_or(VaultError::MathOverflow)?;
+    let earned = q64_mul_floor(merchant_deposit.total_deposited, index_delta).ok_or(VaultError::MathOverflow)?;
+
+    credit_rewards(merchant_deposit, earned)?;
+    merchant_deposit.reward_index = index;
+
+    Ok(earned)
+}
+
+/// Cap yield earned over `[from, until)` by the vault's reward epoch
+/// Only time inside the epoch earns, scaled pro-rata when the vault's projected
+/// demand exceeds the budget, and never more than the budget has left
+fn epoch_budgeted_yield(vault: &Vault, gross: u64, from: i64, until: i64) -> Result<u64> {
+    let epoch = RewardEpoch {
+        start: vault.reward_epoch_start,
+        end: vault.reward_epoch_end,
+        budget: vault.reward_epoch_budget,
+    };
+    let demand = rewards::epoch_demand(vault.weighted_yield_sum, &epoch).ok_or(VaultError::MathOverflow)?;
+    let budgeted = rewards::budgeted_yield(gross, from, until, &epoch, demand).ok_or(VaultError::MathOverflow)?;
+
+    Ok(budgeted.min(vault.reward_epoch_budget.saturating_sub(vault.reward_epoch_accrued)))
+}
+
+/// Book a harvest against the reward epoch budget, and its performance fee as
+/// owed to the treasury
+/// Fees are kept per deposit asset until `collect_performance_fees` pays them out.
+/// Returns the `YieldHarvested` event for the caller to emit (none if nothing accrued)
+fn book_harvest(
+    vault: &mut Vault,
+    merchant_deposit: &MerchantDeposit,
+    harvest: YieldHarvest,
+    current_time: i64,
+) -> Result<Option<YieldHarvested>> {
+    if harvest.gross == 0 {
+        return Ok(None);
+    }
+
+    vault.reward_epoch_accrued = vault
+        .reward_epoch_accrued
+        .checked_add(harvest.gross)
+        .ok_or(VaultError::MathOverflow)?;
+
+    let owed = match merchant_deposit.deposit_token {
+        DepositType::Sol => &mut vault.performance_fees_sol,
+        DepositType::SplToken => &mut vault.performance_fees_token,
+    };
+    *owed = owed.checked_add(harvest.performance_fee).ok_or(VaultError::MathOverflow)?;
+    vault.total_performance_fees = vault
+        .total_performance_fees
+        .checked_add(harvest.performance_fee)
+        .ok_or(VaultError::MathOverflow)?;
+
+    Ok(Some(YieldHarvested {
+        merchant: merchant_deposit.merchant,
+        deposit_token: merchant_deposit.deposit_token.clone(),
+        gross_yield: harvest.gross,
+        performance_fee: harvest.performance_fee,
+        merchant_rewards: harvest.net,
+        harvested_at: current_time,
+    }))
+}
+
+// ============================================================================
+// Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(bump: u8, label: [u8; VAULT_LABEL_LEN])]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Vault::LEN,
+        seeds = [VAULT_SEED, authority.key().as_ref(), vault_label_seed(&label)],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<AuditLog>(),
+        seeds = [AUDIT_LOG_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + MerchantDeposit::LEN,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Merchant profile (must be registered before depositing)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Referrer's code (optional, supplied together with `referral`)
+    #[account(
+        mut,
+        seeds = [REFERRAL_CODE_SEED, vault.key().as_ref(), referral_code.referrer.as_ref()],
+        bump = referral_code.bump
+    )]
+    pub referral_code: Option<Account<'info, ReferralCode>>,
+
+    /// Referral relationship record (optional, created with the deposit)
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + Referral::LEN,
+        seeds = [REFERRAL_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// CHECK: Merchant's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub kyc_attestation: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the deposited mint; only read and counted if it exists
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), native_mint::ID.as_ref()], bump)]
+    pub supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositTokenAccounts<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + MerchantDeposit::LEN,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Merchant profile (must be registered before depositing)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(mut)]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    /// Vault's associated token account for `mint`
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Referrer's code (optional, supplied together with `referral`)
+    #[account(
+        mut,
+        seeds = [REFERRAL_CODE_SEED, vault.key().as_ref(), referral_code.referrer.as_ref()],
+        bump = referral_code.bump
+    )]
+    pub referral_code: Option<Account<'info, ReferralCode>>,
+
+    /// Referral relationship record (optional, created with the deposit)
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + Referral::LEN,
+        seeds = [REFERRAL_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// CHECK: Merchant's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub kyc_attestation: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the deposited mint; SPL deposits are only taken in registered mints
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), mint.key().as_ref()], bump)]
+    pub supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositWsol<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + MerchantDeposit::LEN,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Merchant profile (must be registered before depositing)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Box<Account<'info, MerchantProfile>>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Merchant's wSOL token account
+    #[account(mut, token::mint = native_mint)]
+    pub merchant_wsol_account: Box<Account<'info, TokenAccount>>,
+
+    /// Temporary wSOL account the deposit is unwrapped through (closed again)
+    #[account(
+        init,
+        payer = merchant,
+        token::mint = native_mint,
+        token::authority = vault,
+        seeds = [WSOL_UNWRAP_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub wsol_unwrap_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(address = native_mint::ID)]
+    pub native_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Merchant's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub kyc_attestation: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the deposited mint; only read and counted if it exists
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), native_mint.key().as_ref()], bump)]
+    pub supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositFor<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + MerchantDeposit::LEN,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Merchant profile (must be registered before depositing)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Box<Account<'info, MerchantProfile>>,
+
+    /// Sponsor's claim on the merchant's rewards (one per merchant at a time)
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + Sponsorship::LEN,
+        seeds = [SPONSORSHIP_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub sponsorship: Box<Account<'info, Sponsorship>>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Sponsor's token account (SPL deposits)
+    #[account(mut)]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Mint of an SPL deposit
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Vault's associated token account for `mint` (SPL deposits)
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub vault_token_account: Option<UncheckedAccount<'info>>,
+
+    /// Funds the deposit and pays for the accounts
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// Merchant credited with the deposit (consents to the revenue share)
+    pub merchant: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// CHECK: Merchant's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub kyc_attestation: UncheckedAccount<'info>,
+
+    /// CHECK: Sponsor's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), sponsor.key().as_ref()], bump)]
+    pub sponsor_kyc_attestation: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the deposited mint (the native mint for SOL); only read and counted if it exists
+    #[account(
+        mut,
+        seeds = [
+            SUPPORTED_MINT_SEED,
+            vault.key().as_ref(),
+            mint.as_ref().map_or(native_mint::ID, |mint| mint.key()).as_ref()
+        ],
+        bump
+    )]
+    pub supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Closed on withdrawal so the merchant can deposit again
+    #[account(
+        mut,
+        close = merchant,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Merchant profile (receives the deposit's metrics on withdrawal)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Merchant's payout wallet (receives SOL withdrawals)
+    /// CHECK: Verified against the profile's payout wallet at transfer time
+    #[account(mut)]
+    pub payout_wallet: AccountInfo<'info>,
+
+    /// Payout wallet's token account (receives SPL withdrawals, or SOL as wSOL)
+    /// CHECK: Owner and mint verified at transfer time; created first if `mint`
+    /// and the associated token program are supplied
+    #[account(mut)]
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Merchant's payout config PDA; splits only apply once it exists
+    #[account(seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub payout_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized,
+        constraint = vault_token_account.mint == merchant_deposit.mint @ VaultError::InvalidVaultAccount
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Deposit owner (receives the closed account's rent)
+    #[account(mut)]
+    pub merchant: SystemAccount<'info>,
+
+    /// Merchant, or their withdraw delegate within its limits (pays for a new
+    /// payout token account)
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+
+    /// Sponsorship of a sponsored deposit (required for those)
+    #[account(
+        mut,
+        seeds = [SPONSORSHIP_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = sponsorship.bump
+    )]
+    pub sponsorship: Option<Account<'info, Sponsorship>>,
+
+    /// Mint of the payout token account, to create it if missing
+    pub mint: Option<Account<'info, Mint>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// Guardian co-signature, required for a payout flagged by the reward circuit breaker
+    #[account(address = vault.guardian @ VaultError::Unauthorized)]
+    pub guardian: Option<Signer<'info>>,
+
+    /// Share mint of the deposit's asset (required for tokenized deposits)
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref(), merchant_deposit.deposit_token.share_mint_seed()],
+        bump
+    )]
+    pub share_mint: Option<Box<InterfaceAccount<'info, token_interface::Mint>>>,
+
+    /// Operator's share tokens the deposit's shares are burned from
+    #[account(mut)]
+    pub share_token_account: Option<Box<InterfaceAccount<'info, token_interface::TokenAccount>>>,
+
+    pub share_token_program: Option<Program<'info, Token2022>>,
+
+    /// CHECK: Record PDA of the deposit's mint; debited by what the deposit counted toward it
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), merchant_deposit.mint.as_ref()], bump)]
+    pub supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+impl<'info> Withdraw<'info> {
+    /// Check the merchant in when they sign the withdrawal themselves (not a delegate)
+    fn record_merchant_activity(&self, current_time: i64) -> Result<()> {
+        if self.operator.key() != self.merchant.key() {
+            return Ok(());
+        }
+        record_merchant_activity(&self.recovery_config, current_time)
+    }
+
+    /// Redeem a tokenized deposit's payout of `amount` against vault shares
+    /// Burns the shares `amount` is worth at the current share price from the
+    /// operator's share token account, not the count minted: shares of an asset
+    /// are fungible, so they are only ever redeemed at their value.
+    fn burn_position_shares(&mut self, amount: u64) -> Result<()> {
+        if self.merchant_deposit.shares == 0 {
+            return Ok(());
+        }
+        let (Some(share_mint), Some(share_token_account), Some(share_token_program)) =
+            (&self.share_mint, &self.share_token_account, &self.share_token_program)
+        else {
+            return err!(VaultError::ShareAccountsRequired);
+        };
+        let shares = self
+            .vault
+            .shares_to_redeem(&self.merchant_deposit.deposit_token, amount)
+            .ok_or(VaultError::MathOverflow)?;
+        let cpi_accounts = token_2022::Burn {
+            mint: share_mint.to_account_info(),
+            from: share_token_account.to_account_info(),
+            authority: self.operator.to_account_info(),
+        };
+        token_2022::burn(CpiContext::new(share_token_program.to_account_info(), cpi_accounts), shares)?;
+        self.merchant_deposit.shares = 0;
+        Ok(())
+    }
+
+    /// Hand a sponsored deposit's set-aside rewards to its sponsorship before it closes
+    fn settle_sponsorship(&mut self) -> Result<()> {
+        if self.merchant_deposit.sponsor == Pubkey::default() {
+            return Ok(());
+        }
+        let sponsorship = self.sponsorship.as_mut().ok_or(VaultError::InvalidSponsorship)?;
+        require_keys_eq!(sponsorship.sponsor, self.merchant_deposit.sponsor, VaultError::InvalidSponsorship);
+        move_sponsor_rewards(&mut self.merchant_deposit, sponsorship)
+    }
+
+    /// Pay `amount` from the vault to the merchant's payout wallet in the deposit's asset
+    /// `split_recipients` holds one account per active payout split (see `PayoutConfig`)
+    fn transfer_to_payout_wallet(
+        &self,
+        split_recipients: &[AccountInfo<'info>],
+        amount: u64,
+        current_time: i64,
+    ) -> Result<()> {
+        let spl_accounts = match (&self.vault_token_account, &self.token_program) {
+            (Some(vault_token), Some(token_program)) => Some((vault_token, token_program)),
+            _ => None,
+        };
+        if let (Some(payout_token_account), Some(mint), Some(token_program), Some(associated_token_program)) = (
+            &self.payout_token_account,
+            &self.mint,
+            &self.token_program,
+            &self.associated_token_program,
+        ) {
+            create_associated_token_account(
+                &self.operator,
+                payout_token_account,
+                &self.payout_wallet,
+                &mint.to_account_info(),
+                &self.system_program,
+                token_program,
+                associated_token_program,
+            )?;
+        }
+        let splits = load_payout_splits(&self.payout_config, current_time)?;
+        pay_from_vault(
+            &self.vault,
+            &self.merchant_deposit,
+            &self.merchant_profile,
+            &self.vault_sol_account,
+            spl_accounts,
+            PayoutDestination {
+                payout_wallet: &self.payout_wallet,
+                payout_token_account: self.payout_token_account.as_deref(),
+                splits: &splits,
+                split_recipients,
+            },
+            amount,
+        )
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the deposit's mint; only counted if it exists
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), merchant_deposit.mint.as_ref()], bump)]
+    pub supported_mint: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// Holds the merchant's auto-sweep settings
+    #[account(
+        mut,
+        seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = payout_config.bump
+    )]
+    pub payout_config: Account<'info, PayoutConfig>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Merchant's payout wallet (receives SOL sweeps)
+    /// CHECK: Verified against the profile's payout wallet at transfer time
+    #[account(mut)]
+    pub payout_wallet: AccountInfo<'info>,
+
+    /// Payout wallet's token account (receives SPL sweeps)
+    #[account(mut)]
+    pub payout_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized,
+        constraint = vault_token_account.mint == merchant_deposit.mint @ VaultError::InvalidVaultAccount
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Deposit owner; only used to derive PDAs
+    pub merchant: UncheckedAccount<'info>,
+
+    /// Anyone may crank (compatible with automation services)
+    pub keeper: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StreamRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseStreamedRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// The merchant's payout splits (may not exist)
+    /// CHECK: Verified via PDA seeds; read only if initialized
+    #[account(seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub payout_config: UncheckedAccount<'info>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Merchant's payout wallet (receives SOL releases)
+    /// CHECK: Verified against the profile's payout wallet at transfer time
+    #[account(mut)]
+    pub payout_wallet: AccountInfo<'info>,
+
+    /// Payout wallet's token account (receives SPL releases)
+    #[account(mut)]
+    pub payout_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized,
+        constraint = vault_token_account.mint == merchant_deposit.mint @ VaultError::InvalidVaultAccount
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: Deposit owner; only used to derive PDAs
+    pub merchant: UncheckedAccount<'info>,
+
+    /// Anyone may crank (compatible with automation services)
+    pub keeper: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + WithdrawalTicket::LEN,
+        seeds = [WITHDRAWAL_TICKET_SEED, vault.key().as_ref(), &vault.withdraw_queue_tail.to_le_bytes()],
+        bump
+    )]
+    pub withdrawal_ticket: Account<'info, WithdrawalTicket>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FulfillWithdrawals<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Vault token account (required to fulfill SPL deposits)
+    #[account(mut, constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Anyone may crank (compatible with automation services)
+    pub keeper: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Record PDA of the native mint; only counted if it exists
+    #[account(mut, seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), native_mint::ID.as_ref()], bump)]
+    pub sol_supported_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Record PDA of the vault token account's mint; only counted if it exists
+    #[account(
+        mut,
+        seeds = [
+            SUPPORTED_MINT_SEED,
+            vault.key().as_ref(),
+            vault_token_account.as_ref().map_or(native_mint::ID, |account| account.mint).as_ref()
+        ],
+        bump
+    )]
+    pub token_supported_mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawalRequest<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_TICKET_SEED, vault.key().as_ref(), &withdrawal_ticket.id.to_le_bytes()],
+        bump = withdrawal_ticket.bump,
+        constraint = withdrawal_ticket.merchant == merchant.key() @ VaultError::Unauthorized,
+        constraint = !withdrawal_ticket.cancelled @ VaultError::TicketCancelled
+    )]
+    pub withdrawal_ticket: Account<'info, WithdrawalTicket>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateVaultConfig<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateVaultConfig<'info> {
+    /// Apply a config change directly and record it in the audit log
+    fn apply_config_change(&mut self, change: &ConfigChange) -> Result<()> {
+        apply_config_change(&mut self.vault, change)?;
+        record_admin_action(
+            &self.audit_log,
+            AuditAction::ConfigApplied,
+            self.authority.key(),
+            Pubkey::default(),
+            0,
+            change.code(),
+        )
+    }
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeConfigChange<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ConfigProposal::LEN,
+        seeds = [CONFIG_PROPOSAL_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub config_proposal: Account<'info, ConfigProposal>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [CONFIG_PROPOSAL_SEED, vault.key().as_ref()],
+        bump = config_proposal.bump,
+        has_one = proposer
+    )]
+    pub config_proposal: Account<'info, ConfigProposal>,
+
+    /// CHECK: Receives the proposal's rent; must match `config_proposal.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [CONFIG_PROPOSAL_SEED, vault.key().as_ref()],
+        bump = config_proposal.bump,
+        has_one = proposer
+    )]
+    pub config_proposal: Account<'info, ConfigProposal>,
+
+    /// CHECK: Receives the proposal's rent; must match `config_proposal.proposer`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+
+    /// Guardian or platform authority
+    pub canceller: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CalculateRewards<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_amount_usd: u64, buyer_wallet: Pubkey)]
+pub struct RecordOrder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Read-only: orders don't touch the deposit until the metrics crank
+    #[account(
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Authorization record for this agent-merchant pair
+    #[account(
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Agent that processed the order (must be merchant's agent)
+    pub agent: Signer<'info>,
+
+    /// Merchant wallet (for verification)
+    /// CHECK: Verified via PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    /// Buyer's cashback account (only present if the buyer opted in)
+    #[account(
+        mut,
+        seeds = [BUYER_REWARDS_SEED, vault.key().as_ref(), buyer_wallet.as_ref()],
+        bump = buyer_rewards.bump
+    )]
+    pub buyer_rewards: Option<Account<'info, BuyerRewards>>,
+
+    /// Store the order(s) were taken at (optional; parent metrics are always credited)
+    #[account(
+        mut,
+        seeds = [SUB_MERCHANT_SEED, vault.key().as_ref(), merchant.key().as_ref(), sub_merchant.label_hash.as_ref()],
+        bump = sub_merchant.bump
+    )]
+    pub sub_merchant: Option<Account<'info, SubMerchant>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordOrdersBatch<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Authorization record for this agent-merchant pair
+    #[account(
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Agent that processed the orders (pays receipt rent)
+    #[account(mut)]
+    pub agent: Signer<'info>,
+
+    /// Merchant wallet (for verification)
+    /// CHECK: Verified via PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Store the order(s) were taken at (optional; parent metrics are always credited)
+    #[account(
+        mut,
+        seeds = [SUB_MERCHANT_SEED, vault.key().as_ref(), merchant.key().as_ref(), sub_merchant.label_hash.as_ref()],
+        bump = sub_merchant.bump
+    )]
+    pub sub_merchant: Option<Account<'info, SubMerchant>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: [u8; 32])]
+pub struct RecordAttestedOrder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Authorization record for this agent-merchant pair
+    #[account(
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Receipt for this order (creation fails if the order was already recorded)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + OrderReceipt::LEN,
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), merchant.key().as_ref(), order_id.as_ref()],
+        bump
+    )]
+    pub order_receipt: Account<'info, OrderReceipt>,
+
+    /// Agent whose ed25519 signature attests the order
+    /// CHECK: Verified via PDA seeds and the Ed25519 instruction
+    pub agent: AccountInfo<'info>,
+
+    /// Merchant wallet (for verification)
+    /// CHECK: Verified via PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    /// Any wallet may relay the attestation
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, verified by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintReceiptCnft<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), order_receipt.merchant.as_ref(), order_receipt.order_id.as_ref()],
+        bump = order_receipt.bump
+    )]
+    pub order_receipt: Account<'info, OrderReceipt>,
+
+    /// Buyer receiving the receipt
+    /// CHECK: Must match the receipt's buyer
+    pub buyer: AccountInfo<'info>,
+
+    /// CHECK: Bubblegum tree config, validated by the Bubblegum program
+    #[account(mut)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Concurrent merkle tree, validated by the Bubblegum program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// Agent that recorded the order (pays for the mint)
+    #[account(mut)]
+    pub agent: Signer<'info>,
+
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: SPL noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: SPL account compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: [u8; 32])]
+pub struct SettleOrder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Settlement asset and the Pyth feed pricing it
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), price_feed.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Receipt for this order (creation fails if the order was already recorded)
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + OrderReceipt::LEN,
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), merchant.key().as_ref(), order_id.as_ref()],
+        bump
+    )]
+    pub order_receipt: Account<'info, OrderReceipt>,
+
+    /// Merchant receiving the payment
+    /// CHECK: Verified via PDA seeds
+    #[account(mut)]
+    pub merchant: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Treasury wallet (receives SOL platform fees)
+    /// CHECK: Must be the vault's treasury
+    #[account(mut, address = vault.treasury @ VaultError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+
+    /// SPL settlements only
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Merchant's associated token account (SPL settlements only)
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub merchant_token_account: Option<UncheckedAccount<'info>>,
+
+    /// Treasury's associated token account (SPL settlements with a platform fee)
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    /// SPL settlements only
+    #[account(address = price_feed.mint)]
+    pub mint: Option<Account<'info, Mint>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// SPL settlements only
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// CHECK: Instructions sysvar, verified by address (required while the settlement guard is on)
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: [u8; 32])]
+pub struct SettleOrderWithGrant<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Pyth feed for the grant's mint
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), spending_grant.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Receipt for this order (creation fails if the order was already recorded)
+    #[account(
+        init,
+        payer = agent,
+        space = 8 + OrderReceipt::LEN,
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), merchant.key().as_ref(), order_id.as_ref()],
+        bump
+    )]
+    pub order_receipt: Box<Account<'info, OrderReceipt>>,
+
+    /// Merchant receiving the payment
+    /// CHECK: Verified via PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SPENDING_GRANT_SEED, vault.key().as_ref(), spending_grant.buyer.as_ref(), agent.key().as_ref()],
+        bump = spending_grant.bump
+    )]
+    pub spending_grant: Box<Account<'info, SpendingGrant>>,
+
+    /// Agent the grant was made to
+    #[account(mut)]
+    pub agent: Signer<'info>,
+
+    /// Treasury wallet (owner of the platform fee's token account)
+    /// CHECK: Must be the vault's treasury
+    #[account(address = vault.treasury @ VaultError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+
+    #[account(mut, address = spending_grant.buyer_token_account)]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Merchant's associated token account
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub merchant_token_account: UncheckedAccount<'info>,
+
+    /// Treasury's associated token account (settlements with a platform fee)
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    #[account(address = spending_grant.mint)]
+    pub mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: Instructions sysvar, verified by address (required while the settlement guard is on)
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RegisterPriceFeed<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceFeed::LEN,
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// The mint's account, for its decimals (SPL mints only)
+    #[account(address = mint)]
+    pub mint_account: Option<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), price_feed.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct RegisterSupportedMint<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + SupportedMint::LEN,
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), mint.as_ref()],
+        bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Vault authority or guardian
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetMintDepositsPaused<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Vault authority or guardian
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepegBounds<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CheckDepeg<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Oracle feed of the mint
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: [u8; 32])]
+pub struct OpenDispute<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Merchant the order was credited to; only used to derive PDAs
+    pub merchant: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), merchant.key().as_ref(), order_id.as_ref()],
+        bump = order_receipt.bump
+    )]
+    pub order_receipt: Account<'info, OrderReceipt>,
+
+    /// One dispute per order
+    #[account(
+        init,
+        payer = opener,
+        space = 8 + OrderDispute::LEN,
+        seeds = [DISPUTE_SEED, order_receipt.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, OrderDispute>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Active deposit, if any (its yield is re-evaluated)
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Option<Account<'info, MerchantDeposit>>,
+
+    /// Buyer on the receipt or the platform authority
+    #[account(mut)]
+    pub opener: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_id: [u8; 32])]
+pub struct RefundOrder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [RECEIPT_SEED, vault.key().as_ref(), merchant.key().as_ref(), order_id.as_ref()],
+        bump = order_receipt.bump
+    )]
+    pub order_receipt: Account<'info, OrderReceipt>,
+
+    /// The order's dispute (empty if it was never disputed)
+    /// CHECK: Seeds checked here; owner and discriminator verified in the handler
+    #[account(seeds = [DISPUTE_SEED, order_receipt.key().as_ref()], bump)]
+    pub dispute: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Active deposit, if any (its yield is re-evaluated)
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Option<Account<'info, MerchantDeposit>>,
+
+    /// Merchant's token account paying SPL refunds
+    #[account(mut, constraint = merchant_token_account.owner == merchant.key() @ VaultError::Unauthorized)]
+    pub merchant_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Buyer's token account receiving SPL refunds
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Buyer on the receipt
+    /// CHECK: Checked against the receipt
+    #[account(mut, address = order_receipt.buyer @ VaultError::Unauthorized)]
+    pub buyer: UncheckedAccount<'info>,
+
+    /// Merchant the order was credited to, paying the refund
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [DISPUTE_SEED, dispute.order_receipt.as_ref()],
+        bump = dispute.bump
+    )]
+    pub dispute: Account<'info, OrderDispute>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), dispute.merchant.as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), dispute.merchant.as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Active deposit, if any (its yield is re-evaluated)
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), dispute.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Option<Account<'info, MerchantDeposit>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefreshMerchantMetrics<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Profile carrying the merchant's loyalty clock
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// Anyone may crank (compatible with automation services)
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetMerchantStatus<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Profile carrying the merchant's loyalty clock
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+}
+
+impl GetMerchantStatus<'_> {
+    /// Evaluate a copy of the deposit as if the metrics crank had just run
+    fn status(&self, current_time: i64) -> Result<(MerchantDeposit, MerchantStatus)> {
+        let mut snapshot = (*self.merchant_deposit).clone();
+        let mut metrics = *self.merchant_metrics.load()?;
+        let last_order_activity = metrics.last_volume_update;
+        let loyalty_started_at = loyalty_start(&self.merchant_profile, &snapshot);
+        refresh_metrics(&mut snapshot, &mut metrics, &self.vault, loyalty_started_at, current_time)?;
+
+        let last_activity = snapshot.last_metrics_refresh.max(last_order_activity);
+        let health = if !snapshot.is_active {
+            MerchantHealth::Inactive
+        } else if snapshot.frozen {
+            MerchantHealth::Frozen
+        } else if metrics.paused_at > 0 {
+            MerchantHealth::Paused
+        } else if elapsed_seconds(last_activity, current_time) > METRICS_STALE_AFTER {
+            MerchantHealth::MetricsStale
+        } else {
+            MerchantHealth::Healthy
+        };
+
+        let status = MerchantStatus {
+            is_active: snapshot.is_active,
+            total_deposited: snapshot.total_deposited,
+            accrued_rewards: snapshot.accrued_rewards,
+            current_yield_bps: snapshot.current_yield_bps,
+            tier: snapshot.current_tier,
+            monthly_volume_usd: metrics.current_month_volume,
+            unlock_time: snapshot.unlock_time.max(snapshot.term_maturity),
+            is_locked: current_time < snapshot.unlock_time.max(snapshot.term_maturity),
+            health,
+        };
+        Ok((snapshot, status))
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(month: u32)]
+pub struct RollMonthlyStatement<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Only used to derive the merchant's PDAs
+    pub merchant: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Deposit whose reward and fee counters are read (empty once withdrawn)
+    /// CHECK: Seeds checked here; owner and discriminator verified in the handler
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub merchant_deposit: UncheckedAccount<'info>,
+
+    /// Merchant's latest statement, the baseline for this one (None before the first)
+    #[account(
+        seeds = [
+            MONTHLY_STATEMENT_SEED,
+            vault.key().as_ref(),
+            merchant.key().as_ref(),
+            &previous_statement.month.to_le_bytes(),
+        ],
+        bump = previous_statement.bump
+    )]
+    pub previous_statement: Option<Account<'info, MonthlyStatement>>,
+
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + MonthlyStatement::LEN,
+        seeds = [MONTHLY_STATEMENT_SEED, vault.key().as_ref(), merchant.key().as_ref(), &month.to_le_bytes()],
+        bump
+    )]
+    pub statement: Account<'info, MonthlyStatement>,
+
+    /// Anyone may crank and pay the statement's rent
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewWithdraw<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+}
+
+#[derive(Accounts)]
+pub struct AssertInvariants<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVaultStats<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<VaultStats>(),
+        seeds = [VAULT_STATS_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stats: AccountLoader<'info, VaultStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVaultAccounts<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<VaultStats>(),
+        seeds = [VAULT_STATS_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub vault_stats: AccountLoader<'info, VaultStats>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceFund::LEN,
+        seeds = [INSURANCE_FUND_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// Pays rent for every account created
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuditLog<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<AuditLog>(),
+        seeds = [AUDIT_LOG_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLookupTable<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Table to create, derived from the vault and the recent slot
+    /// CHECK: Address verified in the handler; created by the lookup table program
+    #[account(mut)]
+    pub lookup_table: UncheckedAccount<'info>,
+
+    /// Vault authority; pays for the table
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: The address lookup table program
+    #[account(address = address_lookup_table::program::ID)]
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendLookupTable<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Must be the vault's table; owned by the lookup table program
+    #[account(mut, address = vault.lookup_table @ VaultError::InvalidLookupTable)]
+    pub lookup_table: UncheckedAccount<'info>,
+
+    /// Vault authority; pays for the extra space
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: The address lookup table program
+    #[account(address = address_lookup_table::program::ID)]
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(crank: AutomationCrank)]
+pub struct CreateAutomationThread<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Owner of the vault's threads for the crank; holds nothing
+    #[account(seeds = [THREAD_AUTHORITY_SEED, vault.key().as_ref(), &[crank as u8]], bump)]
+    pub thread_authority: UncheckedAccount<'info>,
+
+    /// Thread to create, derived from the thread authority and the merchant or mint
+    /// CHECK: Address verified in the handler; created by the thread program
+    #[account(mut)]
+    pub thread: UncheckedAccount<'info>,
+
+    /// Vault token account of the asset the crank pays out (None for SOL)
+    #[account(constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Vault authority; funds the thread
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: The thread program
+    #[account(address = automation::THREAD_PROGRAM_ID)]
+    pub thread_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(crank: AutomationCrank)]
+pub struct DeleteAutomationThread<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Owner of the vault's threads for the crank; holds nothing
+    #[account(seeds = [THREAD_AUTHORITY_SEED, vault.key().as_ref(), &[crank as u8]], bump)]
+    pub thread_authority: UncheckedAccount<'info>,
+
+    /// CHECK: One of the thread authority's threads; checked by the thread program
+    #[account(mut)]
+    pub thread: UncheckedAccount<'info>,
+
+    /// Vault authority; receives the thread's lamports
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: The thread program
+    #[account(address = automation::THREAD_PROGRAM_ID)]
+    pub thread_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlanAutomationCrank<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Signer of the planned crank (the automation thread); only its key is used
+    pub keeper: UncheckedAccount<'info>,
+
+    /// Vault token account of the asset the crank pays out (None for SOL)
+    #[account(constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+}
+
+impl PlanAutomationCrank<'_> {
+    /// Mint a deposit is paid out in (None for SOL); SPL needs the vault token account of its mint
+    fn payout_mint(&self, deposit: &MerchantDeposit) -> Result<Option<Pubkey>> {
+        match deposit.deposit_token {
+            DepositType::Sol => Ok(None),
+            DepositType::SplToken => {
+                let vault_token = self.vault_token_account.as_ref().ok_or(VaultError::MissingTokenAccount)?;
+                require_keys_eq!(vault_token.mint, deposit.mint, VaultError::InvalidVaultAccount);
+                Ok(Some(vault_token.mint))
+            }
+        }
+    }
+
+    /// `refresh_merchant_metrics` once the merchant's rate limit has passed
+    fn plan_metrics_refresh(
+        &self,
+        remaining_accounts: &[AccountInfo],
+        current_time: i64,
+    ) -> Result<Option<Instruction>> {
+        let [deposit_info, ..] = remaining_accounts else {
+            return err!(VaultError::InvalidAutomationThread);
+        };
+        let Some(deposit) = load_vault_account::<MerchantDeposit>(deposit_info)? else {
+            return Ok(None);
+        };
+        if !deposit.is_active
+            || elapsed_seconds(deposit.last_metrics_refresh, current_time) < MIN_METRICS_REFRESH_INTERVAL
+        {
+            return Ok(None);
+        }
+
+        let vault_key = self.vault.key();
+        Ok(Some(vault_instruction(
+            accounts::RefreshMerchantMetrics {
+                vault: vault_key,
+                merchant_deposit: deposit_info.key(),
+                merchant_metrics: pda::merchant_metrics(&vault_key, &deposit.merchant),
+                yield_history: pda::yield_history(&vault_key, &deposit.merchant),
+                merchant_profile: pda::merchant_profile(&vault_key, &deposit.merchant),
+                cranker: self.keeper.key(),
+                event_authority: pda::event_authority(),
+                program: crate::ID,
+            },
+            instruction::RefreshMerchantMetrics {},
+            [],
+        )))
+    }
+
+    /// `sweep_rewards` once the merchant's checkpointed rewards reach its sweep threshold
+    fn plan_sweep(&self, remaining_accounts: &[AccountInfo], current_time: i64) -> Result<Option<Instruction>> {
+        let [deposit_info, profile_info, payout_config_info] = remaining_accounts else {
+            return err!(VaultError::InvalidAutomationThread);
+        };
+        let (Some(deposit), Some(mut profile), Some(mut payout_config)) = (
+            load_vault_account::<MerchantDeposit>(deposit_info)?,
+            load_vault_account::<MerchantProfile>(profile_info)?,
+            load_payout_config(payout_config_info)?,
+        ) else {
+            return Ok(None);
+        };
+        let vault = &self.vault;
+        let due = deposit.is_active
+            && !deposit.frozen
+            && !vault.emergency_mode
+            && vault.withdraw_queue_head == vault.withdraw_queue_tail
+            && payout_config.sweep_threshold > 0
+            && deposit.accrued_rewards >= payout_config.sweep_threshold
+            && elapsed_seconds(payout_config.last_sweep_at, current_time) >= payout_config.sweep_interval;
+        if !due {
+            return Ok(None);
+        }
+
+        promote_payout_wallet(&mut profile, current_time);
+        payout_config.promote_pending(current_time);
+        let mint = self.payout_mint(&deposit)?;
+        let (payout_wallet, split_recipients) = payout_accounts(&deposit, &profile, &payout_config.splits, mint);
+        let vault_key = vault.key();
+        Ok(Some(vault_instruction(
+            accounts::SweepRewards {
+                vault: vault_key,
+                merchant_deposit: deposit_info.key(),
+                merchant_profile: profile_info.key(),
+                payout_config: payout_config_info.key(),
+                vault_sol_account: vault_key,
+                payout_wallet,
+                payout_token_account: mint.map(|mint| get_associated_token_address(&payout_wallet, &mint)),
+                vault_token_account: mint.and(self.vault_token_account.as_ref().map(|account| account.key())),
+                merchant: deposit.merchant,
+                keeper: self.keeper.key(),
+                token_program: mint.map(|_| token::ID),
+                event_authority: pda::event_authority(),
+                program: crate::ID,
+            },
+            instruction::SweepRewards {},
+            split_recipients,
+        )))
+    }
+
+    /// Look up the head of the withdrawal queue, then `fulfill_withdrawals` for that ticket
+    fn plan_fulfillment(&self, remaining_accounts: &[AccountInfo], current_time: i64) -> Result<Option<Instruction>> {
+        let vault = &self.vault;
+        if vault.emergency_mode || vault.withdraw_queue_head == vault.withdraw_queue_tail {
+            return Ok(None);
+        }
+
+        let vault_key = vault.key();
+        let vault_token_account = self.vault_token_account.as_ref().map(|account| account.key());
+        let head_ticket = pda::withdrawal_ticket(&vault_key, vault.withdraw_queue_head);
+        let plan_next = |accounts: Vec<AccountMeta>| {
+            Ok(Some(vault_instruction(
+                accounts::PlanAutomationCrank {
+                    vault: vault_key,
+                    keeper: self.keeper.key(),
+                    vault_token_account,
+                },
+                instruction::PlanAutomationCrank { crank: AutomationCrank::FulfillWithdrawals },
+                accounts,
+            )))
+        };
+        let (ticket_info, merchant_infos) = match remaining_accounts {
+            [] => return plan_next(vec![AccountMeta::new_readonly(head_ticket, false)]),
+            [ticket_info] => (ticket_info, None),
+            [ticket_info, deposit_info, profile_info, payout_config_info] => {
+                (ticket_info, Some((deposit_info, profile_info, payout_config_info)))
+            }
+            _ => return err!(VaultError::InvalidAutomationThread),
+        };
+        // The queue moved on since the previous step; start over on the next run
+        if ticket_info.key() != head_ticket {
+            return Ok(None);
+        }
+        let Some(ticket) = load_vault_account::<WithdrawalTicket>(ticket_info)? else {
+            return Ok(None);
+        };
+        let merchant = ticket.merchant;
+        let Some((deposit_info, profile_info, payout_config_info)) = merchant_infos else {
+            return plan_next(vec![
+                AccountMeta::new_readonly(head_ticket, false),
+                AccountMeta::new_readonly(pda::merchant_deposit(&vault_key, &merchant), false),
+                AccountMeta::new_readonly(pda::merchant_profile(&vault_key, &merchant), false),
+                AccountMeta::new_readonly(pda::payout_config(&vault_key, &merchant), false),
+            ]);
+        };
+
+        let splits = load_payout_splits(payout_config_info, current_time)?;
+        let deposit = load_vault_account::<MerchantDeposit>(deposit_info)?;
+        let profile = load_vault_account::<MerchantProfile>(profile_info)?;
+        let (payout_wallet, payout_token_account, split_recipients) = match (deposit, profile) {
+            (Some(deposit), Some(mut profile)) if !ticket.cancelled => {
+                // SPL tickets wait for a thread of their mint
+                let token_mint = self.vault_token_account.as_ref().map(|account| account.mint);
+                if deposit.deposit_token == DepositType::SplToken && token_mint != Some(deposit.mint) {
+                    return Ok(None);
+                }
+                promote_payout_wallet(&mut profile, current_time);
+                let mint = self.payout_mint(&deposit)?;
+                let (payout_wallet, split_recipients) = payout_accounts(&deposit, &profile, &splits, mint);
+                let payout_token_account =
+                    mint.map_or(payout_wallet, |mint| get_associated_token_address(&payout_wallet, &mint));
+                (payout_wallet, payout_token_account, split_recipients)
+            }
+            // Closed without a payout; only the number of recipients matters
+            _ => (merchant, merchant, splits.iter().map(|_| AccountMeta::new(merchant, false)).collect()),
+        };
+
+        let mut ticket_accounts = vec![
+            AccountMeta::new(head_ticket, false),
+            AccountMeta::new(deposit_info.key(), false),
+            AccountMeta::new(profile_info.key(), false),
+            AccountMeta::new(merchant, false),
+            AccountMeta::new(payout_wallet, false),
+            AccountMeta::new(payout_token_account, false),
+            AccountMeta::new_readonly(payout_config_info.key(), false),
+        ];
+        ticket_accounts.extend(split_recipients);
+        let mint = self.vault_token_account.as_ref().map(|account| account.mint);
+        Ok(Some(vault_instruction(
+            accounts::FulfillWithdrawals {
+                vault: vault_key,
+                vault_sol_account: vault_key,
+                vault_token_account,
+                keeper: self.keeper.key(),
+                token_program: mint.map(|_| token::ID),
+                system_program: system_program::ID,
+                sol_supported_mint: pda::supported_mint(&vault_key, &native_mint::ID),
+                token_supported_mint: pda::supported_mint(&vault_key, &mint.unwrap_or(native_mint::ID)),
+                event_authority: pda::event_authority(),
+                program: crate::ID,
+            },
+            instruction::FulfillWithdrawals {},
+            ticket_accounts,
+        )))
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeYieldHistory<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant profile (must be registered)
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant_profile.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<YieldHistory>(),
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant_profile.merchant.as_ref()],
+        bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsuranceFund::LEN,
+        seeds = [INSURANCE_FUND_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayInsuranceClaim<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [INSURANCE_FUND_SEED, vault.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// Profile of the merchant being compensated
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant_profile.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(mut, constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = payout_token_account.owner == merchant_profile.payout_wallet @ VaultError::InvalidPayoutWallet
+    )]
+    pub payout_token_account: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CollectPerformanceFees<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    /// Treasury wallet (receives SOL fees)
+    /// CHECK: Must be the vault's treasury
+    #[account(mut, address = vault.treasury @ VaultError::InvalidTreasury)]
+    pub treasury: AccountInfo<'info>,
+
+    /// Treasury's token account (receives SPL fees)
+    #[account(mut, constraint = treasury_token_account.owner == vault.treasury @ VaultError::InvalidTreasury)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveLendingMarket<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingStrategy::LEN,
+        seeds = [LENDING_STRATEGY_SEED, vault.key().as_ref(), reserve.key().as_ref()],
+        bump
+    )]
+    pub lending_strategy: Account<'info, LendingStrategy>,
+
+    /// CHECK: Owner checked against the lending program in the handler
+    pub reserve: UncheckedAccount<'info>,
+
+    pub liquidity_mint: Account<'info, Mint>,
+
+    pub collateral_mint: Account<'info, Mint>,
+
+    /// Vault's associated token account for the collateral (created here)
+    /// CHECK: Address verified when created
+    #[account(mut)]
+    pub vault_collateral_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLendingAllocation<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [LENDING_STRATEGY_SEED, vault.key().as_ref(), lending_strategy.reserve.as_ref()],
+        bump = lending_strategy.bump
+    )]
+    pub lending_strategy: Account<'info, LendingStrategy>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Accounts for supplying to and redeeming from the vault's lending reserve
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LendingLiquidity<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [LENDING_STRATEGY_SEED, vault.key().as_ref(), lending_strategy.reserve.as_ref()],
+        bump = lending_strategy.bump
+    )]
+    pub lending_strategy: Account<'info, LendingStrategy>,
+
+    /// Registration of the strategy's liquidity mint, which tracks what of it is lent
+    #[account(
+        mut,
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), lending_strategy.liquidity_mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Box<Account<'info, SupportedMint>>,
+
+    #[account(
+        mut,
+        address = get_associated_token_address(&vault.key(), &lending_strategy.liquidity_mint)
+            @ VaultError::NotAssociatedTokenAccount
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = get_associated_token_address(&vault.key(), &lending_strategy.collateral_mint)
+            @ VaultError::NotAssociatedTokenAccount
+    )]
+    pub vault_collateral_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Pinned by the strategy; validated by the lending program
+    #[account(mut, address = lending_strategy.reserve @ VaultError::InvalidLendingReserve)]
+    pub reserve: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by the strategy; validated by the lending program
+    #[account(mut, address = lending_strategy.reserve_liquidity_supply @ VaultError::InvalidLendingReserve)]
+    pub reserve_liquidity_supply: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by the strategy; validated by the lending program
+    #[account(mut, address = lending_strategy.collateral_mint @ VaultError::InvalidLendingReserve)]
+    pub reserve_collateral_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by the strategy; validated by the lending program
+    #[account(address = lending_strategy.lending_market @ VaultError::InvalidLendingReserve)]
+    pub lending_market: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by the strategy; validated by the lending program
+    #[account(address = lending_strategy.lending_market_authority @ VaultError::InvalidLendingReserve)]
+    pub lending_market_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by the strategy
+    #[account(address = lending_strategy.lending_program @ VaultError::InvalidLendingReserve)]
+    pub lending_program: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct HarvestRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    /// Authority's token account paying SPL harvests
+    #[account(mut, constraint = source_token_account.owner == authority.key() @ VaultError::Unauthorized)]
+    pub source_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = vault_token_account.owner == vault.key() @ VaultError::Unauthorized)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Current Solana epoch's record (required under the Solana epoch accrual schedule)
+    #[account(
+        mut,
+        seeds = [EPOCH_RECORD_SEED, vault.key().as_ref(), &epoch_record.epoch.to_le_bytes()],
+        bump = epoch_record.bump
+    )]
+    pub epoch_record: Option<Account<'info, EpochRecord>>,
+
+    /// Pays SOL harvests
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct InitializeEpochRecord<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + EpochRecord::LEN,
+        seeds = [EPOCH_RECORD_SEED, vault.key().as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub epoch_record: Account<'info, EpochRecord>,
+
+    /// Anyone may open the record and pay its rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDistribution<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Distribution::LEN,
+        seeds = [DISTRIBUTION_SEED, vault.key().as_ref(), &vault.distribution_count.to_le_bytes()],
+        bump
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    /// Mint the bonuses are paid in
+    pub mint: Box<Account<'info, Mint>>,
+
+    /// Authority's account funding the distribution
+    #[account(mut, token::mint = mint, token::authority = authority)]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Vault's associated token account for `mint`
+    /// CHECK: Address verified and created if missing
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBonus<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_SEED, vault.key().as_ref(), &distribution.id.to_le_bytes()],
+        bump = distribution.bump
+    )]
+    pub distribution: Account<'info, Distribution>,
+
+    /// Claim record (its existence marks the bonus as claimed)
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + BonusClaim::LEN,
+        seeds = [BONUS_CLAIM_SEED, distribution.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub bonus_claim: Account<'info, BonusClaim>,
+
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// Vault's account of the distribution mint, funded when the distribution was created
+    #[account(mut, associated_token::mint = distribution.mint, associated_token::authority = vault)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = payout_token_account.owner == merchant_profile.payout_wallet @ VaultError::InvalidPayoutWallet
+    )]
+    pub payout_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardEmission<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EmissionSchedule::LEN,
+        seeds = [EMISSION_SCHEDULE_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [REWARD_MINT_SEED, vault.key().as_ref()],
+        bump,
+        mint::decimals = REWARD_TOKEN_DECIMALS,
+        mint::authority = reward_mint_authority,
+    )]
+    pub reward_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA mint authority of the reward token; holds no data
+    #[account(seeds = [REWARD_MINT_AUTHORITY_SEED, vault.key().as_ref()], bump)]
+    pub reward_mint_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEmissionSchedule<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [EMISSION_SCHEDULE_SEED, vault.key().as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimTokenRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        mut,
+        seeds = [EMISSION_SCHEDULE_SEED, vault.key().as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+
+    #[account(mut, address = emission_schedule.reward_mint)]
+    pub reward_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA mint authority of the reward token; holds no data
+    #[account(
+        seeds = [REWARD_MINT_AUTHORITY_SEED, vault.key().as_ref()],
+        bump = emission_schedule.mint_authority_bump
+    )]
+    pub reward_mint_authority: UncheckedAccount<'info>,
+
+    /// Payout wallet's reward token account
+    #[account(
+        mut,
+        constraint = payout_reward_account.owner == merchant_profile.payout_wallet @ VaultError::InvalidPayoutWallet,
+        constraint = payout_reward_account.mint == reward_mint.key() @ VaultError::InvalidPayoutWallet
+    )]
+    pub payout_reward_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Merchant's payout config PDA; splits only apply once it exists
+    #[account(seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub payout_config: UncheckedAccount<'info>,
+
+    /// CHECK: Deposit owner; only used to derive PDAs
+    pub merchant: UncheckedAccount<'info>,
+
+    /// Merchant, or their withdraw delegate within its limits
+    pub operator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Merchant's recovery config PDA; checked in once it exists
+    #[account(mut, seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub recovery_config: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier: u8)]
+pub struct MintTierBadge<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Badge record (one per merchant per tier)
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + TierBadge::LEN,
+        seeds = [TIER_BADGE_SEED, vault.key().as_ref(), merchant.key().as_ref(), &[tier]],
+        bump
+    )]
+    pub tier_badge: Box<Account<'info, TierBadge>>,
+
+    #[account(
+        init,
+        payer = merchant,
+        mint::decimals = 0,
+        mint::authority = vault,
+        mint::freeze_authority = vault,
+        seeds = [BADGE_MINT_SEED, vault.key().as_ref(), merchant.key().as_ref(), &[tier]],
+        bump
+    )]
+    pub badge_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = merchant,
+        associated_token::mint = badge_mint,
+        associated_token::authority = merchant
+    )]
+    pub badge_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metadata PDA, derived and validated by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Master edition PDA, derived and validated by the token metadata program
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MintPositionNft<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    #[account(
+        init,
+        payer = merchant,
+        mint::decimals = 0,
+        mint::authority = vault,
+        mint::freeze_authority = vault,
+        seeds = [
+            POSITION_MINT_SEED,
+            vault.key().as_ref(),
+            merchant.key().as_ref(),
+            &merchant_deposit.deposited_at.to_le_bytes()
+        ],
+        bump
+    )]
+    pub position_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = merchant,
+        associated_token::mint = position_mint,
+        associated_token::authority = merchant
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Metadata PDA, derived and validated by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Master edition PDA, derived and validated by the token metadata program
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(deposit_token: DepositType)]
+pub struct InitializeShareMint<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref(), deposit_token.share_mint_seed()],
+        bump,
+        mint::decimals = deposit_token.decimals(),
+        mint::authority = vault,
+        mint::token_program = token_program,
+        extensions::transfer_hook::authority = vault,
+        extensions::transfer_hook::program_id = crate::ID,
+    )]
+    pub share_mint: Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    /// CHECK: Transfer hook validation account, written by the handler
+    #[account(
+        init,
+        payer = authority,
+        space = transfer_hook::EXTRA_ACCOUNT_METAS_LEN,
+        seeds = [transfer_hook::EXTRA_ACCOUNT_METAS_SEED, share_mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_metas: UncheckedAccount<'info>,
+
+    /// CHECK: Metadata PDA, derived and validated by the token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PositionShares<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        mut,
+        seeds = [SHARE_MINT_SEED, vault.key().as_ref(), merchant_deposit.deposit_token.share_mint_seed()],
+        bump
+    )]
+    pub share_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Receives minted shares, or holds the shares to burn
+    #[account(mut, token::mint = share_mint, token::token_program = token_program)]
+    pub share_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub merchant: Signer<'info>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+/// Accounts of the transfer hook `Execute` instruction, in interface order
+#[derive(Accounts)]
+pub struct TransferShareHook<'info> {
+    #[account(token::mint = share_mint)]
+    pub source_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub share_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(token::mint = share_mint)]
+    pub destination_token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// CHECK: Source owner or delegate, verified by the token program
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: The share mint's validation account
+    #[account(seeds = [transfer_hook::EXTRA_ACCOUNT_METAS_SEED, share_mint.key().as_ref()], bump)]
+    pub extra_account_metas: UncheckedAccount<'info>,
+
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Destination owner's approval; only its existence matters
+    #[account(
+        seeds = [APPROVED_HOLDER_SEED, vault.key().as_ref(), destination_token_account.owner.as_ref()],
+        bump
+    )]
+    pub approved_holder: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct ApproveShareHolder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ApprovedHolder::LEN,
+        seeds = [APPROVED_HOLDER_SEED, vault.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub approved_holder: Account<'info, ApprovedHolder>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeShareHolder<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [APPROVED_HOLDER_SEED, vault.key().as_ref(), approved_holder.wallet.as_ref()],
+        bump = approved_holder.bump
+    )]
+    pub approved_holder: Account<'info, ApprovedHolder>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct IssueKycAttestation<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = verifier,
+        space = 8 + KycAttestation::LEN,
+        seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub kyc_attestation: Account<'info, KycAttestation>,
+
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeKycAttestation<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = signer,
+        seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), kyc_attestation.wallet.as_ref()],
+        bump = kyc_attestation.bump
+    )]
+    pub kyc_attestation: Account<'info, KycAttestation>,
+
+    /// The vault's KYC verifier or its authority (receives the account's rent)
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncPositionOwner<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_deposit.bump,
+        constraint = merchant_deposit.position_mint != Pubkey::default() @ VaultError::NoPositionNft
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Token account currently holding the position NFT
+    #[account(
+        constraint = position_token_account.mint == merchant_deposit.position_mint @ VaultError::InvalidPositionTokenAccount,
+        constraint = position_token_account.amount == 1 @ VaultError::InvalidPositionTokenAccount
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SplitPosition<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Box<Account<'info, MerchantProfile>>,
+
+    /// Recipient's new deposit (fails if they already have one)
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + MerchantDeposit::LEN,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub split_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Recipient profile (must be registered)
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_profile.bump
+    )]
+    pub recipient_profile: Box<Account<'info, MerchantProfile>>,
+
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_metrics.load()?.bump
+    )]
+    pub recipient_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_yield_history.load()?.bump
+    )]
+    pub recipient_yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    /// Merchant taking over the split-off principal
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Recipient's KYC attestation PDA; only read when the vault requires KYC
+    #[account(seeds = [KYC_ATTESTATION_SEED, vault.key().as_ref(), recipient.key().as_ref()], bump)]
+    pub recipient_kyc_attestation: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MergePositions<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Box<Account<'info, MerchantProfile>>,
+
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Deposit folded into the merchant's (closed)
+    #[account(
+        mut,
+        close = source_merchant,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), source_merchant.key().as_ref()],
+        bump = source_deposit.bump
+    )]
+    pub source_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), source_merchant.key().as_ref()],
+        bump = source_profile.bump
+    )]
+    pub source_profile: Box<Account<'info, MerchantProfile>>,
+
+    pub merchant: Signer<'info>,
+
+    /// Owner of the source deposit (receives its rent)
+    #[account(mut)]
+    pub source_merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMerchantFrozen<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant_deposit.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMerchantPaused<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Active deposit, if any (backfills the loyalty start of pre-profile deposits
+    /// and carries the tier downgrade grace period)
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Option<Account<'info, MerchantDeposit>>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetEmergencyMode<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    /// Vault to grow (may not deserialize with the current layout yet)
+    /// CHECK: Owner, discriminator and authority verified in the handler
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// Vault authority; pays the extra rent
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMerchantMetrics<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// CHECK: Only used to derive the metrics PDA
+    pub merchant: UncheckedAccount<'info>,
+
+    /// Metrics to grow (can't be loaded with the current layout yet)
+    /// CHECK: Owner and discriminator verified in the handler
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_metrics: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMerchantDeposit<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Deposit to migrate (may not deserialize with the current layout yet)
+    /// CHECK: Owner, discriminator, and PDA verified in the handler
+    #[account(mut)]
+    pub merchant_deposit: UncheckedAccount<'info>,
+
+    /// Metrics account receiving the deposit's legacy metrics (created if missing)
+    /// CHECK: PDA verified in the handler
+    #[account(mut)]
+    pub merchant_metrics: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Registered mint an SPL deposit from before v9 was made in
+    #[account(
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), deposit_mint.mint.as_ref()],
+        bump = deposit_mint.bump
+    )]
+    pub deposit_mint: Option<Account<'info, SupportedMint>>,
+
+    /// Vault authority, attesting `deposit_mint` (SPL deposits from before v9 only)
+    #[account(address = vault.authority @ VaultError::Unauthorized)]
+    pub authority: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotVaultStats<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [VAULT_STATS_SEED, vault.key().as_ref()],
+        bump = vault_stats.load()?.bump
+    )]
+    pub vault_stats: AccountLoader<'info, VaultStats>,
+
+    /// Anyone may crank
+    pub cranker: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GetMerchantTier<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Profile carrying the merchant's loyalty clock
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordPlatformProfit<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    /// Platform authority or agent that processed the order
+    pub platform: Signer<'info>,
+
+    /// Merchant wallet (for verification)
+    /// CHECK: Verified via PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    /// Referral record for this merchant (optional, supplied together with `referral_code`)
+    #[account(
+        mut,
+        seeds = [REFERRAL_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = referral.bump
+    )]
+    pub referral: Option<Account<'info, Referral>>,
+
+    /// Referrer's code credited with the referral share
+    #[account(
+        mut,
+        seeds = [REFERRAL_CODE_SEED, vault.key().as_ref(), referral_code.referrer.as_ref()],
+        bump = referral_code.bump
+    )]
+    pub referral_code: Option<Account<'info, ReferralCode>>,
+
+    /// Insurance fund credited with `insurance_fee_bps` of the profit (optional)
+    #[account(
+        mut,
+        seeds = [INSURANCE_FUND_SEED, vault.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterMerchant<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + MerchantProfile::LEN,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + std::mem::size_of::<MerchantMetrics>(),
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + std::mem::size_of::<YieldHistory>(),
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(label_hash: [u8; 32])]
+pub struct CreateSubMerchant<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Parent profile (merchant must be registered)
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + SubMerchant::LEN,
+        seeds = [SUB_MERCHANT_SEED, vault.key().as_ref(), merchant.key().as_ref(), label_hash.as_ref()],
+        bump
+    )]
+    pub sub_merchant: Account<'info, SubMerchant>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutWallet<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Merchant's withdrawal whitelist PDA; destinations must be on it once it exists
+    #[account(seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub withdrawal_whitelist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawDelegate<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRecoveryConfig<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant must be registered
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + RecoveryConfig::LEN,
+        seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecoveryCheckIn<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = recovery_config.bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRecoveryConfig<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = merchant,
+        seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = recovery_config.bump
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartRecovery<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), recovery_config.merchant.as_ref()],
+        bump = recovery_config.bump,
+        has_one = beneficiary @ VaultError::Unauthorized
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompleteRecovery<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [RECOVERY_CONFIG_SEED, vault.key().as_ref(), recovery_config.merchant.as_ref()],
+        bump = recovery_config.bump,
+        has_one = beneficiary @ VaultError::Unauthorized
+    )]
+    pub recovery_config: Account<'info, RecoveryConfig>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), recovery_config.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), recovery_config.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// CHECK: Merchant's withdrawal whitelist PDA; the beneficiary must be on it once it exists
+    #[account(seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), recovery_config.merchant.as_ref()], bump)]
+    pub withdrawal_whitelist: UncheckedAccount<'info>,
+
+    pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePayoutConfig<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant must be registered
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + PayoutConfig::LEN,
+        seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub payout_config: Account<'info, PayoutConfig>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Merchant's withdrawal whitelist PDA; destinations must be on it once it exists
+    #[account(seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub withdrawal_whitelist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePayoutConfig<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = payout_config.bump
+    )]
+    pub payout_config: Account<'info, PayoutConfig>,
+
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Merchant's withdrawal whitelist PDA; destinations must be on it once it exists
+    #[account(seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub withdrawal_whitelist: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateWithdrawalWhitelist<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant must be registered
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// CHECK: Merchant's payout config PDA; its split wallets are whitelisted if it exists
+    #[account(seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub payout_config: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + WithdrawalWhitelist::LEN,
+        seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump
+    )]
+    pub withdrawal_whitelist: Account<'info, WithdrawalWhitelist>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateWithdrawalWhitelist<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// CHECK: Merchant's payout config PDA; read only if initialized
+    #[account(seeds = [PAYOUT_CONFIG_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump)]
+    pub payout_config: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [WITHDRAWAL_WHITELIST_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = withdrawal_whitelist.bump
+    )]
+    pub withdrawal_whitelist: Account<'info, WithdrawalWhitelist>,
+
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgent<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + AuthorizedAgent::LEN,
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Agent public key to authorize
+    /// CHECK: Can be any pubkey
+    pub agent: AccountInfo<'info>,
+
+    /// Merchant authorizing the agent
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAgent<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    #[account(
+        mut,
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Agent to revoke
+    /// CHECK: Verified via PDA seeds
+    pub agent: AccountInfo<'info>,
+
+    /// Merchant revoking the agent
+    pub merchant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimAgentBond<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = merchant,
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    /// Agent whose bond is reclaimed
     /// CHECK: Verified via PDA seeds
+    pub agent: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SlashAgent<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Governance history the action is appended to
+    #[account(mut, seeds = [AUDIT_LOG_SEED, vault.key().as_ref()], bump = audit_log.load()?.bump)]
+    pub audit_log: AccountLoader<'info, AuditLog>,
+
+    #[account(
+        mut,
+        seeds = [AGENT_AUTH_SEED, vault.key().as_ref(), authorized_agent.merchant.as_ref(), authorized_agent.agent.as_ref()],
+        bump = authorized_agent.bump
+    )]
+    pub authorized_agent: Account<'info, AuthorizedAgent>,
+
+    #[account(
+        mut,
+        seeds = [MERCHANT_PROFILE_SEED, vault.key().as_ref(), authorized_agent.merchant.as_ref()],
+        bump = merchant_profile.bump
+    )]
+    pub merchant_profile: Account<'info, MerchantProfile>,
+
+    /// Receives the slashed bond
+    /// CHECK: Any lamport-receiving account chosen by the guardian
+    #[account(mut)]
+    pub bond_destination: AccountInfo<'info>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenBuyerRewards<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + BuyerRewards::LEN,
+        seeds = [BUYER_REWARDS_SEED, vault.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_rewards: Account<'info, BuyerRewards>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardReserve<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault,
+        seeds = [REWARD_RESERVE_SEED, vault.key().as_ref()],
+        bump
+    )]
+    pub reward_reserve: Account<'info, TokenAccount>,
+
+    /// Mint cashback and referral rewards are paid in
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCashback<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [BUYER_REWARDS_SEED, vault.key().as_ref(), buyer.key().as_ref()],
+        bump = buyer_rewards.bump
+    )]
+    pub buyer_rewards: Account<'info, BuyerRewards>,
+
+    /// Reserve cashback is paid from
+    #[account(mut, seeds = [REWARD_RESERVE_SEED, vault.key().as_ref()], bump)]
+    pub reward_reserve: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = buyer_token_account.mint == reward_reserve.mint @ VaultError::InvalidRewardMint)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateReferralCode<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferralCode::LEN,
+        seeds = [REFERRAL_CODE_SEED, vault.key().as_ref(), referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_code: Account<'info, ReferralCode>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [REFERRAL_CODE_SEED, vault.key().as_ref(), referrer.key().as_ref()],
+        bump = referral_code.bump
+    )]
+    pub referral_code: Account<'info, ReferralCode>,
+
+    /// Reserve referral rewards are paid from
+    #[account(mut, seeds = [REWARD_RESERVE_SEED, vault.key().as_ref()], bump)]
+    pub reward_reserve: Account<'info, TokenAccount>,
+
+    #[account(mut, constraint = referrer_token_account.mint == reward_reserve.mint @ VaultError::InvalidRewardMint)]
+    pub referrer_token_account: Account<'info, TokenAccount>,
+
+    pub referrer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimSponsorRewards<'info> {
+    #[account(mut, seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        has_one = sponsor @ VaultError::Unauthorized,
+        seeds = [SPONSORSHIP_SEED, vault.key().as_ref(), sponsorship.merchant.as_ref()],
+        bump = sponsorship.bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+
+    /// CHECK: Sponsored merchant's deposit PDA; may already be closed
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), sponsorship.merchant.as_ref()],
+        bump
+    )]
+    pub merchant_deposit: UncheckedAccount<'info>,
+
+    /// CHECK: Vault's SOL account: the vault PDA itself, rent-funded by `initialize`
+    #[account(mut, address = vault.key() @ VaultError::InvalidVaultAccount)]
+    pub vault_sol_account: AccountInfo<'info>,
+
+    /// Vault's account of the sponsored mint (SPL sponsorships)
+    #[account(mut, associated_token::mint = sponsorship.mint, associated_token::authority = vault)]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Sponsor's token account (SPL sponsorships)
+    #[account(mut, constraint = sponsor_token_account.owner == sponsor.key() @ VaultError::Unauthorized)]
+    pub sponsor_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Receives SOL rewards and the sponsorship's rent once closed
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSubscription<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant's deposit (subscriptions are only offered by vault merchants)
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Subscription::LEN,
+        seeds = [SUBSCRIPTION_SEED, vault.key().as_ref(), merchant.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Record of the payment mint; subscriptions are only charged in registered SPL mints
+    #[account(
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), buyer_token_account.mint.as_ref()],
+        bump = supported_mint.bump,
+        constraint = supported_mint.mint != native_mint::ID @ VaultError::MintNotSupported
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Payment mint's Pyth feed, valuing the payment against the order minimum in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), buyer_token_account.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Merchant being paid
+    /// CHECK: Verified via merchant_deposit PDA seeds
+    pub merchant: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProcessSubscription<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_SEED, vault.key().as_ref(), subscription.merchant.as_ref(), subscription.buyer.as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), subscription.merchant.as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), subscription.merchant.as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), subscription.merchant.as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(mut, address = subscription.buyer_token_account)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == subscription.merchant @ VaultError::Unauthorized,
+        constraint = merchant_token_account.mint == buyer_token_account.mint @ VaultError::MintNotSupported
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    /// Record of the payment mint (must still be registered)
+    #[account(
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), buyer_token_account.mint.as_ref()],
+        bump = supported_mint.bump
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Payment mint's Pyth feed, valuing each charge in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), buyer_token_account.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Anyone may crank a due subscription
+    pub cranker: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [SUBSCRIPTION_SEED, vault.key().as_ref(), subscription.merchant.as_ref(), buyer.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(mut, address = subscription.buyer_token_account)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, memo_hash: [u8; 32])]
+pub struct CreateInvoice<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant's deposit (invoices are only issued by vault merchants)
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + Invoice::LEN,
+        seeds = [INVOICE_SEED, vault.key().as_ref(), merchant.key().as_ref(), memo_hash.as_ref()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    /// Record of the mint the invoice is paid in (a registered SPL mint)
+    #[account(
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = supported_mint.bump,
+        constraint = supported_mint.mint != native_mint::ID @ VaultError::MintNotSupported
+    )]
+    pub supported_mint: Account<'info, SupportedMint>,
+
+    /// Invoice mint's Pyth feed, valuing the amount against the order minimum in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), supported_mint.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = merchant,
+        seeds = [INVOICE_SEED, vault.key().as_ref(), merchant.key().as_ref(), invoice.memo_hash.as_ref()],
+        bump = invoice.bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key() @ VaultError::Unauthorized,
+        constraint = payer_token_account.mint == invoice.mint @ VaultError::InvalidPaymentMint
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == merchant.key() @ VaultError::Unauthorized,
+        constraint = merchant_token_account.mint == invoice.mint @ VaultError::InvalidPaymentMint
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    /// Invoice mint's Pyth feed, valuing the payment in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), invoice.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Merchant receiving the payment (and the invoice rent)
+    /// CHECK: Verified via invoice PDA seeds
+    #[account(mut)]
     pub merchant: AccountInfo<'info>,
+
+    /// Any wallet may pay the invoice
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelInvoice<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = merchant,
+        seeds = [INVOICE_SEED, vault.key().as_ref(), merchant.key().as_ref(), invoice.memo_hash.as_ref()],
+        bump = invoice.bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(hold_id: [u8; 32])]
+pub struct AuthorizePayment<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    /// Merchant's deposit (holds are only placed for vault merchants)
+    #[account(seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
+    pub merchant_deposit: Account<'info, MerchantDeposit>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + PaymentHold::LEN,
+        seeds = [
+            PAYMENT_HOLD_SEED,
+            vault.key().as_ref(),
+            merchant.key().as_ref(),
+            buyer.key().as_ref(),
+            hold_id.as_ref(),
+        ],
+        bump
+    )]
+    pub payment_hold: Account<'info, PaymentHold>,
+
+    /// Escrow holding the authorized amount until capture or void
+    #[account(
+        init,
+        payer = buyer,
+        token::mint = mint,
+        token::authority = payment_hold,
+        seeds = [HOLD_ESCROW_SEED, payment_hold.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    pub mint: Box<Account<'info, Mint>>,
+
+    /// Record of the hold mint; holds are only placed in registered SPL mints
+    #[account(
+        seeds = [SUPPORTED_MINT_SEED, vault.key().as_ref(), mint.key().as_ref()],
+        bump = supported_mint.bump,
+        constraint = supported_mint.mint != native_mint::ID @ VaultError::MintNotSupported
+    )]
+    pub supported_mint: Box<Account<'info, SupportedMint>>,
+
+    /// Hold mint's Pyth feed, valuing the amount against the order minimum in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), mint.key().as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Merchant the payment is authorized for
+    /// CHECK: Verified via merchant deposit PDA seeds
+    pub merchant: UncheckedAccount<'info>,
+
+    /// Buyer placing the hold (pays the hold's and escrow's rent)
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CapturePayment<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [
+            PAYMENT_HOLD_SEED,
+            vault.key().as_ref(),
+            merchant.key().as_ref(),
+            buyer.key().as_ref(),
+            payment_hold.hold_id.as_ref(),
+        ],
+        bump = payment_hold.bump
+    )]
+    pub payment_hold: Account<'info, PaymentHold>,
+
+    #[account(
+        mut,
+        seeds = [HOLD_ESCROW_SEED, payment_hold.key().as_ref()],
+        bump = payment_hold.escrow_bump
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [DEPOSIT_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_deposit.bump
+    )]
+    pub merchant_deposit: Box<Account<'info, MerchantDeposit>>,
+
+    /// Hot order metrics (zero-copy companion of the deposit)
+    #[account(
+        mut,
+        seeds = [MERCHANT_METRICS_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = merchant_metrics.load()?.bump
+    )]
+    pub merchant_metrics: AccountLoader<'info, MerchantMetrics>,
+
+    /// Recent yield changes (ring buffer companion of the metrics)
+    #[account(
+        mut,
+        seeds = [YIELD_HISTORY_SEED, vault.key().as_ref(), merchant.key().as_ref()],
+        bump = yield_history.load()?.bump
+    )]
+    pub yield_history: AccountLoader<'info, YieldHistory>,
+
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == merchant.key() @ VaultError::Unauthorized,
+        constraint = merchant_token_account.mint == payment_hold.mint @ VaultError::Unauthorized
+    )]
+    pub merchant_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Hold mint's Pyth feed, valuing the captured amount in USD
+    #[account(
+        seeds = [PRICE_FEED_SEED, vault.key().as_ref(), payment_hold.mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Pyth `PriceUpdateV2` for the feed
+    /// CHECK: Owner, layout and feed id verified in `oracle`
+    pub price_update: AccountInfo<'info>,
+
+    /// Receives whatever isn't captured
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Buyer who placed the hold (gets the hold's and escrow's rent back)
+    /// CHECK: Verified via payment hold PDA seeds
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+
+    pub merchant: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VoidAuthorization<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [
+            PAYMENT_HOLD_SEED,
+            vault.key().as_ref(),
+            payment_hold.merchant.as_ref(),
+            buyer.key().as_ref(),
+            payment_hold.hold_id.as_ref(),
+        ],
+        bump = payment_hold.bump
+    )]
+    pub payment_hold: Account<'info, PaymentHold>,
+
+    #[account(
+        mut,
+        seeds = [HOLD_ESCROW_SEED, payment_hold.key().as_ref()],
+        bump = payment_hold.escrow_bump
+    )]
+    pub escrow_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// Buyer who placed the hold (gets the funds and rent back)
+    /// CHECK: Verified via payment hold PDA seeds
+    #[account(mut)]
+    pub buyer: AccountInfo<'info>,
+
+    /// The merchant, or the buyer once the hold has expired
+    pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSpendingGrant<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + SpendingGrant::LEN,
+        seeds = [SPENDING_GRANT_SEED, vault.key().as_ref(), buyer.key().as_ref(), agent.key().as_ref()],
+        bump
+    )]
+    pub spending_grant: Account<'info, SpendingGrant>,
+
+    #[account(mut, constraint = buyer_token_account.owner == buyer.key() @ VaultError::Unauthorized)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// Agent allowed to spend
+    /// CHECK: Any key; it only has to sign settlements
+    pub agent: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSpendingGrant<'info> {
+    #[account(seeds = [VAULT_SEED, vault.authority.as_ref(), vault.label_seed()], bump = vault.bump)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [SPENDING_GRANT_SEED, vault.key().as_ref(), buyer.key().as_ref(), spending_grant.agent.as_ref()],
+        bump = spending_grant.bump
+    )]
+    pub spending_grant: Account<'info, SpendingGrant>,
+
+    #[account(mut, address = spending_grant.buyer_token_account)]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    /// Authority that can update vault parameters
+    pub authority: Pubkey,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Total deposits in vault
+    pub total_deposits: u64,
+    /// Number of merchants with active deposits
+    pub total_merchants: u32,
+    /// Minimum SOL deposit required
+    pub min_deposit_sol: u64,
+    /// Minimum token deposit required
+    pub min_deposit_token: u64,
+    /// Protocol's cut of harvested yield (basis points, e.g. 2000 = 20%)
+    pub performance_fee_bps: u16,
+    /// Whether staking is enabled
+    pub staking_enabled: bool,
+    /// Buyer cashback rate (basis points of order value, 0 = disabled)
+    pub cashback_bps: u16,
+    /// Share of a referred merchant's platform fees paid to the referrer (basis points)
+    pub referral_fee_bps: u16,
+    /// Lifetime rewards paid out on withdrawal
+    pub total_rewards_paid: u64,
+    /// Sum of principal x yield_bps over active deposits (for average yield)
+    pub weighted_yield_sum: u128,
+    /// APY boost per fixed-term lock (basis points, indexed 30/90/180/365 days)
+    pub lock_boost_schedule: [u16; 4],
+    /// Principal penalty for exiting a lock early (basis points)
+    pub early_exit_penalty_bps: u16,
+    /// Key allowed to toggle emergency mode
+    pub guardian: Pubkey,
+    /// Emergency mode: deposits, withdrawals and accrual halted; principal-only exits
+    pub emergency_mode: bool,
+    /// When emergency mode was last enabled
+    pub emergency_activated_at: i64,
+    /// Share of platform fees routed to the insurance fund (basis points)
+    pub insurance_fee_bps: u16,
+    /// Bond escrowed by the merchant for each registered agent (lamports)
+    pub agent_bond_lamports: u64,
+    /// Number of bonus distributions created (next distribution id)
+    pub distribution_count: u64,
+    /// Length of a withdrawal rate-limit epoch (seconds)
+    pub withdraw_epoch_duration: i64,
+    /// Maximum withdrawn per epoch (0 = no absolute cap)
+    pub withdraw_cap_amount: u64,
+    /// Maximum withdrawn per epoch as basis points of TVL (0 = no TVL cap)
+    pub withdraw_cap_tvl_bps: u16,
+    /// Start of the current withdrawal epoch
+    pub withdraw_epoch_start: i64,
+    /// TVL at the start of the current withdrawal epoch
+    pub withdraw_epoch_tvl: u64,
+    /// Amount withdrawn during the current epoch
+    pub withdrawn_this_epoch: u64,
+    /// Id of the oldest unfulfilled withdrawal ticket
+    pub withdraw_queue_head: u64,
+    /// Id the next withdrawal ticket will get
+    pub withdraw_queue_tail: u64,
+    /// Delay before a proposed config change can execute (0 = setters apply immediately)
+    pub config_timelock: i64,
+    /// Orders a merchant can record per hour (0 = unlimited)
+    pub max_orders_per_hour: u32,
+    /// Order volume per merchant per day credited toward yield and tier (0 = unlimited)
+    pub max_daily_order_volume: u64,
+    /// Window over which repeat purchases from a buyer are dampened (0 = disabled)
+    pub repeat_purchase_window: i64,
+    /// Volume credited for a buyer's 1st, 2nd and later purchases in a window (basis points)
+    pub repeat_purchase_credit_bps: [u16; 3],
+    /// Wallet performance fees are paid to
+    pub treasury: Pubkey,
+    /// Performance fees booked on SOL deposits, not yet collected (lamports)
+    pub performance_fees_sol: u64,
+    /// Performance fees booked on SPL token deposits, not yet collected
+    pub performance_fees_token: u64,
+    /// Lifetime performance fees booked across both assets
+    pub total_performance_fees: u64,
+    /// Start of the current reward epoch
+    pub reward_epoch_start: i64,
+    /// End of the current reward epoch (0 = no reward budget)
+    pub reward_epoch_end: i64,
+    /// Most yield that can accrue across all merchants during the epoch
+    pub reward_epoch_budget: u64,
+    /// Yield accrued against the current epoch's budget
+    pub reward_epoch_accrued: u64,
+    /// Cumulative harvest per unit of SOL principal (Q64.64)
+    pub reward_index_sol: u128,
+    /// Cumulative harvest per unit of SPL token principal (Q64.64)
+    pub reward_index_token: u128,
+    /// Principal of active SOL deposits
+    pub total_sol_deposits: u64,
+    /// Principal of active SPL token deposits
+    pub total_token_deposits: u64,
+    /// Lifetime yield harvested into the vault, before performance fees
+    pub total_harvested: u64,
+    /// How long a merchant keeps a tier after volume dips below it (0 = no grace)
+    pub tier_downgrade_grace: i64,
+    /// Platform fee on settled orders for Bronze, Silver, Gold, Platinum (basis points)
+    pub platform_fee_bps_by_tier: [u16; 4],
+    /// Reject blocked token instructions before `settle_order` in its transaction
+    pub settlement_guard_before: bool,
+    /// Reject blocked token instructions after `settle_order` in its transaction
+    pub settlement_guard_after: bool,
+    /// SPL Token instruction tags the settlement guard rejects (bit n = tag n)
+    pub settlement_blocked_token_ixs: u32,
+    /// Slots a deposit is held before withdrawals and reward claims
+    pub min_holding_slots: u64,
+    /// Most merchant rewards paid out per withdrawal epoch (0 = no cap)
+    pub reward_payout_cap: u64,
+    /// Rewards paid out in the current withdrawal epoch
+    pub rewards_paid_this_epoch: u64,
+    /// Payouts above this multiple of their expected rewards need the guardian (basis points, 0 = off)
+    pub reward_anomaly_multiple_bps: u32,
+    /// When the performance fee was last changed
+    pub performance_fee_updated_at: i64,
+    /// Smallest order credited toward volume metrics (0 = MIN_ORDER_USD, for vaults
+    /// created before it was configurable)
+    pub min_order_usd: u64,
+    /// The vault's address lookup table (default = none, see `create_lookup_table`)
+    pub lookup_table: Pubkey,
+    /// How the vault's SOL is staked
+    pub stake_strategy: StakeStrategy,
+    /// Lifetime MEV tips harvested (lamports; also counted in `total_harvested`)
+    pub total_mev_tips_harvested: u64,
+    /// Sum of the lending strategies' target allocations (basis points)
+    pub lending_allocation_bps: u16,
+    /// Share of SPL deposits kept undeployed for instant withdrawals (basis points)
+    pub liquidity_buffer_bps: u16,
+    /// Principal supplied across the lending strategies
+    pub total_lent: u64,
+    /// When deposit yield accrues
+    pub accrual_schedule: AccrualSchedule,
+    /// Period claimed rewards are streamed over (seconds; 0 = streaming off)
+    pub reward_stream_duration: i64,
+    /// Vault shares can only be transferred to approved holders
+    pub share_transfers_restricted: bool,
+    /// Deposits need a KYC attestation from `kyc_verifier`
+    pub kyc_required: bool,
+    /// Key that issues KYC attestations
+    pub kyc_verifier: Pubkey,
+    /// Program the vault runs, e.g. "us-regulated" (all zero for the authority's default vault)
+    pub label: [u8; VAULT_LABEL_LEN],
+    /// Stablecoins currently outside their depeg bounds (see `check_depeg`)
+    pub depegged_mints: u8,
+    /// When SPL accrual was suspended by the first of them
+    pub depeg_suspended_at: i64,
+    /// Program notified of deposits and settlements (default = none, see `notifier`)
+    pub notifier_program: Pubkey,
+    /// Seconds SOL accrual spent suspended before the running suspension (see `suspended_seconds`)
+    pub sol_suspended_seconds: i64,
+    /// Seconds SPL accrual spent suspended before the running suspension
+    pub spl_suspended_seconds: i64,
+    /// Zeroed space for future fields (see `migrate_vault`)
+    pub _reserved: [u8; 1],
+}
+
+/// Vault address seed for `label`; the zero label adds none, so an authority's
+/// default vault keeps its original address
+pub fn vault_label_seed(label: &[u8; VAULT_LABEL_LEN]) -> &[u8] {
+    if *label == [0; VAULT_LABEL_LEN] {
+        &[]
+    } else {
+        label
+    }
+}
+
+impl Vault {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// The vault's label as an address seed (see `vault_label_seed`)
+    pub fn label_seed(&self) -> &[u8] {
+        vault_label_seed(&self.label)
+    }
+
+    /// The label as text, without its zero padding
+    pub fn label_str(&self) -> String {
+        String::from_utf8_lossy(&self.label).trim_end_matches('\0').to_string()
+    }
+
+    /// When yield accrual of `deposit_token` stopped, if it is suspended: by
+    /// emergency mode, or for SPL deposits by a depegged stablecoin
+    pub fn accrual_suspended_at(&self, deposit_token: &DepositType) -> Option<i64> {
+        let emergency = self.emergency_mode.then_some(self.emergency_activated_at);
+        let depeg = (*deposit_token == DepositType::SplToken && self.depegged_mints > 0)
+            .then_some(self.depeg_suspended_at);
+        match (emergency, depeg) {
+            (Some(emergency), Some(depeg)) => Some(emergency.min(depeg)),
+            (emergency, depeg) => emergency.or(depeg),
+        }
+    }
+
+    /// Seconds `deposit_token` accrual has spent suspended up to `now`, the running
+    /// suspension included; deposits checkpoint it to skip that time when they accrue
+    pub fn suspended_seconds(&self, deposit_token: &DepositType, now: i64) -> i64 {
+        let carried = match deposit_token {
+            DepositType::Sol => self.sol_suspended_seconds,
+            DepositType::SplToken => self.spl_suspended_seconds,
+        };
+        let running = self.accrual_suspended_at(deposit_token).map_or(0, |at| elapsed_seconds(at, now));
+        carried.saturating_add(running)
+    }
+
+    /// Apply a change to what suspends accrual (emergency mode, depegged mints),
+    /// carrying the suspended time so far over so `suspended_seconds` doesn't jump
+    pub fn update_suspension(&mut self, now: i64, change: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let sol = self.suspended_seconds(&DepositType::Sol, now);
+        let spl = self.suspended_seconds(&DepositType::SplToken, now);
+        change(self)?;
+        self.sol_suspended_seconds = self
+            .sol_suspended_seconds
+            .checked_add(sol - self.suspended_seconds(&DepositType::Sol, now))
+            .ok_or(VaultError::MathOverflow)?;
+        self.spl_suspended_seconds = self
+            .spl_suspended_seconds
+            .checked_add(spl - self.suspended_seconds(&DepositType::SplToken, now))
+            .ok_or(VaultError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Platform fee charged on a settled order for a merchant at `tier`
+    pub fn platform_fee_bps(&self, tier: u8) -> u16 {
+        self.platform_fee_bps_by_tier[(tier as usize).min(self.platform_fee_bps_by_tier.len() - 1)]
+    }
+
+    /// Share of a mint's `principal` that must stay idle in the vault under the liquidity buffer
+    pub fn liquidity_buffer(&self, principal: u64) -> Option<u64> {
+        mul_div_floor(principal, self.liquidity_buffer_bps as u64, math::BPS_DENOMINATOR)
+    }
+
+    /// Share of SPL principal not supplied to lending strategies (basis points)
+    pub fn idle_token_bps(&self) -> u16 {
+        if self.total_token_deposits == 0 {
+            return math::BPS_DENOMINATOR as u16;
+        }
+        let idle = self.total_token_deposits.saturating_sub(self.total_lent);
+        mul_div_floor(idle, math::BPS_DENOMINATOR, self.total_token_deposits).unwrap_or(0) as u16
+    }
+
+    /// Reward index of the asset `deposit_token`
+    pub fn reward_index(&self, deposit_token: &DepositType) -> u128 {
+        match deposit_token {
+            DepositType::Sol => self.reward_index_sol,
+            DepositType::SplToken => self.reward_index_token,
+        }
+    }
+
+    /// Principal one vault share of the asset is worth (Q64.64)
+    /// Shares start at par and appreciate with every harvest booked into the
+    /// asset's reward index.
+    pub fn share_price(&self, deposit_token: &DepositType) -> Option<u128> {
+        Q64_ONE.checked_add(self.reward_index(deposit_token))
+    }
+
+    /// Shares `amount` of principal is worth at the current share price
+    pub fn shares_for(&self, deposit_token: &DepositType, amount: u64) -> Option<u64> {
+        q64_div_floor(amount, self.share_price(deposit_token)?)
+    }
+
+    /// Shares that redeem `amount` at the current share price (rounded up, against the holder)
+    pub fn shares_to_redeem(&self, deposit_token: &DepositType, amount: u64) -> Option<u64> {
+        q64_div_ceil(amount, self.share_price(deposit_token)?)
+    }
+
+    /// Principal `shares` are worth at the current share price
+    pub fn share_value(&self, deposit_token: &DepositType, shares: u64) -> Option<u64> {
+        q64_mul_floor(shares, self.share_price(deposit_token)?)
+    }
+
+    /// Smallest order credited toward volume metrics
+    pub fn min_order_usd(&self) -> u64 {
+        if self.min_order_usd == 0 {
+            MIN_ORDER_USD
+        } else {
+            self.min_order_usd
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantDeposit {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault this deposit belongs to
+    pub vault: Pubkey,
+    /// Type of deposit (SOL or SPL Token)
+    pub deposit_token: DepositType,
+    /// Total amount deposited
+    pub total_deposited: u64,
+    /// Accrued merchant rewards (checkpointed by the metrics crank and on withdrawal)
+    pub accrued_rewards: u64,
+    /// Whether deposit is active
+    pub is_active: bool,
+    /// Timestamp of deposit
+    pub deposited_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    // Dynamic yield (order metrics live in MerchantMetrics)
+    /// Current yield in basis points (e.g., 1200 = 12%)
+    pub current_yield_bps: u16,
+
+    // Lock period and profit sharing
+    /// Lock period selection (6mo/1yr/3yr/5yr)
+    pub lock_period: LockPeriod,
+    /// Unlock timestamp (deposited_at + lock_period)
+    pub unlock_time: i64,
+    /// Platform profit earned from this merchant's orders (for profit share calculation)
+    pub platform_profit_earned: u64,
+    /// Profit share bonus allocated (up to 50% of platform profit)
+    pub profit_share_allocated: u64,
+
+    // Maintenance checkpoints (see refresh_merchant_metrics)
+    /// Rewards are accrued into `accrued_rewards` up to this timestamp
+    pub last_accrual_at: i64,
+    /// Tier as of the last metrics refresh (0=Bronze .. 3=Platinum)
+    pub current_tier: u8,
+    /// Timestamp of last metrics refresh
+    pub last_metrics_refresh: i64,
+    /// Yield last folded into the vault's weighted yield sum
+    pub reported_yield_bps: u16,
+
+    // Schema versioning (see migrate_merchant_deposit)
+    /// Layout version, 0 = created before versioning
+    pub version: u8,
+
+    /// Frozen by the vault authority (blocks withdrawals and order recording)
+    pub frozen: bool,
+
+    // Optional fixed-term lock
+    /// Fixed-term lock chosen at deposit (None = no term lock)
+    pub lock_term: Option<LockTerm>,
+    /// Fixed-term lock maturity (0 = no term lock)
+    pub term_maturity: i64,
+    /// APY boost locked in for the term (basis points)
+    pub lock_boost_bps: u16,
+
+    // Delegated withdrawal (see set_withdraw_delegate)
+    /// Key allowed to withdraw/claim for the merchant (default = none)
+    pub withdraw_delegate: Pubkey,
+    /// Delegation expiry timestamp
+    pub delegate_expiry: i64,
+    /// Amount the delegate may still move
+    pub delegate_allowance: u64,
+
+    /// A withdrawal ticket is waiting in the queue for this deposit
+    pub withdrawal_queued: bool,
+
+    /// Share of rewards set aside for the sponsor (basis points, see deposit_for)
+    pub sponsor_share_bps: u16,
+
+    // Harvest distribution (see harvest_rewards)
+    /// Vault reward index this deposit has been credited up to
+    pub reward_index: u128,
+
+    // Tier hysteresis (see refresh_merchant_metrics)
+    /// When volume first dipped below `current_tier` (0 = not in a downgrade grace period)
+    pub tier_grace_started_at: i64,
+
+    // Position NFT (see mint_position_nft)
+    /// Mint of the deposit's position NFT (default = none minted)
+    pub position_mint: Pubkey,
+    /// Position NFT holder as of the last `sync_position_owner`
+    pub position_owner: Pubkey,
+
+    // Sponsorship (see deposit_for)
+    /// Wallet that funded the deposit (default = not sponsored)
+    pub sponsor: Pubkey,
+    /// Sponsor's share of rewards not yet moved to its `Sponsorship`
+    pub sponsor_rewards: u64,
+
+    // Holding period (see require_holding_period)
+    /// Slot of the latest principal added (0 = deposited before the holding period)
+    pub deposited_slot: u64,
+
+    // Statement counters (see roll_monthly_statement)
+    /// Rewards credited to the merchant over the deposit's life
+    pub total_rewards_accrued: u64,
+    /// Platform fees paid on settled orders over the deposit's life (USD micro-units)
+    pub total_fees_paid_usd: u64,
+    /// Claimed rewards in the reward stream not yet paid out (see stream_rewards)
+    pub streaming_rewards: u64,
+    /// Part of `streaming_rewards` already unlocked and waiting to be released
+    pub stream_unlocked: u64,
+    /// Stream unlocked up to this timestamp
+    pub stream_checkpoint_at: i64,
+    /// Everything still locked in the stream unlocks by this timestamp
+    pub stream_ends_at: i64,
+    /// Vault shares minted against the deposit (see tokenize_position), burned to withdraw it
+    pub shares: u64,
+
+    /// Mint the deposit was made in (the native mint for SOL); payouts come from its vault account
+    pub mint: Pubkey,
+    /// Principal counted toward the mint's `SupportedMint` total (see credit_mint_deposits)
+    pub mint_principal: u64,
+    /// Vault's `suspended_seconds` for the deposit's asset as of the last accrual
+    pub suspended_seconds_checkpoint: i64,
+
+    /// Zeroed space for future fields, so they don't need a realloc
+    pub _reserved: [u8; 48],
+}
+
+impl MerchantDeposit {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// v8 layout size (before the deposit mint)
+    pub const V8_LEN: usize = Self::V7_LEN + 64;
+
+    /// v7 layout size (before the reserved space)
+    pub const V7_LEN: usize = Self::V6_LEN + 8;
+
+    /// v6 layout size (before the holding period)
+    pub const V6_LEN: usize = Self::V5_LEN + 32 + 8;
+
+    /// v5 layout size (before sponsorship)
+    pub const V5_LEN: usize = Self::V4_LEN + 32 + 32;
+
+    /// v4 layout size (before the position NFT)
+    pub const V4_LEN: usize = Self::V3_LEN + 8;
+
+    /// v3 layout size (before tier hysteresis)
+    pub const V3_LEN: usize = Self::V2_LEN + 16;
+
+    /// v2 layout size (before the reward index)
+    // 32 + 32 + 1 + 8 + 8 + 1 + 8 + 1 + 2 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 2 + 1 + 1 + 2 + 8 + 2 + 32 + 8 + 8 + 1 + 2
+    pub const V2_LEN: usize = 32 + 32 + 1 + 8 + 8 + 1 + 8 + 1 + 2 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 2 + 1 + 1 + 2 + 8 + 2 + 32 + 8 + 8 + 1 + 2;
+
+    /// v0/v1 layout size: the v2 layout plus the inline order metrics
+    pub const LEGACY_LEN: usize = Self::V2_LEN + LEGACY_METRICS_LEN;
+
+    /// Principal plus accrued and streaming rewards: the most a payout from this deposit can be
+    pub fn attributable_balance(&self) -> Option<u64> {
+        self.total_deposited
+            .checked_add(self.accrued_rewards)?
+            .checked_add(self.streaming_rewards)
+    }
+
+    /// Unlock the reward stream's linear share for the time since its last checkpoint
+    pub fn unlock_reward_stream(&mut self, current_time: i64) -> Result<()> {
+        let locked = self.streaming_rewards.saturating_sub(self.stream_unlocked);
+        let until = current_time.min(self.stream_ends_at);
+        if locked == 0 || until <= self.stream_checkpoint_at {
+            return Ok(());
+        }
+
+        let unlocked = if until == self.stream_ends_at {
+            locked
+        } else {
+            mul_div_floor(
+                locked,
+                (until - self.stream_checkpoint_at) as u64,
+                (self.stream_ends_at - self.stream_checkpoint_at) as u64,
+            )
+            .ok_or(VaultError::MathOverflow)?
+        };
+        self.stream_unlocked = self.stream_unlocked.checked_add(unlocked).ok_or(VaultError::MathOverflow)?;
+        self.stream_checkpoint_at = until;
+        Ok(())
+    }
+
+    /// Stop the reward stream, returning whatever it hasn't paid out to `accrued_rewards`
+    pub fn end_reward_stream(&mut self) -> Result<()> {
+        self.accrued_rewards = self
+            .accrued_rewards
+            .checked_add(self.streaming_rewards)
+            .ok_or(VaultError::MathOverflow)?;
+        self.streaming_rewards = 0;
+        self.stream_unlocked = 0;
+        self.stream_checkpoint_at = 0;
+        self.stream_ends_at = 0;
+        Ok(())
+    }
+
+    /// Wallet holding the deposit's withdrawal rights: the position NFT holder
+    /// once one is minted, otherwise the merchant
+    pub fn position_holder(&self) -> Pubkey {
+        if self.position_mint == Pubkey::default() {
+            self.merchant
+        } else {
+            self.position_owner
+        }
+    }
+}
+
+/// Hot per-merchant order metrics, written on every recorded order
+///
+/// Zero-copy so order recording touches a fixed-offset account instead of
+/// deserializing and reserializing the whole deposit.
+#[account(zero_copy)]
+pub struct MerchantMetrics {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault these metrics belong to
+    pub vault: Pubkey,
+    /// Total orders processed (lifetime)
+    pub total_orders_processed: u64,
+    /// Total volume in USD (micro-units, 6 decimals)
+    pub total_volume_usd: u64,
+    /// Rolling monthly volume in USD (exponentially decayed, see `decay_metric`)
+    pub current_month_volume: u64,
+    /// Timestamp the rolling metrics were last decayed
+    pub last_volume_update: i64,
+    /// Rolling monthly unique customers (simplified tracking, decayed like volume)
+    pub monthly_unique_customers: u32,
+    /// Bump seed for PDA
+    pub bump: u8,
+    pub _padding: [u8; 3],
+    /// Lifetime volume currently held out by open disputes
+    pub disputed_volume_usd: u64,
+    /// Disputes awaiting resolution
+    pub open_disputes: u32,
+    /// Orders recorded in the current hourly window
+    pub hour_order_count: u32,
+    /// Start of the current hourly order window
+    pub order_hour_start: i64,
+    /// Start of the current daily volume window
+    pub order_day_start: i64,
+    /// Volume credited toward rolling volume in the current daily window
+    pub day_volume_credited: u64,
+    /// Start of the current repeat-purchase window
+    pub buyer_window_start: i64,
+    /// Per-buyer purchase counts this window (count-min sketch of 2-bit counters)
+    pub buyer_filter: [u8; BUYER_FILTER_LEN],
+    /// When the merchant paused activity (0 = active, see pause_merchant_activity)
+    pub paused_at: i64,
+    /// Last month closed into a `MonthlyStatement` (see `time::calendar_month`, 0 = none)
+    pub last_statement_month: u32,
+    /// Reserved for future counters
+    pub _reserved: [u8; 4],
+    /// Settled volume per currency, in the order each currency first settled
+    pub currency_volumes: [CurrencyVolume; MAX_TRACKED_CURRENCIES],
+    /// Settled volume in currencies past the tracked ones (USD micro-units)
+    pub other_currency_volume_usd: u64,
+}
+
+/// Volume a merchant has settled in one currency
+#[zero_copy]
+pub struct CurrencyVolume {
+    /// Settlement mint (the native mint for SOL, default = free slot)
+    pub mint: Pubkey,
+    /// Orders settled in this currency
+    pub order_count: u64,
+    /// Amount settled, in the mint's base units
+    pub amount: u64,
+    /// USD value credited for those orders (micro-units, 6 decimals)
+    pub volume_usd: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantProfile {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault this profile belongs to
+    pub vault: Pubkey,
+    /// Hash of the off-chain metadata URI
+    pub metadata_uri_hash: [u8; 32],
+    /// Wallet that receives the merchant's payouts
+    pub payout_wallet: Pubkey,
+    /// Scheduled replacement payout wallet (default = none pending)
+    pub pending_payout_wallet: Pubkey,
+    /// When the pending payout wallet takes effect (0 = none pending)
+    pub payout_wallet_effective_at: i64,
+    /// When the merchant registered
+    pub created_at: i64,
+    /// Agents currently authorized for this merchant (max MAX_PROFILE_AGENTS)
+    #[max_len(MAX_PROFILE_AGENTS)]
+    pub registered_agents: Vec<Pubkey>,
+    /// Tier as of the last withdrawal (0=Bronze .. 3=Platinum)
+    pub current_tier: u8,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Start of continuous deposit history for tiers (0 = never deposited)
+    /// Survives a withdrawal if the merchant redeposits within the grace period
+    pub loyalty_started_at: i64,
+    /// When the merchant last withdrew fully (0 = deposit active)
+    pub last_withdrawn_at: i64,
+}
+
+impl MerchantProfile {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PayoutConfig {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault this config belongs to
+    pub vault: Pubkey,
+    /// Active splits (max MAX_PAYOUT_SPLITS); the payout wallet receives the rest
+    #[max_len(MAX_PAYOUT_SPLITS)]
+    pub splits: Vec<PayoutSplit>,
+    /// Scheduled replacement splits
+    #[max_len(MAX_PAYOUT_SPLITS)]
+    pub pending_splits: Vec<PayoutSplit>,
+    /// When the pending splits take effect (0 = none pending)
+    pub splits_effective_at: i64,
+    /// Accrued rewards that trigger an auto-sweep (0 = disabled)
+    pub sweep_threshold: u64,
+    /// Minimum seconds between auto-sweeps
+    pub sweep_interval: i64,
+    /// Last auto-sweep timestamp
+    pub last_sweep_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PayoutConfig {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// Splits in effect at `current_time`
+    pub fn active_splits(&self, current_time: i64) -> &[PayoutSplit] {
+        if self.splits_effective_at != 0 && current_time >= self.splits_effective_at {
+            &self.pending_splits
+        } else {
+            &self.splits
+        }
+    }
+
+    /// Apply a scheduled split change once its timelock has passed
+    pub fn promote_pending(&mut self, current_time: i64) {
+        if self.splits_effective_at != 0 && current_time >= self.splits_effective_at {
+            self.splits = std::mem::take(&mut self.pending_splits);
+            self.splits_effective_at = 0;
+        }
+    }
+}
+
+/// A beneficiary's share of a merchant's payouts
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
+pub struct PayoutSplit {
+    /// Beneficiary wallet
+    pub wallet: Pubkey,
+    /// Share of each payout (basis points)
+    pub bps: u16,
+}
+
+impl PayoutSplit {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+/// Destinations a merchant's payout wallet and splits may be set to
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalWhitelist {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault this whitelist belongs to
+    pub vault: Pubkey,
+    /// Approved destinations (max MAX_WITHDRAWAL_DESTINATIONS)
+    #[max_len(MAX_WITHDRAWAL_DESTINATIONS)]
+    pub destinations: Vec<WithdrawalDestination>,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl WithdrawalWhitelist {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// `wallet` is listed and its addition timelock has passed
+    pub fn is_approved(&self, wallet: &Pubkey, current_time: i64) -> bool {
+        self.destinations
+            .iter()
+            .any(|destination| destination.wallet == *wallet && current_time >= destination.effective_at)
+    }
+
+    /// List `wallet`, approved from `effective_at`
+    /// Returns false if it was already listed (keeping the earlier approval).
+    pub fn insert(&mut self, wallet: Pubkey, effective_at: i64) -> Result<bool> {
+        if let Some(destination) = self.destinations.iter_mut().find(|destination| destination.wallet == wallet) {
+            destination.effective_at = destination.effective_at.min(effective_at);
+            return Ok(false);
+        }
+        require!(
+            self.destinations.len() < MAX_WITHDRAWAL_DESTINATIONS,
+            VaultError::WithdrawalWhitelistFull
+        );
+        self.destinations.push(WithdrawalDestination { wallet, effective_at });
+        Ok(true)
+    }
+
+    /// Unlist `wallet`; returns false if it wasn't listed
+    pub fn remove(&mut self, wallet: &Pubkey) -> bool {
+        let len = self.destinations.len();
+        self.destinations.retain(|destination| destination.wallet != *wallet);
+        self.destinations.len() < len
+    }
+}
+
+/// A whitelisted payout destination
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
+pub struct WithdrawalDestination {
+    pub wallet: Pubkey,
+    /// When it may first be chosen (0 = from the start)
+    pub effective_at: i64,
+}
+
+/// A merchant's dead-man switch: who takes over the deposit if they go quiet
+#[account]
+#[derive(InitSpace)]
+pub struct RecoveryConfig {
+    /// Merchant public key
+    pub merchant: Pubkey,
+    /// Vault this config belongs to
+    pub vault: Pubkey,
+    /// Key that can recover the deposit
+    pub beneficiary: Pubkey,
+    /// Seconds without a check-in before a recovery can start
+    pub inactivity_period: i64,
+    /// Merchant's last check-in (or config change)
+    pub last_activity_at: i64,
+    /// When the beneficiary started a recovery (0 = none in progress)
+    pub claim_started_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl RecoveryConfig {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// Record merchant activity, cancelling a recovery in progress
+    pub fn check_in(&mut self, current_time: i64) {
+        self.last_activity_at = current_time;
+        self.claim_started_at = 0;
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SubMerchant {
+    /// Parent merchant public key
+    pub merchant: Pubkey,
+    /// Vault this sub-merchant belongs to
+    pub vault: Pubkey,
+    /// Hash of the store/location label (PDA seed)
+    pub label_hash: [u8; 32],
+    /// Orders recorded at this store (lifetime)
+    pub total_orders_processed: u64,
+    /// Volume recorded at this store in USD (micro-units, 6 decimals)
+    pub total_volume_usd: u64,
+    /// Rolling monthly volume at this store (decayed like the parent's)
+    pub current_month_volume: u64,
+    /// Timestamp the rolling volume was last decayed
+    pub last_volume_update: i64,
+    /// When the sub-merchant was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SubMerchant {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    /// Vault this fund insures
+    pub vault: Pubkey,
+    /// Funds currently earmarked for claims (held in the vault's token account)
+    pub balance: u64,
+    /// Lifetime platform fee contributions
+    pub total_contributed: u64,
+    /// Lifetime claims paid out
+    pub total_claims_paid: u64,
+    /// Number of claims paid
+    pub claims_count: u32,
+    /// Balance relative to vault TVL (basis points, 10000 = fully covered)
+    pub coverage_ratio_bps: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl InsuranceFund {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Distribution {
+    /// Vault paying this distribution
+    pub vault: Pubkey,
+    /// Sequential distribution id
+    pub id: u64,
+    /// Root of the (merchant, amount) merkle tree
+    pub merkle_root: [u8; 32],
+    /// Total amount funded for this distribution
+    pub total_amount: u64,
+    /// Amount claimed so far
+    pub total_claimed: u64,
+    /// Number of claims made
+    pub num_claims: u32,
+    /// When the distribution was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Mint the bonuses are paid in
+    pub mint: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct GetMerchantTier<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+impl Distribution {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
 
-    #[account(seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()], bump = merchant_deposit.bump)]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+#[account]
+#[derive(InitSpace)]
+pub struct BonusClaim {
+    /// Distribution claimed from
+    pub distribution: Pubkey,
+    /// Merchant who claimed
+    pub merchant: Pubkey,
+    /// Amount claimed
+    pub amount: u64,
+    /// When the bonus was claimed
+    pub claimed_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
 
-    pub merchant: Signer<'info>,
+impl BonusClaim {
+    pub const LEN: usize = Self::INIT_SPACE;
 }
 
-#[derive(Accounts)]
-pub struct RecordPlatformProfit<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalTicket {
+    /// Vault the withdrawal is queued against
+    pub vault: Pubkey,
+    /// Merchant withdrawing
+    pub merchant: Pubkey,
+    /// Position in the vault's withdrawal queue
+    pub id: u64,
+    /// When the withdrawal was requested
+    pub requested_at: i64,
+    /// Cancelled by the merchant; closed when it reaches the head of the queue
+    pub cancelled: bool,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
 
-    #[account(
-        mut,
-        seeds = [b"deposit", vault.key().as_ref(), merchant.key().as_ref()],
-        bump = merchant_deposit.bump
-    )]
-    pub merchant_deposit: Account<'info, MerchantDeposit>,
+impl WithdrawalTicket {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
 
-    /// Platform authority or agent that processed the order
-    pub platform: Signer<'info>,
+#[account]
+#[derive(InitSpace)]
+pub struct EmissionSchedule {
+    /// Vault issuing the reward token
+    pub vault: Pubkey,
+    /// Protocol reward token mint (authority: reward mint authority PDA)
+    pub reward_mint: Pubkey,
+    /// Length of an emission epoch (seconds)
+    pub epoch_duration: i64,
+    /// Tokens claimable during the current epoch
+    pub epoch_emission: u64,
+    /// Budget reduction applied each epoch (basis points)
+    pub decay_bps: u16,
+    /// Start of the current epoch
+    pub current_epoch_start: i64,
+    /// Tokens claimed during the current epoch
+    pub epoch_emitted: u64,
+    /// Lifetime tokens emitted
+    pub total_emitted: u64,
+    /// Lifetime emission cap
+    pub max_supply: u64,
+    /// Bump of the reward mint authority PDA
+    pub mint_authority_bump: u8,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
 
-    /// Merchant wallet (for verification)
-    /// CHECK: Verified via PDA seeds
-    pub merchant: AccountInfo<'info>,
+impl EmissionSchedule {
+    pub const LEN: usize = Self::INIT_SPACE;
 }
 
-#[derive(Accounts)]
-pub struct RegisterAgent<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+#[account]
+#[derive(InitSpace)]
+pub struct TierBadge {
+    /// Vault that issued the badge
+    pub vault: Pubkey,
+    /// Merchant holding the badge
+    pub merchant: Pubkey,
+    /// Tier the badge certifies (1=Silver, 2=Gold, 3=Platinum)
+    pub tier: u8,
+    /// Badge NFT mint
+    pub mint: Pubkey,
+    /// When the badge was minted
+    pub minted_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
 
-    #[account(
-        init,
-        payer = merchant,
-        space = 8 + AuthorizedAgent::LEN,
-        seeds = [b"agent_auth", vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
-        bump
-    )]
-    pub authorized_agent: Account<'info, AuthorizedAgent>,
+impl TierBadge {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
 
-    /// Agent public key to authorize
-    /// CHECK: Can be any pubkey
-    pub agent: AccountInfo<'info>,
+#[account]
+#[derive(InitSpace)]
+pub struct AuthorizedAgent {
+    /// Merchant who authorized this agent
+    pub merchant: Pubkey,
+    /// Agent public key (payment processor)
+    pub agent: Pubkey,
+    /// Vault this authorization belongs to
+    pub vault: Pubkey,
+    /// When this agent was authorized
+    pub authorized_at: i64,
+    /// Whether this authorization is active
+    pub is_active: bool,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Bond escrowed in this account (lamports above rent)
+    pub bond_lamports: u64,
+    /// When the agent was revoked (0 = never)
+    pub revoked_at: i64,
+    /// Whether the bond was slashed
+    pub slashed: bool,
+}
+
+impl AuthorizedAgent {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BuyerRewards {
+    /// Buyer wallet earning cashback
+    pub buyer: Pubkey,
+    /// Vault this cashback account belongs to
+    pub vault: Pubkey,
+    /// Cashback credited but not yet claimed (USDC micro-units)
+    pub accrued_cashback: u64,
+    /// Lifetime cashback claimed
+    pub total_claimed: u64,
+    /// Timestamp of last claim
+    pub last_claimed_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl BuyerRewards {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralCode {
+    /// Wallet that owns this code and receives referral rewards
+    pub referrer: Pubkey,
+    /// Vault this code belongs to
+    pub vault: Pubkey,
+    /// Number of merchants that deposited with this code
+    pub referred_merchants: u32,
+    /// Referral rewards credited but not yet claimed
+    pub accrued_rewards: u64,
+    /// Lifetime referral rewards claimed
+    pub total_claimed: u64,
+    /// When this code was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ReferralCode {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Referral {
+    /// Referred merchant
+    pub merchant: Pubkey,
+    /// Referrer credited for this merchant
+    pub referrer: Pubkey,
+    /// Vault this referral belongs to
+    pub vault: Pubkey,
+    /// When the referral was recorded
+    pub created_at: i64,
+    /// Lifetime platform fees shared with the referrer
+    pub total_fees_shared: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Referral {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    /// Wallet that funded the merchant's deposit and receives the revenue share
+    pub sponsor: Pubkey,
+    /// Sponsored merchant
+    pub merchant: Pubkey,
+    /// Vault this sponsorship belongs to
+    pub vault: Pubkey,
+    /// Asset of the sponsored deposit (and of the rewards paid)
+    pub deposit_token: DepositType,
+    /// Share of the merchant's rewards owed to the sponsor (basis points)
+    pub share_bps: u16,
+    /// Principal the sponsor funded
+    pub principal: u64,
+    /// Revenue share moved here but not yet claimed
+    pub accrued_rewards: u64,
+    /// Lifetime revenue share claimed
+    pub total_claimed: u64,
+    /// When the sponsored deposit was made
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Mint of the sponsored deposit (the native mint for SOL); rewards are paid in it
+    pub mint: Pubkey,
+}
+
+impl Sponsorship {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    /// Merchant receiving payments
+    pub merchant: Pubkey,
+    /// Buyer being charged
+    pub buyer: Pubkey,
+    /// Vault this subscription belongs to
+    pub vault: Pubkey,
+    /// Buyer token account the subscription PDA is delegate over
+    pub buyer_token_account: Pubkey,
+    /// Amount charged per period (USDC micro-units)
+    pub amount: u64,
+    /// Billing interval in seconds
+    pub interval_seconds: i64,
+    /// When the next charge (or retry) becomes due
+    pub next_payment_at: i64,
+    /// Timestamp of last successful charge
+    pub last_payment_at: i64,
+    /// Successful charges so far
+    pub payments_made: u32,
+    /// Charges covered by the buyer's delegate approval
+    pub max_payments: u32,
+    /// Consecutive failed charge attempts
+    pub failed_attempts: u8,
+    /// Whether the subscription is still billing
+    pub is_active: bool,
+    /// When the subscription was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Invoice {
+    /// Merchant requesting payment
+    pub merchant: Pubkey,
+    /// Vault this invoice belongs to
+    pub vault: Pubkey,
+    /// Amount due (USDC micro-units)
+    pub amount: u64,
+    /// Mint the invoice must be paid in
+    pub mint: Pubkey,
+    /// Hash of the off-chain invoice memo
+    pub memo_hash: [u8; 32],
+    /// Invoice cannot be paid after this timestamp
+    pub expiry: i64,
+    /// When the invoice was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl Invoice {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentHold {
+    /// Merchant the payment is authorized for
+    pub merchant: Pubkey,
+    /// Vault this hold belongs to
+    pub vault: Pubkey,
+    /// Buyer whose funds are held
+    pub buyer: Pubkey,
+    /// Mint of the held tokens
+    pub mint: Pubkey,
+    /// Buyer-chosen reference (e.g. hash of the booking)
+    pub hold_id: [u8; 32],
+    /// Amount held in escrow (USDC micro-units)
+    pub amount: u64,
+    /// Hold can't be captured after this timestamp
+    pub expiry: i64,
+    /// When the payment was authorized
+    pub authorized_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Bump seed of the escrow token account
+    pub escrow_bump: u8,
+}
+
+impl PaymentHold {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SpendingGrant {
+    /// Buyer whose funds the agent may spend
+    pub buyer: Pubkey,
+    /// Agent allowed to settle orders for the buyer
+    pub agent: Pubkey,
+    /// Vault this grant belongs to
+    pub vault: Pubkey,
+    /// Buyer token account the grant PDA is delegate over
+    pub buyer_token_account: Pubkey,
+    /// Mint of that token account
+    pub mint: Pubkey,
+    /// Most the agent may spend per period (base units, platform fee included)
+    pub period_cap: u64,
+    /// Period length in seconds
+    pub period_seconds: i64,
+    /// Start of the current period
+    pub period_start: i64,
+    /// Spent so far in the current period
+    pub spent_in_period: u64,
+    /// Spent over the grant's lifetime
+    pub total_spent: u64,
+    /// When the grant was created
+    pub created_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl SpendingGrant {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// Count `amount` against the period cap, first starting a new period if the
+    /// current one has ended (periods stay aligned to `created_at`)
+    pub fn record_spend(&mut self, amount: u64, current_time: i64) -> Result<()> {
+        let elapsed = current_time.saturating_sub(self.period_start);
+        if elapsed >= self.period_seconds {
+            self.period_start = current_time - elapsed % self.period_seconds;
+            self.spent_in_period = 0;
+        }
+
+        let spent = self.spent_in_period.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        require!(spent <= self.period_cap, VaultError::SpendingCapExceeded);
+        self.spent_in_period = spent;
+        self.total_spent = self.total_spent.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Wallet allowed to receive vault shares while transfers are restricted
+#[account]
+#[derive(InitSpace)]
+pub struct ApprovedHolder {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    pub approved_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ApprovedHolder {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+/// A verifier's attestation that a wallet passed KYC, checked by deposits into
+/// `kyc_required` vaults
+#[account]
+#[derive(InitSpace)]
+pub struct KycAttestation {
+    pub vault: Pubkey,
+    pub wallet: Pubkey,
+    /// Verifier that issued it; attestations only count while it is the vault's verifier
+    pub verifier: Pubkey,
+    pub issued_at: i64,
+    /// When it stops counting (0 = never)
+    pub expires_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl KycAttestation {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OrderReceipt {
+    /// Merchant the order was credited to
+    pub merchant: Pubkey,
+    /// Vault this receipt belongs to
+    pub vault: Pubkey,
+    /// Agent that recorded the order
+    pub agent: Pubkey,
+    /// Buyer wallet
+    pub buyer: Pubkey,
+    /// Off-chain order identifier
+    pub order_id: [u8; 32],
+    /// Order amount in USD (micro-units, 6 decimals)
+    pub amount_usd: u64,
+    /// When the order was recorded
+    pub recorded_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Metadata hash of the buyer's cNFT receipt (zero = not minted)
+    pub cnft_metadata_hash: [u8; 32],
+    /// Hash of the off-chain order contents (cart, invoice) committed when the
+    /// order was recorded (zero = none)
+    pub metadata_hash: [u8; 32],
+    /// Mint the order was paid in by `settle_order` (default = agent-recorded)
+    pub settled_mint: Pubkey,
+    /// Amount paid, in the settled mint's base units (0 = agent-recorded)
+    pub settled_amount: u64,
+    /// Amount refunded so far (see refund_order), in the settled mint's base units
+    pub refunded_amount: u64,
+    /// USD value taken back off the merchant's volume by those refunds (micro-units)
+    pub refunded_usd: u64,
+}
+
+impl OrderReceipt {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OrderDispute {
+    /// Receipt of the disputed order
+    pub order_receipt: Pubkey,
+    /// Merchant the order was credited to
+    pub merchant: Pubkey,
+    /// Vault this dispute belongs to
+    pub vault: Pubkey,
+    /// Buyer or platform authority that opened the dispute
+    pub opened_by: Pubkey,
+    /// Disputed order amount in USD, net of refunds (micro-units, 6 decimals)
+    pub amount_usd: u64,
+    /// Rolling monthly volume removed when the dispute was opened
+    pub held_volume: u64,
+    /// Hash of the off-chain dispute reason/evidence
+    pub reason_hash: [u8; 32],
+    /// Current status
+    pub status: DisputeStatus,
+    /// When the dispute was opened
+    pub opened_at: i64,
+    /// When the dispute was resolved (0 = open)
+    pub resolved_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl OrderDispute {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EpochRecord {
+    /// Vault whose harvests these are
+    pub vault: Pubkey,
+    /// Solana epoch recorded
+    pub epoch: u64,
+    /// When the epoch started
+    pub started_at: i64,
+    /// SOL harvested during the epoch (lamports)
+    pub harvested_sol: u64,
+    /// SPL tokens harvested during the epoch
+    pub harvested_token: u64,
+    /// SOL reward index increase from those harvests (Q64.64)
+    pub reward_index_delta_sol: u128,
+    /// SPL token reward index increase from those harvests (Q64.64)
+    pub reward_index_delta_token: u128,
+    /// Harvests recorded
+    pub harvest_count: u32,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl EpochRecord {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+/// A merchant's activity over one calendar month (immutable once rolled)
+#[account]
+#[derive(InitSpace)]
+pub struct MonthlyStatement {
+    /// Merchant the statement is for
+    pub merchant: Pubkey,
+    /// Vault the merchant belongs to
+    pub vault: Pubkey,
+    /// Calendar month covered (see `time::calendar_month`)
+    pub month: u32,
+    /// When the previous statement was rolled (0 = since registration)
+    pub opened_at: i64,
+    /// When this statement was rolled
+    pub closed_at: i64,
+    /// Order volume recorded in the period, net of refunds and disputes (USD micro-units, floored at 0)
+    pub volume_usd: u64,
+    /// Orders recorded in the period
+    pub orders: u64,
+    /// Rolling monthly unique customers when the statement was rolled
+    pub unique_customers: u32,
+    /// Rewards credited to the merchant in the period
+    pub rewards_accrued: u64,
+    /// Platform fees paid on settled orders in the period (USD micro-units)
+    pub fees_paid_usd: u64,
+    /// Lifetime order volume at close (USD micro-units)
+    pub cumulative_volume_usd: u64,
+    /// Lifetime orders at close
+    pub cumulative_orders: u64,
+    /// `deposited_at` of the deposit the reward and fee totals were read from (0 = none)
+    pub deposit_started_at: i64,
+    /// That deposit's lifetime rewards at close
+    pub cumulative_rewards_accrued: u64,
+    /// That deposit's lifetime platform fees at close (USD micro-units)
+    pub cumulative_fees_paid_usd: u64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl MonthlyStatement {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LendingStrategy {
+    /// Vault supplying to the reserve
+    pub vault: Pubkey,
+    /// Lending program owning the market and reserve
+    pub lending_program: Pubkey,
+    pub lending_market: Pubkey,
+    /// Lending market PDA owning the reserve's token accounts
+    pub lending_market_authority: Pubkey,
+    pub reserve: Pubkey,
+    /// Reserve token account holding supplied liquidity
+    pub reserve_liquidity_supply: Pubkey,
+    /// Token supplied (a 6-decimal stablecoin such as USDC)
+    pub liquidity_mint: Pubkey,
+    /// Mint of the reserve's collateral (cToken) receipts
+    pub collateral_mint: Pubkey,
+    /// Principal currently supplied, in liquidity mint units
+    pub supplied: u64,
+    /// Collateral held by the vault for it
+    pub collateral: u64,
+    /// Lifetime interest realized and booked into the reward index
+    pub total_interest_harvested: u64,
+    /// When interest was last booked
+    pub last_harvest_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Share of the vault's SPL deposits to keep supplied (basis points)
+    pub target_allocation_bps: u16,
+    /// May call `rebalance` besides the authority (default = authority only)
+    pub rebalance_operator: Pubkey,
+    /// Most principal a single rebalance moves (0 = unbounded)
+    pub max_rebalance_amount: u64,
+    /// When the strategy was last rebalanced
+    pub last_rebalanced_at: i64,
+    /// When the guardian unwound the strategy (0 = active)
+    pub exited_at: i64,
+}
+
+impl LendingStrategy {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// Accounts the lending program instructions touch
+    pub fn reserve_accounts(&self) -> lending::Reserve {
+        lending::Reserve {
+            program: self.lending_program,
+            lending_market: self.lending_market,
+            lending_market_authority: self.lending_market_authority,
+            reserve: self.reserve,
+            liquidity_supply: self.reserve_liquidity_supply,
+            liquidity_mint: self.liquidity_mint,
+            collateral_mint: self.collateral_mint,
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    /// Vault this feed belongs to
+    pub vault: Pubkey,
+    /// Settlement mint (the native mint for SOL)
+    pub mint: Pubkey,
+    /// Pyth price feed id for the mint in USD
+    pub feed_id: [u8; 32],
+    /// Oldest price accepted, in seconds before the settlement
+    pub max_age: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Decimals of the mint, stored at registration
+    pub decimals: u8,
+}
+
+impl PriceFeed {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    /// USD value (micro-units) of `amount` base units at `price`, rounded down
+    pub fn usd_value(&self, amount: u64, price: oracle::OraclePrice) -> Option<u64> {
+        oracle::usd_value(amount, price, self.decimals)
+    }
+
+    /// USD value (micro-units) of `amount` base units at the conservative price
+    /// of a verified `PriceUpdateV2` for this feed
+    pub fn verified_usd_value(&self, price_update: &AccountInfo, amount: u64, current_time: i64) -> Result<u64> {
+        let price_update = oracle::load_price_update(price_update)?;
+        let price = oracle::conservative_price(&price_update, &self.feed_id, self.max_age, current_time)?;
+        Ok(self.usd_value(amount, price).ok_or(VaultError::MathOverflow)?)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SupportedMint {
+    /// Vault this flag belongs to
+    pub vault: Pubkey,
+    /// Deposit mint (the native mint for SOL)
+    pub mint: Pubkey,
+    /// New deposits of the mint are refused; withdrawals are unaffected
+    pub deposits_paused: bool,
+    /// When deposits were last paused
+    pub paused_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+    /// Lowest healthy oracle price, micro-USD per whole token (0 with `depeg_upper_bound` = no band)
+    pub depeg_lower_bound: u64,
+    /// Highest healthy oracle price, micro-USD per whole token
+    pub depeg_upper_bound: u64,
+    /// The last `check_depeg` found the price outside the band
+    pub depegged: bool,
+    /// When the mint last left its band
+    pub depegged_at: i64,
+    /// Principal deposited in this mint and not yet withdrawn in it (SOL and wSOL under the native mint)
+    pub total_token_deposits: u64,
+    /// Principal of this mint supplied to lending reserves and not yet redeemed
+    pub total_lent: u64,
+}
+
+impl SupportedMint {
+    pub const LEN: usize = Self::INIT_SPACE;
+
+    pub fn has_depeg_bounds(&self) -> bool {
+        self.depeg_upper_bound > 0
+    }
+
+    /// `price` (micro-USD per whole token) lies outside the band
+    pub fn is_out_of_bounds(&self, price: u64) -> bool {
+        price < self.depeg_lower_bound || price > self.depeg_upper_bound
+    }
+
+    /// New deposits of the mint are neither paused nor depegged
+    pub fn require_deposits_open(&self) -> Result<()> {
+        require!(!self.deposits_paused, VaultError::MintDepositsPaused);
+        require!(!self.depegged, VaultError::MintDepegged);
+        Ok(())
+    }
+
+    /// Count `amount` of new principal toward the mint
+    pub fn credit_deposits(&mut self, amount: u64) -> Result<()> {
+        self.total_token_deposits = self
+            .total_token_deposits
+            .checked_add(amount)
+            .ok_or(VaultError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ConfigProposal {
+    /// Vault the change applies to
+    pub vault: Pubkey,
+    /// Authority that proposed the change (refunded the rent on close)
+    pub proposer: Pubkey,
+    /// The pending change
+    pub change: ConfigChange,
+    /// When the change was proposed
+    pub proposed_at: i64,
+    /// Earliest time the change can execute
+    pub executable_at: i64,
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ConfigProposal {
+    pub const LEN: usize = Self::INIT_SPACE;
+}
 
-    /// Merchant authorizing the agent
-    #[account(mut)]
-    pub merchant: Signer<'info>,
+/// Merchant status returned by `get_merchant_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MerchantStatus {
+    /// Whether the deposit is active
+    pub is_active: bool,
+    /// Principal deposited
+    pub total_deposited: u64,
+    /// Merchant rewards accrued as of now (merchant share applied)
+    pub accrued_rewards: u64,
+    /// Yield as of now in basis points
+    pub current_yield_bps: u16,
+    /// Tier as of now (0=Bronze .. 3=Platinum)
+    pub tier: u8,
+    /// Rolling monthly volume as of now
+    pub monthly_volume_usd: u64,
+    /// Unlock timestamp
+    pub unlock_time: i64,
+    /// Whether the lock period is still running
+    pub is_locked: bool,
+    /// Overall health
+    pub health: MerchantHealth,
+}
 
-    pub system_program: Program<'info, System>,
+/// Deposit health returned by `get_deposit_health`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DepositHealth {
+    /// Whether the deposit is active
+    pub is_active: bool,
+    /// Frozen by the vault authority (blocks withdrawals and order recording)
+    pub frozen: bool,
+    /// The vault is in emergency mode (only `emergency_withdraw` is open)
+    pub emergency_mode: bool,
+    /// Whether the lock period or fixed term is still running
+    pub is_locked: bool,
+    /// Unlock timestamp (the later of lock period and fixed term)
+    pub unlock_time: i64,
+    /// A withdrawal ticket is waiting in the queue (a deposit has at most one)
+    pub withdrawal_queued: bool,
+    /// Principal deposited
+    pub total_deposited: u64,
+    /// Merchant rewards accrued as of now (merchant share applied)
+    pub accrued_rewards: u64,
+    /// Tier as of now (0=Bronze .. 3=Platinum)
+    pub tier: u8,
+    /// Yield as of now in basis points
+    pub current_yield_bps: u16,
+    /// Overall health
+    pub health: MerchantHealth,
 }
 
-#[derive(Accounts)]
-pub struct RevokeAgent<'info> {
-    #[account(seeds = [b"vault", vault.authority.as_ref()], bump = vault.bump)]
-    pub vault: Account<'info, Vault>,
+/// Books and balances returned by `assert_invariants`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AccountingReport {
+    /// Principal of active deposits across both assets
+    pub total_deposits: u64,
+    /// Lamports in the vault PDA above its rent reserve
+    pub sol_assets: u64,
+    /// SOL principal plus uncollected SOL performance fees
+    pub sol_liabilities: u64,
+    /// Vault token account balances plus principal lent out
+    pub token_assets: u64,
+    /// SPL principal plus uncollected SPL performance fees
+    pub token_liabilities: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"agent_auth", vault.key().as_ref(), merchant.key().as_ref(), agent.key().as_ref()],
-        bump = authorized_agent.bump
-    )]
-    pub authorized_agent: Account<'info, AuthorizedAgent>,
+/// Withdrawal preview returned by `preview_withdraw`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WithdrawalPreview {
+    /// Instruction that applies right now
+    pub route: WithdrawalRoute,
+    /// Principal deposited
+    pub principal: u64,
+    /// Merchant rewards accrued as of now (performance fee and sponsor share taken)
+    pub accrued_rewards: u64,
+    /// Rewards included in the payout (forfeited unless the route is `Withdraw`)
+    pub rewards_paid: u64,
+    /// Performance fee on the yield accrued since the last checkpoint
+    pub performance_fee: u64,
+    /// Sponsor's share of rewards, paid to the sponsorship rather than the merchant
+    pub sponsor_rewards: u64,
+    /// Early exit penalty taken from principal (`WithdrawEarly` only)
+    pub early_exit_penalty: u64,
+    /// Amount paid to the payout wallet (and its splits)
+    pub total_payout: u64,
+    /// When the lock period and fixed term have both ended
+    pub unlock_time: i64,
+    /// Whether the lock period or fixed term is still running
+    pub is_locked: bool,
+    /// First slot at which the holding period allows a withdrawal
+    pub holding_until_slot: u64,
+    /// First check the withdrawal would fail right now (none = it would succeed)
+    pub blocker: Option<WithdrawalBlocker>,
+}
 
-    /// Agent to revoke
-    /// CHECK: Verified via PDA seeds
-    pub agent: AccountInfo<'info>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithdrawalRoute {
+    Withdraw,          // Unlocked: principal plus rewards
+    WithdrawEarly,     // Locked: principal less the penalty, rewards forfeited
+    EmergencyWithdraw, // Emergency mode: principal only
+}
 
-    /// Merchant revoking the agent
-    pub merchant: Signer<'info>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithdrawalBlocker {
+    Inactive,         // Deposit already withdrawn
+    Frozen,           // Frozen by the vault authority
+    HoldingPeriod,    // Deposited too few slots ago
+    GuardianRequired, // Rewards flagged by the circuit breaker; the guardian must co-sign
+    RewardPayoutCap,  // Epoch reward payout cap reached
+    WithdrawalQueue,  // Queued withdrawals go first
+    WithdrawalCap,    // Epoch withdrawal cap reached
 }
 
-// ============================================================================
-// Account Structures
-// ============================================================================
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum MerchantHealth {
+    Healthy,
+    MetricsStale, // No orders or metric refresh within METRICS_STALE_AFTER
+    Inactive,     // Deposit withdrawn
+    Frozen,       // Frozen by the vault authority
+    Paused,       // Paused by the merchant (pause_merchant_activity)
+}
 
-#[account]
-pub struct Vault {
-    /// Authority that can update vault parameters
-    pub authority: Pubkey,
+/// A single order in a `record_orders_batch` call
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OrderInput {
+    /// Off-chain order identifier (receipt PDA seed)
+    pub order_id: [u8; 32],
+    /// Order amount in USD (micro-units, 6 decimals)
+    pub amount_usd: u64,
+    /// Buyer wallet
+    pub buyer_wallet: Pubkey,
+    /// Hash of the off-chain order contents (zero = none)
+    pub metadata_hash: [u8; 32],
+}
+
+#[account(zero_copy)]
+pub struct VaultStats {
+    /// Vault these statistics belong to
+    pub vault: Pubkey,
+    /// Timestamp of the most recent snapshot
+    pub last_snapshot_at: i64,
+    /// `vault.total_rewards_paid` at the most recent snapshot
+    pub last_rewards_paid: u64,
+    /// Index the next snapshot is written to
+    pub head: u32,
+    /// Number of valid snapshots (saturates at capacity)
+    pub count: u32,
     /// Bump seed for PDA
     pub bump: u8,
-    /// Total deposits in vault
-    pub total_deposits: u64,
-    /// Number of merchants with active deposits
-    pub total_merchants: u32,
-    /// Minimum SOL deposit required
-    pub min_deposit_sol: u64,
-    /// Minimum token deposit required
-    pub min_deposit_token: u64,
-    /// Reward share rate for merchants (basis points, e.g. 8000 = 80%)
-    pub reward_share_rate: u16,
-    /// Whether staking is enabled
-    pub staking_enabled: bool,
+    pub _padding: [u8; 7],
+    /// Daily snapshots, oldest overwritten first
+    pub snapshots: [StatsSnapshot; VAULT_STATS_CAPACITY],
 }
 
-impl Vault {
-    pub const LEN: usize = 32 + 1 + 8 + 4 + 8 + 8 + 2 + 1;
+#[zero_copy]
+pub struct StatsSnapshot {
+    /// When the snapshot was taken
+    pub timestamp: i64,
+    /// Total value locked
+    pub tvl: u64,
+    /// Rewards paid out since the previous snapshot
+    pub rewards_paid: u64,
+    /// Active merchants
+    pub total_merchants: u32,
+    /// Principal-weighted average yield (basis points)
+    pub average_yield_bps: u16,
+    /// Share of SPL principal left undeployed (basis points; 0 in snapshots
+    /// taken before it was recorded)
+    pub idle_token_bps: u16,
 }
 
-#[account]
-pub struct MerchantDeposit {
-    /// Merchant public key
-    pub merchant: Pubkey,
-    /// Vault this deposit belongs to
+#[account(zero_copy)]
+pub struct AuditLog {
+    /// Vault whose governance history this is
     pub vault: Pubkey,
-    /// Type of deposit (SOL or SPL Token)
-    pub deposit_token: DepositType,
-    /// Total amount deposited
-    pub total_deposited: u64,
-    /// Accrued rewards (updated on withdrawal)
-    pub accrued_rewards: u64,
-    /// Whether deposit is active
-    pub is_active: bool,
-    /// Timestamp of deposit
-    pub deposited_at: i64,
+    /// Entries ever recorded (the next entry's `seq`)
+    pub total_entries: u64,
+    /// Index the next entry is written to
+    pub head: u32,
+    /// Number of valid entries (saturates at capacity)
+    pub count: u32,
     /// Bump seed for PDA
     pub bump: u8,
+    pub _padding: [u8; 7],
+    /// Authority actions, oldest overwritten first
+    pub entries: [AuditEntry; AUDIT_LOG_CAPACITY],
+}
 
-    // Performance metrics for dynamic yield
-    /// Total orders processed (lifetime)
-    pub total_orders_processed: u64,
-    /// Total volume in USD (micro-units, 6 decimals)
-    pub total_volume_usd: u64,
-    /// Current month volume in USD
-    pub current_month_volume: u64,
-    /// Timestamp of last volume reset
-    pub last_volume_reset: i64,
-    /// Monthly unique customers (simplified tracking)
-    pub monthly_unique_customers: u32,
-    /// Current yield in basis points (e.g., 1200 = 12%)
-    pub current_yield_bps: u16,
+#[zero_copy]
+pub struct AuditEntry {
+    /// Position in the vault's audit history, from 0
+    pub seq: u64,
+    /// When the action was taken
+    pub timestamp: i64,
+    /// Signer that took the action (authority, guardian or proposer)
+    pub actor: Pubkey,
+    /// Account the action applied to (merchant, agent, mint, proposal...), if any
+    pub target: Pubkey,
+    /// Amount moved or configured, if any
+    pub amount: u64,
+    /// `AuditAction` code
+    pub action: u16,
+    /// Action-specific detail (see `AuditAction`)
+    pub detail: u16,
+    pub _padding: [u8; 4],
+}
 
-    // Lock period and profit sharing
-    /// Lock period selection (6mo/1yr/3yr/5yr)
-    pub lock_period: LockPeriod,
-    /// Unlock timestamp (deposited_at + lock_period)
-    pub unlock_time: i64,
-    /// Platform profit earned from this merchant's orders (for profit share calculation)
-    pub platform_profit_earned: u64,
-    /// Profit share bonus allocated (up to 50% of platform profit)
-    pub profit_share_allocated: u64,
+/// Authority actions recorded in the audit log; the discriminant is the stored code
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuditAction {
+    ConfigApplied,             // detail: ConfigChange code
+    ConfigProposed,            // target: proposal, detail: ConfigChange code
+    ConfigCancelled,           // target: proposal, detail: ConfigChange code
+    EmergencyMode,             // detail: 1 enabled, 0 disabled
+    RewardEpochFunded,         // amount: budget
+    AgentSlashed,              // target: agent, amount: bond slashed
+    PriceFeedRegistered,       // target: mint, amount: max age
+    PriceFeedUpdated,          // target: mint, amount: max age
+    DisputeResolved,           // target: merchant, amount: USD, detail: DisputeStatus
+    InsuranceClaimPaid,        // target: merchant, amount: paid
+    PerformanceFeesCollected,  // target: treasury, amount: paid, detail: DepositType
+    RewardsHarvested,          // amount: harvested, detail: DepositType
+    DistributionCreated,       // target: distribution, amount: total
+    RewardEmissionInitialized, // target: reward mint, amount: per epoch, detail: decay bps
+    EmissionScheduleSet,       // target: reward mint, amount: per epoch, detail: decay bps
+    MerchantFrozen,            // target: merchant
+    MerchantUnfrozen,          // target: merchant
+    LendingMarketApproved,     // target: reserve
+    LendingSupplied,           // target: reserve, amount: supplied
+    LendingRedeemed,           // target: reserve, amount: received
+    LendingAllocationSet,      // target: reserve, amount: max rebalance, detail: target bps
+    StrategyExited,            // target: reserve, amount: received
+    ShareMintInitialized,      // target: share mint, detail: DepositType
+    ShareHolderApproved,       // target: wallet
+    ShareHolderRevoked,        // target: wallet
+    MintDepositsPaused,        // target: mint, detail: 1 paused, 0 resumed
+    DepegBoundsSet,            // target: mint, amount: lower bound
 }
 
-impl MerchantDeposit {
-    // 32 + 32 + 1 + 8 + 8 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 4 + 2 + 1 + 8 + 8 + 8
-    pub const LEN: usize = 32 + 32 + 1 + 8 + 8 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 4 + 2 + 1 + 8 + 8 + 8;
+impl AuditAction {
+    const ALL: [AuditAction; 27] = [
+        AuditAction::ConfigApplied,
+        AuditAction::ConfigProposed,
+        AuditAction::ConfigCancelled,
+        AuditAction::EmergencyMode,
+        AuditAction::RewardEpochFunded,
+        AuditAction::AgentSlashed,
+        AuditAction::PriceFeedRegistered,
+        AuditAction::PriceFeedUpdated,
+        AuditAction::DisputeResolved,
+        AuditAction::InsuranceClaimPaid,
+        AuditAction::PerformanceFeesCollected,
+        AuditAction::RewardsHarvested,
+        AuditAction::DistributionCreated,
+        AuditAction::RewardEmissionInitialized,
+        AuditAction::EmissionScheduleSet,
+        AuditAction::MerchantFrozen,
+        AuditAction::MerchantUnfrozen,
+        AuditAction::LendingMarketApproved,
+        AuditAction::LendingSupplied,
+        AuditAction::LendingRedeemed,
+        AuditAction::LendingAllocationSet,
+        AuditAction::StrategyExited,
+        AuditAction::ShareMintInitialized,
+        AuditAction::ShareHolderApproved,
+        AuditAction::ShareHolderRevoked,
+        AuditAction::MintDepositsPaused,
+        AuditAction::DepegBoundsSet,
+    ];
+
+    /// Decode an `AuditEntry::action` code
+    pub fn from_code(code: u16) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
 }
 
-#[account]
-pub struct AuthorizedAgent {
-    /// Merchant who authorized this agent
+#[account(zero_copy)]
+pub struct YieldHistory {
+    /// Merchant whose yield this tracks
     pub merchant: Pubkey,
-    /// Agent public key (payment processor)
-    pub agent: Pubkey,
-    /// Vault this authorization belongs to
+    /// Vault the merchant belongs to
     pub vault: Pubkey,
-    /// When this agent was authorized
-    pub authorized_at: i64,
-    /// Whether this authorization is active
-    pub is_active: bool,
+    /// Changes ever recorded
+    pub total_entries: u64,
+    /// Index the next entry is written to
+    pub head: u32,
+    /// Number of valid entries (saturates at capacity)
+    pub count: u32,
     /// Bump seed for PDA
     pub bump: u8,
+    pub _padding: [u8; 7],
+    /// Yield changes, oldest overwritten first
+    pub entries: [YieldChange; YIELD_HISTORY_CAPACITY],
 }
 
-impl AuthorizedAgent {
-    pub const LEN: usize = 32 + 32 + 32 + 8 + 1 + 1;
+#[zero_copy]
+pub struct YieldChange {
+    /// When the yield was recalculated
+    pub timestamp: i64,
+    /// Yield before the recalculation (0 when a deposit opens)
+    pub previous_bps: u16,
+    /// Yield after the recalculation
+    pub yield_bps: u16,
+    /// `YieldTrigger` code
+    pub trigger: u8,
+    pub _padding: [u8; 3],
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+/// What recalculated a merchant's yield; the discriminant is the stored code
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum YieldTrigger {
+    Deposit,         // deposit opened at its starting yield
+    Orders,          // order volume credited (agent, attested, settled, subscription, invoice)
+    MetricsRefresh,  // metrics crank decayed volume or moved the tier
+    Compound,        // compounded rewards grew the principal
+    PlatformProfit,  // platform profit share allocated
+    DisputeOpened,   // disputed volume held back
+    DisputeResolved, // held volume restored or dropped
+    PositionSplit,   // deposit split off to this merchant
+    PositionsMerged, // another deposit merged into this one
+    OrderRefunded,   // refunded volume taken back
+}
+
+impl YieldTrigger {
+    const ALL: [YieldTrigger; 10] = [
+        YieldTrigger::Deposit,
+        YieldTrigger::Orders,
+        YieldTrigger::MetricsRefresh,
+        YieldTrigger::Compound,
+        YieldTrigger::PlatformProfit,
+        YieldTrigger::DisputeOpened,
+        YieldTrigger::DisputeResolved,
+        YieldTrigger::PositionSplit,
+        YieldTrigger::PositionsMerged,
+        YieldTrigger::OrderRefunded,
+    ];
+
+    /// Decode a `YieldChange::trigger` code
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
 pub enum DepositType {
     Sol,
     SplToken,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+impl DepositType {
+    /// Seed telling the asset's share mint apart (see SHARE_MINT_SEED)
+    pub fn share_mint_seed(&self) -> &'static [u8] {
+        match self {
+            DepositType::Sol => b"sol",
+            DepositType::SplToken => b"token",
+        }
+    }
+
+    /// Decimals of the asset's principal, and so of its vault shares
+    pub fn decimals(&self) -> u8 {
+        match self {
+            DepositType::Sol => SOL_DECIMALS,
+            DepositType::SplToken => CANONICAL_DECIMALS,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq, Debug)]
+pub enum StakeStrategy {
+    Native, // Plain native stake: inflation rewards only
+    Jito,   // Jito validators: inflation rewards plus MEV tips
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq, Debug)]
+pub enum AccrualSchedule {
+    Daily,       // Whole 86400-second days
+    SolanaEpoch, // Up to the start of the current Solana epoch, when staking rewards land
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq, Debug)]
+pub enum HarvestSource {
+    Inflation,       // Staking inflation rewards (and other yield earned outside the vault)
+    MevTips,         // MEV tips claimed from the tip distribution program (Jito strategy)
+    LendingInterest, // Interest realized from the lending strategy
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq, Debug)]
+pub enum RebalanceDirection {
+    Supply, // Idle tokens supplied to the lending reserve
+    Redeem, // Collateral redeemed back to the idle balance
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
 pub enum LockPeriod {
     SixMonths,    // 180 days, max 5% APY
     OneYear,      // 365 days, max 6.5% APY
@@ -991,6 +15957,434 @@ impl LockPeriod {
     }
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
+pub enum LockTerm {
+    Days30,
+    Days90,
+    Days180,
+    Days365,
+}
+
+impl LockTerm {
+    /// Get term duration in seconds
+    pub fn duration_seconds(&self) -> i64 {
+        match self {
+            LockTerm::Days30 => 30 * 86400,
+            LockTerm::Days90 => 90 * 86400,
+            LockTerm::Days180 => 180 * 86400,
+            LockTerm::Days365 => 365 * 86400,
+        }
+    }
+
+    /// Index into `Vault::lock_boost_schedule`
+    pub fn schedule_index(&self) -> usize {
+        match self {
+            LockTerm::Days30 => 0,
+            LockTerm::Days90 => 1,
+            LockTerm::Days180 => 2,
+            LockTerm::Days365 => 3,
+        }
+    }
+}
+
+/// A vault config change, applied directly or through a timelocked proposal
+/// Each variant mirrors one of the admin config setters
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq, Debug)]
+pub enum ConfigChange {
+    VaultConfig {
+        min_deposit_sol: Option<u64>,
+        min_deposit_token: Option<u64>,
+        staking_enabled: Option<bool>,
+        cashback_bps: Option<u16>,
+        referral_fee_bps: Option<u16>,
+    },
+    WithdrawalLimits { epoch_duration: i64, cap_amount: u64, cap_tvl_bps: u16 },
+    LockTerms { lock_boost_schedule: [u16; 4], early_exit_penalty_bps: u16 },
+    AgentBond { agent_bond_lamports: u64 },
+    InsuranceFee { insurance_fee_bps: u16 },
+    PerformanceFee { performance_fee_bps: u16, treasury: Pubkey },
+    Guardian { new_guardian: Pubkey },
+    ConfigTimelock { delay: i64 },
+    OrderRateLimits { max_orders_per_hour: u32, max_daily_order_volume: u64 },
+    RepeatPurchaseDampening { window: i64, credit_bps: [u16; 3] },
+    TierDowngradeGrace { grace_period: i64 },
+    PlatformFees { platform_fee_bps_by_tier: [u16; 4] },
+    SettlementGuard { guard_before: bool, guard_after: bool, blocked_token_ixs: u32 },
+    HoldingPeriod { min_holding_slots: u64 },
+    RewardCircuitBreaker { payout_cap: u64, anomaly_multiple_bps: u32 },
+    MinOrderAmount { min_order_usd: u64 },
+    StakeStrategy { strategy: StakeStrategy },
+    LiquidityBuffer { liquidity_buffer_bps: u16 },
+    AccrualSchedule { schedule: AccrualSchedule },
+    RewardStreamDuration { duration: i64 },
+    ShareTransferRestriction { restricted: bool },
+    KycPolicy { required: bool, verifier: Pubkey },
+    Notifier { program: Pubkey },
+}
+
+impl ConfigChange {
+    /// Largest serialized variant
+    pub const MAX_LEN: usize = Self::INIT_SPACE;
+
+    /// Code recorded in the audit log (declaration order)
+    pub fn code(&self) -> u16 {
+        match self {
+            ConfigChange::VaultConfig { .. } => 0,
+            ConfigChange::WithdrawalLimits { .. } => 1,
+            ConfigChange::LockTerms { .. } => 2,
+            ConfigChange::AgentBond { .. } => 3,
+            ConfigChange::InsuranceFee { .. } => 4,
+            ConfigChange::PerformanceFee { .. } => 5,
+            ConfigChange::Guardian { .. } => 6,
+            ConfigChange::ConfigTimelock { .. } => 7,
+            ConfigChange::OrderRateLimits { .. } => 8,
+            ConfigChange::RepeatPurchaseDampening { .. } => 9,
+            ConfigChange::TierDowngradeGrace { .. } => 10,
+            ConfigChange::PlatformFees { .. } => 11,
+            ConfigChange::SettlementGuard { .. } => 12,
+            ConfigChange::HoldingPeriod { .. } => 13,
+            ConfigChange::RewardCircuitBreaker { .. } => 14,
+            ConfigChange::MinOrderAmount { .. } => 15,
+            ConfigChange::StakeStrategy { .. } => 16,
+            ConfigChange::LiquidityBuffer { .. } => 17,
+            ConfigChange::AccrualSchedule { .. } => 18,
+            ConfigChange::RewardStreamDuration { .. } => 19,
+            ConfigChange::ShareTransferRestriction { .. } => 20,
+            ConfigChange::KycPolicy { .. } => 21,
+            ConfigChange::Notifier { .. } => 22,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, PartialEq, Eq)]
+pub enum DisputeStatus {
+    Open,         // Volume held out of metrics
+    MerchantWins, // Volume restored
+    Refunded,     // Volume dropped
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct DepegStatusChanged {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    /// The mint left its band (true) or returned to it
+    pub depegged: bool,
+    /// Oracle price, micro-USD per whole token
+    pub price: u64,
+    pub lower_bound: u64,
+    pub upper_bound: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct YieldHarvested {
+    pub merchant: Pubkey,
+    pub deposit_token: DepositType,
+    pub gross_yield: u64,
+    pub performance_fee: u64,
+    pub merchant_rewards: u64,
+    pub harvested_at: i64,
+}
+
+#[event]
+pub struct RewardEpochFunded {
+    pub vault: Pubkey,
+    pub budget: u64,
+    pub start: i64,
+    pub end: i64,
+}
+
+#[event]
+pub struct RewardsHarvested {
+    pub vault: Pubkey,
+    pub deposit_token: DepositType,
+    pub source: HarvestSource,
+    pub amount: u64,
+    pub performance_fee: u64,
+    pub distributed: u64,
+    pub reward_index: u128,
+}
+
+#[event]
+pub struct StrategyRebalanced {
+    pub vault: Pubkey,
+    pub reserve: Pubkey,
+    pub operator: Pubkey,
+    pub direction: RebalanceDirection,
+    /// Principal moved
+    pub amount: u64,
+    /// Collateral minted (supply) or burned (redeem)
+    pub collateral_amount: u64,
+    /// Tokens paid out (supply) or received back, interest included (redeem)
+    pub received: u64,
+    /// Principal supplied after the move
+    pub supplied: u64,
+    /// Principal the strategy's allocation targets
+    pub target: u64,
+}
+
+#[event]
+pub struct StrategyExited {
+    pub vault: Pubkey,
+    pub reserve: Pubkey,
+    pub guardian: Pubkey,
+    /// Collateral redeemed
+    pub collateral_amount: u64,
+    /// Principal supplied before the exit
+    pub supplied: u64,
+    /// Tokens received back, interest included
+    pub received: u64,
+    /// Principal the venue didn't pay back
+    pub loss: u64,
+}
+
+#[event]
+pub struct PerformanceFeesCollected {
+    pub vault: Pubkey,
+    pub treasury: Pubkey,
+    pub deposit_token: DepositType,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OrderSettled {
+    pub merchant: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub amount_usd: u64,
+    pub price: u64,
+    pub exponent: i32,
+    pub platform_fee: u64,
+}
+
+/// Where every unit of a settled payment went, in the settled mint's base units
+/// `gross` is always `platform_fee + processor_fee + net_to_merchant + vault_credited`.
+#[event]
+pub struct SettlementBreakdown {
+    pub order_receipt: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    /// Amount the buyer paid
+    pub gross: u64,
+    /// Sent to the treasury
+    pub platform_fee: u64,
+    /// Kept by a payment processor agent
+    pub processor_fee: u64,
+    /// Sent to the merchant
+    pub net_to_merchant: u64,
+    /// Credited to the vault
+    pub vault_credited: u64,
+}
+
+#[event]
+pub struct OrderRefunded {
+    pub order_receipt: Pubkey,
+    pub merchant: Pubkey,
+    pub buyer: Pubkey,
+    pub order_id: [u8; 32],
+    pub mint: Pubkey,
+    /// Refunded now, in the mint's base units
+    pub amount: u64,
+    /// USD value taken off the merchant's volume for this refund (micro-units)
+    pub amount_usd: u64,
+    /// Running totals on the receipt
+    pub refunded_amount: u64,
+    pub refunded_usd: u64,
+}
+
+#[event]
+pub struct RewardsQuoted {
+    pub merchant: Pubkey,
+    pub rewards: u64,
+    pub yield_bps: u16,
+    pub days_elapsed: i64,
+    pub quoted_at: i64,
+}
+
+#[event]
+pub struct TierQuoted {
+    pub merchant: Pubkey,
+    pub tier: u8,
+    /// Decayed monthly volume in micro-USD
+    pub monthly_volume_usd: u64,
+    pub yield_bps: u16,
+    pub quoted_at: i64,
+}
+
+#[event]
+pub struct YieldUpdated {
+    pub merchant: Pubkey,
+    pub previous_yield_bps: u16,
+    pub yield_bps: u16,
+    /// `YieldTrigger` discriminant
+    pub trigger: u8,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct TierChanged {
+    pub merchant: Pubkey,
+    pub previous_tier: u8,
+    pub tier: u8,
+    pub changed_at: i64,
+}
+
+#[event]
+pub struct PositionOwnerSynced {
+    pub merchant_deposit: Pubkey,
+    pub position_mint: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct SharesMinted {
+    pub merchant: Pubkey,
+    pub share_mint: Pubkey,
+    pub shares: u64,
+    pub principal: u64,
+    /// Principal per share (Q64.64)
+    pub share_price: u128,
+}
+
+#[event]
+pub struct SharesBurned {
+    pub merchant: Pubkey,
+    pub share_mint: Pubkey,
+    pub shares: u64,
+}
+
+#[event]
+pub struct DepositSponsored {
+    pub merchant: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub sponsor_share_bps: u16,
+}
+
+#[event]
+pub struct PositionSplit {
+    pub merchant: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub rewards_moved: u64,
+}
+
+#[event]
+pub struct PositionsMerged {
+    pub merchant: Pubkey,
+    pub source_merchant: Pubkey,
+    pub amount: u64,
+    pub total_deposited: u64,
+}
+
+#[event]
+pub struct InvoicePaid {
+    pub invoice: Pubkey,
+    pub merchant: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub memo_hash: [u8; 32],
+    pub paid_at: i64,
+}
+
+#[event]
+pub struct PaymentAuthorized {
+    pub payment_hold: Pubkey,
+    pub merchant: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub expiry: i64,
+}
+
+#[event]
+pub struct PaymentCaptured {
+    pub payment_hold: Pubkey,
+    pub merchant: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub authorized: u64,
+    pub captured: u64,
+    /// Returned to the buyer
+    pub released: u64,
+    pub captured_at: i64,
+}
+
+#[event]
+pub struct AuthorizationVoided {
+    pub payment_hold: Pubkey,
+    pub merchant: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    pub voided_by: Pubkey,
+}
+
+#[event]
+pub struct InsuranceClaimPaid {
+    pub merchant: Pubkey,
+    pub amount: u64,
+    pub claim_hash: [u8; 32],
+    pub remaining_balance: u64,
+    pub coverage_ratio_bps: u64,
+}
+
+#[event]
+pub struct AgentSlashed {
+    pub agent: Pubkey,
+    pub merchant: Pubkey,
+    pub bond_slashed: u64,
+    pub bond_destination: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub slashed_at: i64,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub dispute: Pubkey,
+    pub merchant: Pubkey,
+    pub order_id: [u8; 32],
+    pub opened_by: Pubkey,
+    pub amount_usd: u64,
+    pub reason_hash: [u8; 32],
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub dispute: Pubkey,
+    pub merchant: Pubkey,
+    pub outcome: DisputeStatus,
+    pub amount_usd: u64,
+    pub resolved_at: i64,
+}
+
+#[event]
+pub struct ConfigChangeProposed {
+    pub vault: Pubkey,
+    pub proposer: Pubkey,
+    pub change: ConfigChange,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct ConfigChangeExecuted {
+    pub vault: Pubkey,
+    pub change: ConfigChange,
+    pub executed_at: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub vault: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub change: ConfigChange,
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -1015,4 +16409,302 @@ pub enum VaultError {
     DepositStillLocked,
     #[msg("Agent is not authorized for this merchant")]
     UnauthorizedAgent,
+    #[msg("Cashback rate exceeds maximum")]
+    CashbackRateTooHigh,
+    #[msg("No cashback to claim")]
+    NoCashbackToClaim,
+    #[msg("Invalid referral accounts")]
+    InvalidReferral,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewardsToClaim,
+    #[msg("Invalid subscription amount, interval, or payment count")]
+    InvalidSubscriptionTerms,
+    #[msg("Subscription is not active")]
+    SubscriptionNotActive,
+    #[msg("Subscription payment is not due yet")]
+    SubscriptionNotDue,
+    #[msg("Invoice has expired")]
+    InvoiceExpired,
+    #[msg("Batch must contain between 1 and MAX_BATCH_ORDERS orders")]
+    InvalidBatchSize,
+    #[msg("Receipt account missing or does not match the expected PDA")]
+    InvalidReceiptAccount,
+    #[msg("Missing or invalid ed25519 order attestation")]
+    InvalidAttestation,
+    #[msg("Order attestation timestamp is too old or in the future")]
+    StaleAttestation,
+    #[msg("Merchant metrics were refreshed too recently")]
+    MetricsRefreshTooSoon,
+    #[msg("Vault stats snapshot taken too recently")]
+    SnapshotTooSoon,
+    #[msg("Account is not a migratable MerchantDeposit")]
+    InvalidMigration,
+    #[msg("MerchantDeposit is already at the current version")]
+    AlreadyMigrated,
+    #[msg("Merchant is frozen")]
+    MerchantFrozen,
+    #[msg("Merchant is not frozen")]
+    MerchantNotFrozen,
+    #[msg("Deposit is not locked; use withdraw")]
+    DepositNotLocked,
+    #[msg("Merchant profile already lists the maximum number of agents")]
+    TooManyAgents,
+    #[msg("Payout account does not match the merchant's payout wallet")]
+    InvalidPayoutWallet,
+    #[msg("Vault is in emergency mode")]
+    EmergencyModeActive,
+    #[msg("Vault is not in emergency mode")]
+    EmergencyModeInactive,
+    #[msg("Insurance fund balance is too low for this claim")]
+    InsufficientInsuranceFunds,
+    #[msg("Agent must be revoked first")]
+    AgentStillActive,
+    #[msg("Agent bond is still within its slashing cooldown")]
+    AgentBondLocked,
+    #[msg("Agent has already been slashed")]
+    AgentAlreadySlashed,
+    #[msg("Invalid merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Claim exceeds the distribution's remaining amount")]
+    DistributionExhausted,
+    #[msg("Invalid tier")]
+    InvalidTier,
+    #[msg("Merchant has not reached this tier")]
+    TierNotReached,
+    #[msg("Order receipt has no matching buyer wallet")]
+    ReceiptHasNoBuyer,
+    #[msg("Receipt cNFT already minted")]
+    ReceiptAlreadyMinted,
+    #[msg("No accrued rewards to compound")]
+    NoRewardsToCompound,
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    #[msg("No rewards claimable within the current emission budget")]
+    NoClaimableEmission,
+    #[msg("Invalid withdraw delegate")]
+    InvalidDelegate,
+    #[msg("Withdraw delegation has expired")]
+    DelegateExpired,
+    #[msg("Amount exceeds the delegate's remaining allowance")]
+    DelegateLimitExceeded,
+    #[msg("Withdrawal cap for this epoch reached; queue with request_withdrawal")]
+    WithdrawalCapReached,
+    #[msg("Queued withdrawals must be fulfilled first")]
+    WithdrawalQueueNotEmpty,
+    #[msg("A withdrawal is already queued for this deposit")]
+    WithdrawalAlreadyQueued,
+    #[msg("Ticket is not at the head of the withdrawal queue")]
+    NotQueueHead,
+    #[msg("Invalid withdrawal limits")]
+    InvalidWithdrawalLimits,
+    #[msg("Withdrawal ticket accounts missing or mismatched")]
+    InvalidTicketAccounts,
+    #[msg("Withdrawal ticket already cancelled")]
+    TicketCancelled,
+    #[msg("Order is past its dispute window")]
+    DisputeWindowClosed,
+    #[msg("Dispute is not open")]
+    DisputeNotOpen,
+    #[msg("Invalid dispute outcome")]
+    InvalidDisputeOutcome,
+    #[msg("Invalid payout splits or split recipient accounts")]
+    InvalidPayoutSplits,
+    #[msg("Invalid auto-sweep configuration")]
+    InvalidSweepConfig,
+    #[msg("Auto-sweep threshold or interval not reached")]
+    SweepNotDue,
+    #[msg("Config changes must go through a timelocked proposal")]
+    ConfigTimelocked,
+    #[msg("Invalid config timelock")]
+    InvalidConfigTimelock,
+    #[msg("Config proposal timelock has not elapsed")]
+    ConfigProposalNotReady,
+    #[msg("Merchant order rate limit exceeded")]
+    OrderRateLimitExceeded,
+    #[msg("Invalid repeat purchase dampening configuration")]
+    InvalidRepeatPurchaseConfig,
+    #[msg("Invalid or unverified Pyth price update")]
+    InvalidPriceUpdate,
+    #[msg("Price is too old")]
+    StalePrice,
+    #[msg("Missing or mismatched settlement token accounts")]
+    InvalidSettlementAccounts,
+    #[msg("No performance fees to collect")]
+    NoPerformanceFees,
+    #[msg("Account is not the vault treasury")]
+    InvalidTreasury,
+    #[msg("Reward epoch must end after it starts and not overlap the previous epoch")]
+    InvalidRewardEpoch,
+    #[msg("Current reward epoch has not ended")]
+    RewardEpochNotEnded,
+    #[msg("No active deposits in this asset to distribute a harvest to")]
+    NoDepositsToHarvest,
+    #[msg("Merchant activity is paused")]
+    MerchantPaused,
+    #[msg("Merchant activity is not paused")]
+    MerchantNotPaused,
+    #[msg("Invalid tier downgrade grace period")]
+    InvalidTierDowngradeGrace,
+    #[msg("Deposit already has a position NFT")]
+    PositionNftExists,
+    #[msg("Deposit has no position NFT")]
+    NoPositionNft,
+    #[msg("Token account does not hold the deposit's position NFT")]
+    InvalidPositionTokenAccount,
+    #[msg("Both parts of a split must meet the minimum deposit")]
+    InvalidSplitAmount,
+    #[msg("Positions must belong to different merchants and hold the same asset")]
+    CannotMergePositions,
+    #[msg("Sponsor share exceeds the maximum")]
+    InvalidSponsorShare,
+    #[msg("Sponsorship account missing or not the deposit's sponsor")]
+    InvalidSponsorship,
+    #[msg("Not available for sponsored deposits")]
+    SponsoredPosition,
+    #[msg("Not the associated token account for this owner and mint")]
+    NotAssociatedTokenAccount,
+    #[msg("SPL deposits must use a 6-decimal mint")]
+    UnsupportedMintDecimals,
+    #[msg("Clock is behind the account's recorded time")]
+    ClockBehindCheckpoint,
+    #[msg("Settlement guard needs blocked token instructions when enabled")]
+    InvalidSettlementGuard,
+    #[msg("Instructions sysvar required while the settlement guard is on")]
+    MissingInstructionsSysvar,
+    #[msg("Settlement must be a top-level instruction while the guard is on")]
+    SettlementNotTopLevel,
+    #[msg("Settlement is sandwiched by a blocked token instruction")]
+    SandwichedSettlement,
+    #[msg("Holding period must be between 1 slot and MAX_HOLDING_SLOTS")]
+    InvalidHoldingPeriod,
+    #[msg("Deposit is still in its holding period")]
+    HoldingPeriodActive,
+    #[msg("Anomaly multiple must be 0 or at least 10000 bps")]
+    InvalidCircuitBreaker,
+    #[msg("Reward payout cap reached for this epoch")]
+    RewardPayoutCapReached,
+    #[msg("Reward payout flagged as abnormal; needs the guardian's co-signature")]
+    RewardPayoutFlagged,
+    #[msg("Performance fee can change by at most MAX_PERFORMANCE_FEE_STEP_BPS per update")]
+    PerformanceFeeStepTooLarge,
+    #[msg("Performance fee was changed too recently")]
+    PerformanceFeeUpdateTooSoon,
+    #[msg("Account is not the vault's own account")]
+    InvalidVaultAccount,
+    #[msg("Vault SOL balance can't cover this payout without dropping below rent exemption")]
+    InsufficientVaultLiquidity,
+    #[msg("Payout exceeds the deposit's principal and accrued rewards")]
+    PayoutExceedsBalance,
+    #[msg("Minimum order amount must be between LOWEST_MIN_ORDER_USD and HIGHEST_MIN_ORDER_USD")]
+    InvalidMinOrderAmount,
+    #[msg("Lookup table doesn't match the vault's")]
+    InvalidLookupTable,
+    #[msg("MEV tips can only be harvested in SOL under the Jito stake strategy")]
+    MevTipsNotEnabled,
+    #[msg("Lending interest is harvested by redeem_lending_collateral")]
+    InvalidHarvestSource,
+    #[msg("Reserve isn't owned by the lending program or didn't mint collateral")]
+    InvalidLendingReserve,
+    #[msg("Collateral amount exceeds what the lending strategy holds")]
+    InsufficientLendingCollateral,
+    #[msg("Lending allocations and the liquidity buffer can't exceed 100% of deposits")]
+    InvalidAllocation,
+    #[msg("Strategy is already at its target allocation")]
+    NothingToRebalance,
+    #[msg("Rebalance returned less than the minimum out")]
+    RebalanceSlippage,
+    #[msg("Strategy was exited; set a new allocation to reactivate it")]
+    StrategyExited,
+    #[msg("Supplying this much would dip idle tokens below the liquidity buffer")]
+    LiquidityBufferBreached,
+    #[msg("Harvests under the Solana epoch accrual schedule must pass the epoch's record")]
+    EpochRecordRequired,
+    #[msg("Epoch record isn't for the current Solana epoch")]
+    StaleEpochRecord,
+    #[msg("Statements can only be rolled for months that have ended")]
+    StatementPeriodOpen,
+    #[msg("Statement must follow the merchant's latest statement")]
+    StatementOutOfOrder,
+    #[msg("Payment hold has expired")]
+    HoldExpired,
+    #[msg("Payment hold would outlast MAX_HOLD_DURATION")]
+    HoldTooLong,
+    #[msg("Cannot capture more than the held amount")]
+    CaptureExceedsHold,
+    #[msg("Only the merchant can void a payment hold before it expires")]
+    HoldNotExpired,
+    #[msg("Only orders paid through settle_order can be refunded")]
+    OrderNotSettled,
+    #[msg("Refund must be more than zero and no more than what's left of the order")]
+    InvalidRefund,
+    #[msg("Order is disputed; refunds wait for the merchant to win the dispute")]
+    OrderDisputed,
+    #[msg("Grant needs a positive cap and allowance and a period of at least MIN_GRANT_PERIOD")]
+    InvalidGrantTerms,
+    #[msg("Payment would exceed the spending grant's cap for this period")]
+    SpendingCapExceeded,
+    #[msg("Reward stream duration must be 0 or between the minimum and maximum")]
+    InvalidRewardStreamDuration,
+    #[msg("Reward streaming is turned off for this vault")]
+    RewardStreamingDisabled,
+    #[msg("No accrued rewards to stream")]
+    NoRewardsToStream,
+    #[msg("Nothing in the reward stream has unlocked yet")]
+    NothingToRelease,
+    #[msg("Deposit has vault shares outstanding; burn them first")]
+    PositionTokenized,
+    #[msg("Deposit has no vault shares")]
+    PositionNotTokenized,
+    #[msg("Tokenized deposits need the share mint and a share token account to burn from")]
+    ShareAccountsRequired,
+    #[msg("Vault shares can only be transferred to approved holders")]
+    ShareHolderNotApproved,
+    #[msg("Requiring KYC needs a verifier")]
+    InvalidKycVerifier,
+    #[msg("This vault requires a KYC attestation from its verifier")]
+    KycAttestationRequired,
+    #[msg("KYC attestation has expired")]
+    KycAttestationExpired,
+    #[msg("Payout destination is not on the merchant's withdrawal whitelist (or still timelocked)")]
+    DestinationNotWhitelisted,
+    #[msg("Payout destination is already whitelisted")]
+    DestinationAlreadyWhitelisted,
+    #[msg("Withdrawal whitelist is full")]
+    WithdrawalWhitelistFull,
+    #[msg("Destination is the payout wallet or a split; change those first")]
+    DestinationInUse,
+    #[msg("Recovery needs a beneficiary other than the merchant and an inactivity period of at least 30 days")]
+    InvalidRecoveryConfig,
+    #[msg("Merchant has been active within the inactivity period")]
+    MerchantStillActive,
+    #[msg("A recovery is already in progress")]
+    RecoveryAlreadyStarted,
+    #[msg("No recovery has been started")]
+    RecoveryNotStarted,
+    #[msg("The recovery challenge window has not passed yet")]
+    RecoveryChallengeWindowOpen,
+    #[msg("Deposits of this mint are paused")]
+    MintDepositsPaused,
+    #[msg("Depeg bounds must be zero, or a positive lower bound below the upper bound, for an SPL mint")]
+    InvalidDepegBounds,
+    #[msg("The mint has no depeg bounds")]
+    DepegBoundsNotSet,
+    #[msg("The mint's oracle price is outside its depeg bounds")]
+    MintDepegged,
+    #[msg("Vault accounting no longer matches its balances")]
+    AccountingInvariantViolated,
+    #[msg("The vault has a notifier: pass it and the notify authority first in the remaining accounts")]
+    NotifierAccountsRequired,
+    #[msg("Not the vault's notifier program")]
+    InvalidNotifier,
+    #[msg("Automation thread doesn't match its crank, merchant or schedule")]
+    InvalidAutomationThread,
+    #[msg("Mint isn't registered with the vault")]
+    MintNotSupported,
+    #[msg("Lending program moved a different amount than requested")]
+    LendingBalanceMismatch,
+    #[msg("Token account isn't in the reward reserve's mint")]
+    InvalidRewardMint,
+    #[msg("Token account isn't in the mint the payment is due in")]
+    InvalidPaymentMint,
 }
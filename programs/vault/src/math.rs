@@ -0,0 +1,137 @@
+//! Fixed-point arithmetic shared by reward, fee and share calculations
+//!
+//! Intermediates are carried in u128 so products never truncate before the
+//! final division. Ratios use Q64.64 fixed point: a `u128` whose low 64 bits
+//! are the fraction (`Q64_ONE` = 1.0).
+//!
+//! Every helper names its rounding direction. Amounts paid out by the vault
+//! (rewards, shares minted, cashback) round down; amounts owed to the vault
+//! (fees, penalties, shares burned) round up, so rounding never leaves the
+//! vault short.
+
+/// 1.0 in Q64.64
+pub const Q64_ONE: u128 = 1 << 64;
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Days per year used for APY accrual
+pub const DAYS_PER_YEAR: u64 = 365;
+
+/// Seconds per accrual day
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Decimals of the canonical accounting unit (micro-units): USD values, SPL
+/// deposits and the reward token all use it
+pub const CANONICAL_DECIMALS: u8 = 6;
+
+/// `a * b / denominator`, rounded down
+pub fn mul_div_floor(a: u64, b: u64, denominator: u64) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let result = (a as u128) * (b as u128) / (denominator as u128);
+    u64::try_from(result).ok()
+}
+
+/// `a * b / denominator`, rounded up
+pub fn mul_div_ceil(a: u64, b: u64, denominator: u64) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let product = (a as u128) * (b as u128);
+    let result = product.div_ceil(denominator as u128);
+    u64::try_from(result).ok()
+}
+
+/// `amount * bps / 10000`, rounded down (payouts)
+pub fn bps_floor(amount: u64, bps: u64) -> Option<u64> {
+    mul_div_floor(amount, bps, BPS_DENOMINATOR)
+}
+
+/// `amount * bps / 10000`, rounded up (fees and penalties)
+pub fn bps_ceil(amount: u64, bps: u64) -> Option<u64> {
+    mul_div_ceil(amount, bps, BPS_DENOMINATOR)
+}
+
+/// Yield earned by `principal` at `apy_bps` over `days`, rounded down
+///
+/// Computed as one division so daily truncation doesn't compound
+/// (the old `principal * bps / 10000 / 365 * days` lost up to a day's
+/// reward per step).
+pub fn yield_for_days(principal: u64, apy_bps: u64, days: u64) -> Option<u64> {
+    let numerator = (principal as u128)
+        .checked_mul(apy_bps as u128)?
+        .checked_mul(days as u128)?;
+    let denominator = (BPS_DENOMINATOR as u128) * (DAYS_PER_YEAR as u128);
+    u64::try_from(numerator / denominator).ok()
+}
+
+/// Yield on `principal` at `apy_bps` over `seconds`, rounded down
+/// Agrees with `yield_for_days` on whole days.
+pub fn yield_for_seconds(principal: u64, apy_bps: u64, seconds: u64) -> Option<u64> {
+    let numerator = (principal as u128)
+        .checked_mul(apy_bps as u128)?
+        .checked_mul(seconds as u128)?;
+    let denominator = (BPS_DENOMINATOR as u128) * (DAYS_PER_YEAR as u128) * (SECONDS_PER_DAY as u128);
+    u64::try_from(numerator / denominator).ok()
+}
+
+/// `numerator / denominator` as Q64.64, rounded down
+pub fn q64_from_ratio_floor(numerator: u64, denominator: u64) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    Some(((numerator as u128) << 64) / denominator as u128)
+}
+
+/// `numerator / denominator` as Q64.64, rounded up
+pub fn q64_from_ratio_ceil(numerator: u64, denominator: u64) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    Some(((numerator as u128) << 64).div_ceil(denominator as u128))
+}
+
+/// `amount * ratio` for a Q64.64 ratio, rounded down
+pub fn q64_mul_floor(amount: u64, ratio: u128) -> Option<u64> {
+    let (whole, _) = q64_mul_parts(amount, ratio)?;
+    u64::try_from(whole).ok()
+}
+
+/// `amount * ratio` for a Q64.64 ratio, rounded up
+pub fn q64_mul_ceil(amount: u64, ratio: u128) -> Option<u64> {
+    let (whole, fraction) = q64_mul_parts(amount, ratio)?;
+    let rounded = if fraction != 0 { whole.checked_add(1)? } else { whole };
+    u64::try_from(rounded).ok()
+}
+
+/// `amount / ratio` for a Q64.64 ratio, rounded down
+pub fn q64_div_floor(amount: u64, ratio: u128) -> Option<u64> {
+    if ratio == 0 {
+        return None;
+    }
+    u64::try_from(((amount as u128) << 64) / ratio).ok()
+}
+
+/// `amount / ratio` for a Q64.64 ratio, rounded up
+pub fn q64_div_ceil(amount: u64, ratio: u128) -> Option<u64> {
+    if ratio == 0 {
+        return None;
+    }
+    u64::try_from(((amount as u128) << 64).div_ceil(ratio)).ok()
+}
+
+/// Split `amount * ratio` into its integer part and the 64-bit fraction
+/// The ratio is split into whole and fractional halves so no step exceeds u128.
+fn q64_mul_parts(amount: u64, ratio: u128) -> Option<(u128, u128)> {
+    let ratio_whole = ratio >> 64;
+    let ratio_fraction = ratio & (Q64_ONE - 1);
+
+    let fraction_product = (amount as u128) * ratio_fraction;
+    let whole = (amount as u128)
+        .checked_mul(ratio_whole)?
+        .checked_add(fraction_product >> 64)?;
+
+    Some((whole, fraction_product & (Q64_ONE - 1)))
+}
@@ -0,0 +1,70 @@
+//! Notifier CPI interface
+//!
+//! A vault can name a downstream program (`set_notifier`) that it invokes after
+//! every deposit and order settlement, so operators can hang their own on-chain
+//! automation off vault activity without forking the vault. The call is one
+//! `Notify` instruction:
+//!
+//! - data: `NOTIFY_DISCRIMINATOR` followed by the Borsh-encoded `Notification`
+//! - accounts: the vault's notify authority ([NOTIFY_AUTHORITY_SEED, vault],
+//!   signer), then whatever accounts the transaction appended for the notifier,
+//!   never as signers
+//!
+//! The notify authority holds nothing and signs nothing else, so a notifier that
+//! sees it sign knows the call came from the vault. A failing notifier fails the
+//! deposit or settlement with it.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+
+/// Signer of a vault's notifications: [NOTIFY_AUTHORITY_SEED, vault]
+pub const NOTIFY_AUTHORITY_SEED: &[u8] = b"notify_authority";
+
+/// First 8 bytes of sha256("shaw-vault-notifier-interface:notify")
+pub const NOTIFY_DISCRIMINATOR: [u8; 8] = [77, 60, 220, 92, 10, 2, 40, 157];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// A merchant deposit was opened
+    Deposit,
+    /// A buyer's order was settled to a merchant
+    Settlement,
+}
+
+/// Payload of a `Notify` instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub vault: Pubkey,
+    pub merchant: Pubkey,
+    /// Mint moved (the native mint for SOL and wSOL)
+    pub mint: Pubkey,
+    /// Amount in the mint's base units
+    pub amount: u64,
+    /// Order id of a settlement (zero for deposits)
+    pub order_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+impl Notification {
+    /// Decode a `Notify` instruction's data (None if it isn't one)
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let payload = data.strip_prefix(&NOTIFY_DISCRIMINATOR[..])?;
+        Self::try_from_slice(payload).ok()
+    }
+}
+
+/// `Notify` instruction to `notifier`, signed by `notify_authority` and passing `accounts` on
+pub fn notify(
+    notifier: &Pubkey,
+    notify_authority: &Pubkey,
+    notification: &Notification,
+    accounts: impl IntoIterator<Item = AccountMeta>,
+) -> Result<Instruction> {
+    let mut data = NOTIFY_DISCRIMINATOR.to_vec();
+    notification.serialize(&mut data)?;
+
+    let mut metas = vec![AccountMeta::new_readonly(*notify_authority, true)];
+    metas.extend(accounts);
+    Ok(Instruction { program_id: *notifier, accounts: metas, data })
+}
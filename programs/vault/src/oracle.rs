@@ -0,0 +1,141 @@
+//! Pyth price reads for oracle-priced order settlement
+//!
+//! Prices come from the Pyth pull oracle: `PriceUpdateV2` accounts posted by
+//! the Pyth receiver program. The account is decoded here directly (owner,
+//! discriminator and Borsh layout) rather than through the Pyth SDK crates.
+
+use anchor_lang::prelude::*;
+
+use crate::math::CANONICAL_DECIMALS;
+use crate::VaultError;
+
+/// Pyth Solana receiver program (owner of `PriceUpdateV2` accounts)
+pub mod pyth_receiver {
+    use anchor_lang::declare_id;
+
+    declare_id!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+}
+
+/// Anchor discriminator of `PriceUpdateV2` (sha256("account:PriceUpdateV2")[..8])
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+#[derive(AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    /// Checked against fewer than the full set of Wormhole guardian signatures
+    Partial { num_signatures: u8 },
+    /// Checked against the full guardian set
+    Full,
+}
+
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+/// Layout of the receiver's `PriceUpdateV2` account (after the discriminator)
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+/// A price usable for crediting volume: `price * 10^exponent` USD per whole token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: u64,
+    pub exponent: i32,
+}
+
+/// Decode a `PriceUpdateV2` account, checking it is owned by the Pyth receiver
+pub fn load_price_update(price_update: &AccountInfo) -> Result<PriceUpdateV2> {
+    require_keys_eq!(*price_update.owner, pyth_receiver::ID, VaultError::InvalidPriceUpdate);
+
+    let data = price_update.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        VaultError::InvalidPriceUpdate
+    );
+    PriceUpdateV2::deserialize(&mut &data[8..]).map_err(|_| error!(VaultError::InvalidPriceUpdate))
+}
+
+/// The price message of a fully verified update for `feed_id`, published no
+/// more than `max_age` seconds before `current_time`
+fn verified_message<'a>(
+    update: &'a PriceUpdateV2,
+    feed_id: &[u8; 32],
+    max_age: i64,
+    current_time: i64,
+) -> Result<&'a PriceFeedMessage> {
+    let message = &update.price_message;
+
+    require!(update.verification_level == VerificationLevel::Full, VaultError::InvalidPriceUpdate);
+    require!(message.feed_id == *feed_id, VaultError::InvalidPriceUpdate);
+    require!(
+        message.publish_time >= current_time.saturating_sub(max_age),
+        VaultError::StalePrice
+    );
+    Ok(message)
+}
+
+/// Fully verified price for `feed_id`, published no more than `max_age` seconds
+/// before `current_time`
+///
+/// Returns the lower end of the confidence interval, so credited volume never
+/// exceeds what the oracle can vouch for.
+pub fn conservative_price(
+    update: &PriceUpdateV2,
+    feed_id: &[u8; 32],
+    max_age: i64,
+    current_time: i64,
+) -> Result<OraclePrice> {
+    let message = verified_message(update, feed_id, max_age, current_time)?;
+
+    let price = u64::try_from(message.price)
+        .ok()
+        .and_then(|price| price.checked_sub(message.conf))
+        .filter(|price| *price > 0)
+        .ok_or(VaultError::InvalidPriceUpdate)?;
+
+    Ok(OraclePrice { price, exponent: message.exponent })
+}
+
+/// Fully verified price for `feed_id` as published, for comparing with a band
+/// rather than crediting value
+pub fn spot_price(update: &PriceUpdateV2, feed_id: &[u8; 32], max_age: i64, current_time: i64) -> Result<OraclePrice> {
+    let message = verified_message(update, feed_id, max_age, current_time)?;
+
+    let price = u64::try_from(message.price)
+        .ok()
+        .filter(|price| *price > 0)
+        .ok_or(VaultError::InvalidPriceUpdate)?;
+
+    Ok(OraclePrice { price, exponent: message.exponent })
+}
+
+/// USD value (canonical micro-units) of `amount` base units of a token with
+/// `decimals`, rounded down (None on overflow)
+pub fn usd_value(amount: u64, price: OraclePrice, decimals: u8) -> Option<u64> {
+    let value = (amount as u128).checked_mul(price.price as u128)?;
+    let scale = (CANONICAL_DECIMALS as i32)
+        .checked_add(price.exponent)?
+        .checked_sub(decimals as i32)?;
+
+    let value = if scale >= 0 {
+        value.checked_mul(10u128.checked_pow(scale as u32)?)?
+    } else {
+        match 10u128.checked_pow(scale.unsigned_abs()) {
+            Some(divisor) => value / divisor,
+            None => 0,
+        }
+    };
+    u64::try_from(value).ok()
+}
@@ -0,0 +1,243 @@
+//! PDA derivation for the vault program's accounts
+//!
+//! Usable off-chain and from programs composing with the vault over CPI (the
+//! `cpi` feature), so callers derive the same addresses the program checks.
+
+use anchor_lang::prelude::*;
+
+use crate::automation::{self, AutomationCrank, THREAD_AUTHORITY_SEED};
+use crate::notifier::NOTIFY_AUTHORITY_SEED;
+use crate::transfer_hook::EXTRA_ACCOUNT_METAS_SEED;
+use crate::{
+    vault_label_seed, DepositType, AGENT_AUTH_SEED, APPROVED_HOLDER_SEED, AUDIT_LOG_SEED, BADGE_MINT_SEED,
+    BONUS_CLAIM_SEED, BUYER_REWARDS_SEED, CONFIG_PROPOSAL_SEED, DEPOSIT_SEED, DISPUTE_SEED, DISTRIBUTION_SEED,
+    EMISSION_SCHEDULE_SEED, EPOCH_RECORD_SEED, HOLD_ESCROW_SEED, INSURANCE_FUND_SEED, INVOICE_SEED,
+    KYC_ATTESTATION_SEED, LENDING_STRATEGY_SEED, MERCHANT_METRICS_SEED, MERCHANT_PROFILE_SEED, MONTHLY_STATEMENT_SEED,
+    PAYMENT_HOLD_SEED, PAYOUT_CONFIG_SEED, POSITION_MINT_SEED, PRICE_FEED_SEED, RECEIPT_SEED, RECOVERY_CONFIG_SEED,
+    REFERRAL_CODE_SEED, REFERRAL_SEED, REWARD_MINT_AUTHORITY_SEED, REWARD_MINT_SEED, REWARD_RESERVE_SEED,
+    SHARE_MINT_SEED, SPENDING_GRANT_SEED, SPONSORSHIP_SEED, SUBSCRIPTION_SEED, SUB_MERCHANT_SEED, SUPPORTED_MINT_SEED,
+    TIER_BADGE_SEED, VAULT_LABEL_LEN, VAULT_SEED, VAULT_STATS_SEED, WITHDRAWAL_TICKET_SEED, WITHDRAWAL_WHITELIST_SEED,
+    WSOL_UNWRAP_SEED, YIELD_HISTORY_SEED,
+};
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::ID).0
+}
+
+/// Vault owned by `authority`
+pub fn vault(authority: &Pubkey) -> Pubkey {
+    find(&[VAULT_SEED, authority.as_ref()])
+}
+
+/// Vault `authority` runs for the program `label` (see `vault_label_seed`)
+pub fn labeled_vault(authority: &Pubkey, label: &[u8; VAULT_LABEL_LEN]) -> Pubkey {
+    find(&[VAULT_SEED, authority.as_ref(), vault_label_seed(label)])
+}
+
+pub fn merchant_deposit(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[DEPOSIT_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn merchant_profile(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[MERCHANT_PROFILE_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn merchant_metrics(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[MERCHANT_METRICS_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn yield_history(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[YIELD_HISTORY_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn payout_config(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[PAYOUT_CONFIG_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn withdrawal_whitelist(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[WITHDRAWAL_WHITELIST_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn recovery_config(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[RECOVERY_CONFIG_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn sub_merchant(vault: &Pubkey, merchant: &Pubkey, label_hash: &[u8; 32]) -> Pubkey {
+    find(&[SUB_MERCHANT_SEED, vault.as_ref(), merchant.as_ref(), label_hash.as_ref()])
+}
+
+pub fn authorized_agent(vault: &Pubkey, merchant: &Pubkey, agent: &Pubkey) -> Pubkey {
+    find(&[AGENT_AUTH_SEED, vault.as_ref(), merchant.as_ref(), agent.as_ref()])
+}
+
+pub fn order_receipt(vault: &Pubkey, merchant: &Pubkey, order_id: &[u8; 32]) -> Pubkey {
+    find(&[RECEIPT_SEED, vault.as_ref(), merchant.as_ref(), order_id.as_ref()])
+}
+
+pub fn order_dispute(order_receipt: &Pubkey) -> Pubkey {
+    find(&[DISPUTE_SEED, order_receipt.as_ref()])
+}
+
+pub fn withdrawal_ticket(vault: &Pubkey, id: u64) -> Pubkey {
+    find(&[WITHDRAWAL_TICKET_SEED, vault.as_ref(), &id.to_le_bytes()])
+}
+
+pub fn buyer_rewards(vault: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    find(&[BUYER_REWARDS_SEED, vault.as_ref(), buyer.as_ref()])
+}
+
+pub fn referral_code(vault: &Pubkey, referrer: &Pubkey) -> Pubkey {
+    find(&[REFERRAL_CODE_SEED, vault.as_ref(), referrer.as_ref()])
+}
+
+pub fn referral(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[REFERRAL_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn subscription(vault: &Pubkey, merchant: &Pubkey, buyer: &Pubkey) -> Pubkey {
+    find(&[SUBSCRIPTION_SEED, vault.as_ref(), merchant.as_ref(), buyer.as_ref()])
+}
+
+pub fn invoice(vault: &Pubkey, merchant: &Pubkey, memo_hash: &[u8; 32]) -> Pubkey {
+    find(&[INVOICE_SEED, vault.as_ref(), merchant.as_ref(), memo_hash.as_ref()])
+}
+
+pub fn payment_hold(vault: &Pubkey, merchant: &Pubkey, buyer: &Pubkey, hold_id: &[u8; 32]) -> Pubkey {
+    find(&[PAYMENT_HOLD_SEED, vault.as_ref(), merchant.as_ref(), buyer.as_ref(), hold_id.as_ref()])
+}
+
+/// Token account escrowing `payment_hold`'s funds
+pub fn hold_escrow(payment_hold: &Pubkey) -> Pubkey {
+    find(&[HOLD_ESCROW_SEED, payment_hold.as_ref()])
+}
+
+pub fn spending_grant(vault: &Pubkey, buyer: &Pubkey, agent: &Pubkey) -> Pubkey {
+    find(&[SPENDING_GRANT_SEED, vault.as_ref(), buyer.as_ref(), agent.as_ref()])
+}
+
+pub fn distribution(vault: &Pubkey, id: u64) -> Pubkey {
+    find(&[DISTRIBUTION_SEED, vault.as_ref(), &id.to_le_bytes()])
+}
+
+pub fn bonus_claim(distribution: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[BONUS_CLAIM_SEED, distribution.as_ref(), merchant.as_ref()])
+}
+
+pub fn vault_stats(vault: &Pubkey) -> Pubkey {
+    find(&[VAULT_STATS_SEED, vault.as_ref()])
+}
+
+pub fn audit_log(vault: &Pubkey) -> Pubkey {
+    find(&[AUDIT_LOG_SEED, vault.as_ref()])
+}
+
+pub fn insurance_fund(vault: &Pubkey) -> Pubkey {
+    find(&[INSURANCE_FUND_SEED, vault.as_ref()])
+}
+
+/// Token account buyer cashback and referral rewards are paid from
+pub fn reward_reserve(vault: &Pubkey) -> Pubkey {
+    find(&[REWARD_RESERVE_SEED, vault.as_ref()])
+}
+
+pub fn emission_schedule(vault: &Pubkey) -> Pubkey {
+    find(&[EMISSION_SCHEDULE_SEED, vault.as_ref()])
+}
+
+pub fn reward_mint(vault: &Pubkey) -> Pubkey {
+    find(&[REWARD_MINT_SEED, vault.as_ref()])
+}
+
+pub fn reward_mint_authority(vault: &Pubkey) -> Pubkey {
+    find(&[REWARD_MINT_AUTHORITY_SEED, vault.as_ref()])
+}
+
+pub fn tier_badge(vault: &Pubkey, merchant: &Pubkey, tier: u8) -> Pubkey {
+    find(&[TIER_BADGE_SEED, vault.as_ref(), merchant.as_ref(), &[tier]])
+}
+
+pub fn badge_mint(vault: &Pubkey, merchant: &Pubkey, tier: u8) -> Pubkey {
+    find(&[BADGE_MINT_SEED, vault.as_ref(), merchant.as_ref(), &[tier]])
+}
+
+/// Position NFT mint of the deposit `merchant` opened at `deposited_at`
+pub fn position_mint(vault: &Pubkey, merchant: &Pubkey, deposited_at: i64) -> Pubkey {
+    find(&[POSITION_MINT_SEED, vault.as_ref(), merchant.as_ref(), &deposited_at.to_le_bytes()])
+}
+
+pub fn share_mint(vault: &Pubkey, deposit_token: &DepositType) -> Pubkey {
+    find(&[SHARE_MINT_SEED, vault.as_ref(), deposit_token.share_mint_seed()])
+}
+
+/// Transfer hook validation account of a share mint
+pub fn extra_account_metas(share_mint: &Pubkey) -> Pubkey {
+    find(&[EXTRA_ACCOUNT_METAS_SEED, share_mint.as_ref()])
+}
+
+/// Signer of the vault's calls into its notifier program
+pub fn notify_authority(vault: &Pubkey) -> Pubkey {
+    find(&[NOTIFY_AUTHORITY_SEED, vault.as_ref()])
+}
+
+pub fn approved_holder(vault: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    find(&[APPROVED_HOLDER_SEED, vault.as_ref(), wallet.as_ref()])
+}
+
+pub fn kyc_attestation(vault: &Pubkey, wallet: &Pubkey) -> Pubkey {
+    find(&[KYC_ATTESTATION_SEED, vault.as_ref(), wallet.as_ref()])
+}
+
+pub fn wsol_unwrap(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[WSOL_UNWRAP_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn sponsorship(vault: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    find(&[SPONSORSHIP_SEED, vault.as_ref(), merchant.as_ref()])
+}
+
+pub fn config_proposal(vault: &Pubkey) -> Pubkey {
+    find(&[CONFIG_PROPOSAL_SEED, vault.as_ref()])
+}
+
+pub fn price_feed(vault: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[PRICE_FEED_SEED, vault.as_ref(), mint.as_ref()])
+}
+
+pub fn supported_mint(vault: &Pubkey, mint: &Pubkey) -> Pubkey {
+    find(&[SUPPORTED_MINT_SEED, vault.as_ref(), mint.as_ref()])
+}
+
+pub fn epoch_record(vault: &Pubkey, epoch: u64) -> Pubkey {
+    find(&[EPOCH_RECORD_SEED, vault.as_ref(), &epoch.to_le_bytes()])
+}
+
+pub fn lending_strategy(vault: &Pubkey, reserve: &Pubkey) -> Pubkey {
+    find(&[LENDING_STRATEGY_SEED, vault.as_ref(), reserve.as_ref()])
+}
+
+/// Merchant's statement for calendar `month` (see `time::calendar_month`)
+pub fn monthly_statement(vault: &Pubkey, merchant: &Pubkey, month: u32) -> Pubkey {
+    find(&[MONTHLY_STATEMENT_SEED, vault.as_ref(), merchant.as_ref(), &month.to_le_bytes()])
+}
+
+/// Signer of the program's self-CPI event instructions (`emit_cpi!`)
+pub fn event_authority() -> Pubkey {
+    find(&[b"__event_authority"])
+}
+
+/// Owner of the vault's automation threads for `crank`
+pub fn thread_authority(vault: &Pubkey, crank: AutomationCrank) -> Pubkey {
+    find(&[THREAD_AUTHORITY_SEED, vault.as_ref(), &[crank as u8]])
+}
+
+/// `crank`'s automation thread for `id`: the merchant, or the mint of a
+/// fulfillment thread (owned by the thread program, not this one)
+pub fn automation_thread(vault: &Pubkey, crank: AutomationCrank, id: &Pubkey) -> Pubkey {
+    automation::thread(&thread_authority(vault, crank), id.as_ref())
+}
+
+/// Address lookup table `create_lookup_table` makes for `vault` at `recent_slot`
+/// (owned by the lookup table program, not this one)
+pub fn lookup_table(vault: &Pubkey, recent_slot: u64) -> Pubkey {
+    anchor_lang::solana_program::address_lookup_table::instruction::derive_lookup_table_address(vault, recent_slot).0
+}
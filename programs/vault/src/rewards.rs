@@ -0,0 +1,224 @@
+//! Pure yield, reward accrual and tier calculations
+//!
+//! Nothing here reads accounts or sysvars: callers pass balances, metrics and
+//! the current time explicitly, so the economics can be exercised off-chain.
+//! Amounts are micro-units (6 decimals) and rates are basis points.
+
+use crate::math::{
+    self, bps_ceil, mul_div_floor, q64_from_ratio_floor, q64_mul_floor, yield_for_days, BPS_DENOMINATOR,
+    DAYS_PER_YEAR,
+};
+use crate::VOLUME_DECAY_HALF_LIFE;
+
+/// Guaranteed base yield (3.00%)
+pub const BASE_YIELD_BPS: u16 = 300;
+
+/// Monthly volume that earns the full volume bonus ($1M)
+pub const TARGET_MONTHLY_VOLUME: u64 = 1_000_000_000000;
+
+pub const TIER_BRONZE: u8 = 0;
+pub const TIER_SILVER: u8 = 1;
+pub const TIER_GOLD: u8 = 2;
+pub const TIER_PLATINUM: u8 = 3;
+
+/// Monthly volume needed per tier: $10k, $50k, $200k
+pub const VOLUME_SILVER: u64 = 10_000_000000;
+pub const VOLUME_GOLD: u64 = 50_000_000000;
+pub const VOLUME_PLATINUM: u64 = 200_000_000000;
+
+/// Days deposited needed per tier
+pub const DAYS_SILVER: i64 = 90;
+pub const DAYS_GOLD: i64 = 180;
+pub const DAYS_PLATINUM: i64 = 365;
+
+const SECONDS_PER_DAY: i64 = math::SECONDS_PER_DAY as i64;
+
+/// Profit share allocated to a deposit, as bps of the deposit (0 when nothing is deposited)
+pub fn profit_share_bps(profit_share_allocated: u64, total_deposited_value: u64) -> u16 {
+    mul_div_floor(profit_share_allocated, math::BPS_DENOMINATOR, total_deposited_value)
+        .unwrap_or(0)
+        .min(u16::MAX as u64) as u16
+}
+
+/// Dynamic yield in bps, e.g. 650 = 6.5%
+///
+/// - Base: 3% guaranteed
+/// - Profit Share Bonus: platform profit allocated to the deposit
+/// - Volume Bonus: linear in monthly volume, filling the room left under the lock cap
+/// - Capped at the lock period maximum APY, with the fixed-term boost on top
+pub fn dynamic_yield_bps(
+    lock_max_apy_bps: u16,
+    lock_boost_bps: u16,
+    profit_share_allocated: u64,
+    total_deposited_value: u64,
+    current_month_volume: u64,
+) -> u16 {
+    let mut yield_bps = BASE_YIELD_BPS
+        .saturating_add(profit_share_bps(profit_share_allocated, total_deposited_value));
+
+    let available_for_volume = lock_max_apy_bps.saturating_sub(yield_bps);
+    yield_bps = yield_bps.saturating_add(volume_bonus_bps(current_month_volume, available_for_volume));
+
+    yield_bps.min(lock_max_apy_bps).saturating_add(lock_boost_bps)
+}
+
+/// Share of `available_bps` earned by `current_month_volume`, rounded down
+/// Scales linearly up to `TARGET_MONTHLY_VOLUME`
+pub fn volume_bonus_bps(current_month_volume: u64, available_bps: u16) -> u16 {
+    if available_bps == 0 {
+        return 0;
+    }
+    if current_month_volume >= TARGET_MONTHLY_VOLUME {
+        return available_bps;
+    }
+
+    let volume_ratio = q64_from_ratio_floor(current_month_volume, TARGET_MONTHLY_VOLUME).unwrap_or(0);
+    q64_mul_floor(available_bps as u64, volume_ratio)
+        .unwrap_or(0)
+        .min(available_bps as u64) as u16
+}
+
+/// Seconds from `from` to `until`, saturating: 0 when the clock reads earlier
+/// than `from` (drift on test validators), never wrapping at the extremes
+pub fn elapsed_seconds(from: i64, until: i64) -> i64 {
+    until.saturating_sub(from).max(0)
+}
+
+/// Whole days between two timestamps (0 if `until` isn't later)
+pub fn whole_days_between(from: i64, until: i64) -> i64 {
+    elapsed_seconds(from, until) / SECONDS_PER_DAY
+}
+
+/// Yield harvested from a deposit, split between the protocol and the merchant
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct YieldHarvest {
+    pub gross: u64,
+    pub performance_fee: u64,
+    pub net: u64,
+}
+
+/// `days` of yield on `principal` at `apy_bps`, less `performance_fee_bps` of it
+/// The fee applies to yield only (never principal) and rounds up
+pub fn harvest_for_days(
+    principal: u64,
+    apy_bps: u16,
+    performance_fee_bps: u16,
+    days: u64,
+) -> Option<YieldHarvest> {
+    let gross = yield_for_days(principal, apy_bps as u64, days)?;
+    YieldHarvest::split(gross, performance_fee_bps)
+}
+
+impl YieldHarvest {
+    /// Split `gross` yield into the performance fee (rounded up) and the merchant's net
+    pub fn split(gross: u64, performance_fee_bps: u16) -> Option<Self> {
+        let performance_fee = bps_ceil(gross, performance_fee_bps as u64)?;
+        let net = gross.checked_sub(performance_fee)?;
+        Some(YieldHarvest { gross, performance_fee, net })
+    }
+}
+
+/// A funded reward epoch: at most `budget` of yield accrues over `[start, end)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardEpoch {
+    pub start: i64,
+    pub end: i64,
+    pub budget: u64,
+}
+
+/// Yield owed over the whole epoch at a principal-weighted rate of
+/// `weighted_yield_sum` (sum of principal x yield bps), saturating at `u64::MAX`
+pub fn epoch_demand(weighted_yield_sum: u128, epoch: &RewardEpoch) -> Option<u64> {
+    let duration = epoch.end.saturating_sub(epoch.start).max(0) as u128;
+    let denominator = BPS_DENOMINATOR as u128 * DAYS_PER_YEAR as u128 * SECONDS_PER_DAY as u128;
+    let demand = weighted_yield_sum.checked_mul(duration)? / denominator;
+    Some(u64::try_from(demand).unwrap_or(u64::MAX))
+}
+
+/// Part of `gross`, earned evenly over `[from, until)`, that falls inside the
+/// epoch, scaled down pro-rata when the epoch's `demand` exceeds its budget
+pub fn budgeted_yield(gross: u64, from: i64, until: i64, epoch: &RewardEpoch, demand: u64) -> Option<u64> {
+    let span = until.saturating_sub(from);
+    let overlap = until.min(epoch.end).saturating_sub(from.max(epoch.start));
+    if span <= 0 || overlap <= 0 {
+        return Some(0);
+    }
+
+    let in_epoch = mul_div_floor(gross, overlap as u64, span as u64)?;
+    if demand <= epoch.budget {
+        Some(in_epoch)
+    } else {
+        mul_div_floor(in_epoch, epoch.budget, demand)
+    }
+}
+
+/// Merchant tier (0=Bronze, 1=Silver, 2=Gold, 3=Platinum)
+/// Each tier needs both the monthly volume and the time deposited
+pub fn calculate_merchant_tier(monthly_volume_usd: u64, deposited_at: i64, current_time: i64) -> u8 {
+    let days_deposited = whole_days_between(deposited_at, current_time);
+
+    if monthly_volume_usd >= VOLUME_PLATINUM && days_deposited >= DAYS_PLATINUM {
+        TIER_PLATINUM
+    } else if monthly_volume_usd >= VOLUME_GOLD && days_deposited >= DAYS_GOLD {
+        TIER_GOLD
+    } else if monthly_volume_usd >= VOLUME_SILVER && days_deposited >= DAYS_SILVER {
+        TIER_SILVER
+    } else {
+        TIER_BRONZE
+    }
+}
+
+/// Apply downgrade hysteresis to a freshly computed tier
+/// Upgrades take effect at once. A dip below `current_tier` starts a grace period
+/// at `now`; the tier only drops once the dip has lasted `grace_period` seconds.
+/// Returns the tier to hold and the grace start to store (0 = not in grace).
+pub fn hold_tier(
+    current_tier: u8,
+    computed_tier: u8,
+    grace_started_at: i64,
+    grace_period: i64,
+    now: i64,
+) -> (u8, i64) {
+    if computed_tier >= current_tier || grace_period == 0 {
+        (computed_tier, 0)
+    } else if grace_started_at == 0 {
+        (current_tier, now)
+    } else if now.saturating_sub(grace_started_at) >= grace_period {
+        (computed_tier, 0)
+    } else {
+        (current_tier, grace_started_at)
+    }
+}
+
+/// Get tier name for display
+pub fn tier_name(tier: u8) -> &'static str {
+    match tier {
+        TIER_BRONZE => "Bronze",
+        TIER_SILVER => "Silver",
+        TIER_GOLD => "Gold",
+        TIER_PLATINUM => "Platinum",
+        _ => "Unknown",
+    }
+}
+
+/// Exponentially decay a rolling metric over `elapsed` seconds
+///
+/// Whole half-lives are applied as bit shifts and the remainder is linearly
+/// interpolated (2^-x ~= 1 - x/2 for x in [0, 1)), which stays within ~6% of
+/// the exact curve without floating point.
+pub fn decay_metric(value: u64, elapsed: i64) -> u64 {
+    if value == 0 || elapsed <= 0 {
+        return value;
+    }
+
+    let half_lives = elapsed / VOLUME_DECAY_HALF_LIFE;
+    if half_lives >= 64 {
+        return 0;
+    }
+
+    let shifted = value >> half_lives;
+    let remainder = (elapsed % VOLUME_DECAY_HALF_LIFE) as u128;
+    let two_half_lives = 2 * VOLUME_DECAY_HALF_LIFE as u128;
+
+    ((shifted as u128 * (two_half_lives - remainder)) / two_half_lives) as u64
+}
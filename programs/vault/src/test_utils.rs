@@ -0,0 +1,220 @@
+//! Localnet test fixtures for the vault program
+//!
+//! Builds a `solana-program-test` bank with the vault loaded natively, plus
+//! helpers to create a vault, a test USDC mint, funded merchants and to move the
+//! bank clock forward so rewards accrue. Enabled with the `test-utils` feature.
+//!
+//! Setup helpers panic on failure so fixtures read linearly; `process` returns
+//! the transaction result so tests can assert on program errors.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{entrypoint::ProgramResult, system_instruction};
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use anchor_spl::associated_token::{get_associated_token_address, spl_associated_token_account};
+use anchor_spl::token::spl_token;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::client::{self, decode_account, decode_zero_copy};
+use crate::{pda, VAULT_LABEL_LEN};
+
+/// Decimals of the test USDC mint
+pub const TEST_USDC_DECIMALS: u8 = 6;
+
+/// SOL given to each funded merchant by default (10 SOL)
+pub const DEFAULT_MERCHANT_LAMPORTS: u64 = 10_000_000_000;
+
+/// A vault created by `setup_vault`
+pub struct TestVault {
+    pub authority: Keypair,
+    pub vault: Pubkey,
+    pub usdc_mint: Pubkey,
+}
+
+/// A registered merchant created by `create_merchant`
+pub struct TestMerchant {
+    pub keypair: Keypair,
+    /// Merchant's test USDC token account (ATA)
+    pub usdc_account: Pubkey,
+}
+
+impl TestMerchant {
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+}
+
+/// `entry` ties the account slice to `'info`; hand it a leaked copy
+fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(accounts.to_vec().into_boxed_slice());
+    crate::entry(program_id, accounts, data)
+}
+
+/// Program test bank with the vault program loaded natively
+/// Add extra programs or accounts before starting it
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("shaw_vault", crate::ID, processor!(process_instruction));
+    program_test.prefer_bpf(false);
+    program_test
+}
+
+/// Start a bank with only the vault program loaded
+pub async fn start() -> ProgramTestContext {
+    program_test().start_with_context().await
+}
+
+/// Send `ixs` in one transaction paid by the context payer
+pub async fn process(
+    context: &mut ProgramTestContext,
+    ixs: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+/// Create a keypair holding `lamports`
+pub async fn create_funded_keypair(context: &mut ProgramTestContext, lamports: u64) -> Keypair {
+    let keypair = Keypair::new();
+    let ix = system_instruction::transfer(&context.payer.pubkey(), &keypair.pubkey(), lamports);
+    process(context, &[ix], &[]).await.expect("fund keypair");
+    keypair
+}
+
+/// Create a vault with a fresh authority and a test USDC mint (with the vault's ATA)
+/// The context payer is the mint authority
+pub async fn setup_vault(context: &mut ProgramTestContext) -> TestVault {
+    let authority = create_funded_keypair(context, 1_000_000_000).await;
+    let vault = pda::vault(&authority.pubkey());
+    process(context, &[client::initialize(&authority.pubkey(), [0; VAULT_LABEL_LEN])], &[&authority])
+        .await
+        .expect("initialize vault");
+
+    let usdc_mint = create_usdc_mint(context).await;
+    create_token_account(context, &vault, &usdc_mint).await;
+
+    TestVault { authority, vault, usdc_mint }
+}
+
+/// Create a test USDC mint owned by the context payer
+pub async fn create_usdc_mint(context: &mut ProgramTestContext) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.expect("rent");
+    let payer = context.payer.pubkey();
+    let ixs = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::ID, &mint.pubkey(), &payer, None, TEST_USDC_DECIMALS)
+            .expect("initialize mint"),
+    ];
+    process(context, &ixs, &[&mint]).await.expect("create mint");
+    mint.pubkey()
+}
+
+/// Create `owner`'s associated token account for `mint` (no-op if it exists)
+pub async fn create_token_account(context: &mut ProgramTestContext, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let ix = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        &context.payer.pubkey(),
+        owner,
+        mint,
+        &spl_token::ID,
+    );
+    process(context, &[ix], &[]).await.expect("create token account");
+    get_associated_token_address(owner, mint)
+}
+
+/// Mint test USDC to `owner`'s associated token account
+pub async fn mint_usdc(context: &mut ProgramTestContext, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+    let token_account = create_token_account(context, owner, mint).await;
+    let ix = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        mint,
+        &token_account,
+        &context.payer.pubkey(),
+        &[],
+        amount,
+    )
+    .expect("mint_to");
+    process(context, &[ix], &[]).await.expect("mint usdc");
+    token_account
+}
+
+/// Create a merchant holding `lamports` and `usdc` test USDC, registered with the
+/// vault (paying out to itself)
+pub async fn create_merchant(
+    context: &mut ProgramTestContext,
+    test_vault: &TestVault,
+    lamports: u64,
+    usdc: u64,
+) -> TestMerchant {
+    let keypair = create_funded_keypair(context, lamports).await;
+    let usdc_account = mint_usdc(context, &test_vault.usdc_mint, &keypair.pubkey(), usdc).await;
+
+    let ix = client::register_merchant(&test_vault.vault, &keypair.pubkey(), [0; 32], keypair.pubkey());
+    process(context, &[ix], &[&keypair]).await.expect("register merchant");
+
+    TestMerchant { keypair, usdc_account }
+}
+
+/// Move the bank clock forward by `seconds` so time-based rewards accrue
+/// Also advances a slot so repeated transactions get a fresh blockhash. To pin
+/// the program's time without touching the bank, use `time::set_mock_time`.
+pub async fn warp_forward(context: &mut ProgramTestContext, seconds: i64) {
+    let clock: Clock = context.banks_client.get_sysvar().await.expect("clock");
+    let unix_timestamp = clock.unix_timestamp + seconds;
+    context.warp_to_slot(clock.slot + 1).expect("warp");
+
+    let mut clock: Clock = context.banks_client.get_sysvar().await.expect("clock");
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+/// Current bank clock time
+pub async fn now(context: &mut ProgramTestContext) -> i64 {
+    let clock: Clock = context.banks_client.get_sysvar().await.expect("clock");
+    clock.unix_timestamp
+}
+
+/// Fetch and decode a program account from the bank
+pub async fn fetch_account<T: AccountDeserialize>(context: &mut ProgramTestContext, address: &Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(*address)
+        .await
+        .expect("get account")
+        .expect("account not found");
+    decode_account(&account.data).expect("decode account")
+}
+
+/// Fetch and decode a zero-copy program account from the bank
+pub async fn fetch_zero_copy<T: ZeroCopy>(context: &mut ProgramTestContext, address: &Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(*address)
+        .await
+        .expect("get account")
+        .expect("account not found");
+    decode_zero_copy(&account.data).expect("decode account")
+}
+
+/// Token balance of an SPL token account
+pub async fn token_balance(context: &mut ProgramTestContext, token_account: &Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(*token_account)
+        .await
+        .expect("get account")
+        .expect("token account not found");
+    spl_token::state::Account::unpack(&account.data).expect("token account").amount
+}
@@ -0,0 +1,93 @@
+//! Time source for the program's time-based logic
+//!
+//! Handlers read the current time through `now()` rather than the Clock sysvar
+//! directly. On-chain it is always the sysvar; off-chain builds with `cfg(test)`
+//! or the `test-utils` feature can pin it with `set_mock_time` so reward and
+//! tier logic runs deterministically.
+
+use anchor_lang::prelude::*;
+
+/// Something that knows the current unix time
+pub trait TimeSource {
+    fn unix_timestamp(&self) -> Result<i64>;
+}
+
+/// The Clock sysvar (production)
+pub struct SysvarClock;
+
+impl TimeSource for SysvarClock {
+    fn unix_timestamp(&self) -> Result<i64> {
+        Ok(Clock::get()?.unix_timestamp)
+    }
+}
+
+/// A fixed time, for tests
+pub struct FixedClock(pub i64);
+
+impl TimeSource for FixedClock {
+    fn unix_timestamp(&self) -> Result<i64> {
+        Ok(self.0)
+    }
+}
+
+/// Current unix time (the sysvar unless a mock time is set)
+pub fn now() -> Result<i64> {
+    #[cfg(all(not(target_os = "solana"), any(test, feature = "test-utils")))]
+    if let Some(mock_time) = mock::get() {
+        return FixedClock(mock_time).unix_timestamp();
+    }
+
+    SysvarClock.unix_timestamp()
+}
+
+/// Current slot (always the Clock sysvar; only unix time is mocked)
+pub fn current_slot() -> Result<u64> {
+    Ok(Clock::get()?.slot)
+}
+
+/// Current Solana epoch and the unix time it started (always the Clock sysvar)
+pub fn current_epoch() -> Result<(u64, i64)> {
+    let clock = Clock::get()?;
+    Ok((clock.epoch, clock.epoch_start_timestamp))
+}
+
+/// Calendar month (UTC) containing `unix_timestamp`, as `year * 12 + month - 1`
+/// So January 2026 is 24312 and consecutive months differ by one.
+pub fn calendar_month(unix_timestamp: i64) -> u32 {
+    // Days to civil date (Howard Hinnant's algorithm, proleptic Gregorian calendar)
+    let days = unix_timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153; // 0 = March
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    (year * 12 + month - 1) as u32
+}
+
+/// Pin `now()` to `time` (None restores the sysvar clock)
+/// Process-wide, so natively loaded programs in `solana-program-test` see it too
+#[cfg(all(not(target_os = "solana"), any(test, feature = "test-utils")))]
+pub fn set_mock_time(time: Option<i64>) {
+    mock::set(time);
+}
+
+#[cfg(all(not(target_os = "solana"), any(test, feature = "test-utils")))]
+mod mock {
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    /// `i64::MIN` means unset
+    static MOCK_TIME: AtomicI64 = AtomicI64::new(i64::MIN);
+
+    pub fn get() -> Option<i64> {
+        match MOCK_TIME.load(Ordering::SeqCst) {
+            i64::MIN => None,
+            time => Some(time),
+        }
+    }
+
+    pub fn set(time: Option<i64>) {
+        MOCK_TIME.store(time.unwrap_or(i64::MIN), Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,75 @@
+//! Token-2022 transfer hook of the vault share mints
+//!
+//! Token-2022 invokes the program's `Execute` interface instruction on every share
+//! transfer, appending the extra accounts listed in the mint's validation account
+//! ([EXTRA_ACCOUNT_METAS_SEED, mint]). The list is encoded here directly, in the
+//! TLV layout of `spl-tlv-account-resolution`, rather than through the interface
+//! crates.
+
+use anchor_lang::prelude::*;
+
+use crate::APPROVED_HOLDER_SEED;
+
+/// Validation account of a share mint: [EXTRA_ACCOUNT_METAS_SEED, mint]
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+/// TLV type of the `Execute` instruction's account list
+const EXECUTE_DISCRIMINATOR: [u8; 8] = [105, 37, 101, 197, 75, 251, 102, 26];
+
+/// Extra accounts per transfer: the vault, then the destination owner's approval
+const EXTRA_ACCOUNTS: usize = 2;
+/// Packed size of one extra account meta
+const EXTRA_ACCOUNT_META_LEN: usize = 35;
+
+/// Data size of a share mint's validation account
+pub const EXTRA_ACCOUNT_METAS_LEN: usize = 8 + 4 + 4 + EXTRA_ACCOUNTS * EXTRA_ACCOUNT_META_LEN;
+
+/// Destination token account, as indexed in `Execute`
+const DESTINATION_ACCOUNT_INDEX: u8 = 2;
+/// The vault, the first extra account
+const VAULT_ACCOUNT_INDEX: u8 = 5;
+/// Offset of the owner in token account data
+const TOKEN_OWNER_OFFSET: u8 = 32;
+
+/// Meta types: a fixed address, or a PDA of the hook program from seed configs
+const FIXED_ADDRESS: u8 = 0;
+const HOOK_PDA: u8 = 1;
+
+/// Seed config types
+const SEED_LITERAL: u8 = 1;
+const SEED_ACCOUNT_KEY: u8 = 3;
+const SEED_ACCOUNT_DATA: u8 = 4;
+
+fn write_meta(dst: &mut [u8], discriminator: u8, address_config: [u8; 32]) {
+    dst[0] = discriminator;
+    dst[1..33].copy_from_slice(&address_config);
+    // Neither signer nor writable
+    dst[33] = 0;
+    dst[34] = 0;
+}
+
+/// Seeds of the destination owner's approval: [APPROVED_HOLDER_SEED, vault, owner]
+fn approved_holder_seeds() -> [u8; 32] {
+    let mut config = [0u8; 32];
+    let literal_len = APPROVED_HOLDER_SEED.len();
+    config[0] = SEED_LITERAL;
+    config[1] = literal_len as u8;
+    config[2..2 + literal_len].copy_from_slice(APPROVED_HOLDER_SEED);
+    let rest = &mut config[2 + literal_len..];
+    rest[..2].copy_from_slice(&[SEED_ACCOUNT_KEY, VAULT_ACCOUNT_INDEX]);
+    rest[2..6].copy_from_slice(&[SEED_ACCOUNT_DATA, DESTINATION_ACCOUNT_INDEX, TOKEN_OWNER_OFFSET, 32]);
+    config
+}
+
+/// Validation account data for the share mints of `vault`
+pub fn extra_account_metas(vault: &Pubkey) -> [u8; EXTRA_ACCOUNT_METAS_LEN] {
+    let mut data = [0u8; EXTRA_ACCOUNT_METAS_LEN];
+    data[..8].copy_from_slice(&EXECUTE_DISCRIMINATOR);
+    data[8..12].copy_from_slice(&((4 + EXTRA_ACCOUNTS * EXTRA_ACCOUNT_META_LEN) as u32).to_le_bytes());
+    data[12..16].copy_from_slice(&(EXTRA_ACCOUNTS as u32).to_le_bytes());
+
+    let (vault_meta, holder_meta) = data[16..].split_at_mut(EXTRA_ACCOUNT_META_LEN);
+    write_meta(vault_meta, FIXED_ADDRESS, vault.to_bytes());
+    write_meta(holder_meta, HOOK_PDA, approved_holder_seeds());
+    data
+}
@@ -0,0 +1,110 @@
+//! Space audits for accounts with variable-length fields
+//!
+//! `LEN` comes from `InitSpace`; these check that a fully populated account
+//! serializes to exactly that size, so a full account never fails to fit.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AnchorSerialize;
+use shaw_vault::{
+    AccrualSchedule, ConfigChange, MerchantDeposit, MerchantProfile, PayoutConfig, PayoutSplit, StakeStrategy,
+    WithdrawalDestination, WithdrawalWhitelist, MAX_PAYOUT_SPLITS, MAX_PROFILE_AGENTS, MAX_WITHDRAWAL_DESTINATIONS,
+};
+
+fn full_splits() -> Vec<PayoutSplit> {
+    (0..MAX_PAYOUT_SPLITS)
+        .map(|_| PayoutSplit { wallet: Pubkey::new_unique(), bps: 1_000 })
+        .collect()
+}
+
+#[test]
+fn merchant_profile_with_max_agents_fits() {
+    let profile = MerchantProfile {
+        merchant: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        metadata_uri_hash: [7; 32],
+        payout_wallet: Pubkey::new_unique(),
+        pending_payout_wallet: Pubkey::new_unique(),
+        payout_wallet_effective_at: i64::MAX,
+        created_at: i64::MAX,
+        registered_agents: (0..MAX_PROFILE_AGENTS).map(|_| Pubkey::new_unique()).collect(),
+        current_tier: 3,
+        bump: 255,
+        loyalty_started_at: i64::MAX,
+        last_withdrawn_at: i64::MAX,
+    };
+    assert_eq!(profile.try_to_vec().unwrap().len(), MerchantProfile::LEN);
+}
+
+#[test]
+fn payout_config_with_max_splits_fits() {
+    let config = PayoutConfig {
+        merchant: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        splits: full_splits(),
+        pending_splits: full_splits(),
+        splits_effective_at: i64::MAX,
+        sweep_threshold: u64::MAX,
+        sweep_interval: i64::MAX,
+        last_sweep_at: i64::MAX,
+        bump: 255,
+    };
+    assert_eq!(config.try_to_vec().unwrap().len(), PayoutConfig::LEN);
+}
+
+#[test]
+fn withdrawal_whitelist_with_max_destinations_fits() {
+    let whitelist = WithdrawalWhitelist {
+        merchant: Pubkey::new_unique(),
+        vault: Pubkey::new_unique(),
+        destinations: (0..MAX_WITHDRAWAL_DESTINATIONS)
+            .map(|_| WithdrawalDestination { wallet: Pubkey::new_unique(), effective_at: i64::MAX })
+            .collect(),
+        bump: 255,
+    };
+    assert_eq!(whitelist.try_to_vec().unwrap().len(), WithdrawalWhitelist::LEN);
+}
+
+#[test]
+fn every_config_change_fits_max_len() {
+    let changes = [
+        ConfigChange::VaultConfig {
+            min_deposit_sol: Some(u64::MAX),
+            min_deposit_token: Some(u64::MAX),
+            staking_enabled: Some(true),
+            cashback_bps: Some(u16::MAX),
+            referral_fee_bps: Some(u16::MAX),
+        },
+        ConfigChange::WithdrawalLimits { epoch_duration: 1, cap_amount: 1, cap_tvl_bps: 1 },
+        ConfigChange::LockTerms { lock_boost_schedule: [1; 4], early_exit_penalty_bps: 1 },
+        ConfigChange::AgentBond { agent_bond_lamports: 1 },
+        ConfigChange::InsuranceFee { insurance_fee_bps: 1 },
+        ConfigChange::PerformanceFee { performance_fee_bps: 1, treasury: Pubkey::new_unique() },
+        ConfigChange::Guardian { new_guardian: Pubkey::new_unique() },
+        ConfigChange::ConfigTimelock { delay: 1 },
+        ConfigChange::OrderRateLimits { max_orders_per_hour: 1, max_daily_order_volume: 1 },
+        ConfigChange::RepeatPurchaseDampening { window: 1, credit_bps: [1; 3] },
+        ConfigChange::TierDowngradeGrace { grace_period: 1 },
+        ConfigChange::PlatformFees { platform_fee_bps_by_tier: [1; 4] },
+        ConfigChange::SettlementGuard { guard_before: true, guard_after: true, blocked_token_ixs: 1 },
+        ConfigChange::HoldingPeriod { min_holding_slots: 1 },
+        ConfigChange::RewardCircuitBreaker { payout_cap: 1, anomaly_multiple_bps: 1 },
+        ConfigChange::MinOrderAmount { min_order_usd: 1 },
+        ConfigChange::StakeStrategy { strategy: StakeStrategy::Jito },
+        ConfigChange::LiquidityBuffer { liquidity_buffer_bps: 1 },
+        ConfigChange::AccrualSchedule { schedule: AccrualSchedule::SolanaEpoch },
+        ConfigChange::RewardStreamDuration { duration: 1 },
+        ConfigChange::ShareTransferRestriction { restricted: true },
+        ConfigChange::KycPolicy { required: true, verifier: Pubkey::new_unique() },
+        ConfigChange::Notifier { program: Pubkey::new_unique() },
+    ];
+    for change in changes {
+        let len = change.try_to_vec().unwrap().len();
+        assert!(len <= ConfigChange::MAX_LEN, "{:?} is {} bytes", change, len);
+    }
+}
+
+#[test]
+fn merchant_deposit_reserves_space_past_v8() {
+    // v8's reserved bytes start the deposit mint
+    assert_eq!(MerchantDeposit::LEN, MerchantDeposit::V8_LEN - 8 + 32 + 64);
+}
@@ -0,0 +1,375 @@
+//! Property tests for the fixed-point math and the yield / tier calculations
+//!
+//! Inputs span the full integer ranges; every helper must either return a
+//! value satisfying its bounds or `None`, never panic.
+
+use proptest::prelude::*;
+use shaw_vault::math::{
+    bps_ceil, bps_floor, mul_div_ceil, mul_div_floor, q64_div_floor, q64_from_ratio_floor,
+    q64_mul_ceil, q64_mul_floor, yield_for_days, yield_for_seconds, BPS_DENOMINATOR,
+    CANONICAL_DECIMALS, DAYS_PER_YEAR, Q64_ONE, SECONDS_PER_DAY,
+};
+use shaw_vault::rewards::{
+    budgeted_yield, calculate_merchant_tier, decay_metric, dynamic_yield_bps, elapsed_seconds,
+    epoch_demand, harvest_for_days, hold_tier, volume_bonus_bps, whole_days_between, RewardEpoch,
+    BASE_YIELD_BPS, TARGET_MONTHLY_VOLUME, TIER_PLATINUM,
+};
+use shaw_vault::oracle::{usd_value, OraclePrice};
+use shaw_vault::time::calendar_month;
+use shaw_vault::{LockPeriod, VOLUME_DECAY_HALF_LIFE};
+
+fn lock_max_apy() -> impl Strategy<Value = u16> {
+    prop_oneof![
+        Just(LockPeriod::SixMonths.max_apy_bps()),
+        Just(LockPeriod::OneYear.max_apy_bps()),
+        Just(LockPeriod::ThreeYears.max_apy_bps()),
+        Just(LockPeriod::FiveYears.max_apy_bps()),
+    ]
+}
+
+// ============================================================================
+// Fixed-point math
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn mul_div_matches_u128_reference(a: u64, b: u64, d in 1u64..) {
+        let exact = a as u128 * b as u128;
+        let floor = exact / d as u128;
+        let ceil = exact.div_ceil(d as u128);
+
+        prop_assert_eq!(mul_div_floor(a, b, d), u64::try_from(floor).ok());
+        prop_assert_eq!(mul_div_ceil(a, b, d), u64::try_from(ceil).ok());
+    }
+
+    #[test]
+    fn mul_div_by_zero_is_none(a: u64, b: u64) {
+        prop_assert_eq!(mul_div_floor(a, b, 0), None);
+        prop_assert_eq!(mul_div_ceil(a, b, 0), None);
+    }
+
+    #[test]
+    fn bps_rounding_brackets_exact(amount: u64, bps in 0u64..=BPS_DENOMINATOR) {
+        let floor = bps_floor(amount, bps).unwrap();
+        let ceil = bps_ceil(amount, bps).unwrap();
+
+        prop_assert!(floor <= ceil && ceil - floor <= 1);
+        prop_assert!(ceil <= amount);
+    }
+
+    #[test]
+    fn q64_ratio_never_overpays(amount: u64, numerator: u64, denominator in 1u64..) {
+        prop_assume!(numerator <= denominator);
+        let ratio = q64_from_ratio_floor(numerator, denominator).unwrap();
+        let exact = mul_div_floor(amount, numerator, denominator).unwrap();
+
+        prop_assert!(ratio <= Q64_ONE);
+        prop_assert!(q64_mul_floor(amount, ratio).unwrap() <= exact);
+        prop_assert!(q64_mul_floor(amount, ratio).unwrap() <= q64_mul_ceil(amount, ratio).unwrap());
+    }
+
+    #[test]
+    fn reward_index_shares_never_exceed_harvest(
+        amount: u64,
+        principal_a in 1u64..(1 << 62),
+        principal_b in 1u64..(1 << 62),
+    ) {
+        // Index increase from one harvest, as harvest_rewards computes it
+        if let Some(increase) = q64_from_ratio_floor(amount, principal_a + principal_b) {
+            let paid = q64_mul_floor(principal_a, increase).unwrap() as u128
+                + q64_mul_floor(principal_b, increase).unwrap() as u128;
+            prop_assert!(paid <= amount as u128);
+        }
+    }
+
+    #[test]
+    fn q64_one_is_identity(amount: u64) {
+        prop_assert_eq!(q64_mul_floor(amount, Q64_ONE), Some(amount));
+        prop_assert_eq!(q64_mul_ceil(amount, Q64_ONE), Some(amount));
+        prop_assert_eq!(q64_div_floor(amount, Q64_ONE), Some(amount));
+    }
+
+    #[test]
+    fn yield_for_days_is_superadditive(
+        principal: u64,
+        apy_bps in 0u64..=u16::MAX as u64,
+        a in 0u64..=36_500,
+        b in 0u64..=36_500,
+    ) {
+        // Splitting an accrual period can only lose rounding dust, never gain
+        if let (Some(ya), Some(yb), Some(yab)) = (
+            yield_for_days(principal, apy_bps, a),
+            yield_for_days(principal, apy_bps, b),
+            yield_for_days(principal, apy_bps, a + b),
+        ) {
+            prop_assert!(ya + yb <= yab);
+        }
+    }
+
+    #[test]
+    fn yield_for_one_year_equals_apy(principal: u64, apy_bps in 0u64..=u16::MAX as u64) {
+        prop_assert_eq!(
+            yield_for_days(principal, apy_bps, DAYS_PER_YEAR),
+            mul_div_floor(principal, apy_bps, BPS_DENOMINATOR)
+        );
+    }
+
+    #[test]
+    fn yield_for_seconds_agrees_on_whole_days(
+        principal: u64,
+        apy_bps in 0u64..=u16::MAX as u64,
+        days in 0u64..=36_500,
+    ) {
+        prop_assert_eq!(
+            yield_for_seconds(principal, apy_bps, days * SECONDS_PER_DAY),
+            yield_for_days(principal, apy_bps, days)
+        );
+    }
+}
+
+// ============================================================================
+// Yield
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn dynamic_yield_respects_cap_and_floor(
+        lock_max in lock_max_apy(),
+        boost in 0u16..=500,
+        profit_share: u64,
+        deposited: u64,
+        volume: u64,
+    ) {
+        let yield_bps = dynamic_yield_bps(lock_max, boost, profit_share, deposited, volume);
+
+        prop_assert!(yield_bps <= lock_max + boost);
+        prop_assert!(yield_bps >= BASE_YIELD_BPS.min(lock_max) + boost);
+    }
+
+    #[test]
+    fn dynamic_yield_is_monotonic_in_volume(
+        lock_max in lock_max_apy(),
+        boost in 0u16..=500,
+        profit_share: u64,
+        deposited: u64,
+        volume: u64,
+        extra: u64,
+    ) {
+        let before = dynamic_yield_bps(lock_max, boost, profit_share, deposited, volume);
+        let after = dynamic_yield_bps(lock_max, boost, profit_share, deposited, volume.saturating_add(extra));
+        prop_assert!(after >= before);
+    }
+
+    #[test]
+    fn dynamic_yield_is_monotonic_in_profit_share(
+        lock_max in lock_max_apy(),
+        boost in 0u16..=500,
+        profit_share: u64,
+        extra: u64,
+        deposited: u64,
+        volume: u64,
+    ) {
+        let before = dynamic_yield_bps(lock_max, boost, profit_share, deposited, volume);
+        let after = dynamic_yield_bps(lock_max, boost, profit_share.saturating_add(extra), deposited, volume);
+        prop_assert!(after >= before);
+    }
+
+    #[test]
+    fn volume_bonus_bounded_and_monotonic(volume: u64, extra: u64, available: u16) {
+        let bonus = volume_bonus_bps(volume, available);
+
+        prop_assert!(bonus <= available);
+        prop_assert!(volume_bonus_bps(volume.saturating_add(extra), available) >= bonus);
+        if volume >= TARGET_MONTHLY_VOLUME {
+            prop_assert_eq!(bonus, available);
+        }
+    }
+
+    #[test]
+    fn harvest_splits_yield_and_is_monotonic(
+        principal: u64,
+        apy_bps: u16,
+        fee_bps in 0u16..=10_000,
+        days in 0u64..=36_500,
+    ) {
+        if let Some(harvest) = harvest_for_days(principal, apy_bps, fee_bps, days) {
+            prop_assert_eq!(harvest.gross, yield_for_days(principal, apy_bps as u64, days).unwrap());
+            prop_assert_eq!(harvest.performance_fee + harvest.net, harvest.gross);
+            prop_assert_eq!(harvest.performance_fee, bps_ceil(harvest.gross, fee_bps as u64).unwrap());
+
+            if let Some(later) = harvest_for_days(principal, apy_bps, fee_bps, days + 1) {
+                prop_assert!(later.net >= harvest.net);
+            }
+        }
+    }
+
+    #[test]
+    fn budgeted_yield_never_exceeds_gross_or_budget_share(
+        gross: u64,
+        from in 0i64..1_000_000_000,
+        span in 1i64..100_000_000,
+        start in 0i64..1_000_000_000,
+        duration in 1i64..100_000_000,
+        budget: u64,
+        weighted_yield_sum: u128,
+    ) {
+        let epoch = RewardEpoch { start, end: start + duration, budget };
+        if let Some(demand) = epoch_demand(weighted_yield_sum, &epoch) {
+            let budgeted = budgeted_yield(gross, from, from + span, &epoch, demand).unwrap();
+            prop_assert!(budgeted <= gross);
+
+            // Entirely inside an epoch that covers its demand: nothing is cut
+            if from >= start && from + span <= start + duration && demand <= budget {
+                prop_assert_eq!(budgeted, gross);
+            }
+            // Entirely outside the epoch: nothing accrues
+            if from + span <= start || from >= start + duration {
+                prop_assert_eq!(budgeted, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn whole_days_never_negative(from: i64, until: i64) {
+        let days = whole_days_between(from, until);
+
+        prop_assert!(days >= 0);
+        prop_assert!(days * 86400 <= until.saturating_sub(from).max(0));
+    }
+
+    #[test]
+    fn elapsed_seconds_saturates_instead_of_going_negative(from: i64, until: i64) {
+        let elapsed = elapsed_seconds(from, until);
+
+        prop_assert!(elapsed >= 0);
+        if until <= from {
+            prop_assert_eq!(elapsed, 0);
+        } else if let Some(exact) = until.checked_sub(from) {
+            prop_assert_eq!(elapsed, exact);
+        } else {
+            prop_assert_eq!(elapsed, i64::MAX);
+        }
+    }
+
+    #[test]
+    fn calendar_month_advances_one_month_at_a_time(time in 0i64..(1i64 << 40)) {
+        let month = calendar_month(time);
+        let midnight = time - time.rem_euclid(86400);
+
+        // Months only roll over at midnight, and never skip one
+        prop_assert_eq!(calendar_month(midnight), month);
+        let next_day = calendar_month(midnight + 86400);
+        prop_assert!(next_day == month || next_day == month + 1);
+
+        // 2026-01-01T00:00:00Z starts January 2026; the second before is December 2025
+        prop_assert_eq!(calendar_month(1_767_225_600), 2026 * 12);
+        prop_assert_eq!(calendar_month(1_767_225_599), 2025 * 12 + 11);
+    }
+}
+
+// ============================================================================
+// Tier and decay
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn tier_is_monotonic_in_volume_and_time(
+        volume: u64,
+        extra_volume: u64,
+        deposited_at: i64,
+        now: i64,
+        extra_time in 0i64..,
+    ) {
+        let tier = calculate_merchant_tier(volume, deposited_at, now);
+
+        prop_assert!(tier <= TIER_PLATINUM);
+        prop_assert!(calculate_merchant_tier(volume.saturating_add(extra_volume), deposited_at, now) >= tier);
+        prop_assert!(calculate_merchant_tier(volume, deposited_at, now.saturating_add(extra_time)) >= tier);
+    }
+
+    #[test]
+    fn tier_hold_only_downgrades_after_grace(
+        current_tier in 0u8..=TIER_PLATINUM,
+        computed_tier in 0u8..=TIER_PLATINUM,
+        grace_started_at in 0i64..(1i64 << 40),
+        grace_period in 0i64..(1i64 << 30),
+        now in 0i64..(1i64 << 41),
+    ) {
+        let (tier, started) = hold_tier(current_tier, computed_tier, grace_started_at, grace_period, now);
+
+        prop_assert!(tier == current_tier || tier == computed_tier);
+        if computed_tier >= current_tier {
+            prop_assert_eq!((tier, started), (computed_tier, 0));
+        } else if tier < current_tier {
+            // Downgraded: the dip had lasted the whole grace period
+            prop_assert!(grace_period == 0 || (grace_started_at > 0 && now - grace_started_at >= grace_period));
+            prop_assert_eq!(started, 0);
+        } else {
+            prop_assert!(started > 0 || now == 0);
+        }
+    }
+
+    #[test]
+    fn decay_never_grows_and_is_monotonic(value: u64, elapsed in 0i64.., extra in 0i64..(1i64 << 40)) {
+        let decayed = decay_metric(value, elapsed);
+
+        prop_assert!(decayed <= value);
+        prop_assert!(decay_metric(value, elapsed.saturating_add(extra)) <= decayed);
+    }
+
+    #[test]
+    fn decay_halves_per_half_life(value: u64, half_lives in 0i64..64) {
+        prop_assert_eq!(decay_metric(value, half_lives * VOLUME_DECAY_HALF_LIFE), value >> half_lives);
+    }
+
+    #[test]
+    fn decay_ignores_non_positive_elapsed(value: u64, elapsed in i64::MIN..=0) {
+        prop_assert_eq!(decay_metric(value, elapsed), value);
+    }
+}
+
+// ============================================================================
+// Oracle pricing
+// ============================================================================
+
+proptest! {
+    #[test]
+    fn usd_value_matches_reference(
+        amount: u64,
+        price in 1u64..=u32::MAX as u64,
+        exponent in -12i32..=0,
+        decimals in 0u8..=12,
+    ) {
+        // USD micro-units = amount * price * 10^(6 + exponent - decimals), rounded down
+        let scale = 6 + exponent - decimals as i32;
+        let exact = amount as u128 * price as u128;
+        let reference = if scale >= 0 {
+            exact.checked_mul(10u128.pow(scale as u32))
+        } else {
+            Some(exact / 10u128.pow(scale.unsigned_abs()))
+        };
+
+        let value = usd_value(amount, OraclePrice { price, exponent }, decimals);
+        prop_assert_eq!(value, reference.and_then(|value| u64::try_from(value).ok()));
+    }
+
+    #[test]
+    fn usd_value_is_monotonic(amount: u64, extra: u64, price in 1u64.., exponent in -12i32..=0, decimals in 0u8..=12) {
+        let price = OraclePrice { price, exponent };
+        if let (Some(before), Some(after)) = (
+            usd_value(amount, price, decimals),
+            usd_value(amount.saturating_add(extra), price, decimals),
+        ) {
+            prop_assert!(after >= before);
+        }
+    }
+
+    #[test]
+    fn canonical_mint_at_unit_price_is_worth_its_amount(amount: u64) {
+        // A 6-decimal token at exactly $1 is valued at its own base units
+        prop_assert_eq!(
+            usd_value(amount, OraclePrice { price: 1, exponent: 0 }, CANONICAL_DECIMALS),
+            Some(amount)
+        );
+    }
+}
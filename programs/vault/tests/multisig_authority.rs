@@ -0,0 +1,203 @@
+//! Vault administration by a multisig-controlled authority
+//!
+//! Squads executes an approved proposal by CPI-ing into the target program with
+//! its vault PDA as signer (and rent payer). `mock_multisig` reproduces that
+//! execution path with an M-of-N member check, so the vault's admin instructions
+//! are exercised exactly as they would be under a Squads vault authority.
+#![cfg(feature = "test-utils")]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::{system_program, InstructionData};
+use shaw_vault::{accounts, instruction, pda, Vault};
+use solana_program_test::{processor, tokio, BanksClientError, ProgramTestContext};
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+const MULTISIG_PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+const MULTISIG_VAULT_SEED: &[u8] = b"multisig_vault";
+const THRESHOLD: usize = 2;
+
+fn multisig_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MULTISIG_VAULT_SEED], &MULTISIG_PROGRAM_ID)
+}
+
+/// Execute an approved proposal: data is `[member_count, inner ix data..]`,
+/// accounts are `[target program, members.., inner accounts..]`
+fn mock_multisig(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (&member_count, inner_data) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    let member_count = member_count as usize;
+    let (target, rest) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (members, inner_accounts) = rest.split_at(member_count);
+
+    let approvals = members.iter().filter(|member| member.is_signer).count();
+    if approvals < THRESHOLD {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (vault, bump) = multisig_vault();
+    let inner = Instruction {
+        program_id: *target.key,
+        accounts: inner_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || *account.key == vault,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: inner_data.to_vec(),
+    };
+    invoke_signed(&inner, accounts, &[&[MULTISIG_VAULT_SEED, &[bump]]])
+}
+
+async fn start() -> ProgramTestContext {
+    let mut program_test = shaw_vault::test_utils::program_test();
+    program_test.add_program("mock_multisig", MULTISIG_PROGRAM_ID, processor!(mock_multisig));
+    // The multisig vault is a system account funding rent for admin inits
+    program_test.add_account(
+        multisig_vault().0,
+        SolanaAccount::new(10_000_000_000, 0, &system_program::ID),
+    );
+    program_test.start_with_context().await
+}
+
+/// Wrap a vault instruction in a multisig execution approved by `approvers`
+async fn execute_proposal(
+    context: &mut ProgramTestContext,
+    members: &[Keypair],
+    approvers: &[&Keypair],
+    inner: Instruction,
+) -> std::result::Result<(), BanksClientError> {
+    let mut metas = vec![AccountMeta::new_readonly(inner.program_id, false)];
+    metas.extend(members.iter().map(|member| {
+        let approved = approvers.iter().any(|approver| approver.pubkey() == member.pubkey());
+        AccountMeta::new_readonly(member.pubkey(), approved)
+    }));
+    // The multisig vault signs inside the CPI, not on the outer transaction
+    let (vault, _) = multisig_vault();
+    metas.extend(inner.accounts.into_iter().map(|meta| AccountMeta {
+        is_signer: meta.is_signer && meta.pubkey != vault,
+        ..meta
+    }));
+
+    let mut data = vec![members.len() as u8];
+    data.extend(inner.data);
+    let ix = Instruction { program_id: MULTISIG_PROGRAM_ID, accounts: metas, data };
+
+    let mut signers = vec![&context.payer];
+    signers.extend_from_slice(approvers);
+    let blockhash = context.banks_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&context.payer.pubkey()), &signers, blockhash);
+    context.banks_client.process_transaction(tx).await
+}
+
+fn vault_ix(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: shaw_vault::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+async fn fetch_vault(context: &mut ProgramTestContext, address: &Pubkey) -> Vault {
+    let account = context.banks_client.get_account(*address).await.unwrap().unwrap();
+    Vault::try_deserialize(&mut &account.data[..]).unwrap()
+}
+
+#[tokio::test]
+async fn multisig_authority_administers_vault() {
+    let mut context = start().await;
+    let members: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let (authority, _) = multisig_vault();
+    let (vault, vault_bump) =
+        Pubkey::find_program_address(&[shaw_vault::VAULT_SEED, authority.as_ref()], &shaw_vault::ID);
+    let audit_log = pda::audit_log(&vault);
+    let event_authority = pda::event_authority();
+    let program = shaw_vault::ID;
+
+    // Vault created by proposal, with the multisig vault paying rent
+    let initialize = vault_ix(
+        accounts::Initialize { vault, audit_log, authority, system_program: system_program::ID },
+        instruction::Initialize { bump: vault_bump, label: [0; shaw_vault::VAULT_LABEL_LEN] },
+    );
+    execute_proposal(&mut context, &members, &[&members[0], &members[1]], initialize)
+        .await
+        .unwrap();
+    assert_eq!(fetch_vault(&mut context, &vault).await.authority, authority);
+
+    // Config update through an approved proposal
+    let update = vault_ix(
+        accounts::UpdateVaultConfig { vault, audit_log, authority, event_authority, program },
+        instruction::UpdateVaultConfig {
+            min_deposit_sol: None,
+            min_deposit_token: None,
+            staking_enabled: None,
+            cashback_bps: Some(75),
+            referral_fee_bps: None,
+        },
+    );
+    execute_proposal(&mut context, &members, &[&members[1], &members[2]], update)
+        .await
+        .unwrap();
+    assert_eq!(fetch_vault(&mut context, &vault).await.cashback_bps, 75);
+
+    let limits = vault_ix(
+        accounts::UpdateVaultConfig { vault, audit_log, authority, event_authority, program },
+        instruction::SetWithdrawalLimits { epoch_duration: 86400, cap_amount: 0, cap_tvl_bps: 1000 },
+    );
+    execute_proposal(&mut context, &members, &[&members[0], &members[2]], limits)
+        .await
+        .unwrap();
+    assert_eq!(fetch_vault(&mut context, &vault).await.withdraw_cap_tvl_bps, 1000);
+
+    // Admin account inits are paid by the multisig vault
+    let init_stats = vault_ix(
+        accounts::InitializeVaultStats {
+            vault,
+            vault_stats: pda::vault_stats(&vault),
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeVaultStats {},
+    );
+    execute_proposal(&mut context, &members, &[&members[0], &members[1]], init_stats)
+        .await
+        .unwrap();
+    assert!(context.banks_client.get_account(pda::vault_stats(&vault)).await.unwrap().is_some());
+
+    // Below threshold: the multisig refuses to sign
+    let agent_bond = fetch_vault(&mut context, &vault).await.agent_bond_lamports;
+    let below_threshold = vault_ix(
+        accounts::UpdateVaultConfig { vault, audit_log, authority, event_authority, program },
+        instruction::SetAgentBond { agent_bond_lamports: agent_bond + 1 },
+    );
+    assert!(execute_proposal(&mut context, &members, &[&members[0]], below_threshold)
+        .await
+        .is_err());
+    assert_eq!(fetch_vault(&mut context, &vault).await.agent_bond_lamports, agent_bond);
+
+    // A member can't act as the authority on their own
+    let direct = vault_ix(
+        accounts::UpdateVaultConfig {
+            vault,
+            audit_log,
+            authority: members[0].pubkey(),
+            event_authority,
+            program,
+        },
+        instruction::SetAgentBond { agent_bond_lamports: agent_bond + 1 },
+    );
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[direct],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &members[0]],
+        blockhash,
+    );
+    assert!(context.banks_client.process_transaction(tx).await.is_err());
+}
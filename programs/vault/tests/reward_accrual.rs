@@ -0,0 +1,89 @@
+//! Reward accrual on a localnet bank, built on the `test_utils` fixtures
+//!
+//! Each test creates a vault and a registered merchant, deposits, moves the bank
+//! clock forward and runs the metrics crank, which accrues the deposit to now.
+#![cfg(feature = "test-utils")]
+
+use shaw_vault::math::yield_for_days;
+use shaw_vault::rewards::YieldHarvest;
+use shaw_vault::test_utils::*;
+use shaw_vault::{client, pda, LockPeriod, MerchantDeposit, Vault};
+use solana_program_test::{tokio, ProgramTestContext};
+use solana_sdk::signature::Signer;
+
+const DEPOSIT_LAMPORTS: u64 = 2_000_000_000;
+const DAY: i64 = 86400;
+
+/// Net rewards `days` of accrual at the deposit's yield come to
+fn net_rewards(vault: &Vault, deposit: &MerchantDeposit, days: u64) -> u64 {
+    let gross = yield_for_days(deposit.total_deposited, deposit.current_yield_bps as u64, days).unwrap();
+    YieldHarvest::split(gross, vault.performance_fee_bps).unwrap().net
+}
+
+async fn deposit_sol(context: &mut ProgramTestContext, test_vault: &TestVault, merchant: &TestMerchant) {
+    let ix = client::deposit_sol(
+        &test_vault.vault,
+        &merchant.pubkey(),
+        DEPOSIT_LAMPORTS,
+        LockPeriod::SixMonths,
+        None,
+        None,
+    );
+    process(context, &[ix], &[&merchant.keypair]).await.expect("deposit");
+}
+
+async fn refresh_metrics(context: &mut ProgramTestContext, test_vault: &TestVault, merchant: &TestMerchant) {
+    let ix = client::refresh_merchant_metrics(&test_vault.vault, &merchant.pubkey(), &merchant.pubkey());
+    process(context, &[ix], &[&merchant.keypair]).await.expect("refresh metrics");
+}
+
+#[tokio::test]
+async fn deposit_accrues_rewards_over_time() {
+    let mut context = start().await;
+    let test_vault = setup_vault(&mut context).await;
+    let merchant = create_merchant(&mut context, &test_vault, DEFAULT_MERCHANT_LAMPORTS, 0).await;
+    let deposit_address = pda::merchant_deposit(&test_vault.vault, &merchant.pubkey());
+
+    deposit_sol(&mut context, &test_vault, &merchant).await;
+    let deposit: MerchantDeposit = fetch_account(&mut context, &deposit_address).await;
+    assert_eq!(deposit.total_deposited, DEPOSIT_LAMPORTS);
+    assert_eq!(deposit.accrued_rewards, 0);
+
+    warp_forward(&mut context, DAY).await;
+    refresh_metrics(&mut context, &test_vault, &merchant).await;
+
+    let vault: Vault = fetch_account(&mut context, &test_vault.vault).await;
+    let accrued: MerchantDeposit = fetch_account(&mut context, &deposit_address).await;
+    assert!(accrued.accrued_rewards > 0);
+    assert_eq!(accrued.accrued_rewards, net_rewards(&vault, &deposit, 1));
+    assert_eq!(accrued.last_accrual_at, deposit.last_accrual_at + DAY);
+}
+
+#[tokio::test]
+async fn emergency_mode_time_is_skipped_after_it_lifts() {
+    let mut context = start().await;
+    let test_vault = setup_vault(&mut context).await;
+    let merchant = create_merchant(&mut context, &test_vault, DEFAULT_MERCHANT_LAMPORTS, 0).await;
+    let deposit_address = pda::merchant_deposit(&test_vault.vault, &merchant.pubkey());
+    let guardian = &test_vault.authority;
+
+    deposit_sol(&mut context, &test_vault, &merchant).await;
+    let deposit: MerchantDeposit = fetch_account(&mut context, &deposit_address).await;
+
+    // One day of accrual, three in emergency mode, then another day; the deposit
+    // isn't touched until the end
+    warp_forward(&mut context, DAY).await;
+    let ix = client::set_emergency_mode(&test_vault.vault, &guardian.pubkey(), true);
+    process(&mut context, &[ix], &[guardian]).await.expect("enable emergency mode");
+    warp_forward(&mut context, 3 * DAY).await;
+    let ix = client::set_emergency_mode(&test_vault.vault, &guardian.pubkey(), false);
+    process(&mut context, &[ix], &[guardian]).await.expect("disable emergency mode");
+    warp_forward(&mut context, DAY).await;
+    refresh_metrics(&mut context, &test_vault, &merchant).await;
+
+    let vault: Vault = fetch_account(&mut context, &test_vault.vault).await;
+    assert_eq!(vault.sol_suspended_seconds, 3 * DAY);
+    let accrued: MerchantDeposit = fetch_account(&mut context, &deposit_address).await;
+    assert_eq!(accrued.accrued_rewards, net_rewards(&vault, &deposit, 2));
+    assert_eq!(accrued.suspended_seconds_checkpoint, 3 * DAY);
+}